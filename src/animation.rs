@@ -0,0 +1,199 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::arena::Arena;
+use crate::settings::Settings;
+use crate::{AppState, Food, Player, PlayerMeals, Position, SnakeHead, SnakeSegments, StepEvent};
+
+/// The snake's head facing up: eyes open, eyes shut and mouth open. Drawn
+/// in white so the head's color tints it.
+const HEAD_SHEET: &str = "sprites/head.png";
+const HEAD_TILE: UVec2 = UVec2::splat(16);
+const FRAME_OPEN: usize = 0;
+const FRAME_BLINK: usize = 1;
+const FRAME_MOUTH: usize = 2;
+
+/// Seconds without a step before the snake starts blinking, as when the
+/// game is paused or a puzzle waits for a move.
+const IDLE_BEFORE_BLINK: f32 = 1.0;
+/// Seconds from one blink to the next, and how long the eyes stay shut.
+const BLINK_INTERVAL: f32 = 2.5;
+const BLINK_LENGTH: f32 = 0.15;
+
+/// Segments per second a swallowed meal travels down the body.
+const GULP_SPEED: f32 = 14.0;
+/// How much the segment holding the meal swells, as a share of its size.
+const GULP_SWELL: f32 = 0.4;
+
+#[derive(Resource)]
+struct HeadSheet {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+/// Seconds since the snake last stepped.
+#[derive(Resource, Default)]
+struct Stillness(f32);
+
+/// Meals on their way down the players' bodies, as the snake that ate each
+/// and its distance from the head in segments.
+#[derive(Resource, Default)]
+struct Gulps(Vec<(Entity, f32)>);
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stillness>()
+            .init_resource::<Gulps>()
+            .add_systems(Startup, load_head_sheet)
+            .add_systems(OnEnter(AppState::Playing), reset_animations)
+            .add_systems(
+                Update,
+                (
+                    dress_heads,
+                    track_stillness,
+                    animate_heads.after(dress_heads).after(track_stillness),
+                    start_gulps,
+                    ripple_gulps.after(start_gulps),
+                    fall_back_to_plain_heads.after(dress_heads),
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn load_head_sheet(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    commands.insert_resource(HeadSheet {
+        image: asset_server.load(HEAD_SHEET),
+        layout: layouts.add(TextureAtlasLayout::from_grid(HEAD_TILE, 3, 1, None, None)),
+    });
+}
+
+fn reset_animations(mut stillness: ResMut<Stillness>, mut gulps: ResMut<Gulps>) {
+    stillness.0 = 0.0;
+    gulps.0.clear();
+}
+
+/// Gives new heads a face.
+fn dress_heads(sheet: Res<HeadSheet>, mut heads: Query<&mut Sprite, Added<SnakeHead>>) {
+    for mut sprite in heads.iter_mut() {
+        sprite.image = sheet.image.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: sheet.layout.clone(),
+            index: FRAME_OPEN,
+        });
+    }
+}
+
+fn track_stillness(
+    time: Res<Time>,
+    mut step_reader: MessageReader<StepEvent>,
+    mut stillness: ResMut<Stillness>,
+) {
+    if step_reader.read().next().is_some() {
+        stillness.0 = 0.0;
+    } else {
+        stillness.0 += time.delta_secs();
+    }
+}
+
+/// Turns each head the way it's going and picks its frame: mouth open with
+/// food right ahead, blinking now and then while the snake stands still.
+fn animate_heads(
+    arena: Res<Arena>,
+    stillness: Res<Stillness>,
+    food: Query<&Position, With<Food>>,
+    // The 3D style turns board entities into cubes, which don't turn.
+    mut heads: Query<(&SnakeHead, &Position, &mut Sprite, &mut Transform), Without<Mesh3d>>,
+) {
+    let grid = arena.grid();
+    for (head, position, mut sprite, mut transform) in heads.iter_mut() {
+        let next = grid.step(*position, head.direction);
+        let ahead = grid.to_world(next, 1.0) - grid.to_world(*position, 1.0);
+        transform.rotation = Quat::from_rotation_z(ahead.to_angle() - FRAC_PI_2);
+
+        let idle = stillness.0 - IDLE_BEFORE_BLINK;
+        let frame = if food.iter().any(|pos| *pos == next) {
+            FRAME_MOUTH
+        } else if idle > 0.0 && idle % BLINK_INTERVAL < BLINK_LENGTH {
+            FRAME_BLINK
+        } else {
+            FRAME_OPEN
+        };
+        if let Some(atlas) = &mut sprite.texture_atlas
+            && atlas.index != frame
+        {
+            atlas.index = frame;
+        }
+    }
+}
+
+fn start_gulps(mut growth_reader: PlayerMeals, settings: Res<Settings>, mut gulps: ResMut<Gulps>) {
+    for eaten in growth_reader.read() {
+        if !settings.reduced_motion {
+            gulps.0.push((eaten.snake, 0.0));
+        }
+    }
+}
+
+/// Moves each meal along and swells the segments it passes through.
+fn ripple_gulps(
+    time: Res<Time>,
+    bodies: Query<(Entity, &SnakeSegments), With<Player>>,
+    mut gulps: ResMut<Gulps>,
+    mut sprites: Query<&mut Sprite, Without<SnakeHead>>,
+) {
+    if gulps.0.is_empty() {
+        return;
+    }
+    for (_, gulp) in gulps.0.iter_mut() {
+        *gulp += GULP_SPEED * time.delta_secs();
+    }
+    // A meal is gone once it passes the tail, or with the snake that ate it.
+    gulps.0.retain(|(snake, gulp)| {
+        bodies
+            .get(*snake)
+            .is_ok_and(|(_, segments)| *gulp < segments.len() as f32 + 1.0)
+    });
+    for (snake, segments) in bodies.iter() {
+        if !gulps.0.iter().any(|(eater, _)| *eater == snake) {
+            continue;
+        }
+        for (index, segment) in segments.iter().enumerate().skip(1) {
+            let Ok(mut sprite) = sprites.get_mut(*segment) else {
+                continue;
+            };
+            let swell = gulps
+                .0
+                .iter()
+                .filter(|(eater, _)| *eater == snake)
+                .map(|(_, gulp)| (1.0 - (index as f32 - gulp).abs()).max(0.0))
+                .fold(0.0, f32::max);
+            sprite.custom_size = Some(Vec2::splat(1.0 + GULP_SWELL * swell));
+        }
+    }
+}
+
+/// Heads go back to plain squares of their color if the sheet is missing or
+/// broken.
+fn fall_back_to_plain_heads(
+    asset_server: Res<AssetServer>,
+    sheet: Res<HeadSheet>,
+    mut heads: Query<&mut Sprite, With<SnakeHead>>,
+) {
+    if !matches!(asset_server.load_state(&sheet.image), LoadState::Failed(_)) {
+        return;
+    }
+    for mut sprite in heads.iter_mut() {
+        if sprite.texture_atlas.is_some() {
+            sprite.image = Handle::default();
+            sprite.texture_atlas = None;
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::locale::Locale;
+use crate::settings::Settings;
+use crate::sound::{Bus, from_side};
+use crate::{
+    AppState, Direction, DirectionChanged, Food, GameState, Player, PlayerMeals, Position,
+    SnakeDied, StepEvent, attract,
+};
+
+/// Steps between reminders of where the food is, on top of the one after
+/// every turn and every bite.
+const BEARING_EVERY: u32 = 12;
+const CUE_LENGTH: Duration = Duration::from_millis(90);
+/// Cues for heading up are higher, for heading down lower.
+const CUE_PITCH: f32 = 587.0;
+const CUE_PITCH_SPREAD: f32 = 1.5;
+const CAPTION_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.75);
+
+/// Something a player who can't see the board well should know about. Read
+/// out loud and shown in large print along the bottom of the screen, while
+/// [`Settings::announcements`] is on.
+#[derive(Message)]
+pub struct Announcement(pub String);
+
+/// What's been announced so far this run.
+#[derive(Resource, Default)]
+struct Announcer {
+    score: u32,
+    /// Steps since the food's whereabouts were last announced.
+    since_bearing: u32,
+    /// The food's whereabouts should be announced on the next step, once the
+    /// new food is out after a bite.
+    bearing_due: bool,
+}
+
+/// The large-print copy of the latest announcement.
+#[derive(Component)]
+struct Caption;
+
+pub struct AnnouncePlugin;
+
+impl Plugin for AnnouncePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Announcement>()
+            .init_resource::<Announcer>()
+            .add_systems(OnEnter(AppState::Playing), setup_caption)
+            .add_systems(
+                Update,
+                (announce_run, read_out)
+                    .chain()
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(announcements_on)
+                    .run_if(attract::not_demo),
+            );
+    }
+}
+
+fn announcements_on(settings: Res<Settings>) -> bool {
+    settings.announcements
+}
+
+fn setup_caption(mut commands: Commands, mut announcer: ResMut<Announcer>) {
+    *announcer = Announcer {
+        bearing_due: true,
+        ..default()
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(24.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 36.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(CAPTION_COLOR),
+                Visibility::Hidden,
+                Caption,
+            ));
+        });
+}
+
+/// Which way `to` lies from `from`, if it's anywhere else.
+fn bearing(from: Position, to: Position) -> Option<Direction> {
+    match ((to.x - from.x).signum(), (to.y - from.y).signum()) {
+        (-1, 0) => Some(Direction::Left),
+        (1, 0) => Some(Direction::Right),
+        (0, 1) => Some(Direction::Up),
+        (0, -1) => Some(Direction::Down),
+        (-1, 1) => Some(Direction::UpLeft),
+        (1, 1) => Some(Direction::UpRight),
+        (-1, -1) => Some(Direction::DownLeft),
+        (1, -1) => Some(Direction::DownRight),
+        _ => None,
+    }
+}
+
+/// A short tone heard from the side `direction` points to, higher the more
+/// it points up.
+fn cue(direction: Direction, settings: &Settings, pitches: &mut Assets<Pitch>) -> impl Bundle {
+    let (side, rise) = match direction {
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+        Direction::Up => (0.0, 1.0),
+        Direction::Down => (0.0, -1.0),
+        Direction::UpLeft => (-1.0, 0.5),
+        Direction::UpRight => (1.0, 0.5),
+        Direction::DownLeft => (-1.0, -0.5),
+        Direction::DownRight => (1.0, -0.5),
+    };
+    let pitch = CUE_PITCH * CUE_PITCH_SPREAD.powf(rise);
+    (
+        AudioPlayer(pitches.add(Pitch::new(pitch, CUE_LENGTH))),
+        Bus::Sfx,
+        Bus::Sfx.play_once(settings).with_spatial(true),
+        from_side(side),
+    )
+}
+
+/// Puts what happened since last frame into words: turns, the score, deaths
+/// and now and then where the nearest food is. Turns and the food's
+/// whereabouts also get a cue from the matching side.
+fn announce_run(
+    mut commands: Commands,
+    mut turn_reader: MessageReader<DirectionChanged>,
+    mut step_reader: MessageReader<StepEvent>,
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut announcement_writer: MessageWriter<Announcement>,
+    mut announcer: ResMut<Announcer>,
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    heads: Query<&Position, With<Player>>,
+    players: Query<(), With<Player>>,
+    food: Query<&Position, With<Food>>,
+) {
+    let mut parts = Vec::new();
+    if let Some(turn) = turn_reader
+        .read()
+        .filter(|turn| players.contains(turn.snake))
+        .last()
+    {
+        parts.push(locale.get(turn.to.name()).to_string());
+        commands.spawn(cue(turn.to, &settings, &mut pitches));
+        announcer.bearing_due = true;
+    }
+    if game_state.score > announcer.score {
+        parts.push(locale.format("announce.score", &[("score", &game_state.score)]));
+    }
+    announcer.score = game_state.score;
+    if let Some(died) = game_over_reader.read().last() {
+        parts.push(locale.format(
+            "announce.died",
+            &[("cause", &locale.get(died.cause.name()))],
+        ));
+        announcer.bearing_due = true;
+    }
+
+    // Right after a bite the eaten food may still be on the board, so the
+    // new one waits for the next step.
+    let ate = growth_reader.read().next().is_some();
+    let stepped = step_reader.read().next().is_some();
+    if stepped {
+        announcer.since_bearing += 1;
+    }
+    if stepped && !ate && (announcer.bearing_due || announcer.since_bearing >= BEARING_EVERY) {
+        let nearest = heads.iter().next().and_then(|head| {
+            food.iter()
+                .map(|pos| (*pos, (pos.x - head.x).abs() + (pos.y - head.y).abs()))
+                .min_by_key(|(_, cells)| *cells)
+                .and_then(|(pos, cells)| Some((bearing(*head, pos)?, cells)))
+        });
+        if let Some((direction, cells)) = nearest {
+            parts.push(locale.format(
+                "announce.food",
+                &[
+                    ("direction", &locale.get(direction.name())),
+                    ("cells", &cells),
+                ],
+            ));
+            commands.spawn(cue(direction, &settings, &mut pitches));
+        }
+        announcer.bearing_due = false;
+        announcer.since_bearing = 0;
+    } else if ate {
+        announcer.bearing_due = true;
+    }
+
+    if !parts.is_empty() {
+        announcement_writer.write(Announcement(parts.join(". ")));
+    }
+}
+
+/// Shows the latest announcement and reads it out, cutting off whatever was
+/// still being read.
+fn read_out(
+    mut announcement_reader: MessageReader<Announcement>,
+    mut captions: Query<(&mut Text, &mut Visibility), With<Caption>>,
+) {
+    let Some(Announcement(text)) = announcement_reader.read().last() else {
+        return;
+    };
+    for (mut caption, mut visibility) in captions.iter_mut() {
+        caption.0.clone_from(text);
+        *visibility = Visibility::Inherited;
+    }
+    speak(text);
+}
+
+/// Hands the text to whichever speech tool the platform ships with: `say`
+/// on macOS, Speech Dispatcher or eSpeak on Linux. Where there's none, the
+/// caption and the cues are all there is.
+#[cfg(not(target_arch = "wasm32"))]
+fn speak(text: &str) {
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Mutex;
+
+    const TOOLS: [&str; 4] = ["say", "spd-say", "espeak-ng", "espeak"];
+    static SPEAKING: Mutex<Option<Child>> = Mutex::new(None);
+
+    let Ok(mut speaking) = SPEAKING.lock() else {
+        return;
+    };
+    if let Some(mut child) = speaking.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    *speaking = TOOLS.iter().find_map(|tool| {
+        Command::new(tool)
+            .arg(text)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+    });
+}
+
+/// Uses the browser's own speech synthesis.
+#[cfg(target_arch = "wasm32")]
+fn speak(text: &str) {
+    let Some(synthesis) = web_sys::window().and_then(|window| window.speech_synthesis().ok())
+    else {
+        return;
+    };
+    synthesis.cancel();
+    if let Ok(utterance) = web_sys::SpeechSynthesisUtterance::new_with_text(text) {
+        synthesis.speak(&utterance);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::grid::{Grid, GridShape};
+use crate::rules::{Rules, WallBehavior};
+use crate::settings::Settings;
+use crate::terrain::Terrain;
+use crate::{AppState, Position, Size};
+
+const BORDER_WALL_COLOR: Color = Color::srgb(0.55, 0.55, 0.6);
+
+/// The board the current run is played on: its size, the cells on it that
+/// can't be entered and what its edges do to a snake running off them.
+/// Follows [`GridShape`], the run's [`Rules`] and the level's [`Terrain`].
+#[derive(Resource, Clone)]
+pub struct Arena {
+    pub walls: WallBehavior,
+    /// The run's [`GridShape`] laid out at the arena's size.
+    grid: Arc<dyn Grid + Send>,
+    /// Walls and shut doors.
+    blocked: HashSet<Position>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        let rules = Rules::default();
+        let (width, height) = rules.arena_size;
+        Self {
+            walls: rules.walls,
+            grid: GridShape::default().sized(width, height),
+            blocked: HashSet::new(),
+        }
+    }
+}
+
+impl Arena {
+    pub fn grid(&self) -> &dyn Grid {
+        &*self.grid
+    }
+
+    /// The number of columns on the board.
+    pub fn width(&self) -> u32 {
+        self.grid.size().0
+    }
+
+    /// The number of rows on the board.
+    pub fn height(&self) -> u32 {
+        self.grid.size().1
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        self.grid.contains(pos)
+    }
+
+    /// Whether `pos` is on the board but can't be entered.
+    pub fn blocks(&self, pos: Position) -> bool {
+        self.blocked.contains(&pos)
+    }
+
+    /// Cells just off the board that a snake could step onto from it.
+    pub fn border(&self) -> HashSet<Position> {
+        let grid = self.grid();
+        (0..self.width())
+            .flat_map(|column| (0..self.height()).map(move |row| (column, row)))
+            .flat_map(|(column, row)| grid.neighbors(grid.cell_position(column, row)))
+            .filter(|pos| !self.contains(*pos))
+            .collect()
+    }
+}
+
+/// Drawn around the board when [`Settings::border_walls`] is on.
+#[derive(Component)]
+struct BorderWall;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            place_border_walls
+                .after(sync_arena)
+                .run_if(resource_changed::<Arena>.or(resource_changed::<Settings>))
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Rebuilds the [`Arena`] at the size the rules ask for whenever the grid,
+/// the rules or the terrain change, which includes every new run and every
+/// wall rammed down.
+pub(crate) fn sync_arena(
+    mut arena: ResMut<Arena>,
+    shape: Res<GridShape>,
+    rules: Res<Rules>,
+    terrain: Res<Terrain>,
+) {
+    let (width, height) = rules.arena_size;
+    *arena = Arena {
+        walls: rules.walls,
+        grid: shape.sized(width, height),
+        blocked: terrain.blocked().collect(),
+    };
+}
+
+/// Lines the board with walls, unless the edges wrap around and there's
+/// nothing to run into.
+fn place_border_walls(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    settings: Res<Settings>,
+    walls: Query<Entity, With<BorderWall>>,
+) {
+    for entity in walls.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !settings.border_walls || arena.walls == WallBehavior::Wrap {
+        return;
+    }
+    for position in arena.border() {
+        commands.spawn((
+            Sprite::from_color(BORDER_WALL_COLOR, Vec2::ONE),
+            Transform::default(),
+            position,
+            Size::square(1.0),
+            BorderWall,
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::countdown;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::terrain::{Terrain, Tile};
+use crate::{
+    AppState, Food, GameMode, GameRng, GameSet, Player, Position, RunStarted, SnakeSegment,
+    StepClock,
+};
+
+const BANNER_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+const MIRROR_DURATION: Duration = Duration::from_secs(5);
+
+/// Width of the opening left in an obstacle row.
+const OBSTACLE_GAP: u32 = 3;
+
+/// Something that shakes up an endless run now and then.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArenaEvent {
+    /// Walls fill a row, except for a gap.
+    ObstacleRow,
+    /// Every piece of food moves somewhere else.
+    RelocateFood,
+    /// Left and right swap for a few seconds.
+    MirrorControls,
+}
+
+impl ArenaEvent {
+    /// Locale key of the banner announcing the event.
+    fn banner(self) -> &'static str {
+        match self {
+            Self::ObstacleRow => "event.obstacle_row",
+            Self::RelocateFood => "event.relocate_food",
+            Self::MirrorControls => "event.mirror_controls",
+        }
+    }
+}
+
+#[derive(Resource)]
+struct EventTimer(Timer);
+
+/// Counts down while the controls are mirrored.
+#[derive(Resource, Default)]
+pub struct MirroredControls(Option<Timer>);
+
+impl MirroredControls {
+    pub fn active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Component)]
+struct EventBanner(Timer);
+
+pub struct ArenaEventPlugin;
+
+impl Plugin for ArenaEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MirroredControls>()
+            .add_systems(OnEnter(AppState::Playing), start_event_timer)
+            .add_systems(OnExit(AppState::Playing), clear_mirror)
+            .add_systems(
+                Update,
+                (
+                    reset_events_on_respawn,
+                    trigger_events
+                        .in_set(GameSet::Spawning)
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    tick_mirror.run_if(in_state(PauseState::Running)),
+                    dismiss_banners,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless)),
+            );
+    }
+}
+
+fn start_event_timer(mut commands: Commands, rules: Res<Rules>) {
+    commands.insert_resource(EventTimer(Timer::new(
+        rules.event_interval,
+        TimerMode::Repeating,
+    )));
+}
+
+fn clear_mirror(mut mirror: ResMut<MirroredControls>) {
+    *mirror = MirroredControls::default();
+}
+
+/// A new run starts on a clear arena, with a full interval before the first
+/// event.
+fn reset_events_on_respawn(
+    mut run_reader: MessageReader<RunStarted>,
+    mut timer: ResMut<EventTimer>,
+    mut mirror: ResMut<MirroredControls>,
+    mut terrain: ResMut<Terrain>,
+) {
+    if run_reader.read().next().is_none() {
+        return;
+    }
+    timer.0.reset();
+    *mirror = MirroredControls::default();
+    *terrain = Terrain::default();
+}
+
+fn trigger_events(
+    mut commands: Commands,
+    step_clock: Res<StepClock>,
+    rules: Res<Rules>,
+    locale: Res<Locale>,
+    arena: Res<Arena>,
+    mut timer: ResMut<EventTimer>,
+    mut rng: ResMut<GameRng>,
+    mut terrain: ResMut<Terrain>,
+    mut mirror: ResMut<MirroredControls>,
+    mut food: Query<(Entity, &mut Position), With<Food>>,
+    segments: Query<&Position, (With<SnakeSegment>, Without<Food>)>,
+    heads: Query<&Position, (With<Player>, Without<Food>)>,
+) {
+    if !timer.0.tick(step_clock.delta()).just_finished() || rules.event_pool.is_empty() {
+        return;
+    }
+    let event = rules.event_pool[rng.rng.random_range(0..rules.event_pool.len())];
+    let grid = arena.grid();
+    let snake: Vec<Position> = segments.iter().copied().collect();
+
+    match event {
+        ArenaEvent::ObstacleRow => {
+            let head_row = heads
+                .iter()
+                .next()
+                .and_then(|head| grid.to_cell(*head))
+                .map(|(_, row)| row);
+            // Rows the snake is in or about to reach are left alone.
+            let rows: Vec<u32> = (0..arena.height())
+                .filter(|row| head_row.is_none_or(|head_row| head_row.abs_diff(*row) > 2))
+                .filter(|row| {
+                    !snake
+                        .iter()
+                        .any(|segment| grid.to_cell(*segment).is_some_and(|(_, r)| r == *row))
+                })
+                .collect();
+            if rows.is_empty() {
+                return;
+            }
+            let row = rows[rng.rng.random_range(0..rows.len())];
+            let gap = rng
+                .rng
+                .random_range(0..=arena.width().saturating_sub(OBSTACLE_GAP));
+            for column in
+                (0..arena.width()).filter(|column| !(gap..gap + OBSTACLE_GAP).contains(column))
+            {
+                let cell = grid.cell_position(column, row);
+                terrain.set(cell, Tile::Wall { hp: 1 });
+                for (entity, position) in food.iter() {
+                    if *position == cell {
+                        commands.entity(entity).despawn();
+                    }
+                }
+            }
+        }
+        ArenaEvent::RelocateFood => {
+            let mut taken = snake.clone();
+            for (_, mut position) in food.iter_mut() {
+                let free = (0..100)
+                    .map(|_| grid.random_cell(&mut rng.rng))
+                    .find(|cell| !taken.contains(cell) && !terrain.blocks(*cell));
+                if let Some(cell) = free {
+                    *position = cell;
+                    taken.push(cell);
+                }
+            }
+        }
+        ArenaEvent::MirrorControls => {
+            *mirror = MirroredControls(Some(Timer::new(MIRROR_DURATION, TimerMode::Once)));
+        }
+    }
+
+    commands.spawn((
+        Text::new(locale.get(event.banner())),
+        TextFont {
+            font_size: 48.0,
+            ..default()
+        },
+        TextColor(BANNER_COLOR),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        EventBanner(Timer::new(Duration::from_secs(2), TimerMode::Once)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn tick_mirror(time: Res<Time>, mut mirror: ResMut<MirroredControls>) {
+    if let Some(timer) = mirror.0.as_mut()
+        && timer.tick(time.delta()).just_finished()
+    {
+        mirror.0 = None;
+    }
+}
+
+fn dismiss_banners(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut EventBanner)>,
+) {
+    for (entity, mut banner) in banners.iter_mut() {
+        if banner.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
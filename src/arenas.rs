@@ -0,0 +1,134 @@
+use bevy::app::AppLabel;
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::env::headless_app;
+
+/// Names one of the games an [`ArenasPlugin`] hosts, and the sub-app it's
+/// played in.
+#[derive(AppLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ArenaId(pub usize);
+
+/// The arenas an [`ArenasPlugin`] added, in order.
+#[derive(Resource)]
+pub struct Arenas(pub Vec<ArenaId>);
+
+/// Several games of endless snake in one [`App`], for split-screen play (see
+/// [`crate::split_screen`]), AI-vs-AI exhibitions or a server hosting
+/// matches. Each arena is a headless sub-app with a world of its own, so its
+/// snakes, food, rules, timers and score belong to it alone. Every update of
+/// the host app steps every arena, each on its own clock. Reach an arena's
+/// world with `app.sub_app_mut(ArenaId(i))`.
+pub struct ArenasPlugin {
+    pub count: usize,
+}
+
+impl Plugin for ArenasPlugin {
+    fn build(&self, app: &mut App) {
+        let arenas = (0..self.count).map(ArenaId).collect::<Vec<_>>();
+        for id in &arenas {
+            app.insert_sub_app(*id, arena());
+        }
+        app.insert_resource(Arenas(arenas));
+    }
+}
+
+/// A fresh headless game, already on its way into a run.
+fn arena() -> SubApp {
+    let mut game = headless_app();
+    game.finish();
+    game.cleanup();
+    game.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Playing);
+    std::mem::take(game.main_mut())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+    use core::time::Duration;
+
+    use super::*;
+    use crate::arena::Arena;
+    use crate::rules::{Rules, WallBehavior};
+    use crate::{Direction, DirectionChangeRequest, Position, SnakeHead, SnakeTimer};
+
+    fn arenas_app(count: usize) -> App {
+        let mut app = App::new();
+        app.add_plugins(ArenasPlugin { count });
+        for index in 0..count {
+            app.sub_app_mut(ArenaId(index))
+                .world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .set_max_delta(Duration::from_secs(60));
+        }
+        advance(&mut app, Duration::ZERO);
+        app
+    }
+
+    /// One host update, with every arena's clock moved on by `delta`.
+    fn advance(app: &mut App, delta: Duration) {
+        for index in 0..app.world().resource::<Arenas>().0.len() {
+            app.sub_app_mut(ArenaId(index))
+                .insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+        }
+        app.update();
+    }
+
+    fn head(app: &mut App, id: ArenaId) -> (Entity, Position, Direction) {
+        let world = app.sub_app_mut(id).world_mut();
+        let mut heads = world.query::<(Entity, &Position, &SnakeHead)>();
+        let (snake, pos, head) = heads.single(world).unwrap();
+        (snake, *pos, head.direction)
+    }
+
+    #[test]
+    fn every_arena_plays_a_game_of_its_own() {
+        let mut app = arenas_app(2);
+        assert_eq!(app.world().resource::<Arenas>().0, [ArenaId(0), ArenaId(1)]);
+        let (snake, start, direction) = head(&mut app, ArenaId(0));
+        assert_eq!(head(&mut app, ArenaId(1)).1, start);
+
+        // Only the first arena's snake is told to turn.
+        let turn = if direction == Direction::Left {
+            Direction::Up
+        } else {
+            Direction::Left
+        };
+        app.sub_app_mut(ArenaId(0))
+            .world_mut()
+            .write_message(DirectionChangeRequest {
+                snake,
+                direction: turn,
+            });
+        let step = app
+            .sub_app(ArenaId(0))
+            .world()
+            .resource::<SnakeTimer>()
+            .timer
+            .remaining();
+        advance(&mut app, step);
+
+        let (_, first, turned) = head(&mut app, ArenaId(0));
+        let (_, second, straight) = head(&mut app, ArenaId(1));
+        assert_eq!((turned, straight), (turn, direction));
+        assert_ne!(first, start);
+        assert_ne!(second, start);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn each_arena_keeps_its_own_rules() {
+        let mut app = arenas_app(2);
+        app.sub_app_mut(ArenaId(1))
+            .world_mut()
+            .resource_mut::<Rules>()
+            .walls = WallBehavior::Wrap;
+        advance(&mut app, Duration::ZERO);
+
+        let walls = |app: &App, id| app.sub_app(id).world().resource::<Arena>().walls;
+        assert!(walls(&app, ArenaId(0)) == Rules::default().walls);
+        assert!(walls(&app, ArenaId(1)) == WallBehavior::Wrap);
+    }
+}
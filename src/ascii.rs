@@ -0,0 +1,186 @@
+use bevy::camera::visibility::RenderLayers;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::LineHeight;
+
+use crate::boss::{BossPart, PoisonFood, SweepMarker};
+use crate::coop::RevivePickup;
+use crate::grid::WindowLayout;
+use crate::hazard::Hazard;
+use crate::hunter::Hunter;
+use crate::items::Items;
+use crate::key::Key;
+use crate::powerup::PowerUp;
+use crate::rival::Rival;
+use crate::settings::{RenderStyle, Settings};
+use crate::terrain::Terrain;
+use crate::{AppState, Food, Player, Position, SnakeHead, SnakeSegment, SnakeSegments};
+
+/// Sprites stay on the default layer and the glyph grid gets its own, so
+/// switching styles only changes what the camera looks at.
+const SPRITE_LAYER: usize = 0;
+const ASCII_LAYER: usize = 1;
+
+const ASCII_COLOR: Color = Color::srgb(0.3, 1.0, 0.3);
+
+/// Fira Mono glyphs are 0.6em wide. Every cell is a glyph followed by a space,
+/// so a font size of `tile / (2 * 0.6)` makes the cells square.
+const CELLS_PER_EM: f32 = 1.2;
+
+#[derive(Component)]
+struct AsciiBoard;
+
+/// Heads of the player's snake, or of both snakes in co-op.
+type HeadQuery<'w, 's> = Query<'w, 's, &'static Position, With<Player>>;
+
+type SegmentQuery<'w, 's> =
+    Query<'w, 's, &'static Position, (With<SnakeSegment>, Without<SnakeHead>)>;
+
+/// Rival bodies, and where every snake's cells are.
+type RivalQueries<'w, 's> = (
+    Query<'w, 's, &'static SnakeSegments, With<Rival>>,
+    Query<'w, 's, &'static Position, With<SnakeSegment>>,
+);
+
+pub struct AsciiPlugin;
+
+impl Plugin for AsciiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_ascii_board)
+            .add_systems(
+                Update,
+                apply_render_style.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(
+                PostUpdate,
+                draw_ascii_board
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(|settings: Res<Settings>| settings.render_style == RenderStyle::Ascii),
+            );
+    }
+}
+
+fn apply_render_style(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<Camera2d>>,
+) {
+    let layers = match settings.render_style {
+        RenderStyle::Sprites | RenderStyle::Isometric => RenderLayers::layer(SPRITE_LAYER),
+        RenderStyle::Ascii => RenderLayers::layer(ASCII_LAYER),
+        // The board is drawn by the 3D camera; this one is left with the UI.
+        RenderStyle::ThreeD => RenderLayers::none(),
+    };
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(layers.clone());
+    }
+}
+
+fn spawn_ascii_board(mut commands: Commands) {
+    commands.spawn((
+        Text2d::default(),
+        TextColor(ASCII_COLOR),
+        Anchor::TOP_LEFT,
+        RenderLayers::layer(ASCII_LAYER),
+        AsciiBoard,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn draw_ascii_board(
+    layout: WindowLayout,
+    // Grouped to stay within Bevy's limit on system parameters.
+    (terrain, items): (Res<Terrain>, Res<Items>),
+    heads: HeadQuery,
+    segments: SegmentQuery,
+    food: Query<(&Position, &Food)>,
+    power_ups: Query<(&Position, &PowerUp)>,
+    keys: Query<&Position, With<Key>>,
+    hazards: Query<&Position, With<Hazard>>,
+    hunters: Query<&Position, With<Hunter>>,
+    boss: Query<&Position, With<BossPart>>,
+    poison: Query<&Position, With<PoisonFood>>,
+    sweep_markers: Query<&Position, With<SweepMarker>>,
+    (rivals, cells): RivalQueries,
+    revives: Query<&Position, With<RevivePickup>>,
+    mut boards: Query<(&mut Text2d, &mut TextFont, &mut Transform), With<AsciiBoard>>,
+) {
+    let Some(layout) = layout.get() else {
+        return;
+    };
+    let grid = layout.grid;
+    let (width, height) = grid.size();
+    let tile_size = layout.cell_size;
+    let cell_center = |column, row| layout.cell_to_world(grid.cell_position(column, row));
+    let row_height = cell_center(0, 1).y - cell_center(0, 0).y;
+
+    let rivals = rivals
+        .iter()
+        .flat_map(|segments| segments.iter())
+        .filter_map(|segment| cells.get(*segment).ok());
+    let mut rows = vec![vec!['.'; width as usize]; height as usize];
+    for (row, glyphs) in rows.iter_mut().enumerate() {
+        for (column, glyph) in glyphs.iter_mut().enumerate() {
+            if let Some(tile) = terrain.at(grid.cell_position(column as u32, row as u32)) {
+                *glyph = tile.glyph();
+            }
+        }
+    }
+    let glyphs = sweep_markers
+        .iter()
+        .map(|pos| (pos, '-'))
+        .chain(
+            food.iter()
+                .map(|(pos, food)| (pos, items.food(*food).glyph)),
+        )
+        .chain(poison.iter().map(|pos| (pos, '?')))
+        .chain(
+            power_ups
+                .iter()
+                .map(|(pos, power_up)| (pos, items.power_up(*power_up).glyph)),
+        )
+        .chain(revives.iter().map(|pos| (pos, '+')))
+        .chain(keys.iter().map(|pos| (pos, 'k')))
+        .chain(hazards.iter().map(|pos| (pos, 'x')))
+        .chain(hunters.iter().map(|pos| (pos, 'H')))
+        .chain(boss.iter().map(|pos| (pos, 'B')))
+        .chain(segments.iter().map(|pos| (pos, 'o')))
+        .chain(rivals.map(|pos| (pos, 'r')))
+        .chain(heads.iter().map(|pos| (pos, '@')));
+    for (pos, glyph) in glyphs {
+        // The head briefly sits outside the arena on the step that kills it.
+        if let Some((column, row)) = grid.to_cell(*pos) {
+            rows[row as usize][column as usize] = glyph;
+        }
+    }
+    let text = rows
+        .iter()
+        .zip(0..height)
+        .rev()
+        .map(|(glyphs, row)| {
+            // Hex rows are offset by half a cell, which is exactly one space.
+            let indent = if cell_center(0, row).x > cell_center(0, 0).x {
+                " "
+            } else {
+                ""
+            };
+            indent
+                .chars()
+                .chain(glyphs.iter().flat_map(|glyph| [*glyph, ' ']))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let top_left = Vec2::new(
+        // Glyphs sit in the left half of their cell; shift them to its middle.
+        cell_center(0, 0).x - tile_size / 4.0,
+        cell_center(0, height - 1).y + row_height / 2.0,
+    );
+    for (mut board, mut font, mut transform) in boards.iter_mut() {
+        board.0.clone_from(&text);
+        font.font_size = tile_size / CELLS_PER_EM;
+        font.line_height = LineHeight::Px(row_height);
+        transform.translation = top_left.extend(0.0);
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::boss::{BossPart, PoisonFood};
+use crate::hazard::Hazard;
+use crate::hunter::Hunter;
+use crate::powerup::Ram;
+use crate::replay::not_watching;
+use crate::rules::{Rules, WallBehavior};
+use crate::settings::Settings;
+use crate::tournament::Tournament;
+use crate::{
+    AppState, Direction, DirectionChangeRequest, GameSet, Player, Position, SnakeHead,
+    SnakeSegment, SnakeSegments, StepEvent, Terrain, Tile, allowed_turn, apply_direction_changes,
+};
+
+/// Everything that kills on contact: hazards, hunters, the boss and its
+/// poison, and every snake's cells, the player's own included.
+type Dangers<'w, 's> = Query<
+    'w,
+    's,
+    &'static Position,
+    Or<(
+        With<Hazard>,
+        With<Hunter>,
+        With<BossPart>,
+        With<PoisonFood>,
+        With<SnakeSegment>,
+    )>,
+>;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            steer_clear
+                .in_set(GameSet::Steering)
+                .before(apply_direction_changes)
+                .run_if(in_state(AppState::Playing))
+                .run_if(assisted)
+                .run_if(not_watching),
+        );
+    }
+}
+
+/// Whether the auto-turn assist is on for this run. Hardcore and tournament
+/// runs are ranked, so they're always played unaided.
+fn assisted(settings: Res<Settings>, rules: Res<Rules>, tournament: Res<Tournament>) -> bool {
+    settings.auto_turn && rules.hardcore.is_none() && tournament.entered().is_none()
+}
+
+/// Just before the snake moves, asks to turn it aside if the way it's heading
+/// would kill it outright and a turn to either side wouldn't. Where both
+/// sides are clear it turns the way the grid turns counterclockwise. The ask
+/// comes after the player's, so a turn the player made this step stands.
+fn steer_clear(
+    mut step_reader: MessageReader<StepEvent>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    ram: Res<Ram>,
+    rules: Res<Rules>,
+    heads: Query<(Entity, &SnakeHead, &Position, &SnakeSegments), With<Player>>,
+    positions: Query<&Position, Without<SnakeHead>>,
+    dangers: Dangers,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let grid = arena.grid();
+    for (snake, head, head_pos, segments) in heads.iter() {
+        let body = segments
+            .iter()
+            .filter_map(|segment| positions.get(*segment).ok().copied())
+            .collect::<Vec<_>>();
+        // The same checks the step itself makes, plus whatever else is on
+        // the board right now that kills on contact.
+        let fatal = |direction: Direction| {
+            let squeezed = grid
+                .flanking_cells(*head_pos, direction)
+                .is_some_and(|cells| cells.iter().all(|cell| body.contains(cell)));
+            let mut target = grid.step(*head_pos, direction);
+            if matches!(arena.walls, WallBehavior::Wrap) {
+                target = grid.wrap(target);
+            }
+            if !arena.contains(target) {
+                return matches!(arena.walls, WallBehavior::Kill);
+            }
+            let hits_wall = match terrain.at(target) {
+                Some(Tile::Wall { .. }) => !ram.active(),
+                Some(Tile::Door(_)) => true,
+                _ => false,
+            };
+            hits_wall
+                || (rules.self_collision && (squeezed || body.contains(&target)))
+                || dangers
+                    .iter()
+                    .any(|pos| *pos == target && !body.contains(pos))
+        };
+        if !fatal(head.direction) {
+            continue;
+        }
+        let neck = segments
+            .get(1)
+            .and_then(|neck| positions.get(*neck).ok())
+            .copied();
+        let current = head.direction;
+        let safe = [false, true]
+            .into_iter()
+            .filter_map(|clockwise| {
+                allowed_turn(
+                    grid,
+                    *head_pos,
+                    neck,
+                    current,
+                    grid.rotate(current, clockwise),
+                )
+            })
+            .find(|direction| *direction != current && !fatal(*direction));
+        if let Some(direction) = safe {
+            request_writer.write(DirectionChangeRequest { snake, direction });
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::locale::Locale;
+use crate::rules::Rules;
+use crate::settings::Settings;
+use crate::{AppState, GameMode, GameSet, SnakeDied};
+
+/// How long the menu sits untouched before the demo starts.
+const IDLE_TIME: Duration = Duration::from_secs(30);
+
+/// Whether the game on screen is the attract demo: a silent run steered by
+/// the autopilot, started when nobody has touched the menu for a while,
+/// like an arcade cabinet. It doesn't count toward anything.
+#[derive(Resource, Default)]
+pub struct Demo(bool);
+
+/// Time the menu has sat untouched.
+#[derive(Resource)]
+struct MenuIdle(Timer);
+
+impl Default for MenuIdle {
+    fn default() -> Self {
+        Self(Timer::new(IDLE_TIME, TimerMode::Once))
+    }
+}
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Demo>()
+            .init_resource::<MenuIdle>()
+            .add_systems(OnEnter(AppState::Menu), stop_demo)
+            .add_systems(
+                Update,
+                start_demo_when_idle.run_if(in_state(AppState::Menu)),
+            )
+            .add_systems(OnEnter(AppState::Playing), setup_banner.run_if(demo))
+            .add_systems(
+                Update,
+                end_demo
+                    .after(GameSet::Spawning)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(demo),
+            );
+    }
+}
+
+pub fn demo(demo: Res<Demo>) -> bool {
+    demo.0
+}
+
+pub fn not_demo(demo: Res<Demo>) -> bool {
+    !demo.0
+}
+
+fn stop_demo(mut demo: ResMut<Demo>, mut idle: ResMut<MenuIdle>) {
+    demo.0 = false;
+    idle.0.reset();
+}
+
+/// Whether anything was pressed this frame: a key, a mouse button, a finger
+/// or a gamepad button.
+fn anything_pressed(
+    keyboard_input: &ButtonInput<KeyCode>,
+    mouse_input: &ButtonInput<MouseButton>,
+    touches: &Touches,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || touches.any_just_pressed()
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.get_just_pressed().next().is_some())
+}
+
+fn start_demo_when_idle(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    settings: Res<Settings>,
+    mut idle: ResMut<MenuIdle>,
+    mut demo: ResMut<Demo>,
+    mut mode: ResMut<GameMode>,
+    mut rules: ResMut<Rules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if anything_pressed(&keyboard_input, &mouse_input, &touches, &gamepads) {
+        idle.0.reset();
+        return;
+    }
+    if !idle.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    demo.0 = true;
+    *rules = Rules::default();
+    rules.self_collision = settings.self_collision;
+    *mode = GameMode::Endless;
+    next_state.set(AppState::Playing);
+}
+
+fn setup_banner(mut commands: Commands, locale: Res<Locale>) {
+    commands.spawn((
+        Text::new(locale.get("attract.banner")),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.85, 0.3)),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(24.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Any press hands the game back to the player on the menu, and so does the
+/// demo snake dying, rather than waiting on the results.
+fn end_demo(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    if died || anything_pressed(&keyboard_input, &mouse_input, &touches, &gamepads) {
+        next_state.set(AppState::Menu);
+    }
+}
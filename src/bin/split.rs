@@ -0,0 +1,3 @@
+fn main() {
+    snake::split_screen::main();
+}
@@ -0,0 +1,3 @@
+fn main() -> std::io::Result<()> {
+    snake::tui::run()
+}
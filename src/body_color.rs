@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::profile::Profile;
+use crate::rules::Rules;
+use crate::settings::Settings;
+use crate::shop::Skin;
+use crate::{AppState, GameMode, Player, SnakeSegments};
+
+/// Spread of brightness from the neck to the tail: the neck is lit by half
+/// of it and the tail darkened by the other half.
+const GRADIENT: f32 = 0.2;
+
+/// Degrees of hue between neighbouring segments of a rainbow snake.
+const RAINBOW_STEP: f32 = 24.0;
+/// Degrees per second the rainbow ripples down the body.
+const RAINBOW_SPEED: f32 = 120.0;
+
+/// A body segment's own color, as its skin set it, and the shade of it last
+/// shown, to tell when something else recolors the segment.
+#[derive(Component)]
+struct Shade {
+    base: Color,
+    shown: Color,
+}
+
+pub struct BodyColorPlugin;
+
+impl Plugin for BodyColorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, shade_body.run_if(in_state(AppState::Playing)));
+    }
+}
+
+/// Whether the player's snake wears the rainbow skin, which, like the other
+/// skins, is left out of puzzles, team matches and co-op.
+fn rainbow(profile: &Profile, mode: GameMode, rules: &Rules) -> bool {
+    profile.skin == Skin::Rainbow
+        && mode == GameMode::Endless
+        && rules.teams.is_none()
+        && !rules.coop
+}
+
+/// Fades the body from light at the neck to dark at the tail, so the
+/// snake's direction and length read at a glance. Recomputed every frame,
+/// so it follows the snake as it grows and shrinks. A rainbow snake's
+/// segments each cycle through the hues, a step behind the one before.
+fn shade_body(
+    mut commands: Commands,
+    time: Res<Time>,
+    profile: Res<Profile>,
+    mode: Res<GameMode>,
+    rules: Res<Rules>,
+    settings: Res<Settings>,
+    bodies: Query<(&SnakeSegments, Has<Player>)>,
+    mut sprites: Query<(&mut Sprite, Option<&mut Shade>)>,
+) {
+    let rainbow = rainbow(&profile, *mode, &rules);
+    let flow = if settings.reduced_motion {
+        0.0
+    } else {
+        time.elapsed_secs() * RAINBOW_SPEED
+    };
+    for (segments, player) in bodies.iter() {
+        let body = segments.get(1..).unwrap_or_default();
+        let last = body.len().saturating_sub(1).max(1) as f32;
+        for (index, segment) in body.iter().enumerate() {
+            let Ok((mut sprite, shade)) = sprites.get_mut(*segment) else {
+                continue;
+            };
+            let base = match &shade {
+                _ if rainbow && player => {
+                    let hue = (flow - index as f32 * RAINBOW_STEP).rem_euclid(360.0);
+                    Color::hsl(hue, 0.85, 0.55)
+                }
+                Some(shade) if sprite.color == shade.shown => shade.base,
+                _ => sprite.color,
+            };
+            let amount = GRADIENT * (0.5 - index as f32 / last);
+            let shown = if amount >= 0.0 {
+                base.lighter(amount)
+            } else {
+                base.darker(-amount)
+            };
+            sprite.color = shown;
+            match shade {
+                Some(mut shade) => *shade = Shade { base, shown },
+                None => {
+                    commands.entity(*segment).insert(Shade { base, shown });
+                }
+            }
+        }
+    }
+}
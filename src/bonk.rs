@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::settings::Settings;
+use crate::sound::Bus;
+use crate::{AppState, attract};
+
+/// A low, short knock.
+const BONK_PITCH: f32 = 110.0;
+const BONK_LENGTH: Duration = Duration::from_millis(120);
+
+/// The snake bumped its head on the edge of the board, with walls set to
+/// [`crate::rules::WallBehavior::Bonk`].
+#[derive(Message)]
+pub struct WallBonked;
+
+pub struct BonkPlugin;
+
+impl Plugin for BonkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<WallBonked>().add_systems(
+            Update,
+            play_bonk
+                .run_if(in_state(AppState::Playing))
+                .run_if(attract::not_demo),
+        );
+    }
+}
+
+fn play_bonk(
+    mut commands: Commands,
+    mut bonk_reader: MessageReader<WallBonked>,
+    settings: Res<Settings>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    if bonk_reader.read().next().is_none() {
+        return;
+    }
+    commands.spawn((
+        AudioPlayer(pitches.add(Pitch::new(BONK_PITCH, BONK_LENGTH))),
+        Bus::Sfx,
+        Bus::Sfx.play_once(&settings),
+    ));
+}
@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::grid::Grid;
+use crate::hazard::{Hazard, spawn_hazard};
+use crate::terrain::Terrain;
+use crate::{
+    AppState, DeathCause, GameSet, Ground, Player, Position, Size, SnakeDied, SnakeSegments,
+    StepEvent, snake_movement,
+};
+
+const BOSS_COLOR: Color = Color::srgb(0.45, 0.2, 0.55);
+pub const POISON_COLOR: Color = Color::srgb(0.4, 0.9, 0.2);
+const SWEEP_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.35);
+const HEALTH_BAR_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const HEALTH_COLOR: Color = Color::srgb(0.85, 0.15, 0.2);
+
+const BOSS_HEALTH: u32 = 3;
+
+/// The boss takes a turn on every this many snake steps.
+const BOSS_PACE: u32 = 2;
+
+/// The boss drops a hazard every this many turns, and halfway between drops
+/// it sweeps a row.
+const ATTACK_INTERVAL: u32 = 6;
+
+/// Cells the boss covers, relative to its core.
+const FOOTPRINT: [(i32, i32); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+/// A creature several cells large that chases the snake. It's hurt by
+/// eating poison and by running into walls.
+#[derive(Component, Clone, Copy)]
+pub struct Boss {
+    health: u32,
+    /// Snake steps since its last turn.
+    rest: u32,
+    turns: u32,
+    /// The row it's about to sweep on its next turn.
+    sweep: Option<i32>,
+}
+
+impl Default for Boss {
+    fn default() -> Self {
+        Self {
+            health: BOSS_HEALTH,
+            rest: 0,
+            turns: 0,
+            sweep: None,
+        }
+    }
+}
+
+/// One cell of the boss, offset from the core that carries [`Boss`].
+#[derive(Component)]
+pub struct BossPart((i32, i32));
+
+/// Deadly to the snake; the boss is lured into eating it.
+#[derive(Component)]
+pub struct PoisonFood;
+
+/// Marks the row the boss is about to sweep.
+#[derive(Component)]
+pub struct SweepMarker;
+
+#[derive(Component)]
+struct HealthBar;
+
+#[derive(Component)]
+struct HealthFill;
+
+type PartQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static BossPart, &'static mut Position), Without<Boss>>;
+
+type PoisonQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static Position), (With<PoisonFood>, Without<BossPart>)>;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_health_bar)
+            .add_systems(
+                Update,
+                (
+                    boss_turn.in_set(GameSet::Movement).after(snake_movement),
+                    mark_sweep.after(boss_turn),
+                    update_health_bar.after(boss_turn),
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn offset(pos: Position, (dx, dy): (i32, i32)) -> Position {
+    Position {
+        x: pos.x + dx,
+        y: pos.y + dy,
+    }
+}
+
+fn footprint(core: Position) -> [Position; 4] {
+    FOOTPRINT.map(|cell| offset(core, cell))
+}
+
+pub fn spawn_boss(commands: &mut Commands, core: Position, boss: Boss) {
+    for cell in FOOTPRINT {
+        let mut part = commands.spawn((
+            Sprite::from_color(BOSS_COLOR, Vec2::ONE),
+            Transform::default(),
+            offset(core, cell),
+            Size::square(1.0),
+            BossPart(cell),
+            DespawnOnExit(AppState::Playing),
+        ));
+        if cell == (0, 0) {
+            part.insert(boss);
+        }
+    }
+}
+
+pub fn spawn_poison(commands: &mut Commands, position: Position) {
+    commands.spawn((
+        Sprite::from_color(POISON_COLOR, Vec2::ONE),
+        Transform::default(),
+        position,
+        Size::square(0.6),
+        PoisonFood,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Lines the row the boss is about to sweep with markers, across the whole
+/// arena, once it has picked one.
+fn mark_sweep(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    bosses: Query<&Boss>,
+    markers: Query<(), With<SweepMarker>>,
+) {
+    let Some(row) = bosses.iter().find_map(|boss| boss.sweep) else {
+        return;
+    };
+    if !markers.is_empty() {
+        return;
+    }
+    for x in 0..arena.width() as i32 {
+        commands.spawn((
+            Sprite::from_color(SWEEP_COLOR, Vec2::ONE),
+            Transform::default(),
+            Position { x, y: row },
+            Size::square(1.0),
+            Ground,
+            SweepMarker,
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+/// Of the four cells next to `core`, the one that brings the boss closest to
+/// `target`, or `core` itself if none is closer.
+fn chase(grid: &dyn Grid, core: Position, target: Position) -> Position {
+    let distance = |core: Position| {
+        let center = (grid.to_world(core, 1.0) + grid.to_world(offset(core, (1, 1)), 1.0)) / 2.0;
+        center.distance_squared(grid.to_world(target, 1.0))
+    };
+    [(-1, 0), (0, 1), (1, 0), (0, -1)]
+        .map(|step| offset(core, step))
+        .into_iter()
+        .filter(|next| footprint(*next).iter().all(|cell| grid.contains(*cell)))
+        .fold(core, |best, next| {
+            if distance(next) < distance(best) {
+                next
+            } else {
+                best
+            }
+        })
+}
+
+/// Every few steps the boss attacks, then moves towards the snake's head.
+/// Touching any part of it is fatal.
+fn boss_turn(
+    mut commands: Commands,
+    mut step_reader: MessageReader<StepEvent>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    bodies: Query<(Entity, &SnakeSegments), With<Player>>,
+    mut bosses: Query<(Entity, &mut Boss, &mut Position), With<BossPart>>,
+    mut parts: PartQuery,
+    poison: PoisonQuery,
+    markers: Query<Entity, With<SweepMarker>>,
+    positions: Query<&Position, Without<BossPart>>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let Ok((core_entity, mut boss, mut core)) = bosses.single_mut() else {
+        return;
+    };
+    let Ok((snake_entity, segments)) = bodies.single() else {
+        return;
+    };
+    let snake: Vec<Position> = segments
+        .iter()
+        .filter_map(|e| positions.get(*e).ok())
+        .copied()
+        .collect();
+    let Some(head) = snake.first().copied() else {
+        return;
+    };
+
+    boss.rest += 1;
+    if boss.rest >= BOSS_PACE {
+        boss.rest = 0;
+        if let Some(row) = boss.sweep.take() {
+            for marker in markers.iter() {
+                commands.entity(marker).despawn();
+            }
+            if snake.iter().any(|segment| segment.y == row) {
+                game_over_writer.write(SnakeDied {
+                    snake: snake_entity,
+                    cause: DeathCause::Boss,
+                });
+            }
+        }
+
+        boss.turns += 1;
+        if boss.turns.is_multiple_of(ATTACK_INTERVAL) {
+            spawn_hazard(&mut commands, *core, Hazard::stationary());
+        } else if boss.turns % ATTACK_INTERVAL == ATTACK_INTERVAL / 2 {
+            boss.sweep = Some(head.y);
+        }
+
+        let next = chase(arena.grid(), *core, head);
+        let bumped = footprint(next).iter().any(|cell| terrain.blocks(*cell));
+        if bumped {
+            boss.health = boss.health.saturating_sub(1);
+        } else {
+            *core = next;
+        }
+        for (entity, position) in poison.iter() {
+            if footprint(*core).contains(position) {
+                commands.entity(entity).despawn();
+                boss.health = boss.health.saturating_sub(1);
+            }
+        }
+    }
+
+    for (_, part, mut position) in parts.iter_mut() {
+        *position = offset(*core, part.0);
+    }
+
+    if boss.health == 0 {
+        commands.entity(core_entity).despawn();
+        for (entity, _, _) in parts.iter() {
+            commands.entity(entity).despawn();
+        }
+        for marker in markers.iter() {
+            commands.entity(marker).despawn();
+        }
+        return;
+    }
+    if snake
+        .iter()
+        .any(|segment| footprint(*core).contains(segment))
+        || poison.iter().any(|(_, position)| *position == head)
+    {
+        game_over_writer.write(SnakeDied {
+            snake: snake_entity,
+            cause: DeathCause::Boss,
+        });
+    }
+}
+
+fn spawn_health_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            HealthBar,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Node {
+                    width: Val::Px(300.0),
+                    height: Val::Px(14.0),
+                    ..default()
+                },
+                BackgroundColor(HEALTH_BAR_COLOR),
+            ))
+            .with_child((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(HEALTH_COLOR),
+                HealthFill,
+            ));
+        });
+}
+
+fn update_health_bar(
+    bosses: Query<&Boss>,
+    mut bars: Query<&mut Visibility, With<HealthBar>>,
+    mut fills: Query<&mut Node, With<HealthFill>>,
+) {
+    let boss = bosses.single().ok();
+    for mut visibility in bars.iter_mut() {
+        visibility.set_if_neq(if boss.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    if let Some(boss) = boss {
+        let width = Val::Percent(100.0 * boss.health as f32 / BOSS_HEALTH as f32);
+        for mut fill in fills.iter_mut() {
+            if fill.width != width {
+                fill.width = width;
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use core::time::Duration;
+use rhai::{AST, Array, Dynamic, Engine, Map, Scope};
+
+use crate::arena::Arena;
+use crate::attract;
+use crate::grid::Grid;
+use crate::hazard::Hazard;
+use crate::pause::PauseState;
+use crate::replay;
+use crate::settings::Settings;
+use crate::storage;
+use crate::terrain::Terrain;
+use crate::tournament::Tournament;
+use crate::{
+    Direction, DirectionChangeRequest, Food, GameMode, GameSet, Player, Position, SnakeHead,
+    SnakeSegment, SnakeSegments, StepEvent, snake_timer_tick,
+};
+
+/// Steers rivals instead of their built-in food chasing.
+const RIVAL_SCRIPT: &str = "bots/rival.rhai";
+/// Steers the player's snake while the autopilot is on.
+const AUTOPILOT_SCRIPT: &str = "bots/autopilot.rhai";
+
+/// How often the scripts are checked for edits.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Cap on the work a script does per step, so a runaway loop can't hang the
+/// game.
+const MAX_OPERATIONS: u64 = 200_000;
+
+/// Everything on the board a snake can run into: every snake's cells and
+/// the hazards.
+type Obstacles<'w, 's> =
+    Query<'w, 's, (Entity, &'static Position), Or<(With<SnakeSegment>, With<Hazard>)>>;
+
+/// Which script is asked.
+#[derive(Clone, Copy)]
+pub enum Bot {
+    Rival,
+    Autopilot,
+}
+
+/// A Rhai script with a `think(board)` function that's called before every
+/// step and returns the direction to take, like `"left"` or `"up_right"`.
+/// `board` holds `width`, `height`, `head`, `direction`, `length`, `food`,
+/// `blocked` and `moves`, the cell each allowed direction leads to and
+/// whether it's `open`. Cells are `#{x, y}` maps.
+#[derive(Default)]
+struct Brain {
+    /// The script as last read, to tell when it's been edited.
+    source: Option<String>,
+    ast: Option<AST>,
+}
+
+/// The bot scripts, reloaded whenever they change on disk so a bot can be
+/// swapped mid-game.
+#[derive(Resource)]
+pub struct Brains {
+    engine: Engine,
+    rival: Brain,
+    autopilot: Brain,
+}
+
+impl Default for Brains {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        Self {
+            engine,
+            rival: Brain::default(),
+            autopilot: Brain::default(),
+        }
+    }
+}
+
+impl Brains {
+    fn brain(&self, bot: Bot) -> &Brain {
+        match bot {
+            Bot::Rival => &self.rival,
+            Bot::Autopilot => &self.autopilot,
+        }
+    }
+
+    /// The direction `bot` picks on the board `board` builds, or `None`
+    /// without a working script.
+    pub fn think(&self, bot: Bot, board: impl FnOnce() -> Map) -> Option<Direction> {
+        let ast = self.brain(bot).ast.as_ref()?;
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), ast, "think", (board(),));
+        let name = match result {
+            Ok(name) => name.into_string().unwrap_or_default(),
+            Err(err) => {
+                warn!("Failed to run {}: {}", bot.path(), err);
+                return None;
+            }
+        };
+        let direction = Direction::ALL
+            .into_iter()
+            .find(|direction| direction_name(*direction) == name);
+        if direction.is_none() {
+            warn!("{} picked no direction: {:?}", bot.path(), name);
+        }
+        direction
+    }
+}
+
+impl Bot {
+    fn path(self) -> &'static str {
+        match self {
+            Self::Rival => RIVAL_SCRIPT,
+            Self::Autopilot => AUTOPILOT_SCRIPT,
+        }
+    }
+}
+
+pub struct BrainPlugin;
+
+impl Plugin for BrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brains>()
+            .add_systems(Startup, reload_brains)
+            .add_systems(Update, reload_brains.run_if(on_timer(RELOAD_INTERVAL)))
+            .add_systems(
+                Update,
+                autopilot
+                    .in_set(GameSet::Input)
+                    .after(snake_timer_tick)
+                    .run_if(in_state(PauseState::Running))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(
+                        autopilot_enabled
+                            .and(replay::not_watching)
+                            .or(attract::demo),
+                    ),
+            );
+    }
+}
+
+/// Whether the autopilot steers this run. Tournament runs are ranked, so
+/// they're always played by hand.
+pub(crate) fn autopilot_enabled(settings: Res<Settings>, tournament: Res<Tournament>) -> bool {
+    settings.autopilot && tournament.entered().is_none()
+}
+
+/// Recompiles the scripts that were edited, added or removed since the last
+/// look. A script that doesn't compile leaves its bot switched off.
+fn reload_brains(mut brains: ResMut<Brains>) {
+    let brains = &mut *brains;
+    for (bot, brain) in [
+        (Bot::Rival, &mut brains.rival),
+        (Bot::Autopilot, &mut brains.autopilot),
+    ] {
+        let source = storage::read(bot.path());
+        if source == brain.source {
+            continue;
+        }
+        brain.ast = source
+            .as_deref()
+            .and_then(|source| match brains.engine.compile(source) {
+                Ok(ast) => {
+                    info!("Loaded {}", bot.path());
+                    Some(ast)
+                }
+                Err(err) => {
+                    warn!("Failed to compile {}: {}", bot.path(), err);
+                    None
+                }
+            });
+        brain.source = source;
+    }
+}
+
+/// Lets the autopilot script steer the player's snake just before it steps.
+fn autopilot(
+    mut step_reader: MessageReader<StepEvent>,
+    brains: Res<Brains>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<(Entity, &SnakeHead, &Position, &SnakeSegments), With<Player>>,
+    bodies: Obstacles,
+    food: Query<&Position, With<Food>>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let Ok((snake, head, head_pos, segments)) = heads.single() else {
+        return;
+    };
+    let grid = arena.grid();
+    // The tail moves out of the way on this step.
+    let blocked: Vec<Position> = bodies
+        .iter()
+        .filter(|(entity, _)| *entity != snake && Some(entity) != segments.last())
+        .map(|(_, pos)| *pos)
+        .collect();
+    let open =
+        |pos: &Position| grid.contains(*pos) && !terrain.blocks(*pos) && !blocked.contains(pos);
+    let food: Vec<Position> = food.iter().copied().collect();
+    let Some(wanted) = brains.think(Bot::Autopilot, || {
+        board(
+            grid,
+            *head_pos,
+            head.direction,
+            segments.len(),
+            &food,
+            &blocked,
+            &open,
+        )
+    }) else {
+        return;
+    };
+    request_writer.write(DirectionChangeRequest {
+        snake,
+        direction: wanted,
+    });
+}
+
+/// What a script sees of the board, from the point of view of the snake
+/// whose head is at `head`.
+pub fn board(
+    grid: &dyn Grid,
+    head: Position,
+    direction: Direction,
+    length: usize,
+    food: &[Position],
+    blocked: &[Position],
+    open: &impl Fn(&Position) -> bool,
+) -> Map {
+    let moves: Map = Direction::ALL
+        .into_iter()
+        .filter(|direction| grid.steer(*direction, *direction) == *direction)
+        .map(|direction| {
+            let next = grid.step(head, direction);
+            let mut cell = cell(next);
+            cell.insert("open".into(), open(&next).into());
+            (direction_name(direction).into(), cell.into())
+        })
+        .collect();
+    let cells =
+        |cells: &[Position]| -> Array { cells.iter().map(|pos| cell(*pos).into()).collect() };
+    let (width, height) = grid.size();
+    Map::from([
+        ("width".into(), (width as i64).into()),
+        ("height".into(), (height as i64).into()),
+        ("head".into(), cell(head).into()),
+        ("direction".into(), direction_name(direction).into()),
+        ("length".into(), (length as i64).into()),
+        ("food".into(), cells(food).into()),
+        ("blocked".into(), cells(blocked).into()),
+        ("moves".into(), moves.into()),
+    ])
+}
+
+fn cell(pos: Position) -> Map {
+    Map::from([
+        ("x".into(), (pos.x as i64).into()),
+        ("y".into(), (pos.y as i64).into()),
+    ])
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Left => "left",
+        Direction::Up => "up",
+        Direction::Right => "right",
+        Direction::Down => "down",
+        Direction::UpLeft => "up_left",
+        Direction::UpRight => "up_right",
+        Direction::DownLeft => "down_left",
+        Direction::DownRight => "down_right",
+    }
+}
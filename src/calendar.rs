@@ -0,0 +1,70 @@
+//! Wall-clock dates, all in UTC so every build agrees on what day it is.
+
+/// Milliseconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn now_millis() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// Year, month from 1 to 12 and day of the month, for days since the Unix
+/// epoch. After Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Shifts the calendar to start in March, so leap days fall at the end of
+    // the year.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+/// "YYYY-MM-DD HH:MM" for milliseconds since the Unix epoch.
+pub fn date(millis: u64) -> String {
+    let minutes = millis / 60_000;
+    let (year, month, day) = civil_from_days((minutes / (24 * 60)) as i64);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60 % 24,
+        minutes % 60
+    )
+}
+
+/// Month of the year, from 1 to 12, and day of the month.
+pub fn today() -> (u32, u32) {
+    let (_, month, day) = civil_from_days((now_millis() / 86_400_000) as i64);
+    (month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_formats_epoch_and_leap_days() {
+        assert_eq!(date(0), "1970-01-01 00:00");
+        // 2024-02-29 13:45 UTC.
+        assert_eq!(date(1_709_214_300_000), "2024-02-29 13:45");
+        // The day after, in a year that isn't a leap year.
+        assert_eq!(civil_from_days(19_417), (2023, 3, 1));
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+/// Puts `text` on the system clipboard, logging a warning if it can't.
+pub fn copy(text: &str) {
+    if let Err(err) = write(text) {
+        warn!("Failed to copy to the clipboard: {}", err);
+    }
+}
+
+/// Desktops have no clipboard API in the standard library, so this hands
+/// the text to whichever clipboard tool the platform ships with.
+#[cfg(not(target_arch = "wasm32"))]
+fn write(text: &str) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const TOOLS: [(&str, &[&str]); 5] = [
+        ("pbcopy", &[]),
+        ("clip", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+    for (tool, args) in TOOLS {
+        let Ok(mut child) = Command::new(tool).args(args).stdin(Stdio::piped()).spawn() else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|err| err.to_string())?;
+        }
+        if child.wait().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    Err("no clipboard tool found".to_string())
+}
+
+/// The browser copies in the background; there's nothing to wait for.
+#[cfg(target_arch = "wasm32")]
+fn write(text: &str) -> Result<(), String> {
+    let window = web_sys::window().ok_or_else(|| "no window".to_string())?;
+    let _ = window.navigator().clipboard().write_text(text);
+    Ok(())
+}
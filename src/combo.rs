@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::countdown;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::results::RunStats;
+use crate::rules::Rules;
+use crate::{
+    AppState, GameMode, GameSet, GameState, PlayerMeals, RestartEvent, SnakeDied, StepClock,
+};
+
+const BAR_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const FILL_COLOR: Color = Color::srgb(1.0, 0.75, 0.2);
+
+/// How much game time the snake has after eating to chain the next food.
+const COMBO_WINDOW: Duration = Duration::from_secs(3);
+
+const MAX_MULTIPLIER: u32 = 5;
+
+/// Food eaten in quick succession scores more. Each meal inside the window
+/// raises the multiplier and starts the window over.
+#[derive(Resource)]
+pub struct Combo {
+    pub multiplier: u32,
+    window: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        let mut window = Timer::new(COMBO_WINDOW, TimerMode::Once);
+        window.finish();
+        Self {
+            multiplier: 1,
+            window,
+        }
+    }
+}
+
+impl Combo {
+    fn active(&self) -> bool {
+        !self.window.is_finished()
+    }
+}
+
+#[derive(Component)]
+struct ComboFill;
+
+#[derive(Component)]
+struct ComboText;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Combo>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (reset_combo, spawn_combo_bar).run_if(resource_equals(GameMode::Endless)),
+            )
+            .add_systems(
+                Update,
+                (
+                    chain_combo.after(GameSet::Collisions),
+                    tick_combo
+                        .after(chain_combo)
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    update_combo_bar.after(tick_combo),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless)),
+            );
+    }
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+/// Scores the bonus for chained food on top of what [`score_food`] gave,
+/// and drops the combo when the run ends.
+fn chain_combo(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    rules: Res<Rules>,
+    mut combo: ResMut<Combo>,
+    mut game_state: ResMut<GameState>,
+    mut stats: ResMut<RunStats>,
+) {
+    for growth in growth_reader.read() {
+        combo.multiplier = if combo.active() {
+            (combo.multiplier + 1).min(MAX_MULTIPLIER)
+        } else {
+            1
+        };
+        let bonus = rules.food_points * growth.points * (combo.multiplier - 1);
+        game_state.score += bonus;
+        stats.combo_bonus += bonus;
+        stats.max_combo = stats.max_combo.max(combo.multiplier);
+        combo.window.reset();
+    }
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        *combo = Combo::default();
+    }
+}
+
+/// Runs the window down on [`StepClock`] time, as the bonus it decides
+/// goes into the score.
+fn tick_combo(step_clock: Res<StepClock>, mut combo: ResMut<Combo>) {
+    if combo.window.tick(step_clock.delta()).just_finished() {
+        combo.multiplier = 1;
+    }
+}
+
+fn spawn_combo_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(52.0),
+                left: Val::Px(10.0),
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    width: Val::Px(160.0),
+                    height: Val::Px(6.0),
+                    ..default()
+                },
+                BackgroundColor(BAR_COLOR),
+            ))
+            .with_child((
+                Node {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(FILL_COLOR),
+                ComboFill,
+            ));
+            row.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(FILL_COLOR),
+                ComboText,
+            ));
+        });
+}
+
+/// The fill shows what's left of the window, and the multiplier sits next
+/// to it once there's a chain going.
+fn update_combo_bar(
+    combo: Res<Combo>,
+    locale: Res<Locale>,
+    mut fills: Query<&mut Node, With<ComboFill>>,
+    mut texts: Query<&mut Text, With<ComboText>>,
+) {
+    let width = if combo.active() {
+        Val::Percent(100.0 * combo.window.fraction_remaining())
+    } else {
+        Val::Percent(0.0)
+    };
+    for mut fill in fills.iter_mut() {
+        if fill.width != width {
+            fill.width = width;
+        }
+    }
+    if !combo.is_changed() {
+        return;
+    }
+    let text = if combo.multiplier > 1 {
+        locale.format("hud.combo", &[("multiplier", &combo.multiplier)])
+    } else {
+        String::new()
+    };
+    for mut line in texts.iter_mut() {
+        if line.0 != text {
+            line.0 = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::{DeathCause, Food, FoodEaten, Player, Position, SnakeTimer};
+
+    /// An endless run with no food on the board, so only the meals a test
+    /// serves count, and a snake that takes a minute over each step.
+    fn combo_app(food_points: u32) -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            food_points,
+            food_count: 0,
+            ..default()
+        })
+        .insert_resource(SnakeTimer {
+            base: Duration::from_secs(60),
+            ..default()
+        })
+        .insert_resource(Locale::load("en"))
+        .init_resource::<RunStats>()
+        .add_plugins(ComboPlugin);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    fn player(app: &mut App) -> Entity {
+        let world = app.world_mut();
+        world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .unwrap()
+    }
+
+    /// The player eats a meal worth `points` and a frame goes by.
+    fn eat(app: &mut App, points: u32) {
+        let snake = player(app);
+        app.world_mut().write_message(FoodEaten {
+            snake,
+            kind: Food::BASIC,
+            position: Position { x: 0, y: 0 },
+            segments: 1,
+            points,
+        });
+        advance(app, Duration::ZERO);
+    }
+
+    fn advance(app: &mut App, delta: Duration) {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+        app.update();
+    }
+
+    fn multiplier(app: &App) -> u32 {
+        app.world().resource::<Combo>().multiplier
+    }
+
+    #[test]
+    fn chained_meals_raise_the_multiplier_up_to_the_cap() {
+        let mut app = combo_app(1);
+        let multipliers = (0..MAX_MULTIPLIER + 2)
+            .map(|_| {
+                eat(&mut app, 1);
+                multiplier(&app)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(multipliers, [1, 2, 3, 4, 5, 5, 5]);
+    }
+
+    #[test]
+    fn each_chained_meal_scores_its_points_again_per_step_up() {
+        let mut app = combo_app(3);
+        for _ in 0..3 {
+            eat(&mut app, 2);
+        }
+        // 3 * 2 for the meals themselves, then nothing, 6 and 12 on top.
+        assert_eq!(app.world().resource::<RunStats>().combo_bonus, 18);
+        assert_eq!(app.world().resource::<GameState>().score, 36);
+    }
+
+    #[test]
+    fn the_window_only_runs_down_while_the_snake_steps() {
+        let mut app = combo_app(1);
+        eat(&mut app, 1);
+        eat(&mut app, 1);
+        // Longer than the window, but without a step taken.
+        advance(&mut app, COMBO_WINDOW * 2);
+        assert_eq!(multiplier(&app), 2);
+
+        let step = app.world().resource::<SnakeTimer>().timer.remaining();
+        advance(&mut app, step);
+        assert_eq!(multiplier(&app), 1);
+        eat(&mut app, 1);
+        assert_eq!(multiplier(&app), 1);
+    }
+
+    #[test]
+    fn dying_or_restarting_drops_the_combo() {
+        let mut app = combo_app(1);
+        eat(&mut app, 1);
+        eat(&mut app, 1);
+        let snake = player(&mut app);
+        app.world_mut().write_message(SnakeDied {
+            snake,
+            cause: DeathCause::Wall,
+        });
+        advance(&mut app, Duration::ZERO);
+        assert_eq!(multiplier(&app), 1);
+
+        eat(&mut app, 1);
+        eat(&mut app, 1);
+        app.world_mut()
+            .write_message(RestartEvent { same_seed: false });
+        advance(&mut app, Duration::ZERO);
+        assert_eq!(multiplier(&app), 1);
+    }
+}
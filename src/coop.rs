@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::{Arena, sync_arena};
+use crate::grid::Grid;
+use crate::invulnerable::Invulnerable;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::terrain::Terrain;
+use crate::{
+    AppState, ControlMapping, DeathCause, Direction, DirectionChangeRequest, GameMode, GameRng,
+    GameSet, KeyPresses, Player, Position, RunStarted, SNAKE_HEAD_COLOR, SNAKE_SEGMENT_COLOR, Size,
+    SnakeColors, SnakeCrashed, SnakeDied, SnakeHead, SnakeSegment, SnakeSegments, held_direction,
+    snake_eating, spawn_snake_body,
+};
+
+/// Head and body colors of each player's snake.
+const PLAYER_COLORS: [(Color, Color); 2] = [
+    (SNAKE_HEAD_COLOR, SNAKE_SEGMENT_COLOR),
+    (Color::srgb(0.55, 0.9, 0.55), Color::srgb(0.2, 0.45, 0.25)),
+];
+const REVIVE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+
+/// Left, right, up and down for the second player.
+const WASD_KEYS: [KeyCode; 4] = [KeyCode::KeyA, KeyCode::KeyD, KeyCode::KeyW, KeyCode::KeyS];
+
+/// How long a revive pickup waits for the surviving player.
+const REVIVE_TIME: Duration = Duration::from_secs(10);
+
+/// Dropped when a player dies while the other is still alive. Eating it
+/// brings the fallen player back, until it runs out.
+#[derive(Component)]
+pub struct RevivePickup {
+    player: usize,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct ReviveText;
+
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            (spawn_coop_snakes.after(sync_arena), setup_revive_text)
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(coop_enabled),
+        )
+        .add_systems(
+            Update,
+            (
+                coop_input
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(PauseState::Running)),
+                (fall_coop_snakes, collect_revives)
+                    .chain()
+                    .in_set(GameSet::Collisions)
+                    .before(snake_eating),
+                tick_revive
+                    .after(GameSet::Collisions)
+                    .run_if(in_state(PauseState::Running)),
+                respawn_coop_snakes.after(GameSet::Spawning),
+                update_revive_text.after(tick_revive),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(coop_enabled),
+        );
+    }
+}
+
+fn coop_enabled(rules: Res<Rules>) -> bool {
+    rules.coop
+}
+
+/// Where each player's snake starts: the first in the bottom left corner,
+/// the second in the top right heading the other way.
+fn start(arena: &Arena, player: usize) -> (Position, Direction) {
+    let grid = arena.grid();
+    let direction = grid.start_direction();
+    if player == 0 {
+        (Position { x: 3, y: 3 }, direction)
+    } else {
+        (
+            grid.cell_position(arena.width() - 4, arena.height() - 4),
+            direction.opposite(),
+        )
+    }
+}
+
+fn spawn_coop_snakes(mut commands: Commands, arena: Res<Arena>) {
+    let grid = arena.grid();
+    for player in 0..PLAYER_COLORS.len() {
+        let (head, direction) = start(&arena, player);
+        spawn_coop_snake(
+            &mut commands,
+            player,
+            &[head, grid.step(head, direction.opposite())],
+            direction,
+        );
+    }
+}
+
+/// Spawns `player`'s snake, returning its head.
+fn spawn_coop_snake(
+    commands: &mut Commands,
+    player: usize,
+    body: &[Position],
+    direction: Direction,
+) -> Entity {
+    let (head, body_color) = PLAYER_COLORS[player];
+    let colors = SnakeColors {
+        head,
+        body: body_color,
+    };
+    let head = spawn_snake_body(commands, body, direction, colors)[0];
+    commands.entity(head).insert(Player(player));
+    head
+}
+
+/// A random free cell. With a `direction`, the cell behind it has to be free
+/// as well, to fit a snake's tail.
+fn free_spot(
+    grid: &dyn Grid,
+    rng: &mut GameRng,
+    terrain: &Terrain,
+    occupied: &HashSet<Position>,
+    direction: Option<Direction>,
+) -> Option<Position> {
+    let free =
+        |pos: Position| grid.contains(pos) && !terrain.blocks(pos) && !occupied.contains(&pos);
+    (0..50)
+        .map(|_| grid.random_cell(&mut rng.rng))
+        .find(|cell| {
+            free(*cell)
+                && direction.is_none_or(|direction| free(grid.step(*cell, direction.opposite())))
+        })
+}
+
+/// Steers the second player's snake; the first one is on the arrow keys
+/// like any other run.
+fn coop_input(
+    presses: Res<KeyPresses>,
+    arena: Res<Arena>,
+    controls: ControlMapping,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<(Entity, &Player), With<SnakeHead>>,
+) {
+    let Some(wanted) =
+        held_direction(&presses, WASD_KEYS, arena.grid()).map(|wanted| controls.map(wanted))
+    else {
+        return;
+    };
+    for (snake, _) in heads.iter().filter(|(_, player)| player.0 == 1) {
+        request_writer.write(DirectionChangeRequest {
+            snake,
+            direction: wanted,
+        });
+    }
+}
+
+/// A player whose snake crashed drops a revive pickup for the other one to
+/// fetch. The run ends once neither is left.
+fn fall_coop_snakes(
+    mut commands: Commands,
+    mut crash_reader: MessageReader<SnakeCrashed>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    snakes: Query<(&Player, &SnakeSegments)>,
+    positions: Query<&Position, With<SnakeSegment>>,
+    pickups: Query<&Position, With<RevivePickup>>,
+) {
+    let mut fallen: Vec<(Entity, usize)> = Vec::new();
+    for crash in crash_reader.read() {
+        // A snake can crash into more than one thing at once.
+        if fallen.iter().any(|(snake, _)| *snake == crash.snake) {
+            continue;
+        }
+        let Ok((player, segments)) = snakes.get(crash.snake) else {
+            continue;
+        };
+        for segment in segments.iter() {
+            commands.entity(*segment).despawn();
+        }
+        fallen.push((crash.snake, player.0));
+    }
+    let Some((snake, _)) = fallen.last().copied() else {
+        return;
+    };
+    if fallen.len() == snakes.iter().count() {
+        game_over_writer.write(SnakeDied {
+            snake,
+            cause: DeathCause::Fallen,
+        });
+        return;
+    }
+    let grid = arena.grid();
+    let mut occupied: HashSet<Position> = positions.iter().chain(pickups.iter()).copied().collect();
+    for (_, player) in fallen {
+        let Some(position) = free_spot(grid, &mut rng, &terrain, &occupied, None) else {
+            continue;
+        };
+        commands.spawn((
+            Sprite::from_color(REVIVE_COLOR, Vec2::ONE),
+            Transform::default(),
+            position,
+            Size::square(0.5),
+            RevivePickup {
+                player,
+                timer: Timer::new(REVIVE_TIME, TimerMode::Once),
+            },
+            DespawnOnExit(AppState::Playing),
+        ));
+        occupied.insert(position);
+    }
+}
+
+/// A snake that reaches a revive pickup brings its player back where they
+/// started, or somewhere free if that's taken, shielded for a moment.
+fn collect_revives(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    heads: Query<&Position, With<Player>>,
+    positions: Query<&Position, With<SnakeSegment>>,
+    pickups: Query<(Entity, &Position, &RevivePickup)>,
+) {
+    let grid = arena.grid();
+    for (pickup_entity, position, pickup) in pickups.iter() {
+        if !heads.iter().any(|head| head == position) {
+            continue;
+        }
+        commands.entity(pickup_entity).despawn();
+        let occupied: HashSet<Position> = positions.iter().copied().collect();
+        let (start, direction) = start(&arena, pickup.player);
+        let tail = grid.step(start, direction.opposite());
+        let head = if [start, tail]
+            .iter()
+            .all(|pos| !occupied.contains(pos) && !terrain.blocks(*pos))
+        {
+            Some(start)
+        } else {
+            free_spot(grid, &mut rng, &terrain, &occupied, Some(direction))
+        };
+        if let Some(head) = head {
+            let snake = spawn_coop_snake(
+                &mut commands,
+                pickup.player,
+                &[head, grid.step(head, direction.opposite())],
+                direction,
+            );
+            commands.entity(snake).insert(Invulnerable::default());
+        }
+    }
+}
+
+/// A pickup nobody reached in time is gone, and with it the fallen player for
+/// the rest of the run.
+fn tick_revive(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pickups: Query<(Entity, &mut RevivePickup)>,
+) {
+    for (entity, mut pickup) in pickups.iter_mut() {
+        if pickup.timer.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// The snakes themselves went with the rest of the board when the run
+/// ended.
+fn respawn_coop_snakes(
+    mut commands: Commands,
+    mut run_reader: MessageReader<RunStarted>,
+    pickups: Query<Entity, With<RevivePickup>>,
+    arena: Res<Arena>,
+) {
+    if run_reader.read().next().is_none() {
+        return;
+    }
+    for entity in pickups.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_coop_snakes(commands, arena);
+}
+
+fn setup_revive_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(REVIVE_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        ReviveText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_revive_text(
+    locale: Res<Locale>,
+    pickups: Query<&RevivePickup>,
+    mut query: Query<&mut Text, With<ReviveText>>,
+) {
+    let text = pickups.iter().next().map_or(String::new(), |pickup| {
+        locale.format(
+            "hud.revive",
+            &[
+                ("player", &(pickup.player + 1)),
+                ("seconds", &pickup.timer.remaining_secs().ceil()),
+            ],
+        )
+    });
+    for mut line in query.iter_mut() {
+        if line.0 != text {
+            line.0 = text.clone();
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+
+use crate::boss::POISON_COLOR;
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::items::Items;
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::profile::Profile;
+use crate::shop::{CustomSkin, Hsv, Skin, Theme};
+use crate::terrain::Tile;
+use crate::{AppState, Food};
+
+/// Degrees of hue per nudge.
+const HUE_STEP: f32 = 10.0;
+/// Saturation or value per nudge.
+const SHADE_STEP: f32 = 0.05;
+/// The sample board, a row of cells at a time: `h` is the snake's head and
+/// `b` its body, heading right, `f` food, `p` poison and `#` a wall.
+const PREVIEW_BOARD: [&[u8; 10]; 3] = [b".......#..", b"bbbbbbh.f.", b"...p......"];
+/// Side of a cell of the sample board, in pixels.
+const PREVIEW_CELL: f32 = 32.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Head,
+    Body,
+}
+
+impl Part {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Head => "cosmetics.head",
+            Self::Body => "cosmetics.body",
+        }
+    }
+
+    fn color(self, skin: &CustomSkin) -> Hsv {
+        match self {
+            Self::Head => skin.head,
+            Self::Body => skin.body,
+        }
+    }
+
+    fn color_mut(self, skin: &mut CustomSkin) -> &mut Hsv {
+        match self {
+            Self::Head => &mut skin.head,
+            Self::Body => &mut skin.body,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Hue,
+    Saturation,
+    Value,
+}
+
+impl Channel {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hue => "cosmetics.hue",
+            Self::Saturation => "cosmetics.saturation",
+            Self::Value => "cosmetics.value",
+        }
+    }
+
+    /// What the line shows: degrees of hue, or a percentage.
+    fn shown(self, color: Hsv) -> u32 {
+        match self {
+            Self::Hue => color.hue.round() as u32,
+            Self::Saturation => (color.saturation * 100.0).round() as u32,
+            Self::Value => (color.value * 100.0).round() as u32,
+        }
+    }
+
+    /// Moves the channel of `color` by `steps` nudges. Hue goes round the
+    /// color wheel; saturation and value stop at the ends.
+    fn nudge(self, color: &mut Hsv, steps: i32) {
+        let steps = steps as f32;
+        match self {
+            Self::Hue => color.hue = (color.hue + steps * HUE_STEP).rem_euclid(360.0),
+            Self::Saturation => {
+                color.saturation = (color.saturation + steps * SHADE_STEP).clamp(0.0, 1.0);
+            }
+            Self::Value => color.value = (color.value + steps * SHADE_STEP).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// A line of the cosmetics screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CosmeticsLine {
+    Color(Part, Channel),
+    /// Left and right pick one of [`Skin::PRESETS`], Enter copies its colors.
+    Swatch,
+    Wear,
+    /// Left and right try out the themes the player can pick on the sample
+    /// board, Enter puts the one tried on.
+    Theme,
+}
+
+/// Every line in the order it's listed, which is also the order the focus
+/// moves through them.
+const LINES: [CosmeticsLine; 9] = [
+    CosmeticsLine::Color(Part::Head, Channel::Hue),
+    CosmeticsLine::Color(Part::Head, Channel::Saturation),
+    CosmeticsLine::Color(Part::Head, Channel::Value),
+    CosmeticsLine::Color(Part::Body, Channel::Hue),
+    CosmeticsLine::Color(Part::Body, Channel::Saturation),
+    CosmeticsLine::Color(Part::Body, Channel::Value),
+    CosmeticsLine::Swatch,
+    CosmeticsLine::Wear,
+    CosmeticsLine::Theme,
+];
+
+/// The preset on the swatch line, as an index into [`Skin::PRESETS`].
+#[derive(Resource, Default)]
+struct Swatch(usize);
+
+/// The theme the sample board is shown in, which isn't the player's own
+/// until it's put on.
+#[derive(Resource, Default)]
+struct TriedTheme(Theme);
+
+#[derive(Component)]
+struct CosmeticsRoot;
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Swatch>()
+            .init_resource::<TriedTheme>()
+            .add_systems(
+                OnEnter(AppState::Cosmetics),
+                (try_own_theme, setup_cosmetics).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    cosmetics_input.after(navigate_focus),
+                    refresh_cosmetics.after(cosmetics_input).run_if(
+                        resource_changed::<Profile>
+                            .or(resource_changed::<Swatch>)
+                            .or(resource_changed::<TriedTheme>),
+                    ),
+                )
+                    .run_if(in_state(AppState::Cosmetics)),
+            );
+    }
+}
+
+fn try_own_theme(profile: Res<Profile>, mut tried: ResMut<TriedTheme>) {
+    tried.0 = profile.theme;
+}
+
+fn setup_cosmetics(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    profile: Res<Profile>,
+    swatch: Res<Swatch>,
+    tried: Res<TriedTheme>,
+    items: Res<Items>,
+    mods: Res<Mods>,
+) {
+    let skin = profile.custom_skin;
+    let lines = LINES.map(|line| match line {
+        CosmeticsLine::Color(part, channel) => locale.format(
+            "cosmetics.channel",
+            &[
+                ("part", &locale.get(part.name())),
+                ("channel", &locale.get(channel.name())),
+                ("value", &channel.shown(part.color(&skin))),
+            ],
+        ),
+        CosmeticsLine::Swatch => locale.format(
+            "cosmetics.swatch",
+            &[("name", &locale.get(Skin::PRESETS[swatch.0].name()))],
+        ),
+        CosmeticsLine::Wear if profile.skin == Skin::Custom => {
+            locale.get("cosmetics.worn").to_string()
+        }
+        CosmeticsLine::Wear => locale.get("cosmetics.wear").to_string(),
+        CosmeticsLine::Theme if tried.0 == profile.theme => locale.format(
+            "cosmetics.theme",
+            &[("name", &locale.get(profile.theme.name()))],
+        ),
+        CosmeticsLine::Theme => locale.format(
+            "cosmetics.try_theme",
+            &[("name", &locale.get(tried.0.name()))],
+        ),
+    });
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            CosmeticsRoot,
+            DespawnOnExit(AppState::Cosmetics),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("cosmetics.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            // Everything that has to stand out against the background, on
+            // the background being tried out.
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        margin: UiRect::vertical(Val::Px(12.0)),
+                        ..default()
+                    },
+                    BackgroundColor(mods.sky(tried.0)),
+                ))
+                .with_children(|board| {
+                    for row in PREVIEW_BOARD {
+                        board
+                            .spawn(Node {
+                                column_gap: Val::Px(4.0),
+                                ..default()
+                            })
+                            .with_children(|row_node| {
+                                for cell in row {
+                                    let color = match cell {
+                                        b'h' => skin.head.color(),
+                                        b'b' => skin.body.color(),
+                                        b'f' => items.food(Food(0)).color(),
+                                        b'p' => POISON_COLOR,
+                                        b'#' => Tile::Wall { hp: 2 }.color(),
+                                        _ => Color::NONE,
+                                    };
+                                    row_node.spawn((
+                                        Node {
+                                            width: Val::Px(PREVIEW_CELL),
+                                            height: Val::Px(PREVIEW_CELL),
+                                            ..default()
+                                        },
+                                        BackgroundColor(color),
+                                    ));
+                                }
+                            });
+                    }
+                });
+            for (index, line) in lines.into_iter().enumerate() {
+                parent.spawn((cosmetics_line(line), Focusable::new(index)));
+            }
+            parent.spawn(cosmetics_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn cosmetics_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_cosmetics(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    profile: Res<Profile>,
+    swatch: Res<Swatch>,
+    tried: Res<TriedTheme>,
+    items: Res<Items>,
+    mods: Res<Mods>,
+    roots: Query<Entity, With<CosmeticsRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_cosmetics(commands, locale, profile, swatch, tried, items, mods);
+}
+
+/// Left and right change the focused color channel, pick a preset to start
+/// from, or try out a theme. Enter nudges a channel forward, copies the
+/// picked preset, puts the colors on, or puts the theme tried on. Escape
+/// goes back to the shop.
+fn cosmetics_input(
+    mut input_reader: MessageReader<FocusInput>,
+    mut profile: ResMut<Profile>,
+    mods: Res<Mods>,
+    mut sky: ResMut<ClearColor>,
+    mut swatch: ResMut<Swatch>,
+    mut tried: ResMut<TriedTheme>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for input in input_reader.read() {
+        let (order, steps, activated) = match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Shop);
+                return;
+            }
+            FocusInput::Activate(order) => (order, 1, true),
+            FocusInput::Nudge(order, steps) => (order, steps, false),
+        };
+        match LINES.get(order) {
+            Some(CosmeticsLine::Color(part, channel)) => {
+                channel.nudge(part.color_mut(&mut profile.custom_skin), steps);
+            }
+            Some(CosmeticsLine::Swatch) if activated => {
+                let (head, body) = Skin::PRESETS[swatch.0].colors(&profile.custom_skin);
+                profile.custom_skin = CustomSkin {
+                    head: Hsv::from_color(head),
+                    body: Hsv::from_color(body),
+                };
+            }
+            Some(CosmeticsLine::Swatch) => {
+                swatch.0 =
+                    (swatch.0 as i32 + steps).rem_euclid(Skin::PRESETS.len() as i32) as usize;
+                continue;
+            }
+            Some(CosmeticsLine::Wear) if activated => profile.skin = Skin::Custom,
+            Some(CosmeticsLine::Theme) if activated => {
+                profile.theme = tried.0;
+                sky.0 = mods.sky(profile.theme);
+            }
+            Some(CosmeticsLine::Theme) => {
+                let themes = Theme::ALL
+                    .into_iter()
+                    .filter(|theme| theme.available(&profile))
+                    .collect::<Vec<_>>();
+                let current = themes
+                    .iter()
+                    .position(|theme| *theme == tried.0)
+                    .unwrap_or(0) as i32;
+                tried.0 = themes[(current + steps).rem_euclid(themes.len() as i32) as usize];
+                continue;
+            }
+            _ => continue,
+        }
+        profile.save();
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::{AppState, GameMode, RunStarted};
+
+const COUNTDOWN_FROM: u32 = 3;
+
+/// Holds the snake in place while "3-2-1-GO" is shown. Movement resumes as
+/// soon as "GO!" appears.
+#[derive(Resource)]
+pub struct Countdown {
+    remaining: u32,
+    timer: Timer,
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self {
+            remaining: COUNTDOWN_FROM,
+            timer: Timer::new(Duration::from_millis(700), TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component)]
+struct CountdownText;
+
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            start_countdown.run_if(resource_equals(GameMode::Endless)),
+        )
+        .add_systems(OnExit(AppState::Playing), stop_countdown)
+        .add_systems(
+            Update,
+            (
+                restart_countdown_on_respawn.run_if(resource_equals(GameMode::Endless)),
+                tick_countdown
+                    .run_if(resource_exists::<Countdown>)
+                    .run_if(in_state(PauseState::Running)),
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Run condition for systems that must wait until the countdown reaches "GO!".
+pub fn countdown_finished(countdown: Option<Res<Countdown>>) -> bool {
+    countdown.is_none_or(|countdown| countdown.remaining == 0)
+}
+
+fn start_countdown(mut commands: Commands, locale: Res<Locale>) {
+    commands.insert_resource(Countdown::default());
+    commands.spawn((
+        Text::new(locale.format("countdown.number", &[("number", &COUNTDOWN_FROM)])),
+        TextFont {
+            font_size: 120.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        CountdownText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn stop_countdown(mut commands: Commands) {
+    commands.remove_resource::<Countdown>();
+}
+
+fn restart_countdown_on_respawn(
+    mut commands: Commands,
+    mut run_reader: MessageReader<RunStarted>,
+    locale: Res<Locale>,
+    text: Query<Entity, With<CountdownText>>,
+) {
+    if run_reader.read().next().is_none() {
+        return;
+    }
+    for entity in text.iter() {
+        commands.entity(entity).despawn();
+    }
+    start_countdown(commands, locale);
+}
+
+fn tick_countdown(
+    mut commands: Commands,
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut countdown: ResMut<Countdown>,
+    mut text: Query<(Entity, &mut Text), With<CountdownText>>,
+) {
+    if !countdown.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if countdown.remaining == 0 {
+        for (entity, _) in text.iter() {
+            commands.entity(entity).despawn();
+        }
+        commands.remove_resource::<Countdown>();
+        return;
+    }
+
+    countdown.remaining -= 1;
+    for (_, mut text) in text.iter_mut() {
+        text.0 = match countdown.remaining {
+            0 => locale.get("countdown.go").to_string(),
+            n => locale.format("countdown.number", &[("number", &n)]),
+        };
+    }
+}
@@ -0,0 +1,328 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::rules::Rules;
+use crate::{AppState, GameMode};
+
+const FOOD_COUNTS: [u32; 4] = [1, 2, 3, 5];
+
+/// Poison frequencies to pick from, from none at all to constant.
+const POISON_INTERVALS: [Option<Duration>; 4] = [
+    None,
+    Some(Duration::from_secs(20)),
+    Some(Duration::from_secs(10)),
+    Some(Duration::from_secs(4)),
+];
+
+/// How long a full hunger meter lasts on each difficulty, from no hunger at
+/// all to hard.
+const HUNGER_DURATIONS: [Option<Duration>; 4] = [
+    None,
+    Some(Duration::from_secs(40)),
+    Some(Duration::from_secs(25)),
+    Some(Duration::from_secs(15)),
+];
+
+/// How long the snake can go without eating when decay is on.
+const DECAY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long the snake can loop around without eating before its score
+/// starts draining, when that's on.
+const IDLE_GRACE: Duration = Duration::from_secs(10);
+
+/// Each digit cycles one of the rules, in the order they're listed, and the
+/// last one the mod presets.
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+/// The rules being put together on the custom game screen. They stick
+/// around for the rest of the session.
+#[derive(Resource)]
+struct CustomRules(Rules);
+
+impl Default for CustomRules {
+    fn default() -> Self {
+        Self(Rules {
+            custom: true,
+            ..default()
+        })
+    }
+}
+
+/// The mod preset the custom rules were last reset to, see
+/// [`crate::mods::Preset`].
+#[derive(Resource, Default)]
+struct PresetChoice(Option<usize>);
+
+#[derive(Component)]
+struct CustomRoot;
+
+pub struct CustomPlugin;
+
+impl Plugin for CustomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CustomRules>()
+            .init_resource::<PresetChoice>()
+            .add_systems(OnEnter(AppState::Custom), setup_custom)
+            .add_systems(
+                Update,
+                (
+                    custom_input.after(navigate_focus),
+                    refresh_custom
+                        .after(custom_input)
+                        .run_if(resource_changed::<CustomRules>),
+                )
+                    .run_if(in_state(AppState::Custom)),
+            );
+    }
+}
+
+fn poison_name(interval: Option<Duration>) -> &'static str {
+    match POISON_INTERVALS
+        .iter()
+        .position(|option| *option == interval)
+    {
+        Some(0) => "poison.off",
+        Some(1) => "poison.rare",
+        Some(2) => "poison.often",
+        _ => "poison.constant",
+    }
+}
+
+fn hunger_name(duration: Option<Duration>) -> &'static str {
+    match HUNGER_DURATIONS
+        .iter()
+        .position(|option| *option == duration)
+    {
+        Some(0) => "hunger.off",
+        Some(1) => "hunger.easy",
+        Some(2) => "hunger.normal",
+        _ => "hunger.hard",
+    }
+}
+
+/// One line per adjustable rule, in the order of [`DIGIT_KEYS`]. The results
+/// of a custom game list them too.
+pub fn summary(rules: &Rules, locale: &Locale) -> Vec<String> {
+    let self_collision = if rules.self_collision {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    let decay = if rules.decay.is_some() {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    let idle_drain = if rules.idle_drain.is_some() {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    let mirrored = if rules.mirrored {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    vec![
+        locale.format(
+            "custom.self_collision",
+            &[("state", &locale.get(self_collision))],
+        ),
+        locale.format(
+            "custom.walls",
+            &[("walls", &locale.get(rules.walls.name()))],
+        ),
+        locale.format("custom.food_count", &[("count", &rules.food_count)]),
+        locale.format(
+            "custom.poison",
+            &[("frequency", &locale.get(poison_name(rules.poison_interval)))],
+        ),
+        locale.format(
+            "custom.speed_curve",
+            &[("curve", &locale.get(rules.speed_curve.name()))],
+        ),
+        locale.format("custom.decay", &[("state", &locale.get(decay))]),
+        locale.format(
+            "custom.hunger",
+            &[("difficulty", &locale.get(hunger_name(rules.hunger)))],
+        ),
+        locale.format("custom.idle_drain", &[("state", &locale.get(idle_drain))]),
+        locale.format("custom.mirrored", &[("state", &locale.get(mirrored))]),
+    ]
+}
+
+fn setup_custom(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    custom: Res<CustomRules>,
+    mods: Res<Mods>,
+    choice: Res<PresetChoice>,
+) {
+    let mut lines = summary(&custom.0, &locale);
+    let presets: Vec<_> = mods.presets().collect();
+    if !presets.is_empty() {
+        let name = choice
+            .0
+            .and_then(|index| presets.get(index))
+            .map_or(locale.get("custom.preset.none"), |preset| &preset.name);
+        lines.push(locale.format("custom.preset", &[("name", &name)]));
+    }
+    let options = lines.into_iter().enumerate().map(|(index, line)| {
+        locale.format(
+            "custom.option",
+            &[("number", &((index + 1) % 10)), ("line", &line)],
+        )
+    });
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            CustomRoot,
+            DespawnOnExit(AppState::Custom),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("custom.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            // Starting comes first, so Enter right away plays with the
+            // rules as they are.
+            parent.spawn((
+                custom_line(locale.get("custom.start").to_string()),
+                Focusable::new(0),
+            ));
+            for (index, line) in options.enumerate() {
+                parent.spawn((custom_line(line), Focusable::new(index + 1)));
+            }
+            parent.spawn(custom_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn custom_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_custom(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    custom: Res<CustomRules>,
+    mods: Res<Mods>,
+    choice: Res<PresetChoice>,
+    roots: Query<Entity, With<CustomRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_custom(commands, locale, custom, mods, choice);
+}
+
+/// The entry after `current` in `options`, wrapping around to the first.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let index = options.iter().position(|option| *option == current);
+    options[index.map_or(0, |index| (index + 1) % options.len())]
+}
+
+/// Digits, or Enter and left and right on a rule, change the rules. Enter on
+/// the start line plays with them and Escape goes back to the menu.
+fn custom_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut input_reader: MessageReader<FocusInput>,
+    mut custom: ResMut<CustomRules>,
+    mut choice: ResMut<PresetChoice>,
+    mods: Res<Mods>,
+    mut rules: ResMut<Rules>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut picked = DIGIT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Activate(0) => {
+                *rules = custom.0.clone();
+                *mode = GameMode::Endless;
+                next_state.set(AppState::Playing);
+                return;
+            }
+            FocusInput::Activate(order) | FocusInput::Nudge(order, _) if order > 0 => {
+                picked = Some(order - 1);
+            }
+            _ => {}
+        }
+    }
+    let Some(index) = picked else {
+        return;
+    };
+    let custom = &mut custom.0;
+    match index {
+        0 => custom.self_collision = !custom.self_collision,
+        1 => custom.walls = custom.walls.next(),
+        2 => custom.food_count = cycle(&FOOD_COUNTS, custom.food_count),
+        3 => custom.poison_interval = cycle(&POISON_INTERVALS, custom.poison_interval),
+        4 => custom.speed_curve = custom.speed_curve.next(),
+        5 => {
+            custom.decay = match custom.decay {
+                Some(_) => None,
+                None => Some(DECAY_INTERVAL),
+            }
+        }
+        6 => custom.hunger = cycle(&HUNGER_DURATIONS, custom.hunger),
+        7 => {
+            custom.idle_drain = match custom.idle_drain {
+                Some(_) => None,
+                None => Some(IDLE_GRACE),
+            }
+        }
+        8 => custom.mirrored = !custom.mirrored,
+        _ => {
+            let presets: Vec<_> = mods.presets().collect();
+            if presets.is_empty() {
+                return;
+            }
+            let index = choice.0.map_or(0, |index| (index + 1) % presets.len());
+            choice.0 = Some(index);
+            *custom = Rules {
+                custom: true,
+                ..presets[index].rules.clone()
+            };
+        }
+    }
+}
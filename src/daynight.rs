@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use core::f32::consts::TAU;
+
+use crate::countdown;
+use crate::mods::Mods;
+use crate::pause::PauseState;
+use crate::profile::Profile;
+use crate::{AppState, Food, RunStarted};
+
+const NIGHT_SKY: Color = Color::srgb(0.01, 0.015, 0.05);
+const NIGHT_TINT: Color = Color::srgb(0.05, 0.08, 0.25);
+const FOOD_GLOW: Color = Color::srgb(1.0, 0.4, 1.0);
+
+/// Opacity of the tint over the board at midnight.
+const TINT_ALPHA: f32 = 0.45;
+const GLOW_ALPHA: f32 = 0.3;
+
+/// Seconds from one noon to the next.
+const DAY_LENGTH: f32 = 240.0;
+
+/// Between the ground, at -1, and the pieces on it, at 0.
+const TINT_DEPTH: f32 = -0.5;
+
+/// Seconds played in the current run, counting only while the snake moves.
+#[derive(Resource, Default)]
+struct RunClock(f32);
+
+impl RunClock {
+    /// 0 at noon, rising to 1 at midnight and falling back again. Runs start
+    /// at noon.
+    fn darkness(&self) -> f32 {
+        (1.0 - (self.0 / DAY_LENGTH * TAU).cos()) / 2.0
+    }
+}
+
+#[derive(Component)]
+struct NightTint;
+
+/// Soft halo behind a piece of food, only visible at night.
+#[derive(Component)]
+struct Glow;
+
+type TintQuery<'w, 's> =
+    Query<'w, 's, (&'static mut Sprite, &'static mut Transform), (With<NightTint>, Without<Glow>)>;
+
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunClock>()
+            .add_systems(OnEnter(AppState::Playing), (reset_clock, spawn_tint))
+            .add_systems(OnExit(AppState::Playing), restore_sky)
+            .add_systems(
+                Update,
+                (
+                    reset_clock_on_respawn,
+                    advance_clock
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    attach_glow,
+                    shade,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn reset_clock(mut clock: ResMut<RunClock>) {
+    *clock = RunClock::default();
+}
+
+fn reset_clock_on_respawn(mut run_reader: MessageReader<RunStarted>, mut clock: ResMut<RunClock>) {
+    if run_reader.read().next().is_some() {
+        *clock = RunClock::default();
+    }
+}
+
+fn advance_clock(time: Res<Time>, mut clock: ResMut<RunClock>) {
+    clock.0 += time.delta_secs();
+}
+
+fn restore_sky(mods: Res<Mods>, profile: Res<Profile>, mut sky: ResMut<ClearColor>) {
+    sky.0 = mods.sky(profile.theme);
+}
+
+fn spawn_tint(mut commands: Commands) {
+    commands.spawn((
+        Sprite::from_color(NIGHT_TINT.with_alpha(0.0), Vec2::ONE),
+        Transform::from_xyz(0.0, 0.0, TINT_DEPTH),
+        NightTint,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn attach_glow(mut commands: Commands, food: Query<Entity, Added<Food>>) {
+    for entity in food.iter() {
+        commands.entity(entity).with_child((
+            Sprite::from_color(FOOD_GLOW.with_alpha(0.0), Vec2::splat(2.2)),
+            Transform::from_xyz(0.0, 0.0, -0.1),
+            Glow,
+        ));
+    }
+}
+
+fn shade(
+    clock: Res<RunClock>,
+    mods: Res<Mods>,
+    profile: Res<Profile>,
+    mut sky: ResMut<ClearColor>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut tints: TintQuery,
+    mut glows: Query<&mut Sprite, (With<Glow>, Without<NightTint>)>,
+) {
+    let darkness = clock.darkness();
+    let color = mods.sky(profile.theme).mix(&NIGHT_SKY, darkness);
+    if sky.0 != color {
+        sky.0 = color;
+    }
+    let size = window_query
+        .single()
+        .map_or(Vec2::ZERO, |window| window.size());
+    for (mut sprite, mut transform) in tints.iter_mut() {
+        sprite.color = NIGHT_TINT.with_alpha(TINT_ALPHA * darkness);
+        transform.scale = size.extend(1.0);
+    }
+    for mut sprite in glows.iter_mut() {
+        sprite.color = FOOD_GLOW.with_alpha(GLOW_ALPHA * darkness);
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+
+use crate::countdown;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::{
+    AppState, DeathCause, GameMode, GameSet, Player, PlayerMeals, RestartEvent, SnakeDied,
+    SnakeSegments, StepClock, snake_eating,
+};
+
+const DECAY_COLOR: Color = Color::srgb(0.9, 0.6, 0.3);
+
+/// The snake starves once it's shorter than this.
+const MIN_LENGTH: usize = 2;
+
+/// Time left before the snake loses its tail.
+#[derive(Resource, Default)]
+struct DecayTimer(Timer);
+
+#[derive(Component)]
+struct DecayText;
+
+pub struct DecayPlugin;
+
+impl Plugin for DecayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DecayTimer>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (start_decay, setup_decay_text)
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(decay_enabled),
+            )
+            .add_systems(
+                Update,
+                (
+                    reset_decay.in_set(GameSet::Collisions).after(snake_eating),
+                    decay
+                        .in_set(GameSet::Collisions)
+                        .after(reset_decay)
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    update_decay_text.after(decay),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(decay_enabled),
+            );
+    }
+}
+
+fn decay_enabled(rules: Res<Rules>) -> bool {
+    rules.decay.is_some()
+}
+
+fn start_decay(mut commands: Commands, rules: Res<Rules>) {
+    if let Some(interval) = rules.decay {
+        commands.insert_resource(DecayTimer(Timer::new(interval, TimerMode::Repeating)));
+    }
+}
+
+/// Eating, dying or restarting winds the timer back up.
+fn reset_decay(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    mut timer: ResMut<DecayTimer>,
+) {
+    let ate = growth_reader.read().next().is_some();
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if ate || died || restarted {
+        timer.0.reset();
+    }
+}
+
+fn decay(
+    mut commands: Commands,
+    step_clock: Res<StepClock>,
+    mut timer: ResMut<DecayTimer>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    mut bodies: Query<(Entity, &mut SnakeSegments), With<Player>>,
+) {
+    if !timer.0.tick(step_clock.delta()).just_finished() {
+        return;
+    }
+    for (snake, mut segments) in bodies.iter_mut() {
+        if let Some(tail) = segments.pop() {
+            commands.entity(tail).despawn();
+        }
+        if segments.len() < MIN_LENGTH {
+            game_over_writer.write(SnakeDied {
+                snake,
+                cause: DeathCause::Decay,
+            });
+        }
+    }
+}
+
+fn setup_decay_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(DECAY_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(136.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        DecayText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_decay_text(
+    timer: Res<DecayTimer>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<DecayText>>,
+) {
+    let seconds = timer.0.remaining_secs().ceil() as u32;
+    let text = locale.format("hud.decay", &[("seconds", &seconds)]);
+    for mut line in query.iter_mut() {
+        if line.0 != text {
+            line.0 = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+    use core::time::Duration;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::rules::Spawn;
+    use crate::{Direction, Position, SnakeTimer};
+
+    /// An endless run with no food, where the tail drops off every two
+    /// steps, with the snake `length` long and heading across the board.
+    fn decay_app(length: u32) -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            decay: Some(SnakeTimer::default().base * 2),
+            food_count: 0,
+            spawns: vec![Spawn {
+                position: Position { x: 3, y: 3 },
+                length,
+                direction: Some(Direction::Right),
+            }],
+            ..default()
+        })
+        .insert_resource(Locale::load("en"))
+        .add_plugins(DecayPlugin);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    /// Takes one step, returning how the run ended if it did.
+    fn step(app: &mut App) -> Option<DeathCause> {
+        let remaining = app.world().resource::<SnakeTimer>().timer.remaining();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(remaining));
+        app.update();
+        app.world()
+            .resource::<Messages<SnakeDied>>()
+            .iter_current_update_messages()
+            .next()
+            .map(|died| died.cause)
+    }
+
+    fn length(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world
+            .query_filtered::<&SnakeSegments, With<Player>>()
+            .single(world)
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn the_snake_shrinks_without_food() {
+        let mut app = decay_app(4);
+        let lengths = (0..4)
+            .map(|_| {
+                assert_eq!(step(&mut app), None);
+                length(&mut app)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [4, 3, 3, 2]);
+    }
+
+    #[test]
+    fn the_snake_starves_once_too_short() {
+        let mut app = decay_app(MIN_LENGTH as u32);
+        assert_eq!(step(&mut app), None);
+        assert_eq!(step(&mut app), Some(DeathCause::Decay));
+    }
+}
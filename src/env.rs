@@ -0,0 +1,387 @@
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::arena_event::MirroredControls;
+use crate::bonk::WallBonked;
+use crate::pause::PauseState;
+use crate::powerup::Ram;
+use crate::reverse::ReversedControls;
+use crate::settings::Settings;
+use crate::terrain::{Terrain, WallCrumbled};
+use crate::upgrade::Shield;
+use crate::{
+    AppState, CorePlugin, Direction, DirectionChangeRequest, Food, GameRng, GameSet, GameState,
+    Player, Position, RestartEvent, SnakeDied, SnakeHead, SnakeSegments, SnakeTimer,
+};
+
+/// Planes of the [`Observation::grid`]: the head, the body, basic food and
+/// every other kind of food.
+pub const CHANNELS: usize = 4;
+
+/// Length of [`Observation::features`].
+pub const FEATURES: usize = 9;
+
+/// Reward for the step that ends an episode.
+const DEATH_REWARD: f32 = -1.0;
+
+/// Where the agent wants the snake to go. Turning back into the body is
+/// ignored, as it is for players.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Left,
+    Up,
+    Right,
+    Down,
+}
+
+impl Action {
+    pub const ALL: [Self; 4] = [Self::Left, Self::Up, Self::Right, Self::Down];
+
+    fn direction(self) -> Direction {
+        match self {
+            Self::Left => Direction::Left,
+            Self::Up => Direction::Up,
+            Self::Right => Direction::Right,
+            Self::Down => Direction::Down,
+        }
+    }
+}
+
+/// The board after a step, in two shapes to pick from.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, frozen))]
+pub struct Observation {
+    /// [`CHANNELS`] planes of `width * height` cells each, row by row from
+    /// the bottom, 1.0 where the plane's thing is and 0.0 elsewhere.
+    pub grid: Vec<f32>,
+    pub width: usize,
+    pub height: usize,
+    /// [`FEATURES`] numbers: the head's column and row over the board size,
+    /// the heading as left, up, right and down one-hot, the offset to the
+    /// nearest food over the board size, and the length over the cell count.
+    pub features: Vec<f32>,
+}
+
+/// The agent's action for the next step.
+#[derive(Resource, Default)]
+struct PendingAction(Option<Action>);
+
+/// What the last step did, noted before a death resets the board.
+#[derive(Resource, Default)]
+struct Outcome {
+    observation: Observation,
+    died: bool,
+    score: u32,
+}
+
+/// Endless snake as a reinforcement-learning environment, gym style. It runs
+/// the game's own rules headless, one snake step per [`SnakeEnv::step`], with
+/// the clock advanced by exactly the time a step takes, so food drops and
+/// speed-ups happen just as they do in play.
+pub struct SnakeEnv {
+    app: App,
+    seed: u64,
+    episode: u64,
+}
+
+impl SnakeEnv {
+    /// An environment whose episodes are seeded from `seed` onwards, so a
+    /// training run can be repeated.
+    pub fn new(seed: u64) -> Self {
+        let mut app = headless_app();
+        app.init_resource::<PendingAction>()
+            .init_resource::<Outcome>()
+            .add_systems(
+                Update,
+                (
+                    apply_action.in_set(GameSet::Input),
+                    observe.after(GameSet::Collisions).before(GameSet::Spawning),
+                ),
+            );
+        app.finish();
+        app.cleanup();
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        let mut env = Self {
+            app,
+            seed,
+            episode: 0,
+        };
+        env.advance(Duration::ZERO);
+        env
+    }
+
+    /// Starts a new episode, returning its first observation.
+    pub fn reset(&mut self) -> Observation {
+        let seed = self.seed.wrapping_add(self.episode);
+        self.episode += 1;
+        self.app.insert_resource(GameRng::with_seed(seed));
+        self.app
+            .world_mut()
+            .write_message(RestartEvent { same_seed: true });
+        self.advance(Duration::ZERO);
+        observation(self.app.world_mut())
+    }
+
+    /// Moves the snake one cell, returning what it sees afterwards, the
+    /// points scored and whether the episode is over. Dying scores
+    /// [`DEATH_REWARD`]; call [`SnakeEnv::reset`] after that.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        self.app.world_mut().resource_mut::<PendingAction>().0 = Some(action);
+        let score = self.app.world().resource::<GameState>().score;
+        let step = self.app.world().resource::<SnakeTimer>().timer.remaining();
+        self.advance(step);
+        let outcome = std::mem::take(&mut *self.app.world_mut().resource_mut::<Outcome>());
+        if outcome.died {
+            return (outcome.observation, DEATH_REWARD, true);
+        }
+        let reward = outcome.score.saturating_sub(score) as f32;
+        (outcome.observation, reward, false)
+    }
+
+    fn advance(&mut self, delta: Duration) {
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+        self.app.update();
+    }
+}
+
+/// The game's own rules with none of the window, input, drawing or sound,
+/// and the few resources of other plugins they read. Shared by [`SnakeEnv`],
+/// the terminal frontend and every arena of an
+/// [`ArenasPlugin`](crate::arenas::ArenasPlugin); set [`AppState::Playing`]
+/// to start a run.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<AppState>()
+        .add_sub_state::<PauseState>()
+        .insert_resource(Settings::default())
+        .init_resource::<Terrain>()
+        .init_resource::<Ram>()
+        .init_resource::<Shield>()
+        .init_resource::<MirroredControls>()
+        .init_resource::<ReversedControls>()
+        .add_message::<WallCrumbled>()
+        .add_message::<WallBonked>()
+        .add_plugins(CorePlugin);
+    app
+}
+
+fn apply_action(
+    mut pending: ResMut<PendingAction>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<Entity, With<Player>>,
+) {
+    let Some(action) = pending.0.take() else {
+        return;
+    };
+    let Ok(snake) = heads.single() else {
+        return;
+    };
+    request_writer.write(DirectionChangeRequest {
+        snake,
+        direction: action.direction(),
+    });
+}
+
+/// Notes the board after the step, before a death clears it.
+fn observe(world: &mut World) {
+    let died = world
+        .resource::<Messages<SnakeDied>>()
+        .iter_current_update_messages()
+        .next()
+        .is_some();
+    let observation = observation(world);
+    let score = world.resource::<GameState>().score;
+    *world.resource_mut::<Outcome>() = Outcome {
+        observation,
+        died,
+        score,
+    };
+}
+
+fn observation(world: &mut World) -> Observation {
+    let arena = world.resource::<Arena>().clone();
+    let grid = arena.grid();
+    let (width, height) = (arena.width() as usize, arena.height() as usize);
+    let mut cells = vec![0.0; CHANNELS * width * height];
+    let mut mark = |channel: usize, pos: Position| {
+        if let Some((column, row)) = grid.to_cell(pos) {
+            cells[(channel * height + row as usize) * width + column as usize] = 1.0;
+        }
+    };
+
+    let mut heads = world.query_filtered::<(&SnakeHead, &Position, &SnakeSegments), With<Player>>();
+    let (head, segments) = heads
+        .iter(world)
+        .next()
+        .map(|(head, pos, segments)| (Some((head.direction, *pos)), segments.0.clone()))
+        .unwrap_or_default();
+    let mut positions = world.query::<&Position>();
+    for segment in segments.iter().skip(1) {
+        if let Ok(pos) = positions.get(world, *segment) {
+            mark(1, *pos);
+        }
+    }
+    let mut food_query = world.query::<(&Food, &Position)>();
+    let food = food_query
+        .iter(world)
+        .map(|(food, pos)| (*food, *pos))
+        .collect::<Vec<_>>();
+    for (kind, pos) in &food {
+        let channel = if *kind == Food::BASIC { 2 } else { 3 };
+        mark(channel, *pos);
+    }
+
+    let mut features = vec![0.0; FEATURES];
+    if let Some((direction, pos)) = head {
+        mark(0, pos);
+        let (column, row) = grid.to_cell(pos).unwrap_or_default();
+        features[0] = column as f32 / width as f32;
+        features[1] = row as f32 / height as f32;
+        if let Some(heading) = Action::ALL
+            .iter()
+            .position(|action| action.direction() == direction)
+        {
+            features[2 + heading] = 1.0;
+        }
+        let distance = |food: &Position| (food.x - pos.x).abs() + (food.y - pos.y).abs();
+        if let Some((_, nearest)) = food.iter().min_by_key(|(_, food)| distance(food)) {
+            features[6] = (nearest.x - pos.x) as f32 / width as f32;
+            features[7] = (nearest.y - pos.y) as f32 / height as f32;
+        }
+    }
+    features[8] = segments.len() as f32 / (width * height) as f32;
+
+    Observation {
+        grid: cells,
+        width,
+        height,
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+
+    /// Where the snake's head is and where it's heading.
+    fn head(env: &mut SnakeEnv) -> (Position, Direction) {
+        let world = env.app.world_mut();
+        let mut heads = world.query::<(&Position, &SnakeHead)>();
+        let (pos, head) = heads.single(world).unwrap();
+        (*pos, head.direction)
+    }
+
+    #[test]
+    fn the_snake_moves_without_a_window() {
+        let mut app = headless_app();
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+        app.update();
+        let mut heads = app
+            .world_mut()
+            .query_filtered::<&Position, With<SnakeHead>>();
+        let start = *heads.single(app.world()).unwrap();
+
+        let step = app.world().resource::<SnakeTimer>().timer.remaining();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(step));
+        app.update();
+        assert_eq!(
+            *heads.single(app.world()).unwrap(),
+            Position {
+                x: start.x,
+                y: start.y + 1
+            }
+        );
+    }
+
+    #[test]
+    fn observation_has_every_plane_and_feature() {
+        let mut env = SnakeEnv::new(1);
+        let observation = env.reset();
+        let (width, height) = Rules::default().arena_size;
+        assert_eq!(observation.width, width as usize);
+        assert_eq!(observation.height, height as usize);
+        assert_eq!(
+            observation.grid.len(),
+            CHANNELS * observation.width * observation.height
+        );
+        assert_eq!(observation.features.len(), FEATURES);
+        let cells = observation.width * observation.height;
+        let heads = observation.grid[..cells]
+            .iter()
+            .filter(|cell| **cell == 1.0);
+        assert_eq!(heads.count(), 1);
+    }
+
+    #[test]
+    fn reset_with_the_same_seed_gives_the_same_board() {
+        let first = SnakeEnv::new(7).reset();
+        let second = SnakeEnv::new(7).reset();
+        assert_eq!(first.grid, second.grid);
+        assert_eq!(first.features, second.features);
+    }
+
+    #[test]
+    fn eating_is_rewarded() {
+        let mut env = SnakeEnv::new(3);
+        env.reset();
+        let (pos, direction) = head(&mut env);
+        let world = env.app.world_mut();
+        let ahead = world.resource::<Arena>().grid().step(pos, direction);
+        let mut food = world.query_filtered::<Entity, With<Food>>();
+        for entity in food.iter(world).collect::<Vec<_>>() {
+            world.despawn(entity);
+        }
+        world.spawn((Food::BASIC, ahead));
+        let action = Action::ALL
+            .into_iter()
+            .find(|action| action.direction() == direction)
+            .unwrap();
+        let (_, reward, done) = env.step(action);
+        assert!(!done);
+        assert_eq!(reward, 1.0);
+    }
+
+    #[test]
+    fn running_into_the_wall_ends_the_episode() {
+        let mut env = SnakeEnv::new(5);
+        env.reset();
+        let (_, direction) = head(&mut env);
+        let action = Action::ALL
+            .into_iter()
+            .find(|action| action.direction() == direction)
+            .unwrap();
+        let (width, height) = Rules::default().arena_size;
+        let end = (0..width + height).find_map(|_| {
+            let (_, reward, done) = env.step(action);
+            done.then_some(reward)
+        });
+        assert_eq!(end, Some(DEATH_REWARD));
+    }
+
+    #[test]
+    fn observation_follows_the_arena_size() {
+        let mut env = SnakeEnv::new(9);
+        env.app.world_mut().resource_mut::<Rules>().arena_size = (12, 8);
+        let observation = env.reset();
+        assert_eq!((observation.width, observation.height), (12, 8));
+        assert_eq!(observation.grid.len(), CHANNELS * 12 * 8);
+        assert_eq!(env.app.world().resource::<Arena>().grid().size(), (12, 8));
+    }
+}
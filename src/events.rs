@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::rival::Rival;
+use crate::{
+    AppState, DeathCause, Direction, Food, GameSet, Position, RestartEvent, SnakeHead, StepEvent,
+};
+
+/// `snake` ate `kind` at `position`, scoring `points` times the rules'
+/// points per food, and grows by `segments` segments, one per step.
+///
+/// Written by the game whenever a snake's head reaches food, the player's
+/// and co-op players' as well as rivals', once it has been eaten and the
+/// snake has grown. Only what the players eat scores. Writing one yourself
+/// scores it as if eaten, but doesn't grow the snake.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct FoodEaten {
+    pub snake: Entity,
+    pub kind: Food,
+    pub position: Position,
+    pub segments: u32,
+    pub points: u32,
+}
+
+/// The heading of `snake` changed between two steps, from `from` to `to`.
+///
+/// Only written for turns the snake actually made, not for every key press,
+/// and not when a new run starts.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DirectionChanged {
+    pub snake: Entity,
+    pub from: Direction,
+    pub to: Direction,
+}
+
+/// Asks for `snake` to head towards `direction` from its next step on.
+///
+/// Written by whatever steers a snake: the player's controls, the autopilot,
+/// replays and the training environment. Writing one yourself steers it the
+/// same way, by the same rules: a snake takes at most one turn per step, and
+/// never one that runs it back into its own neck.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DirectionChangeRequest {
+    pub snake: Entity,
+    pub direction: Direction,
+}
+
+/// The run ended when `snake` died, and why. In co-op, `snake` is the last
+/// of the players' snakes to fall.
+///
+/// Written by the game when the snake dies. Writing one yourself ends an
+/// endless run and starts the next.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct SnakeDied {
+    pub snake: Entity,
+    pub cause: DeathCause,
+}
+
+/// One step of the snake has been played out: it moved, ate and grew, or
+/// died. `step` counts the steps of the current run, starting from 1.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TickCompleted {
+    pub step: u64,
+}
+
+/// Reads the [`FoodEaten`] of the snakes people steer, leaving out what the
+/// rivals eat.
+#[derive(SystemParam)]
+pub(crate) struct PlayerMeals<'w, 's> {
+    reader: MessageReader<'w, 's, FoodEaten>,
+    rivals: Query<'w, 's, (), With<Rival>>,
+}
+
+impl PlayerMeals<'_, '_> {
+    pub fn read(&mut self) -> impl Iterator<Item = &FoodEaten> {
+        let rivals = &self.rivals;
+        self.reader
+            .read()
+            .filter(|eaten| !rivals.contains(eaten.snake))
+    }
+}
+
+/// Lets the game tell other plugins what happened. Part of the game's core,
+/// so it runs headless too.
+pub(crate) struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<FoodEaten>()
+            .add_message::<DirectionChanged>()
+            .add_message::<DirectionChangeRequest>()
+            .add_message::<SnakeDied>()
+            .add_message::<TickCompleted>()
+            .init_resource::<Ticks>()
+            .add_systems(OnEnter(AppState::Playing), reset_ticks)
+            .add_systems(
+                Update,
+                (
+                    report_turns.after(GameSet::Movement),
+                    complete_tick.after(report_turns).after(GameSet::Spawning),
+                ),
+            );
+    }
+}
+
+/// Where the current run stands, for the messages above.
+#[derive(Resource, Default)]
+struct Ticks {
+    step: u64,
+    /// Each head's heading as of the last step.
+    headings: HashMap<Entity, Direction>,
+}
+
+fn reset_ticks(mut ticks: ResMut<Ticks>) {
+    *ticks = Ticks::default();
+}
+
+fn report_turns(
+    mut step_reader: MessageReader<StepEvent>,
+    mut turn_writer: MessageWriter<DirectionChanged>,
+    mut ticks: ResMut<Ticks>,
+    heads: Query<(Entity, &SnakeHead)>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let mut headings = HashMap::new();
+    for (snake, head) in heads.iter() {
+        // A new head means a new snake, which hasn't turned yet.
+        if let Some(from) = ticks.headings.get(&snake).copied()
+            && from != head.direction
+        {
+            turn_writer.write(DirectionChanged {
+                snake,
+                from,
+                to: head.direction,
+            });
+        }
+        headings.insert(snake, head.direction);
+    }
+    ticks.headings = headings;
+}
+
+fn complete_tick(
+    mut step_reader: MessageReader<StepEvent>,
+    mut died_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    mut tick_writer: MessageWriter<TickCompleted>,
+    mut ticks: ResMut<Ticks>,
+) {
+    if step_reader.read().next().is_some() {
+        ticks.step += 1;
+        tick_writer.write(TickCompleted { step: ticks.step });
+    }
+    let died = died_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        ticks.step = 0;
+    }
+}
@@ -0,0 +1,294 @@
+use accesskit::{Node as AccessNode, Role};
+use bevy::a11y::AccessibilityNode;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+
+const FOCUSED_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+const UNFOCUSED_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const HINT_COLOR: Color = Color::srgb(0.55, 0.55, 0.55);
+
+/// A menu line that can be picked with the arrow keys, visited in `order`.
+/// Its text is highlighted while it has the focus.
+#[derive(Component)]
+pub struct Focusable {
+    pub order: usize,
+}
+
+impl Focusable {
+    pub fn new(order: usize) -> Self {
+        Self { order }
+    }
+}
+
+/// Which [`Focusable`] has the focus. It's kept by order rather than by
+/// entity, so screens that rebuild their lines keep their place.
+#[derive(Resource, Default)]
+pub struct Focus {
+    pub order: usize,
+}
+
+/// What the player did to the focused line, or to the screen as a whole.
+#[derive(Message)]
+pub enum FocusInput {
+    /// Enter on the line with this order.
+    Activate(usize),
+    /// Left or right on the line with this order, as -1 or 1.
+    Nudge(usize, i32),
+    /// Escape, to leave the screen or go up a level.
+    Back,
+}
+
+/// Whatever the player touched last, so button hints match what's in their
+/// hands.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+impl InputDevice {
+    /// Locale key of the hint bar shown under focusable lines.
+    fn hints(self) -> &'static str {
+        match self {
+            Self::Keyboard => "hints.keyboard",
+            Self::Gamepad => "hints.gamepad",
+        }
+    }
+}
+
+/// A column of focusable lines that may not fit the window. It scrolls so
+/// the focused line stays in view.
+#[derive(Component)]
+pub struct FocusScroll;
+
+/// The bar along the bottom of the screen explaining the menu controls.
+#[derive(Component)]
+struct FocusHints;
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Focus>()
+            .init_resource::<InputDevice>()
+            .init_resource::<InputFocus>()
+            .add_message::<FocusInput>()
+            .add_systems(
+                Update,
+                (
+                    reset_focus.run_if(state_changed::<AppState>.or(state_changed::<PauseState>)),
+                    navigate_focus,
+                    highlight_focus,
+                    (describe_focusables, follow_focus).chain(),
+                    scroll_to_focus,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, (track_input_device, show_focus_hints).chain());
+    }
+}
+
+/// Every screen starts with its first line focused.
+fn reset_focus(mut focus: ResMut<Focus>) {
+    focus.order = 0;
+}
+
+/// Up and down move the focus, wrapping around, on the keyboard or a
+/// gamepad's d-pad. Only screens with focusable lines take any of this input.
+pub fn navigate_focus(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut focus: ResMut<Focus>,
+    mut input_writer: MessageWriter<FocusInput>,
+    focusables: Query<&Focusable>,
+) {
+    let Some(last) = focusables.iter().map(|focusable| focusable.order).max() else {
+        return;
+    };
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keyboard_input.just_pressed(key)
+            || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+    let count = last + 1;
+    if pressed(KeyCode::ArrowUp, GamepadButton::DPadUp) {
+        focus.order = (focus.order + count - 1) % count;
+    }
+    if pressed(KeyCode::ArrowDown, GamepadButton::DPadDown) {
+        focus.order = (focus.order + 1) % count;
+    }
+    if focus.order > last {
+        focus.order = last;
+    }
+    if pressed(KeyCode::Enter, GamepadButton::South) {
+        input_writer.write(FocusInput::Activate(focus.order));
+    }
+    if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft) {
+        input_writer.write(FocusInput::Nudge(focus.order, -1));
+    }
+    if pressed(KeyCode::ArrowRight, GamepadButton::DPadRight) {
+        input_writer.write(FocusInput::Nudge(focus.order, 1));
+    }
+    if pressed(KeyCode::Escape, GamepadButton::East) {
+        input_writer.write(FocusInput::Back);
+    }
+}
+
+fn highlight_focus(focus: Res<Focus>, mut lines: Query<(&Focusable, &mut TextColor)>) {
+    for (focusable, mut color) in lines.iter_mut() {
+        let wanted = if focusable.order == focus.order {
+            FOCUSED_COLOR
+        } else {
+            UNFOCUSED_COLOR
+        };
+        color.set_if_neq(TextColor(wanted));
+    }
+}
+
+/// Focusable lines that are new or have changed their text.
+type RelabeledLines<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Text,
+        Option<&'static mut AccessibilityNode>,
+    ),
+    (With<Focusable>, Changed<Text>),
+>;
+
+/// Lets screen readers see every focusable line as a button named after its
+/// text.
+fn describe_focusables(mut commands: Commands, mut lines: RelabeledLines) {
+    for (entity, text, node) in lines.iter_mut() {
+        match node {
+            Some(mut node) => node.set_label(text.0.as_str()),
+            None => {
+                let mut node = AccessNode::new(Role::Button);
+                node.set_label(text.0.as_str());
+                commands
+                    .entity(entity)
+                    .insert(AccessibilityNode::from(node));
+            }
+        }
+    }
+}
+
+/// Hands the focused line to the platform's accessibility layer, so screen
+/// readers follow the focus around. Screens without focusable lines can
+/// point it at something of their own.
+fn follow_focus(
+    focus: Res<Focus>,
+    mut input_focus: ResMut<InputFocus>,
+    lines: Query<(Entity, &Focusable), With<AccessibilityNode>>,
+    nodes: Query<(), With<AccessibilityNode>>,
+) {
+    // Nothing reaches screen readers while the focus is on something gone.
+    if input_focus
+        .get()
+        .is_some_and(|entity| !nodes.contains(entity))
+    {
+        input_focus.clear();
+    }
+    if let Some((entity, _)) = lines
+        .iter()
+        .find(|(_, focusable)| focusable.order == focus.order)
+        && input_focus.get() != Some(entity)
+    {
+        input_focus.set(entity);
+    }
+}
+
+/// Scrolls each [`FocusScroll`] column just far enough to show the whole
+/// focused line, going by where the last layout put everything.
+fn scroll_to_focus(
+    focus: Res<Focus>,
+    lines: Query<(&Focusable, &ComputedNode, &UiGlobalTransform)>,
+    mut columns: Query<(&mut ScrollPosition, &ComputedNode, &UiGlobalTransform), With<FocusScroll>>,
+) {
+    let Some((_, line, line_transform)) = lines
+        .iter()
+        .find(|(focusable, _, _)| focusable.order == focus.order)
+    else {
+        return;
+    };
+    for (mut scroll, column, column_transform) in columns.iter_mut() {
+        // Laid out yet? Both are in physical pixels until then.
+        if column.size().y <= 0.0 {
+            continue;
+        }
+        let top = column_transform.translation.y - column.size().y / 2.0;
+        let bottom = top + column.size().y;
+        let line_top = line_transform.translation.y - line.size().y / 2.0;
+        let line_bottom = line_top + line.size().y;
+        let by = if line_top < top {
+            line_top - top
+        } else if line_bottom > bottom {
+            line_bottom - bottom
+        } else {
+            continue;
+        };
+        scroll.y = (scroll.y + by * column.inverse_scale_factor()).max(0.0);
+    }
+}
+
+fn track_input_device(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut device: ResMut<InputDevice>,
+) {
+    if keyboard_input.get_just_pressed().next().is_some() {
+        device.set_if_neq(InputDevice::Keyboard);
+    } else if gamepads
+        .iter()
+        .any(|gamepad| gamepad.get_just_pressed().next().is_some())
+    {
+        device.set_if_neq(InputDevice::Gamepad);
+    }
+}
+
+/// Keeps a hint bar up on any screen with focusable lines, worded for the
+/// device in use.
+fn show_focus_hints(
+    mut commands: Commands,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    focusables: Query<(), With<Focusable>>,
+    mut hints: Query<(Entity, &mut Text), With<FocusHints>>,
+) {
+    if focusables.is_empty() {
+        for (entity, _) in hints.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let text = locale.get(device.hints());
+    if let Some((_, mut hint)) = hints.iter_mut().next() {
+        if hint.0 != text {
+            hint.0 = text.to_string();
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(HINT_COLOR),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        // Above the dimmed board behind the pause menu.
+        GlobalZIndex(1),
+        FocusHints,
+    ));
+}
@@ -0,0 +1,530 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::arena::Arena;
+use crate::{Direction, Position};
+
+/// Distance between hex rows, relative to the distance between neighbors.
+const HEX_ROW_HEIGHT: f32 = 0.866_025_4;
+
+/// Every direction in clockwise order, starting from up.
+const COMPASS: [Direction; 8] = [
+    Direction::Up,
+    Direction::UpRight,
+    Direction::Right,
+    Direction::DownRight,
+    Direction::Down,
+    Direction::DownLeft,
+    Direction::Left,
+    Direction::UpLeft,
+];
+
+/// The board's cell layout. Decides which cells are neighbors, where they are
+/// drawn and which directions the snake can take.
+pub trait Grid: Sync {
+    /// The cell one step from `pos` in `direction`.
+    fn step(&self, pos: Position, direction: Direction) -> Position;
+
+    /// Columns across and rows up the arena.
+    fn size(&self) -> (u32, u32);
+
+    /// Column and row of `pos` within the arena, or `None` if it's outside.
+    fn to_cell(&self, pos: Position) -> Option<(u32, u32)>;
+
+    /// The inverse of [`Grid::to_cell`].
+    fn cell_position(&self, column: u32, row: u32) -> Position;
+
+    /// The cell `pos` ends up on when the arena's edges wrap around to the
+    /// opposite side.
+    fn wrap(&self, pos: Position) -> Position;
+
+    /// Center of `pos` with the arena centered on the origin.
+    fn to_world(&self, pos: Position, tile_size: f32) -> Vec2;
+
+    /// The cell under `world`, the inverse of [`Grid::to_world`], or `None`
+    /// if that's off the arena.
+    fn cell_at(&self, world: Vec2, tile_size: f32) -> Option<Position>;
+
+    /// Largest distance between neighbors at which the arena fits in `area`.
+    fn tile_size(&self, area: Vec2) -> f32;
+
+    /// Maps a requested direction onto one this grid supports, given that the
+    /// snake is heading `current`.
+    fn steer(&self, current: Direction, wanted: Direction) -> Direction;
+
+    /// The direction a freshly spawned snake is heading.
+    fn start_direction(&self) -> Direction;
+
+    /// The two cells a diagonal step from `pos` passes between. The snake
+    /// can't slip through a gap in its own body, so the step is fatal when
+    /// both are body segments.
+    fn flanking_cells(&self, _pos: Position, _direction: Direction) -> Option<[Position; 2]> {
+        None
+    }
+
+    /// The next direction round from `current` that this grid supports,
+    /// going clockwise or counterclockwise. Turning this way twice on the
+    /// square grid is a U-turn; finer grids take more turns.
+    fn rotate(&self, current: Direction, clockwise: bool) -> Direction {
+        let supported = COMPASS
+            .into_iter()
+            .filter(|direction| self.steer(*direction, *direction) == *direction)
+            .collect::<Vec<_>>();
+        let Some(index) = supported.iter().position(|direction| *direction == current) else {
+            return current;
+        };
+        let offset = if clockwise { 1 } else { supported.len() - 1 };
+        supported[(index + offset) % supported.len()]
+    }
+
+    /// Cells the snake could reach from `pos` in one step, whichever way it
+    /// was heading.
+    fn neighbors(&self, pos: Position) -> Vec<Position> {
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| self.steer(*direction, *direction) == *direction)
+            .map(|direction| self.step(pos, direction))
+            .collect()
+    }
+
+    fn contains(&self, pos: Position) -> bool {
+        self.to_cell(pos).is_some()
+    }
+
+    fn random_cell(&self, rng: &mut StdRng) -> Position {
+        let (width, height) = self.size();
+        self.cell_position(rng.random_range(0..width), rng.random_range(0..height))
+    }
+}
+
+/// Which [`Grid`] the current run is played on.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridShape {
+    #[default]
+    Square,
+    Freestyle,
+    Hex,
+}
+
+impl GridShape {
+    /// The grid on an arena `width` cells across and `height` cells up.
+    pub fn sized(self, width: u32, height: u32) -> Arc<dyn Grid + Send> {
+        match self {
+            Self::Square => Arc::new(SquareGrid { width, height }),
+            Self::Freestyle => Arc::new(FreestyleGrid(SquareGrid { width, height })),
+            Self::Hex => Arc::new(HexGrid { width, height }),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Square => Self::Freestyle,
+            Self::Freestyle => Self::Hex,
+            Self::Hex => Self::Square,
+        }
+    }
+
+    /// Locale key of the shape's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Square => "grid_shape.square",
+            Self::Freestyle => "grid_shape.freestyle",
+            Self::Hex => "grid_shape.hex",
+        }
+    }
+}
+
+/// Where the board sits in a window: the run's grid, drawn as large as the
+/// arena fits, centered on the origin.
+#[derive(Clone, Copy)]
+pub struct Layout<'a> {
+    pub grid: &'a dyn Grid,
+    /// Distance between neighboring cells.
+    pub cell_size: f32,
+}
+
+impl<'a> Layout<'a> {
+    pub fn new(grid: &'a dyn Grid, window_size: Vec2) -> Self {
+        Self {
+            grid,
+            cell_size: grid.tile_size(window_size),
+        }
+    }
+
+    /// Center of `pos` in the world.
+    pub fn cell_to_world(&self, pos: Position) -> Vec2 {
+        self.grid.to_world(pos, self.cell_size)
+    }
+
+    /// The cell under `world`, if it's on the arena. For picking cells with
+    /// the mouse or a finger, once the cursor is in world space.
+    pub fn world_to_cell(&self, world: Vec2) -> Option<Position> {
+        self.grid.cell_at(world, self.cell_size)
+    }
+
+    pub fn in_bounds(&self, pos: Position) -> bool {
+        self.grid.contains(pos)
+    }
+}
+
+/// The [`Layout`] of the current run in the primary window.
+#[derive(SystemParam)]
+pub struct WindowLayout<'w, 's> {
+    arena: Res<'w, Arena>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl WindowLayout<'_, '_> {
+    /// `None` while there's no window to lay the board out in.
+    pub fn get(&self) -> Option<Layout<'_>> {
+        let window = self.windows.single().ok()?;
+        Some(Layout::new(self.arena.grid(), window.size()))
+    }
+}
+
+/// Plain rows and columns; `Position` is the column and row.
+#[derive(Clone, Copy)]
+pub struct SquareGrid {
+    width: u32,
+    height: u32,
+}
+
+impl Grid for SquareGrid {
+    fn step(&self, pos: Position, direction: Direction) -> Position {
+        let (dx, dy) = match direction {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        };
+        Position {
+            x: pos.x + dx,
+            y: pos.y + dy,
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn to_cell(&self, pos: Position) -> Option<(u32, u32)> {
+        let column = u32::try_from(pos.x).ok().filter(|x| *x < self.width)?;
+        let row = u32::try_from(pos.y).ok().filter(|y| *y < self.height)?;
+        Some((column, row))
+    }
+
+    fn cell_position(&self, column: u32, row: u32) -> Position {
+        Position {
+            x: column as i32,
+            y: row as i32,
+        }
+    }
+
+    fn wrap(&self, pos: Position) -> Position {
+        Position {
+            x: pos.x.rem_euclid(self.width as i32),
+            y: pos.y.rem_euclid(self.height as i32),
+        }
+    }
+
+    fn to_world(&self, pos: Position, tile_size: f32) -> Vec2 {
+        Vec2::new(
+            (pos.x as f32 - (self.width as f32 - 1.) / 2.) * tile_size,
+            (pos.y as f32 - (self.height as f32 - 1.) / 2.) * tile_size,
+        )
+    }
+
+    fn cell_at(&self, world: Vec2, tile_size: f32) -> Option<Position> {
+        let pos = Position {
+            x: (world.x / tile_size + (self.width as f32 - 1.) / 2.).round() as i32,
+            y: (world.y / tile_size + (self.height as f32 - 1.) / 2.).round() as i32,
+        };
+        self.contains(pos).then_some(pos)
+    }
+
+    fn tile_size(&self, area: Vec2) -> f32 {
+        (area.x / self.width as f32).min(area.y / self.height as f32)
+    }
+
+    fn steer(&self, _current: Direction, wanted: Direction) -> Direction {
+        match wanted {
+            Direction::UpLeft | Direction::DownLeft => Direction::Left,
+            Direction::UpRight | Direction::DownRight => Direction::Right,
+            _ => wanted,
+        }
+    }
+
+    fn start_direction(&self) -> Direction {
+        Direction::Up
+    }
+}
+
+/// The square grid with diagonal moves allowed.
+pub struct FreestyleGrid(SquareGrid);
+
+impl Grid for FreestyleGrid {
+    fn step(&self, pos: Position, direction: Direction) -> Position {
+        self.0.step(pos, direction)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.0.size()
+    }
+
+    fn to_cell(&self, pos: Position) -> Option<(u32, u32)> {
+        self.0.to_cell(pos)
+    }
+
+    fn cell_position(&self, column: u32, row: u32) -> Position {
+        self.0.cell_position(column, row)
+    }
+
+    fn wrap(&self, pos: Position) -> Position {
+        self.0.wrap(pos)
+    }
+
+    fn to_world(&self, pos: Position, tile_size: f32) -> Vec2 {
+        self.0.to_world(pos, tile_size)
+    }
+
+    fn cell_at(&self, world: Vec2, tile_size: f32) -> Option<Position> {
+        self.0.cell_at(world, tile_size)
+    }
+
+    fn tile_size(&self, area: Vec2) -> f32 {
+        self.0.tile_size(area)
+    }
+
+    fn steer(&self, _current: Direction, wanted: Direction) -> Direction {
+        wanted
+    }
+
+    fn start_direction(&self) -> Direction {
+        self.0.start_direction()
+    }
+
+    fn flanking_cells(&self, pos: Position, direction: Direction) -> Option<[Position; 2]> {
+        let (horizontal, vertical) = match direction {
+            Direction::UpLeft => (Direction::Left, Direction::Up),
+            Direction::UpRight => (Direction::Right, Direction::Up),
+            Direction::DownLeft => (Direction::Left, Direction::Down),
+            Direction::DownRight => (Direction::Right, Direction::Down),
+            _ => return None,
+        };
+        Some([self.step(pos, horizontal), self.step(pos, vertical)])
+    }
+}
+
+/// Pointy-top hexagons in axial coordinates: `x` runs along a row and `y`
+/// runs up and to the right. The arena is laid out as offset rows, with odd
+/// rows shifted half a cell right, so it stays rectangular.
+pub struct HexGrid {
+    width: u32,
+    height: u32,
+}
+
+impl HexGrid {
+    /// Column of `pos` in its offset row.
+    fn column(pos: Position) -> i32 {
+        pos.x + pos.y.div_euclid(2)
+    }
+}
+
+impl Grid for HexGrid {
+    fn step(&self, pos: Position, direction: Direction) -> Position {
+        let (dx, dy) = match direction {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::Up | Direction::UpRight => (0, 1),
+            Direction::Down | Direction::DownLeft => (0, -1),
+            Direction::DownRight => (1, -1),
+        };
+        Position {
+            x: pos.x + dx,
+            y: pos.y + dy,
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn to_cell(&self, pos: Position) -> Option<(u32, u32)> {
+        let column = u32::try_from(Self::column(pos))
+            .ok()
+            .filter(|x| *x < self.width)?;
+        let row = u32::try_from(pos.y).ok().filter(|y| *y < self.height)?;
+        Some((column, row))
+    }
+
+    fn cell_position(&self, column: u32, row: u32) -> Position {
+        Position {
+            x: column as i32 - (row as i32).div_euclid(2),
+            y: row as i32,
+        }
+    }
+
+    fn wrap(&self, pos: Position) -> Position {
+        self.cell_position(
+            Self::column(pos).rem_euclid(self.width as i32) as u32,
+            pos.y.rem_euclid(self.height as i32) as u32,
+        )
+    }
+
+    fn to_world(&self, pos: Position, tile_size: f32) -> Vec2 {
+        let shift = pos.y.rem_euclid(2) as f32 / 2.;
+        Vec2::new(
+            (Self::column(pos) as f32 + shift - (self.width as f32 - 0.5) / 2.) * tile_size,
+            (pos.y as f32 - (self.height as f32 - 1.) / 2.) * tile_size * HEX_ROW_HEIGHT,
+        )
+    }
+
+    /// A hexagon is every point closer to its center than to any other, so
+    /// this takes the nearest center among the rows around `world`.
+    fn cell_at(&self, world: Vec2, tile_size: f32) -> Option<Position> {
+        let row = (world.y / (tile_size * HEX_ROW_HEIGHT) + (self.height as f32 - 1.) / 2.).round()
+            as i32;
+        let pos = (row - 1..=row + 1)
+            .map(|y| {
+                let shift = y.rem_euclid(2) as f32 / 2.;
+                let column =
+                    (world.x / tile_size - shift + (self.width as f32 - 0.5) / 2.).round() as i32;
+                Position {
+                    x: column - y.div_euclid(2),
+                    y,
+                }
+            })
+            .min_by(|a, b| {
+                let distance = |pos| self.to_world(pos, tile_size).distance_squared(world);
+                distance(*a).total_cmp(&distance(*b))
+            })?;
+        self.contains(pos).then_some(pos)
+    }
+
+    fn tile_size(&self, area: Vec2) -> f32 {
+        let height = (self.height as f32 - 1.) * HEX_ROW_HEIGHT + 1.;
+        (area.x / (self.width as f32 + 0.5)).min(area.y / height)
+    }
+
+    /// Straight up and down aren't hex directions, so they lean towards the
+    /// side the snake is already heading.
+    fn steer(&self, current: Direction, wanted: Direction) -> Direction {
+        let leans_left = matches!(
+            current,
+            Direction::Left | Direction::UpLeft | Direction::DownLeft
+        );
+        match (wanted, leans_left) {
+            (Direction::Up, true) => Direction::UpLeft,
+            (Direction::Up, false) => Direction::UpRight,
+            (Direction::Down, true) => Direction::DownLeft,
+            (Direction::Down, false) => Direction::DownRight,
+            _ => wanted,
+        }
+    }
+
+    fn start_direction(&self) -> Direction {
+        Direction::UpRight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex() -> HexGrid {
+        HexGrid {
+            width: 6,
+            height: 5,
+        }
+    }
+
+    #[test]
+    fn hex_neighbors_are_six_cells_one_step_away() {
+        let grid = hex();
+        for y in 0..5 {
+            let pos = grid.cell_position(2, y);
+            let neighbors = grid.neighbors(pos);
+            assert_eq!(neighbors.len(), 6);
+            for neighbor in &neighbors {
+                assert_ne!(*neighbor, pos);
+                let distance = grid
+                    .to_world(*neighbor, 1.)
+                    .distance(grid.to_world(pos, 1.));
+                assert!(
+                    (distance - 1.).abs() < 1e-4,
+                    "{neighbor:?} is {distance} away"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hex_steps_back_the_way_they_came() {
+        let grid = hex();
+        let pos = grid.cell_position(3, 2);
+        for direction in Direction::ALL {
+            let direction = grid.steer(direction, direction);
+            let back = grid.steer(direction.opposite(), direction.opposite());
+            assert_eq!(grid.step(grid.step(pos, direction), back), pos);
+        }
+    }
+
+    #[test]
+    fn hex_wrap_comes_back_in_on_the_opposite_edge() {
+        let grid = hex();
+        for row in 0..5 {
+            let off_right = grid.step(grid.cell_position(5, row), Direction::Right);
+            assert_eq!(grid.wrap(off_right), grid.cell_position(0, row));
+            let off_left = grid.step(grid.cell_position(0, row), Direction::Left);
+            assert_eq!(grid.wrap(off_left), grid.cell_position(5, row));
+        }
+        // Off the top onto the bottom row, in the same column.
+        let off_top = grid.step(grid.cell_position(2, 4), Direction::UpRight);
+        assert_eq!(grid.wrap(off_top), grid.cell_position(2, 0));
+    }
+
+    #[test]
+    fn hex_wrap_leaves_the_arena_alone() {
+        let grid = hex();
+        for row in 0..5 {
+            for column in 0..6 {
+                let pos = grid.cell_position(column, row);
+                assert_eq!(grid.to_cell(pos), Some((column, row)));
+                assert_eq!(grid.wrap(pos), pos);
+                assert_eq!(grid.cell_at(grid.to_world(pos, 10.), 10.), Some(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn hex_steering_leans_up_and_down_towards_the_heading() {
+        let grid = hex();
+        assert_eq!(
+            grid.steer(Direction::Left, Direction::Up),
+            Direction::UpLeft
+        );
+        assert_eq!(
+            grid.steer(Direction::Right, Direction::Up),
+            Direction::UpRight
+        );
+        assert_eq!(
+            grid.steer(Direction::UpLeft, Direction::Down),
+            Direction::DownLeft
+        );
+        assert_eq!(
+            grid.steer(Direction::DownRight, Direction::Down),
+            Direction::DownRight
+        );
+        assert_eq!(grid.steer(Direction::Up, Direction::Left), Direction::Left);
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::attract;
+use crate::settings::Settings;
+use crate::touch::touch_enabled;
+use crate::{AppState, Direction, Player, PlayerMeals, SnakeDied, SnakeHead};
+
+const TURN_TAP: Duration = Duration::from_millis(10);
+const EAT_TAP: Duration = Duration::from_millis(25);
+const DEATH_BUZZ: Duration = Duration::from_millis(300);
+
+pub struct HapticsPlugin;
+
+impl Plugin for HapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            buzz.run_if(in_state(AppState::Playing))
+                .run_if(haptics_enabled)
+                .run_if(touch_enabled)
+                .run_if(attract::not_demo),
+        );
+    }
+}
+
+fn haptics_enabled(settings: Res<Settings>) -> bool {
+    settings.haptics
+}
+
+/// A light tap when the snake turns or eats and a long buzz when it dies,
+/// for players holding the device they're playing on.
+fn buzz(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut last_direction: Local<Option<Direction>>,
+    heads: Query<&SnakeHead, With<Player>>,
+) {
+    let ate = growth_reader.read().next().is_some();
+    let died = game_over_reader.read().next().is_some();
+    let direction = heads.single().ok().map(|head| head.direction);
+    let turned = last_direction.is_some() && direction.is_some() && direction != *last_direction;
+    *last_direction = direction;
+    let duration = if died {
+        DEATH_BUZZ
+    } else if ate {
+        EAT_TAP
+    } else if turned {
+        TURN_TAP
+    } else {
+        return;
+    };
+    if let Err(err) = vibrate(duration) {
+        warn!("Failed to vibrate: {}", err);
+    }
+}
+
+/// Browsers without the vibration API just ignore the call.
+#[cfg(target_arch = "wasm32")]
+fn vibrate(duration: Duration) -> Result<(), String> {
+    let window = web_sys::window().ok_or_else(|| "no window".to_string())?;
+    window
+        .navigator()
+        .vibrate_with_duration(duration.as_millis() as u32);
+    Ok(())
+}
+
+/// Asks the activity for its vibrator service. The app needs the
+/// `android.permission.VIBRATE` permission in its manifest.
+#[cfg(target_os = "android")]
+fn vibrate(duration: Duration) -> Result<(), String> {
+    use jni::JavaVM;
+    use jni::objects::JObject;
+
+    let context = ndk_context::android_context();
+    // SAFETY: both pointers are handed over by the Android glue and stay
+    // valid for as long as the app runs.
+    let vm = unsafe { JavaVM::from_raw(context.vm().cast()) }.map_err(|err| err.to_string())?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+    let mut env = vm.attach_current_thread().map_err(|err| err.to_string())?;
+    let name = env.new_string("vibrator").map_err(|err| err.to_string())?;
+    let vibrator = env
+        .call_method(
+            &activity,
+            "getSystemService",
+            "(Ljava/lang/String;)Ljava/lang/Object;",
+            &[(&name).into()],
+        )
+        .and_then(|service| service.l())
+        .map_err(|err| err.to_string())?;
+    env.call_method(
+        &vibrator,
+        "vibrate",
+        "(J)V",
+        &[(duration.as_millis() as i64).into()],
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Desktops and iOS have nothing to vibrate with here.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn vibrate(_duration: Duration) -> Result<(), String> {
+    Ok(())
+}
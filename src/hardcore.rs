@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::attract::not_demo;
+use crate::brain::autopilot_enabled;
+use crate::profile::Profile;
+use crate::replay::not_watching;
+use crate::rules::Rules;
+use crate::{AppState, GameMode, GameSet, GameState, RestartEvent, SnakeDied};
+
+/// Keeps the player's hardcore streak, which replays being watched, the
+/// attract demo and runs the autopilot had a hand in don't count towards.
+pub struct HardcorePlugin;
+
+impl Plugin for HardcorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopiloted>()
+            .add_systems(
+                Update,
+                (
+                    note_autopilot
+                        .before(GameSet::Spawning)
+                        .run_if(autopilot_enabled),
+                    track_streak_on_respawn.after(GameSet::Spawning),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(hardcore)
+                    .run_if(not_watching)
+                    .run_if(not_demo),
+            )
+            .add_systems(
+                OnExit(AppState::Playing),
+                track_streak_on_exit
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(hardcore)
+                    .run_if(not_watching)
+                    .run_if(not_demo),
+            );
+    }
+}
+
+/// Whether the autopilot steered at any point of the current run.
+#[derive(Resource, Default)]
+struct Autopiloted(bool);
+
+fn note_autopilot(mut autopiloted: ResMut<Autopiloted>) {
+    autopiloted.0 = true;
+}
+
+fn hardcore(rules: Res<Rules>) -> bool {
+    rules.hardcore.is_some()
+}
+
+/// Adds the run to the streak, or ends it, and saves the outcome. A run the
+/// autopilot had a hand in is left out either way.
+fn record_run(score: u32, rules: &Rules, profile: &mut Profile, autopiloted: &mut Autopiloted) {
+    if core::mem::take(&mut autopiloted.0) {
+        return;
+    }
+    if let Some(target) = rules.hardcore {
+        profile.record_hardcore_run(score >= target);
+        profile.save();
+    }
+}
+
+/// Restarting ends a run as surely as dying does.
+fn track_streak_on_respawn(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    rules: Res<Rules>,
+    mut profile: ResMut<Profile>,
+    mut autopiloted: ResMut<Autopiloted>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        record_run(
+            game_state.last_score,
+            &rules,
+            &mut profile,
+            &mut autopiloted,
+        );
+    }
+}
+
+/// Leaving for the menu ends the run too, unless it never got going, as
+/// when the player quits right after a death.
+fn track_streak_on_exit(
+    game_state: Res<GameState>,
+    rules: Res<Rules>,
+    mut profile: ResMut<Profile>,
+    mut autopiloted: ResMut<Autopiloted>,
+) {
+    if game_state.score > 0 {
+        record_run(game_state.score, &rules, &mut profile, &mut autopiloted);
+    }
+    autopiloted.0 = false;
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use std::borrow::Cow;
+
+use crate::invulnerable::Invulnerable;
+use crate::pause::on_game_timer;
+use crate::rules::Rules;
+use crate::{
+    AppState, DeathCause, GameSet, Player, Position, Size, SnakeCrashed, SnakeDied, SnakeSegment,
+    SnakeSegments, snake_movement,
+};
+
+const HAZARD_COLOR: Color = Color::srgb(0.95, 0.85, 0.1);
+const HAZARD_STEP: Duration = Duration::from_millis(250);
+
+/// Walks its patrol route in a loop and kills the snake on contact.
+#[derive(Component, Clone)]
+pub struct Hazard {
+    /// Corners of the route, visited in order and then from the first again.
+    /// Neighboring corners share a row, column or diagonal. Empty for a
+    /// hazard that stays put.
+    waypoints: Cow<'static, [Position]>,
+    /// Index of the corner it's heading for.
+    target: usize,
+}
+
+impl Hazard {
+    pub fn new(waypoints: Cow<'static, [Position]>) -> Self {
+        Self {
+            target: 1 % waypoints.len(),
+            waypoints,
+        }
+    }
+
+    /// A hazard that stays where it's spawned.
+    pub fn stationary() -> Self {
+        Self {
+            waypoints: Cow::Borrowed(&[]),
+            target: 0,
+        }
+    }
+
+    /// Whether `pos` is anywhere on the patrol route.
+    pub fn covers(&self, pos: Position) -> bool {
+        let mut corners = self
+            .waypoints
+            .iter()
+            .zip(self.waypoints.iter().cycle().skip(1));
+        corners.any(|(from, to)| {
+            let mut cell = *from;
+            loop {
+                if cell == pos {
+                    return true;
+                }
+                if cell == *to {
+                    return false;
+                }
+                cell = step_towards(cell, *to);
+            }
+        })
+    }
+}
+
+fn step_towards(from: Position, to: Position) -> Position {
+    Position {
+        x: from.x + (to.x - from.x).signum(),
+        y: from.y + (to.y - from.y).signum(),
+    }
+}
+
+/// A hazard ran into the snake, or the snake into it, at `position`.
+#[derive(Message)]
+pub struct HazardHit {
+    pub position: Position,
+}
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<HazardHit>()
+            .add_systems(
+                FixedUpdate,
+                patrol
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(on_game_timer(HAZARD_STEP)),
+            )
+            .add_systems(
+                Update,
+                hazard_contact
+                    .in_set(GameSet::Movement)
+                    .after(snake_movement)
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+pub fn spawn_hazard(commands: &mut Commands, position: Position, hazard: Hazard) {
+    commands.spawn((
+        Sprite::from_color(HAZARD_COLOR, Vec2::ONE),
+        Transform::default(),
+        position,
+        Size::square(0.7),
+        hazard,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn patrol(mut hazards: Query<(&mut Hazard, &mut Position)>) {
+    for (mut hazard, mut position) in hazards.iter_mut() {
+        let Some(target) = hazard.waypoints.get(hazard.target).copied() else {
+            continue;
+        };
+        *position = step_towards(*position, target);
+        if *position == target {
+            hazard.target = (hazard.target + 1) % hazard.waypoints.len();
+        }
+    }
+}
+
+/// Checks every segment, not just the head, since hazards also walk into the
+/// snake's side. Only the player's snake ends the run; any other snake
+/// crashes, like it would into a wall.
+fn hazard_contact(
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    mut crash_writer: MessageWriter<SnakeCrashed>,
+    mut hit_writer: MessageWriter<HazardHit>,
+    rules: Res<Rules>,
+    hazards: Query<&Position, With<Hazard>>,
+    bodies: Query<(Entity, &SnakeSegments, Has<Player>), Without<Invulnerable>>,
+    segments: Query<&Position, With<SnakeSegment>>,
+) {
+    for (snake, body, player) in bodies.iter() {
+        let Some(position) = hazards.iter().find(|hazard| {
+            body.iter()
+                .any(|segment| segments.get(*segment).ok() == Some(*hazard))
+        }) else {
+            continue;
+        };
+        if player {
+            hit_writer.write(HazardHit {
+                position: *position,
+            });
+        }
+        if player && !rules.coop {
+            game_over_writer.write(SnakeDied {
+                snake,
+                cause: DeathCause::Hazard,
+            });
+            return;
+        }
+        crash_writer.write(SnakeCrashed { snake });
+    }
+}
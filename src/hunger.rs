@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+
+use crate::countdown;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::{
+    AppState, DeathCause, GameMode, GameSet, Player, PlayerMeals, RestartEvent, SnakeDied,
+    StepClock, snake_eating,
+};
+
+const METER_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const FULL_COLOR: Color = Color::srgb(0.3, 0.85, 0.35);
+const STARVING_COLOR: Color = Color::srgb(0.9, 0.2, 0.15);
+
+/// Share of the meter each segment's worth of food fills back up.
+const REFILL: f32 = 0.4;
+
+/// How full the snake is, from 1 right after a run starts down to 0, which
+/// ends it.
+#[derive(Resource)]
+struct Hunger(f32);
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Component)]
+struct HungerFill;
+
+pub struct HungerPlugin;
+
+impl Plugin for HungerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hunger>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (reset_hunger, spawn_hunger_meter)
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(hunger_enabled),
+            )
+            .add_systems(
+                Update,
+                (
+                    feed.in_set(GameSet::Collisions).after(snake_eating),
+                    starve
+                        .in_set(GameSet::Collisions)
+                        .after(feed)
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    update_hunger_meter.after(starve),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(hunger_enabled),
+            );
+    }
+}
+
+fn hunger_enabled(rules: Res<Rules>) -> bool {
+    rules.hunger.is_some()
+}
+
+fn reset_hunger(mut hunger: ResMut<Hunger>) {
+    *hunger = Hunger::default();
+}
+
+/// Eating refills the meter; a new run starts full.
+fn feed(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    mut hunger: ResMut<Hunger>,
+) {
+    for growth in growth_reader.read() {
+        hunger.0 = (hunger.0 + REFILL * growth.segments as f32).min(1.0);
+    }
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        *hunger = Hunger::default();
+    }
+}
+
+fn starve(
+    step_clock: Res<StepClock>,
+    rules: Res<Rules>,
+    mut hunger: ResMut<Hunger>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    heads: Query<Entity, With<Player>>,
+) {
+    let Some(full) = rules.hunger else {
+        return;
+    };
+    hunger.0 -= step_clock.delta().as_secs_f32() / full.as_secs_f32();
+    if hunger.0 <= 0.0 {
+        hunger.0 = 0.0;
+        if let Some(snake) = heads.iter().next() {
+            game_over_writer.write(SnakeDied {
+                snake,
+                cause: DeathCause::Hunger,
+            });
+        }
+    }
+}
+
+fn spawn_hunger_meter(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|meter| {
+            meter
+                .spawn((
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(METER_COLOR),
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(FULL_COLOR),
+                    HungerFill,
+                ));
+        });
+}
+
+/// The fill shrinks and reddens as the snake gets hungrier.
+fn update_hunger_meter(
+    hunger: Res<Hunger>,
+    mut fills: Query<(&mut Node, &mut BackgroundColor), With<HungerFill>>,
+) {
+    let width = Val::Percent(100.0 * hunger.0);
+    let color = STARVING_COLOR.mix(&FULL_COLOR, hunger.0);
+    for (mut fill, mut background) in fills.iter_mut() {
+        if fill.width != width {
+            fill.width = width;
+        }
+        background.set_if_neq(BackgroundColor(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+    use core::time::Duration;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::{Food, FoodEaten, Position, SnakeTimer};
+
+    /// An endless run with no food on the board, where the snake goes
+    /// hungry after four steps.
+    fn hunger_app() -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            hunger: Some(SnakeTimer::default().base * 4),
+            food_count: 0,
+            ..default()
+        })
+        .add_plugins(HungerPlugin);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    /// Takes one step, returning how the run ended if it did.
+    fn step(app: &mut App) -> Option<DeathCause> {
+        let remaining = app.world().resource::<SnakeTimer>().timer.remaining();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(remaining));
+        app.update();
+        app.world()
+            .resource::<Messages<SnakeDied>>()
+            .iter_current_update_messages()
+            .next()
+            .map(|died| died.cause)
+    }
+
+    #[test]
+    fn the_snake_starves_without_food() {
+        let mut app = hunger_app();
+        let ends = (0..4).map(|_| step(&mut app)).collect::<Vec<_>>();
+        assert_eq!(ends, [None, None, None, Some(DeathCause::Hunger)]);
+    }
+
+    #[test]
+    fn eating_holds_off_starving() {
+        let mut app = hunger_app();
+        step(&mut app);
+        step(&mut app);
+        let world = app.world_mut();
+        let snake = world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .unwrap();
+        world.write_message(FoodEaten {
+            snake,
+            kind: Food::BASIC,
+            position: Position { x: 0, y: 0 },
+            segments: 1,
+            points: 1,
+        });
+        // Past the step it would have starved on otherwise.
+        let ends = (0..3).map(|_| step(&mut app)).collect::<Vec<_>>();
+        assert_eq!(ends, [None, None, None]);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::grid::Grid;
+use crate::invulnerable::Invulnerable;
+use crate::terrain::Terrain;
+use crate::{
+    AppState, DeathCause, GameSet, Player, Position, Size, SnakeDied, SnakeSegments, StepEvent,
+    snake_eating,
+};
+
+const HUNTER_COLOR: Color = Color::srgb(0.6, 0.1, 0.15);
+
+/// The hunter moves on every this many snake steps.
+const HUNTER_PACE: u32 = 2;
+
+/// Chases the snake's head and kills it on contact, but dies when it runs
+/// into the body.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Hunter {
+    /// Snake steps since it last moved.
+    rest: u32,
+}
+
+pub struct HunterPlugin;
+
+impl Plugin for HunterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            hunt.in_set(GameSet::Collisions)
+                .before(snake_eating)
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+pub fn spawn_hunter(commands: &mut Commands, position: Position, hunter: Hunter) {
+    commands.spawn((
+        Sprite::from_color(HUNTER_COLOR, Vec2::ONE),
+        Transform::default(),
+        position,
+        Size::square(0.75),
+        hunter,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn hunt(
+    mut commands: Commands,
+    mut step_reader: MessageReader<StepEvent>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    bodies: Query<(Entity, &SnakeSegments), With<Player>>,
+    mut hunters: Query<(Entity, &mut Hunter, &mut Position)>,
+    positions: Query<&Position, Without<Hunter>>,
+    shielded: Query<(), (With<Player>, With<Invulnerable>)>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let Ok((snake_entity, segments)) = bodies.single() else {
+        return;
+    };
+    let mut snake = segments.iter().filter_map(|e| positions.get(*e).ok());
+    let Some(head) = snake.next() else {
+        return;
+    };
+    let body: Vec<Position> = snake.copied().collect();
+
+    for (entity, mut hunter, mut position) in hunters.iter_mut() {
+        hunter.rest += 1;
+        if hunter.rest >= HUNTER_PACE && *position != *head && !body.contains(&position) {
+            hunter.rest = 0;
+            *position = next_step(arena.grid(), &terrain, &body, *position, *head);
+        }
+
+        if *position == *head {
+            // A shielded snake simply isn't caught yet.
+            if !shielded.is_empty() {
+                continue;
+            }
+            game_over_writer.write(SnakeDied {
+                snake: snake_entity,
+                cause: DeathCause::Hunter,
+            });
+        } else if body.contains(&position) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// The first cell on a shortest path from `from` to `to` around walls and the
+/// snake's body. When the body cuts it off, the hunter heads straight for the
+/// snake, body or not.
+fn next_step(
+    grid: &dyn Grid,
+    terrain: &Terrain,
+    body: &[Position],
+    from: Position,
+    to: Position,
+) -> Position {
+    let walkable = |pos: Position| grid.contains(pos) && !terrain.blocks(pos);
+
+    let mut came_from = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(cell) = queue.pop_front() {
+        if cell == to {
+            let mut step = to;
+            while came_from[&step] != from {
+                step = came_from[&step];
+            }
+            return step;
+        }
+        for next in grid.neighbors(cell) {
+            if walkable(next) && !body.contains(&next) && !came_from.contains_key(&next) {
+                came_from.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let target = grid.to_world(to, 1.0);
+    grid.neighbors(from)
+        .into_iter()
+        .filter(|cell| walkable(*cell))
+        .min_by(|a, b| {
+            let distance = |cell| grid.to_world(cell, 1.0).distance_squared(target);
+            distance(*a).total_cmp(&distance(*b))
+        })
+        .unwrap_or(from)
+}
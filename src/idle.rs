@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use core::f32::consts::TAU;
+
+use crate::countdown;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::{
+    AppState, GameMode, GameSet, GameState, PlayerMeals, RestartEvent, ScoreText, SnakeDied,
+    StepClock,
+};
+
+const DRAIN_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+
+/// Seconds between points lost once the score is draining.
+const DRAIN_INTERVAL: f32 = 1.0;
+
+/// Pulses per second of the score while it drains.
+const PULSE_RATE: f32 = 2.0;
+
+/// Seconds since the snake last ate, and since the score last lost a point.
+#[derive(Resource, Default)]
+struct IdleClock {
+    idle: f32,
+    drained: f32,
+}
+
+pub struct IdlePlugin;
+
+impl Plugin for IdlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleClock>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                reset_clock
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(idle_drain_enabled),
+            )
+            .add_systems(
+                Update,
+                (
+                    wake_up.after(GameSet::Collisions),
+                    drain_score
+                        .after(wake_up)
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    pulse_score.after(drain_score),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(idle_drain_enabled),
+            );
+    }
+}
+
+fn idle_drain_enabled(rules: Res<Rules>) -> bool {
+    rules.idle_drain.is_some()
+}
+
+fn reset_clock(mut clock: ResMut<IdleClock>) {
+    *clock = IdleClock::default();
+}
+
+/// Eating, dying or restarting stops the drain.
+fn wake_up(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    mut clock: ResMut<IdleClock>,
+) {
+    let ate = growth_reader.read().next().is_some();
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if ate || died || restarted {
+        *clock = IdleClock::default();
+    }
+}
+
+fn drain_score(
+    step_clock: Res<StepClock>,
+    rules: Res<Rules>,
+    mut clock: ResMut<IdleClock>,
+    mut game_state: ResMut<GameState>,
+) {
+    let Some(grace) = rules.idle_drain else {
+        return;
+    };
+    let delta = step_clock.delta().as_secs_f32();
+    clock.idle += delta;
+    if clock.idle < grace.as_secs_f32() {
+        return;
+    }
+    clock.drained += delta;
+    if clock.drained >= DRAIN_INTERVAL && game_state.score > 0 {
+        clock.drained -= DRAIN_INTERVAL;
+        game_state.score -= 1;
+    }
+}
+
+/// The score pulses red while it's draining.
+fn pulse_score(
+    time: Res<Time>,
+    rules: Res<Rules>,
+    clock: Res<IdleClock>,
+    game_state: Res<GameState>,
+    mut texts: Query<&mut TextColor, With<ScoreText>>,
+) {
+    let draining = rules
+        .idle_drain
+        .is_some_and(|grace| clock.idle >= grace.as_secs_f32())
+        && game_state.score > 0;
+    let color = if draining {
+        let pulse = (1.0 + (time.elapsed_secs() * PULSE_RATE * TAU).sin()) / 2.0;
+        Color::WHITE.mix(&DRAIN_COLOR, 0.5 + pulse / 2.0)
+    } else {
+        Color::WHITE
+    };
+    for mut text_color in texts.iter_mut() {
+        text_color.set_if_neq(TextColor(color));
+    }
+}
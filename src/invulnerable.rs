@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::{AppState, Player, SnakeSegments};
+
+/// How long a freshly spawned snake is shielded.
+const INVULNERABLE_TIME: Duration = Duration::from_secs(2);
+
+/// Time between the snake showing and hiding while shielded.
+const BLINK_INTERVAL: Duration = Duration::from_millis(125);
+
+/// Hazards and other snakes pass harmlessly through a snake that's just
+/// come back, so it can't be killed the moment it appears. Sits on the head
+/// of a player's snake. Blinks the snake until it runs out.
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+
+impl Default for Invulnerable {
+    fn default() -> Self {
+        Self(Timer::new(INVULNERABLE_TIME, TimerMode::Once))
+    }
+}
+
+pub struct InvulnerablePlugin;
+
+impl Plugin for InvulnerablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(shield_respawns).add_systems(
+            Update,
+            blink
+                .run_if(in_state(AppState::Playing))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Modes where the snake keeps coming back into a board that's still busy:
+/// roguelike runs with lives to spend, and rounds against rivals.
+fn respawns(rules: &Rules) -> bool {
+    rules.roguelike || rules.rivals > 0
+}
+
+fn shield_respawns(add: On<Add, Player>, mut commands: Commands, rules: Res<Rules>) {
+    if respawns(&rules) {
+        commands.entity(add.entity).insert(Invulnerable::default());
+    }
+}
+
+fn blink(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shielded: Query<(Entity, &mut Invulnerable, &SnakeSegments)>,
+    mut visibility: Query<&mut Visibility>,
+) {
+    for (entity, mut invulnerable, segments) in shielded.iter_mut() {
+        let finished = invulnerable.0.tick(time.delta()).is_finished();
+        let shown = finished
+            || (invulnerable.0.elapsed().as_millis() / BLINK_INTERVAL.as_millis())
+                .is_multiple_of(2);
+        for segment in segments.iter() {
+            if let Ok(mut visibility) = visibility.get_mut(*segment) {
+                visibility.set_if_neq(if shown {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                });
+            }
+        }
+        if finished {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::arena::Arena;
+use crate::items::ItemColor;
+use crate::profile::Profile;
+use crate::settings::{RenderStyle, Settings};
+use crate::{AppState, Ground, Position, Size};
+
+const FLOOR_TILE_SIZE: f32 = 0.95;
+
+/// Floor tiles are drawn behind everything standing on the board.
+const FLOOR_DEPTH: f32 = -100.0;
+
+/// An empty cell of the board, only drawn in this style.
+#[derive(Component)]
+struct FloorTile(Position);
+
+#[derive(Resource)]
+struct IsometricAssets {
+    /// A 2:1 diamond one unit across.
+    diamond: Handle<Mesh>,
+    floor: Handle<ColorMaterial>,
+}
+
+pub struct IsometricPlugin;
+
+impl Plugin for IsometricPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_isometric_assets)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_floor
+                    .after(crate::arena::sync_arena)
+                    .run_if(isometric_active),
+            )
+            .add_systems(
+                Update,
+                replace_sprites
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(isometric_active),
+            )
+            .add_systems(
+                PostUpdate,
+                isometric_translation
+                    .after(crate::GameSet::Rendering)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(isometric_active),
+            );
+    }
+}
+
+fn isometric_active(settings: Res<Settings>) -> bool {
+    settings.render_style == RenderStyle::Isometric
+}
+
+fn setup_isometric_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(IsometricAssets {
+        diamond: meshes.add(Rhombus::new(1.0, 0.5)),
+        floor: materials.add(Color::NONE),
+    });
+}
+
+/// Turns the board a quarter turn and squashes it to half height, for tiles
+/// one unit across.
+fn project(world: Vec2) -> Vec2 {
+    Vec2::new(world.x - world.y, (world.x + world.y) / 2.0) / 2.0
+}
+
+fn spawn_floor(
+    mut commands: Commands,
+    assets: Res<IsometricAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    profile: Res<Profile>,
+    arena: Res<Arena>,
+) {
+    if let Some(floor) = materials.get_mut(&assets.floor) {
+        floor.color = profile.theme.floor();
+    }
+    let grid = arena.grid();
+    for row in 0..arena.height() {
+        for column in 0..arena.width() {
+            commands.spawn((
+                Mesh2d(assets.diamond.clone()),
+                MeshMaterial2d(assets.floor.clone()),
+                Transform::default(),
+                FloorTile(grid.cell_position(column, row)),
+                DespawnOnExit(AppState::Playing),
+            ));
+        }
+    }
+}
+
+/// Swaps the square sprite of every new board entity for a diamond of the
+/// same color, or of its item color if it's drawn from a sprite sheet.
+fn replace_sprites(
+    mut commands: Commands,
+    assets: Res<IsometricAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    sprites: Query<(Entity, &Sprite, Option<&ItemColor>), With<Position>>,
+) {
+    for (entity, sprite, item_color) in sprites.iter() {
+        let color = item_color.map_or(sprite.color, |item_color| item_color.0);
+        commands.entity(entity).remove::<Sprite>().insert((
+            Mesh2d(assets.diamond.clone()),
+            MeshMaterial2d(materials.add(color)),
+        ));
+    }
+}
+
+/// Replaces the flat placement with the projected one. Cells lower on the
+/// screen are closer to the viewer, so they're drawn on top.
+fn isometric_translation(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
+    mut tiles: Query<(&Position, &Size, &mut Transform, Has<Ground>), With<Mesh2d>>,
+    mut floor: Query<(&FloorTile, &mut Transform), Without<Position>>,
+) {
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+    let grid = arena.grid();
+    let unit = |pos| project(grid.to_world(pos, 1.0));
+
+    let (min, max) = (0..arena.height())
+        .flat_map(|row| [0, arena.width() - 1].map(|column| (column, row)))
+        .map(|(column, row)| unit(grid.cell_position(column, row)))
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), corner| {
+            (min.min(corner), max.max(corner))
+        });
+    let extent = max - min + Vec2::new(1.0, 0.5);
+    let tile_size = (window.width() / extent.x).min(window.height() / extent.y);
+
+    let place = |pos, scale: Vec2, depth: f32| {
+        let projected = unit(pos);
+        Transform::from_translation((projected * tile_size).extend(depth - projected.y))
+            .with_scale((scale * tile_size).extend(1.0))
+    };
+    for (pos, size, mut transform, ground) in tiles.iter_mut() {
+        let depth = if ground { FLOOR_DEPTH / 2.0 } else { 0.0 };
+        *transform = place(*pos, Vec2::new(size.width, size.height), depth);
+    }
+    for (tile, mut transform) in floor.iter_mut() {
+        *transform = place(tile.0, Vec2::splat(FLOOR_TILE_SIZE), FLOOR_DEPTH);
+    }
+}
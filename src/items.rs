@@ -0,0 +1,283 @@
+use bevy::asset::LoadState;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::Food;
+use crate::powerup::PowerUp;
+
+/// Used when `assets/items.ron` can't be found, and by browser builds, which
+/// can't read the asset directory.
+const EMBEDDED_ITEMS: &str = include_str!("../assets/items.ron");
+
+/// A type of food or power-up, as defined in `assets/items.ron` or a mod
+/// pack. Power-ups only use the look, the effect and the weight.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ItemKind {
+    /// Fills the square drawn without a sprite, and the tile of render
+    /// styles that don't draw sprites.
+    pub color: (f32, f32, f32),
+    /// Drawn instead of a plain square, in its own colors.
+    pub sprite: Option<ItemSprite>,
+    /// Drawn by the ASCII render style.
+    pub glyph: char,
+    /// Score for eating it, in multiples of the rules' points per food.
+    pub points: u32,
+    /// Segments the snake grows by for eating it, one per step.
+    pub growth: u32,
+    pub effect: Option<Effect>,
+    /// Snake steps a ram lasts.
+    pub steps: u32,
+    /// Seconds of game time reversed controls last, counted on the step
+    /// clock so a faster snake doesn't shorten them.
+    pub seconds: u32,
+    /// How likely an endless run is to drop it, relative to the other
+    /// items. Zero never drops it.
+    pub weight: f32,
+}
+
+impl Default for ItemKind {
+    fn default() -> Self {
+        Self {
+            color: (1.0, 1.0, 1.0),
+            sprite: None,
+            glyph: '*',
+            points: 1,
+            growth: 1,
+            effect: None,
+            steps: 0,
+            seconds: 0,
+            weight: 0.0,
+        }
+    }
+}
+
+impl ItemKind {
+    pub fn color(&self) -> Color {
+        let (red, green, blue) = self.color;
+        Color::srgb(red, green, blue)
+    }
+}
+
+/// A frame of a sprite sheet under `assets/`, cut into `columns` by `rows`
+/// tiles of `tile` pixels and numbered left to right, then top to bottom.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ItemSprite {
+    pub image: String,
+    pub tile: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+    pub frame: usize,
+}
+
+impl Default for ItemSprite {
+    fn default() -> Self {
+        Self {
+            image: String::new(),
+            tile: (16, 16),
+            columns: 1,
+            rows: 1,
+            frame: 0,
+        }
+    }
+}
+
+/// What picking an item up does on top of feeding the snake.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Running into a wall knocks a hit point off it instead of ending the
+    /// run, see [`crate::powerup::Ram`].
+    Ram,
+    /// A trap: up and down, and left and right, swap places, see
+    /// [`crate::reverse::ReversedControls`].
+    Reverse,
+}
+
+/// What an endless run drops next.
+pub enum Loot {
+    Food(Food),
+    PowerUp(PowerUp),
+}
+
+/// Every food and power-up type in play. [`Food`] and [`PowerUp`] index
+/// into it.
+#[derive(Resource, Deserialize, Clone)]
+pub struct Items {
+    pub food: Vec<ItemKind>,
+    #[serde(default)]
+    pub power_ups: Vec<ItemKind>,
+}
+
+impl Items {
+    /// Reads `assets/items.ron`, falling back to the copy built into the
+    /// game if it's missing or broken.
+    pub fn load() -> Self {
+        read_items()
+            .and_then(|contents| match ron::from_str::<Self>(&contents) {
+                Ok(items) if !items.food.is_empty() && !items.power_ups.is_empty() => Some(items),
+                Ok(_) => {
+                    warn!("Failed to load items: no food or no power-ups");
+                    None
+                }
+                Err(err) => {
+                    warn!("Failed to load items: {}", err);
+                    None
+                }
+            })
+            .unwrap_or_else(|| ron::from_str(EMBEDDED_ITEMS).expect("embedded items are valid"))
+    }
+
+    pub fn food(&self, food: Food) -> &ItemKind {
+        self.food.get(food.0).unwrap_or(&self.food[0])
+    }
+
+    pub fn power_up(&self, power_up: PowerUp) -> &ItemKind {
+        self.power_ups.get(power_up.0).unwrap_or(&self.power_ups[0])
+    }
+
+    /// Picks a food or power-up by weight, or `None` if nothing has any.
+    pub fn random_drop(&self, rng: &mut StdRng) -> Option<Loot> {
+        let weight = |kind: &ItemKind| kind.weight.max(0.0);
+        let total: f32 = self.food.iter().chain(&self.power_ups).map(weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut roll = rng.random_range(0.0..total);
+        for (index, kind) in self.food.iter().enumerate() {
+            roll -= weight(kind);
+            if roll < 0.0 {
+                return Some(Loot::Food(Food(index)));
+            }
+        }
+        for (index, kind) in self.power_ups.iter().enumerate() {
+            roll -= weight(kind);
+            if roll < 0.0 {
+                return Some(Loot::PowerUp(PowerUp(index)));
+            }
+        }
+        // Rounding can leave a sliver at the end.
+        Some(Loot::Food(Food::BASIC))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_items() -> Option<String> {
+    use bevy::asset::io::file::FileAssetReader;
+
+    std::fs::read_to_string(FileAssetReader::get_base_path().join("assets/items.ron")).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_items() -> Option<String> {
+    Some(EMBEDDED_ITEMS.to_string())
+}
+
+/// The flat color of an item, for render styles that don't draw its sprite.
+#[derive(Component, Clone, Copy)]
+pub struct ItemColor(pub Color);
+
+/// One layout per sprite sheet shape, shared by every item cut from it.
+#[derive(Resource, Default)]
+struct AtlasLayouts(HashMap<(UVec2, u32, u32), Handle<TextureAtlasLayout>>);
+
+pub struct ItemsPlugin;
+
+impl Plugin for ItemsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AtlasLayouts>()
+            .add_observer(paint_food)
+            .add_observer(paint_power_up)
+            .add_systems(Update, fall_back_to_squares);
+    }
+}
+
+/// Everything painting an item needs.
+#[derive(SystemParam)]
+struct Palette<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    atlas_layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    layouts: ResMut<'w, AtlasLayouts>,
+}
+
+fn paint_food(
+    add: On<Add, Food>,
+    items: Res<Items>,
+    mut food: Query<(&mut Sprite, &Food)>,
+    mut palette: Palette,
+) {
+    if let Ok((mut sprite, food)) = food.get_mut(add.entity) {
+        paint(add.entity, &mut sprite, items.food(*food), &mut palette);
+    }
+}
+
+fn paint_power_up(
+    add: On<Add, PowerUp>,
+    items: Res<Items>,
+    mut power_ups: Query<(&mut Sprite, &PowerUp)>,
+    mut palette: Palette,
+) {
+    if let Ok((mut sprite, power_up)) = power_ups.get_mut(add.entity) {
+        paint(
+            add.entity,
+            &mut sprite,
+            items.power_up(*power_up),
+            &mut palette,
+        );
+    }
+}
+
+/// Gives a freshly dropped item the look of its type.
+fn paint(entity: Entity, sprite: &mut Sprite, kind: &ItemKind, palette: &mut Palette) {
+    palette
+        .commands
+        .entity(entity)
+        .insert(ItemColor(kind.color()));
+    let Some(sheet) = &kind.sprite else {
+        sprite.color = kind.color();
+        return;
+    };
+    let tile = UVec2::new(sheet.tile.0, sheet.tile.1);
+    let layout = palette
+        .layouts
+        .0
+        .entry((tile, sheet.columns, sheet.rows))
+        .or_insert_with(|| {
+            palette.atlas_layouts.add(TextureAtlasLayout::from_grid(
+                tile,
+                sheet.columns,
+                sheet.rows,
+                None,
+                None,
+            ))
+        })
+        .clone();
+    sprite.color = Color::WHITE;
+    sprite.image = palette.asset_server.load(sheet.image.clone());
+    sprite.texture_atlas = Some(TextureAtlas {
+        layout,
+        index: sheet.frame,
+    });
+}
+
+/// Items whose sprite sheet is missing or broken are drawn as squares of
+/// their color instead.
+fn fall_back_to_squares(
+    asset_server: Res<AssetServer>,
+    mut items: Query<(&mut Sprite, &ItemColor)>,
+) {
+    for (mut sprite, color) in items.iter_mut() {
+        if sprite.texture_atlas.is_some()
+            && matches!(asset_server.load_state(&sprite.image), LoadState::Failed(_))
+        {
+            sprite.image = Handle::default();
+            sprite.texture_atlas = None;
+            sprite.color = color.0;
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::terrain::Terrain;
+use crate::{AppState, GameSet, Player, Position, Size};
+
+/// Pairs keys with the doors they open.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum KeyColor {
+    Red,
+    Blue,
+}
+
+impl KeyColor {
+    pub fn color(self) -> Color {
+        match self {
+            Self::Red => Color::srgb(0.9, 0.2, 0.2),
+            Self::Blue => Color::srgb(0.25, 0.45, 1.0),
+        }
+    }
+}
+
+/// Picking it up opens every door of its color.
+#[derive(Component, Clone, Copy)]
+pub struct Key(pub KeyColor);
+
+pub struct KeyPlugin;
+
+impl Plugin for KeyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            collect_keys
+                .in_set(GameSet::Collisions)
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+pub fn spawn_key(commands: &mut Commands, position: Position, color: KeyColor) {
+    commands.spawn((
+        Sprite::from_color(color.color(), Vec2::ONE),
+        Transform::default(),
+        position,
+        Size::square(0.5),
+        Key(color),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn collect_keys(
+    mut commands: Commands,
+    mut terrain: ResMut<Terrain>,
+    keys: Query<(Entity, &Position, &Key)>,
+    heads: Query<&Position, With<Player>>,
+) {
+    for head_pos in heads.iter() {
+        for (entity, position, key) in keys.iter() {
+            if position == head_pos {
+                commands.entity(entity).despawn();
+                terrain.open(key.0);
+            }
+        }
+    }
+}
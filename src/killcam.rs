@@ -0,0 +1,338 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::grid::WindowLayout;
+use crate::items::Items;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::settings::Settings;
+use crate::{
+    AppState, Food, GameSet, Player, Position, RestartEvent, SNAKE_HEAD_COLOR, SNAKE_SEGMENT_COLOR,
+    SnakeDied, SnakeSegments, StepEvent,
+};
+
+/// Seconds of play kept for the replay.
+const REWIND_SECS: f32 = 5.0;
+/// How fast the replay runs compared to the game.
+const PLAYBACK_SPEED: f32 = 0.5;
+/// Seconds the last frame stays up before the replay starts over.
+const HOLD_SECS: f32 = 1.0;
+/// Side of the picture-in-picture box, in pixels.
+const BOX_SIZE: f32 = 240.0;
+const BOX_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+const LABEL_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+/// How fast the game runs right after a fatal crash.
+const SLOW_MOTION_SPEED: f32 = 0.25;
+/// Real time the slow motion lasts before the results come up.
+const SLOW_MOTION: Duration = Duration::from_millis(500);
+/// Camera scale at the end of the zoom towards the crash.
+const SLOW_MOTION_ZOOM: f32 = 0.85;
+
+/// The board as it was after one step.
+struct Frame {
+    /// Seconds into the game.
+    at: f32,
+    /// Every snake, head first.
+    snakes: Vec<Vec<Position>>,
+    food: Vec<(Food, Position)>,
+}
+
+/// The last [`REWIND_SECS`] of the current run.
+#[derive(Resource, Default)]
+struct Rewind(VecDeque<Frame>);
+
+/// The frames leading up to the last death, shown next to the results.
+#[derive(Resource, Default)]
+struct Killcam {
+    frames: Vec<Frame>,
+    /// Seconds of the replay played so far.
+    clock: f32,
+    /// The frame on screen, so the box is only redrawn when it changes.
+    shown: Option<usize>,
+}
+
+/// The box the replay is drawn in.
+#[derive(Component)]
+struct KillcamBoard;
+
+/// The moment after a fatal crash, played slowly while the camera closes in.
+#[derive(Resource, Default)]
+pub(crate) struct SlowMotion(Option<Crash>);
+
+struct Crash {
+    /// Counts real time, as the game's own clock is slowed down.
+    timer: Timer,
+    /// Where the camera closes in on, unless motion is reduced.
+    focus: Option<Vec2>,
+}
+
+pub struct KillcamPlugin;
+
+impl Plugin for KillcamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rewind>()
+            .init_resource::<Killcam>()
+            .init_resource::<SlowMotion>()
+            .add_systems(OnEnter(AppState::Playing), clear_rewind)
+            .add_systems(OnExit(AppState::Playing), end_slow_motion)
+            .add_systems(OnEnter(PauseState::Results), setup_killcam)
+            .add_systems(
+                Update,
+                (
+                    (record_frame, start_slow_motion)
+                        .after(GameSet::Collisions)
+                        .before(GameSet::Spawning),
+                    play_slow_motion.run_if(not(slow_motion_over)),
+                    play_killcam.run_if(in_state(PauseState::Results)),
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Run condition for whatever waits until the slow motion after a crash
+/// has played out; apps without the kill-cam never wait.
+pub(crate) fn slow_motion_over(slow: Option<Res<SlowMotion>>) -> bool {
+    slow.is_none_or(|slow| slow.0.is_none())
+}
+
+fn clear_rewind(mut rewind: ResMut<Rewind>) {
+    rewind.0.clear();
+}
+
+/// Notes the board after every step, and keeps what led up to a death
+/// before the board is cleared for the next run.
+fn record_frame(
+    time: Res<Time>,
+    mut step_reader: MessageReader<StepEvent>,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    mut rewind: ResMut<Rewind>,
+    mut killcam: ResMut<Killcam>,
+    bodies: Query<&SnakeSegments, With<Player>>,
+    positions: Query<&Position>,
+    food: Query<(&Food, &Position)>,
+) {
+    if restart_reader.read().next().is_some() {
+        rewind.0.clear();
+    }
+    let stepped = step_reader.read().next().is_some();
+    let died = game_over_reader.read().next().is_some();
+    if !stepped && !died {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    rewind.0.push_back(Frame {
+        at: now,
+        snakes: bodies
+            .iter()
+            .map(|segments| {
+                segments
+                    .iter()
+                    .filter_map(|segment| positions.get(*segment).ok().copied())
+                    .collect()
+            })
+            .collect(),
+        food: food.iter().map(|(food, pos)| (*food, *pos)).collect(),
+    });
+    while rewind
+        .0
+        .front()
+        .is_some_and(|frame| frame.at < now - REWIND_SECS)
+    {
+        rewind.0.pop_front();
+    }
+    if died {
+        *killcam = Killcam {
+            frames: rewind.0.drain(..).collect(),
+            ..default()
+        };
+    }
+}
+
+fn setup_killcam(mut commands: Commands, mut killcam: ResMut<Killcam>, locale: Res<Locale>) {
+    if killcam.frames.is_empty() {
+        return;
+    }
+    killcam.clock = 0.0;
+    killcam.shown = None;
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(24.0),
+                bottom: Val::Px(24.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            DespawnOnExit(PauseState::Results),
+        ))
+        .with_children(|pip| {
+            pip.spawn((
+                Text::new(locale.get("killcam.title")),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(LABEL_COLOR),
+            ));
+            pip.spawn((
+                Node {
+                    width: Val::Px(BOX_SIZE),
+                    height: Val::Px(BOX_SIZE),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(BOX_COLOR),
+                KillcamBoard,
+            ));
+        });
+}
+
+/// Runs the replay at [`PLAYBACK_SPEED`], holding the final frame for a
+/// moment before looping.
+fn play_killcam(
+    mut commands: Commands,
+    time: Res<Time>,
+    arena: Res<Arena>,
+    items: Res<Items>,
+    mut killcam: ResMut<Killcam>,
+    boards: Query<Entity, With<KillcamBoard>>,
+) {
+    let (Some(first), Some(last)) = (killcam.frames.first(), killcam.frames.last()) else {
+        return;
+    };
+    let (start, length) = (first.at, last.at - first.at);
+    killcam.clock += time.delta_secs() * PLAYBACK_SPEED;
+    if killcam.clock > length + HOLD_SECS * PLAYBACK_SPEED {
+        killcam.clock = 0.0;
+    }
+    let clock = killcam.clock;
+    let index = killcam
+        .frames
+        .iter()
+        .rposition(|frame| frame.at - start <= clock)
+        .unwrap_or(0);
+    if killcam.shown == Some(index) {
+        return;
+    }
+    killcam.shown = Some(index);
+
+    let frame = &killcam.frames[index];
+    let grid = arena.grid();
+    let tile = grid.tile_size(Vec2::splat(BOX_SIZE));
+    let cell = |pos: Position, color: Color| {
+        let center = grid.to_world(pos, tile);
+        (
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(BOX_SIZE / 2.0 + center.x - tile * 0.45),
+                top: Val::Px(BOX_SIZE / 2.0 - center.y - tile * 0.45),
+                width: Val::Px(tile * 0.9),
+                height: Val::Px(tile * 0.9),
+                ..default()
+            },
+            BackgroundColor(color),
+        )
+    };
+    for board in boards.iter() {
+        commands
+            .entity(board)
+            .despawn_children()
+            .with_children(|board| {
+                for (food, pos) in &frame.food {
+                    board.spawn(cell(*pos, items.food(*food).color()));
+                }
+                for snake in &frame.snakes {
+                    for (index, pos) in snake.iter().enumerate() {
+                        let color = if index == 0 {
+                            SNAKE_HEAD_COLOR
+                        } else {
+                            SNAKE_SEGMENT_COLOR
+                        };
+                        board.spawn(cell(*pos, color));
+                    }
+                }
+            });
+    }
+}
+
+/// Slows the game down the moment the snake crashes, before the board is
+/// cleared for the next run.
+fn start_slow_motion(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    settings: Res<Settings>,
+    layout: WindowLayout,
+    mut slow: ResMut<SlowMotion>,
+    mut time: ResMut<Time<Virtual>>,
+    heads: Query<&Position, With<Player>>,
+) {
+    if game_over_reader.read().next().is_none() {
+        return;
+    }
+    let focus = heads
+        .iter()
+        .next()
+        .zip(layout.get())
+        .map(|(head, layout)| layout.cell_to_world(*head))
+        .filter(|_| !settings.reduced_motion);
+    slow.0 = Some(Crash {
+        timer: Timer::new(SLOW_MOTION, TimerMode::Once),
+        focus,
+    });
+    time.set_relative_speed(SLOW_MOTION_SPEED);
+}
+
+/// Eases the camera in on the crash, then puts the speed and the camera back.
+fn play_slow_motion(
+    real: Res<Time<Real>>,
+    mut slow: ResMut<SlowMotion>,
+    time: ResMut<Time<Virtual>>,
+    cameras: Query<(&mut Transform, &mut Projection), With<Camera2d>>,
+) {
+    let Some(crash) = slow.0.as_mut() else {
+        return;
+    };
+    if !crash.timer.tick(real.delta()).is_finished() {
+        let progress = crash.timer.fraction();
+        let eased = progress * (2.0 - progress);
+        if let Some(focus) = crash.focus {
+            let scale = 1.0 - (1.0 - SLOW_MOTION_ZOOM) * eased;
+            frame_camera(cameras, scale, focus * (1.0 - scale));
+        }
+        return;
+    }
+    end_slow_motion(slow, time, cameras);
+}
+
+fn end_slow_motion(
+    mut slow: ResMut<SlowMotion>,
+    mut time: ResMut<Time<Virtual>>,
+    cameras: Query<(&mut Transform, &mut Projection), With<Camera2d>>,
+) {
+    if slow.0.take().is_some() {
+        time.set_relative_speed(1.0);
+        frame_camera(cameras, 1.0, Vec2::ZERO);
+    }
+}
+
+/// Zooms the camera to `scale` and centers it on `center`.
+fn frame_camera(
+    mut cameras: Query<(&mut Transform, &mut Projection), With<Camera2d>>,
+    scale: f32,
+    center: Vec2,
+) {
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.scale = scale;
+        }
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+    }
+}
@@ -0,0 +1,2053 @@
+// Bevy systems routinely take more parameters than clippy's default limit.
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use core::time::Duration;
+use rand::rngs::StdRng;
+use rand::{SeedableRng, random};
+use serde::{Deserialize, Serialize};
+
+mod animation;
+mod announce;
+mod arena;
+mod arena_event;
+pub mod arenas;
+mod ascii;
+mod assist;
+mod attract;
+mod body_color;
+mod bonk;
+mod boss;
+mod brain;
+mod calendar;
+mod clipboard;
+mod combo;
+mod coop;
+mod cosmetics;
+mod countdown;
+mod custom;
+mod daynight;
+mod decay;
+pub mod env;
+pub mod events;
+mod focus;
+pub mod grid;
+mod haptics;
+mod hardcore;
+mod hazard;
+mod hunger;
+mod hunter;
+mod idle;
+mod invulnerable;
+mod isometric;
+mod items;
+mod key;
+mod killcam;
+mod locale;
+mod menu;
+mod mirror;
+mod modifier;
+mod mods;
+mod music;
+mod near_miss;
+mod path_preview;
+mod pause;
+mod poison;
+mod powerup;
+mod profile;
+mod pulse;
+mod puzzle;
+#[cfg(feature = "python")]
+mod python;
+mod replay;
+mod results;
+mod reverse;
+mod rival;
+mod rules;
+mod rumble;
+mod scene3d;
+mod session;
+mod settings;
+mod shop;
+mod sound;
+pub mod split_screen;
+mod storage;
+mod team;
+mod terrain;
+mod touch;
+mod tournament;
+#[cfg(feature = "tui")]
+pub mod tui;
+mod tutorial;
+mod upgrade;
+mod victory;
+mod weather;
+
+use events::PlayerMeals;
+pub use events::{DirectionChangeRequest, DirectionChanged, FoodEaten, SnakeDied, TickCompleted};
+use grid::{GridShape, WindowLayout};
+use locale::Locale;
+use pause::PauseState;
+use rules::WallBehavior;
+use terrain::{IceSlide, MudDrag, Terrain, Tile, WallCrumbled};
+
+const SNAKE_HEAD_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const SNAKE_SEGMENT_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+
+const ARENA_HEIGHT: u32 = 20;
+const ARENA_WIDTH: u32 = 20;
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AppState {
+    #[default]
+    Menu,
+    Playing,
+    Shop,
+    /// Picking the snake's own colors, reached from the shop.
+    Cosmetics,
+    Draft,
+    Upgrades,
+    Custom,
+    Replays,
+    Mods,
+    /// What the player did this session, on the way out of the game.
+    Summary,
+    Tournament,
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+enum GameMode {
+    #[default]
+    Endless,
+    Puzzle(usize),
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Resource, Default)]
+struct GameState {
+    score: u32,
+    /// What the score was when the last run ended.
+    last_score: u32,
+    /// Set when the snake dies, until the board is cleared for the next run
+    /// once the slow motion after the crash has played.
+    respawn_due: bool,
+}
+
+/// Seeded randomness for a run, so a run can be retried with the same food
+/// sequence.
+#[derive(Resource)]
+struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::with_seed(random())
+    }
+}
+
+#[derive(Resource)]
+struct SnakeTimer {
+    timer: Timer,
+    /// Time between steps at the start of a run, before the score speeds
+    /// the snake up. Follows the speed setting.
+    base: Duration,
+}
+
+impl Default for SnakeTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(Duration::from_millis(500), TimerMode::Repeating),
+            base: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Game time the step just taken stands for: the step's length on a frame
+/// the snake steps, and nothing on any other. Timers that change how a run
+/// plays out, like food drops and arena events, tick on this rather than on
+/// the frame time, so a replay plays them out on the same steps.
+#[derive(Resource, Default)]
+pub(crate) struct StepClock(Duration);
+
+impl StepClock {
+    pub(crate) fn delta(&self) -> Duration {
+        self.0
+    }
+}
+
+/// Drops a round of food every second of [`StepClock`] time.
+#[derive(Resource)]
+struct FoodTimer(Timer);
+
+impl Default for FoodTimer {
+    fn default() -> Self {
+        Self(Timer::new(Duration::from_secs(1), TimerMode::Repeating))
+    }
+}
+
+#[derive(Component)]
+struct SnakeSegment;
+
+/// A snake's body in head-to-tail order, head included. Kept on the head,
+/// so each snake on the board carries its own: the player's, each co-op
+/// player's and each rival's.
+#[derive(Component, Default, Deref, DerefMut)]
+struct SnakeSegments(Vec<Entity>);
+
+/// Advances the snake by one cell. Written by the movement timer in endless
+/// mode and by key presses in puzzle mode.
+#[derive(Message)]
+struct StepEvent;
+
+/// A snake ran into something and is out, without that ending the run: a
+/// rival, or one of the co-op snakes. Written by [`snake_movement`], for the
+/// snake's own mode to take it off the board.
+#[derive(Message, Clone, Copy)]
+struct SnakeCrashed {
+    snake: Entity,
+}
+
+/// A fresh run is on the board. Written by [`respawn_snake`] and
+/// [`restart_run`] once they have cleared the board and placed the new
+/// snake, so whatever else a run starts with, like rivals, the co-op snakes
+/// and the countdown, is set up after the crash has played out rather than
+/// on the death itself.
+#[derive(Message)]
+struct RunStarted;
+
+/// The snakes have taken a step. Written by [`snake_movement`], so what
+/// follows from the step, like eating and growing, happens on the step it
+/// follows from and never on a frame in between.
+#[derive(Message)]
+struct MovementTick;
+
+/// The phases of a frame of play, run in this order in `Update`. Systems go
+/// in the phase they belong to, or order themselves around a whole phase,
+/// rather than around each other.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    /// Working out where each snake heads next: the step clock, the player,
+    /// bots and replays.
+    Input,
+    /// Snakes taking the turns asked for, where the rules allow.
+    Steering,
+    /// Snakes, rivals and the boss taking their step.
+    Movement,
+    /// What the step ran into, from food to hazards and other snakes, and
+    /// anything else that ends a run, like starving.
+    Collisions,
+    /// Dead snakes coming back and runs starting over.
+    Spawning,
+    /// Putting everything where it goes on screen, in `PostUpdate`.
+    Rendering,
+}
+
+/// What ended a run.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    /// Ran off the edge of the board.
+    Wall,
+    /// Ran into a wall or a locked door on the board.
+    Obstacle,
+    /// Bit its own body.
+    Body,
+    Hunger,
+    Decay,
+    Poison,
+    Boss,
+    Hazard,
+    Hunter,
+    /// Ran into another snake.
+    Rival,
+    /// Every co-op snake is down.
+    Fallen,
+}
+
+impl DeathCause {
+    /// Locale key describing the death.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Wall => "death.wall",
+            Self::Obstacle => "death.obstacle",
+            Self::Body => "death.body",
+            Self::Hunger => "death.hunger",
+            Self::Decay => "death.decay",
+            Self::Poison => "death.poison",
+            Self::Boss => "death.boss",
+            Self::Hazard => "death.hazard",
+            Self::Hunter => "death.hunter",
+            Self::Rival => "death.rival",
+            Self::Fallen => "death.fallen",
+        }
+    }
+}
+
+/// Ends a level in victory, rated from one to [`victory::MAX_STARS`] stars.
+#[derive(Message)]
+struct LevelCompleteEvent {
+    stars: u8,
+}
+
+/// Aborts the current run. `same_seed` retries with the current run's seed.
+#[derive(Message)]
+struct RestartEvent {
+    same_seed: bool,
+}
+
+/// The cell a snake's tail left on its last step, where it grows next. Kept
+/// on the head alongside [`SnakeSegments`].
+#[derive(Component, Default)]
+struct LastTailPosition(Option<Position>);
+
+/// Segments a snake has eaten but not grown yet. Kept on the head alongside
+/// [`SnakeSegments`].
+#[derive(Component, Default)]
+struct PendingGrowth(u32);
+
+/// A cell of the board, counted from the bottom left corner.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Board entities lying flat on the floor, drawn beneath anything sharing
+/// their cell.
+#[derive(Component)]
+struct Ground;
+
+#[derive(Component)]
+struct Size {
+    width: f32,
+    height: f32,
+}
+impl Size {
+    pub fn square(x: f32) -> Self {
+        Self {
+            width: x,
+            height: x,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum Direction {
+    Left,
+    Up,
+    Right,
+    Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    const ALL: [Self; 8] = [
+        Self::Left,
+        Self::Up,
+        Self::Right,
+        Self::Down,
+        Self::UpLeft,
+        Self::UpRight,
+        Self::DownLeft,
+        Self::DownRight,
+    ];
+
+    /// Locale key of the direction's name.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Left => "direction.left",
+            Self::Up => "direction.up",
+            Self::Right => "direction.right",
+            Self::Down => "direction.down",
+            Self::UpLeft => "direction.up_left",
+            Self::UpRight => "direction.up_right",
+            Self::DownLeft => "direction.down_left",
+            Self::DownRight => "direction.down_right",
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::UpLeft => Self::DownRight,
+            Self::DownRight => Self::UpLeft,
+            Self::UpRight => Self::DownLeft,
+            Self::DownLeft => Self::UpRight,
+        }
+    }
+
+    /// Swaps left and right.
+    fn mirrored(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::UpLeft => Self::UpRight,
+            Self::UpRight => Self::UpLeft,
+            Self::DownLeft => Self::DownRight,
+            Self::DownRight => Self::DownLeft,
+            vertical => vertical,
+        }
+    }
+}
+
+#[derive(Component)]
+struct SnakeHead {
+    direction: Direction,
+    /// Bumped into the edge of the board and hasn't moved since.
+    bonked: bool,
+    /// Took a turn since its last step, or is held to its course until the
+    /// next one, as on ice. Any further request is turned down.
+    turned: bool,
+}
+
+/// A snake steered by a person rather than a bot: 0 for the player, on the
+/// arrow keys and touch, and 1 for the second player in co-op, on WASD.
+/// Kept on the head.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+struct Player(usize);
+
+/// The colors a snake was spawned in, which the segments it grows take as
+/// well. Kept on the head.
+#[derive(Component, Clone, Copy, PartialEq)]
+struct SnakeColors {
+    head: Color,
+    body: Color,
+}
+
+impl Default for SnakeColors {
+    fn default() -> Self {
+        Self {
+            head: SNAKE_HEAD_COLOR,
+            body: SNAKE_SEGMENT_COLOR,
+        }
+    }
+}
+
+impl SnakeColors {
+    /// Repaints the snake's `segments`, head first, in these colors.
+    fn paint(&self, segments: &[Entity], sprites: &mut Query<&mut Sprite>) {
+        for (index, segment) in segments.iter().enumerate() {
+            if let Ok(mut sprite) = sprites.get_mut(*segment) {
+                sprite.color = if index == 0 { self.head } else { self.body };
+            }
+        }
+    }
+}
+
+/// Food of the type at this index in the item definitions, see
+/// `assets/items.ron`.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Food(pub usize);
+
+impl Food {
+    /// The plain pellet, the first type defined.
+    pub const BASIC: Self = Self(0);
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+fn reset_game_state(
+    mut game_state: ResMut<GameState>,
+    mut snake_timer: ResMut<SnakeTimer>,
+    mut food_timer: ResMut<FoodTimer>,
+    rules: Res<rules::Rules>,
+) {
+    game_state.last_score = game_state.score;
+    game_state.score = 0;
+    game_state.respawn_due = false;
+    let speed = calculate_speed(snake_timer.base, 0, &rules);
+    snake_timer.timer.set_duration(speed);
+    snake_timer.timer.reset();
+    food_timer.0.reset();
+}
+
+fn new_run_seed(mut rng: ResMut<GameRng>) {
+    reseed(&mut rng);
+}
+
+fn reseed(rng: &mut GameRng) {
+    *rng = GameRng::default();
+    info!("Seed: {}", rng.seed);
+}
+
+/// Endless runs are played on the grid picked in the settings; puzzles are
+/// designed for the square grid.
+fn select_grid_shape(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    settings: Res<settings::Settings>,
+) {
+    commands.insert_resource(match *mode {
+        GameMode::Endless => settings.grid_shape,
+        GameMode::Puzzle(_) => GridShape::Square,
+    });
+}
+
+/// Co-op runs have two snakes of their own instead, see [`coop`].
+fn spawn_snake(
+    mut commands: Commands,
+    arena: Res<arena::Arena>,
+    terrain: Res<Terrain>,
+    rules: Res<rules::Rules>,
+    mut rng: ResMut<GameRng>,
+) {
+    if rules.coop {
+        return;
+    }
+    let (direction, body) = rules.pick_spawn(arena.grid(), &terrain, &mut rng.rng);
+    spawn_player_snake(&mut commands, &body, direction);
+}
+
+/// Spawns the player's snake, see [`spawn_snake_body`], returning its head.
+fn spawn_player_snake(commands: &mut Commands, body: &[Position], direction: Direction) -> Entity {
+    let entities = spawn_snake_body(commands, body, direction, SnakeColors::default());
+    commands.entity(entities[0]).insert(Player(0));
+    entities[0]
+}
+
+/// Spawns a snake whose head is the first of `body`, returning the entities
+/// in head-to-tail order. The head keeps the same list as its
+/// [`SnakeSegments`]. Whoever steers it marks the head as theirs.
+fn spawn_snake_body(
+    commands: &mut Commands,
+    body: &[Position],
+    direction: Direction,
+    colors: SnakeColors,
+) -> Vec<Entity> {
+    let (head, tail) = body.split_first().expect("snake body must not be empty");
+    let mut entities = vec![
+        commands
+            .spawn((
+                Sprite::from_color(colors.head, Vec2::ONE),
+                Transform::default(),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .insert(SnakeHead {
+                direction,
+                bonked: false,
+                turned: false,
+            })
+            .insert(SnakeSegment)
+            .insert(*head)
+            .insert(Size::square(0.8))
+            .id(),
+    ];
+    entities.extend(
+        tail.iter()
+            .map(|position| spawn_segment(commands, *position, colors.body)),
+    );
+    commands.entity(entities[0]).insert((
+        SnakeSegments(entities.clone()),
+        LastTailPosition::default(),
+        PendingGrowth::default(),
+        MudDrag::default(),
+        IceSlide::default(),
+        colors,
+    ));
+    entities
+}
+
+fn spawn_segment(commands: &mut Commands, position: Position, color: Color) -> Entity {
+    commands
+        .spawn((
+            Sprite::from_color(color, Vec2::ONE),
+            Transform::default(),
+            DespawnOnExit(AppState::Playing),
+        ))
+        .insert(SnakeSegment)
+        .insert(position)
+        .insert(Size::square(0.65))
+        .id()
+}
+
+fn spawn_food(commands: &mut Commands, position: Position) {
+    spawn_food_of(commands, position, Food::BASIC);
+}
+
+/// Spawns `food` in white; the game paints it in its type's colors.
+fn spawn_food_of(commands: &mut Commands, position: Position, food: Food) {
+    commands
+        .spawn((
+            Sprite {
+                color: Color::WHITE,
+                custom_size: Some(Vec2::ONE),
+                ..default()
+            },
+            Transform::default(),
+            DespawnOnExit(AppState::Playing),
+        ))
+        .insert(food)
+        .insert(position)
+        .insert(Size::square(0.8));
+}
+
+fn snake_movement_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    presses: Res<KeyPresses>,
+    mut touch_reader: MessageReader<touch::TouchDirection>,
+    settings: Res<settings::Settings>,
+    arena: Res<arena::Arena>,
+    controls: ControlMapping,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<(Entity, &SnakeHead, &Player)>,
+) {
+    let touched = touch_reader.read().last().map(|touch| touch.0);
+    if settings.control_scheme == settings::ControlScheme::TwoButton {
+        // Each press turns once, from wherever the snake is heading. The
+        // on-screen left and right buttons turn too.
+        let clockwise = match touched {
+            Some(Direction::Left) => false,
+            Some(Direction::Right) => true,
+            _ if keyboard_input.just_pressed(KeyCode::ArrowLeft) => false,
+            _ if keyboard_input.just_pressed(KeyCode::ArrowRight) => true,
+            _ => return,
+        };
+        for (snake, head, _) in heads.iter().filter(|(_, _, player)| player.0 == 0) {
+            request_writer.write(DirectionChangeRequest {
+                snake,
+                direction: controls.map(arena.grid().rotate(head.direction, clockwise)),
+            });
+        }
+        return;
+    }
+    let Some(wanted) = touched
+        .or_else(|| held_direction(&presses, ARROW_KEYS, arena.grid()))
+        .map(|wanted| controls.map(wanted))
+    else {
+        return;
+    };
+    for (snake, _, _) in heads.iter().filter(|(_, _, player)| player.0 == 0) {
+        request_writer.write(DirectionChangeRequest {
+            snake,
+            direction: wanted,
+        });
+    }
+}
+
+/// Turns each snake the way it was asked to. A snake takes the first turn
+/// asked for since its last step and ignores the rest until it steps again.
+fn apply_direction_changes(
+    mut request_reader: MessageReader<DirectionChangeRequest>,
+    arena: Res<arena::Arena>,
+    mut heads: Query<(&mut SnakeHead, &Position, &SnakeSegments)>,
+    positions: Query<&Position, Without<SnakeHead>>,
+) {
+    let grid = arena.grid();
+    for request in request_reader.read() {
+        let Ok((mut head, head_pos, segments)) = heads.get_mut(request.snake) else {
+            continue;
+        };
+        if head.turned {
+            continue;
+        }
+        let neck = segments.get(1).and_then(|neck| positions.get(*neck).ok());
+        if let Some(direction) = allowed_turn(
+            grid,
+            *head_pos,
+            neck.copied(),
+            head.direction,
+            request.direction,
+        ) && direction != head.direction
+        {
+            head.direction = direction;
+            head.turned = true;
+        }
+    }
+}
+
+/// Where a snake heading `current` from `head_pos` goes when asked for
+/// `wanted`, as far as the grid allows, or `None` if that would run it back
+/// into its `neck`.
+fn allowed_turn(
+    grid: &dyn grid::Grid,
+    head_pos: Position,
+    neck: Option<Position>,
+    current: Direction,
+    wanted: Direction,
+) -> Option<Direction> {
+    let dir = grid.steer(current, wanted);
+    // Comparing against the neck rather than the opposite direction works
+    // for diagonals too.
+    (neck != Some(grid.step(head_pos, dir))).then_some(dir)
+}
+
+/// Left, right, up and down.
+const ARROW_KEYS: [KeyCode; 4] = [
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+];
+
+/// Keys held down, in the order they were pressed, so the latest press can
+/// win over keys that were already held. Keys pressed on the same frame go
+/// in key code order, so the last of them in that order wins.
+#[derive(Resource, Default)]
+struct KeyPresses(Vec<KeyCode>);
+
+fn track_key_presses(keyboard_input: Res<ButtonInput<KeyCode>>, mut presses: ResMut<KeyPresses>) {
+    presses.0.retain(|key| keyboard_input.pressed(*key));
+    // The input hands over a frame's presses in no particular order.
+    let mut just_pressed = keyboard_input.get_just_pressed().collect::<Vec<_>>();
+    just_pressed.sort();
+    for key in just_pressed {
+        if !presses.0.contains(key) {
+            presses.0.push(*key);
+        }
+    }
+}
+
+/// The direction of the held `keys`, given as left, right, up and down. The
+/// key pressed last wins over the rest, and is combined with the latest
+/// held key of the other axis into a diagonal where `grid` has one. Letting
+/// go of it hands over to whichever key is still held.
+fn held_direction(
+    presses: &KeyPresses,
+    keys: [KeyCode; 4],
+    grid: &dyn grid::Grid,
+) -> Option<Direction> {
+    let arrows = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+    let mut latest = presses.0.iter().rev().filter_map(|pressed| {
+        let index = keys.iter().position(|key| key == pressed)?;
+        Some(arrows[index])
+    });
+    let first = latest.next()?;
+    let horizontal = |direction: Direction| matches!(direction, Direction::Left | Direction::Right);
+    let Some(across) = latest.find(|other| horizontal(*other) != horizontal(first)) else {
+        return Some(first);
+    };
+    let (sideways, vertical) = if horizontal(first) {
+        (first, across)
+    } else {
+        (across, first)
+    };
+    let diagonal = match (sideways, vertical) {
+        (Direction::Left, Direction::Up) => Direction::UpLeft,
+        (Direction::Left, _) => Direction::DownLeft,
+        (_, Direction::Up) => Direction::UpRight,
+        _ => Direction::DownRight,
+    };
+    Some(if grid.steer(diagonal, diagonal) == diagonal {
+        diagonal
+    } else {
+        first
+    })
+}
+
+/// Whatever is currently turning the player's controls around.
+#[derive(SystemParam)]
+struct ControlMapping<'w> {
+    mirror: Res<'w, arena_event::MirroredControls>,
+    reversed: Res<'w, reverse::ReversedControls>,
+}
+
+impl ControlMapping<'_> {
+    /// The direction the snake should take when the player asks for
+    /// `wanted`.
+    fn map(&self, wanted: Direction) -> Direction {
+        let wanted = if self.mirror.active() {
+            wanted.mirrored()
+        } else {
+            wanted
+        };
+        if self.reversed.active() {
+            wanted.opposite()
+        } else {
+            wanted
+        }
+    }
+}
+
+fn snake_timer_tick(
+    time: Res<Time>,
+    mut snake_timer: ResMut<SnakeTimer>,
+    mut step_clock: ResMut<StepClock>,
+    mut step_writer: MessageWriter<StepEvent>,
+) {
+    snake_timer.timer.tick(time.delta());
+
+    if snake_timer.timer.just_finished() {
+        step_clock.0 = snake_timer.timer.duration();
+        step_writer.write(StepEvent);
+    }
+}
+
+/// The step clock only stands still between steps.
+fn clear_step_clock(mut step_clock: ResMut<StepClock>) {
+    step_clock.0 = Duration::ZERO;
+}
+
+/// Every snake on the board, as [`snake_movement`] moves it.
+type MovingSnakes<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut SnakeHead,
+        &'static SnakeSegments,
+        &'static mut LastTailPosition,
+        &'static mut MudDrag,
+        Has<Player>,
+        Option<&'static rival::Rival>,
+        Has<invulnerable::Invulnerable>,
+    ),
+>;
+
+/// Every snake takes a step, in turn, and runs into whatever the step takes
+/// it into: the arena's edge, walls, itself or another snake. The player's
+/// crash ends the run; anyone else's, a rival's or a co-op snake's, is left
+/// to their mode to clear away.
+fn snake_movement(
+    mut step_reader: MessageReader<StepEvent>,
+    mut tick_writer: MessageWriter<MovementTick>,
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    mut crash_writer: MessageWriter<SnakeCrashed>,
+    arena: Res<arena::Arena>,
+    mut terrain: ResMut<Terrain>,
+    ram: Res<powerup::Ram>,
+    mut shield: ResMut<upgrade::Shield>,
+    rules: Res<rules::Rules>,
+    mut crumble_writer: MessageWriter<WallCrumbled>,
+    mut bonk_writer: MessageWriter<bonk::WallBonked>,
+    mut game_state: ResMut<GameState>,
+    mut heads: MovingSnakes,
+    mut positions: Query<&mut Position>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    tick_writer.write(MovementTick);
+
+    let grid = arena.grid();
+    let forgiving = rules.teams.is_some_and(|teams| !teams.friendly_fire);
+    let team_of = |rival: Option<&rival::Rival>| match rival {
+        Some(rival) => rival.team(),
+        None => rules.teams.map(|_| team::Team::PLAYER),
+    };
+    // Who is in each cell, so a snake knows whose body it ran into. Tails
+    // are left out, since they move out of the way on this step.
+    let mut occupied: HashMap<Position, (Entity, Option<team::Team>)> = HashMap::new();
+    for (entity, _, segments, _, _, _, rival, _) in heads.iter() {
+        let team = team_of(rival);
+        occupied.extend(
+            segments[..segments.len() - 1]
+                .iter()
+                .filter_map(|e| positions.get(*e).ok())
+                .map(|pos| (*pos, (entity, team))),
+        );
+    }
+
+    for (
+        head_entity,
+        mut head,
+        segments,
+        mut last_tail_position,
+        mut mud,
+        player,
+        rival,
+        invulnerable,
+    ) in heads.iter_mut()
+    {
+        head.turned = false;
+        let in_mud = positions
+            .get(head_entity)
+            .is_ok_and(|pos| terrain.at(*pos) == Some(Tile::Mud));
+        if mud.holds(in_mud) {
+            continue;
+        }
+        // Only the player's snake, outside co-op, has a run to lose. Score,
+        // power-ups and upgrades are the player's alone too.
+        let the_player = player && !rules.coop;
+        let team = team_of(rival);
+        let mut crashed_out = false;
+        let mut direction = head.direction;
+        // A conveyor under the head pushes the snake one extra cell. The push
+        // itself never triggers another conveyor.
+        for pushed in [false, true] {
+            let segment_positions = segments
+                .iter()
+                .map(|e| *positions.get_mut(*e).unwrap())
+                .collect::<Vec<Position>>();
+            let mut head_pos = positions.get_mut(head_entity).unwrap();
+            let squeezed = grid
+                .flanking_cells(*head_pos, direction)
+                .is_some_and(|cells| cells.iter().all(|cell| segment_positions.contains(cell)));
+            let mut target = grid.step(*head_pos, direction);
+            match arena.walls {
+                WallBehavior::Wrap => target = grid.wrap(target),
+                WallBehavior::Bounce if !arena.contains(target) => {
+                    // The body runs back the way it came, so the tail is
+                    // now the head, heading away from the rest.
+                    for (segment, pos) in segments.iter().zip(segment_positions.iter().rev()) {
+                        *positions.get_mut(*segment).unwrap() = *pos;
+                    }
+                    head.direction = match segment_positions[..] {
+                        [.., neck, tail] => Direction::ALL
+                            .into_iter()
+                            .filter(|d| grid.steer(*d, *d) == *d)
+                            .find(|d| grid.step(neck, *d) == tail)
+                            .unwrap_or(direction.opposite()),
+                        _ => direction.opposite(),
+                    };
+                    break;
+                }
+                WallBehavior::Bonk if !arena.contains(target) => {
+                    // Only the first bump costs anything; after that the
+                    // snake just waits to be turned.
+                    if !head.bonked {
+                        head.bonked = true;
+                        if the_player {
+                            game_state.score = game_state.score.saturating_sub(rules.food_points);
+                            bonk_writer.write(bonk::WallBonked);
+                        }
+                    }
+                    break;
+                }
+                _ => {}
+            }
+            let mut hit_wall = false;
+            match terrain.at(target) {
+                Some(Tile::Wall { .. }) if the_player && ram.active() => {
+                    if terrain.damage(target) {
+                        crumble_writer.write(WallCrumbled(target));
+                    } else {
+                        // The wall held, so the snake stays put this step.
+                        break;
+                    }
+                }
+                _ => hit_wall = arena.blocks(target),
+            }
+            let bitten = squeezed || segment_positions.contains(&target);
+            let rammed = occupied.get(&target).is_some_and(|(owner, owner_team)| {
+                *owner != head_entity
+                    && !invulnerable
+                    && !(forgiving && owner_team.is_some() && *owner_team == team)
+            });
+            let cause = if !arena.contains(target) {
+                Some(DeathCause::Wall)
+            } else if hit_wall {
+                Some(DeathCause::Obstacle)
+            } else if bitten && rules.self_collision {
+                Some(DeathCause::Body)
+            } else if rammed {
+                Some(DeathCause::Rival)
+            } else {
+                None
+            };
+            let crashed = cause.is_some();
+            if crashed && the_player && shield.absorb() {
+                // The shield breaks instead, and the snake stops short.
+                break;
+            }
+            if crashed && !the_player {
+                // Out of the game where it stands, for its mode to clear.
+                crash_writer.write(SnakeCrashed { snake: head_entity });
+                crashed_out = true;
+                break;
+            }
+            *head_pos = target;
+            head.bonked = false;
+            let new_head_pos = *head_pos;
+            if let Some(cause) = cause {
+                game_over_writer.write(SnakeDied {
+                    snake: head_entity,
+                    cause,
+                });
+            }
+            segment_positions
+                .iter()
+                .zip(segments.iter().skip(1))
+                .for_each(|(pos, segment)| {
+                    *positions.get_mut(*segment).unwrap() = *pos;
+                });
+            last_tail_position.0 = Some(*segment_positions.last().unwrap());
+
+            match terrain.at(new_head_pos) {
+                Some(Tile::Conveyor(push)) if !pushed && !crashed => direction = push,
+                _ => break,
+            }
+        }
+
+        occupied.retain(|_, (owner, _)| *owner != head_entity);
+        if !crashed_out {
+            occupied.extend(
+                segments[..segments.len() - 1]
+                    .iter()
+                    .filter_map(|e| positions.get(*e).ok())
+                    .map(|pos| (*pos, (head_entity, team))),
+            );
+        }
+    }
+}
+
+fn size_scaling(layout: WindowLayout, mut q: Query<(&Size, &mut Transform)>) {
+    let Some(layout) = layout.get() else {
+        return;
+    };
+
+    for (sprite_size, mut transform) in q.iter_mut() {
+        transform.scale = Vec3::new(
+            layout.cell_size * sprite_size.width,
+            layout.cell_size * sprite_size.height,
+            1.0,
+        );
+        //println!("Scaling entity: scale={}", scale);
+    }
+}
+
+fn position_translation(
+    layout: WindowLayout,
+    mut q: Query<(&Position, &mut Transform, Has<Ground>)>,
+) {
+    // Tiles keep their proportions and the arena is centered, so portrait
+    // screens get empty bands above and below instead of a stretched board.
+    let Some(layout) = layout.get() else {
+        return;
+    };
+
+    for (pos, mut transform, ground) in q.iter_mut() {
+        let z = if ground { -1.0 } else { 0.0 };
+        transform.translation = layout.cell_to_world(*pos).extend(z);
+        /*
+        println!(
+            "Position: ({}, {}) -> Translation: {:?}",
+            pos.x, pos.y, transform.translation
+        );
+        */
+    }
+}
+
+fn food_spawner(
+    mut commands: Commands,
+    step_clock: Res<StepClock>,
+    mut food_timer: ResMut<FoodTimer>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<arena::Arena>,
+    bodies: Query<&SnakeSegments>,
+    positions: Query<&Position>,
+    hazards: Query<&hazard::Hazard>,
+    terrain: Res<Terrain>,
+    rules: Res<rules::Rules>,
+    items: Res<items::Items>,
+) {
+    if !food_timer.0.tick(step_clock.delta()).just_finished() {
+        return;
+    }
+    for _ in 0..rules.food_count {
+        let food_position = arena.grid().random_cell(&mut rng.rng);
+
+        if !bodies
+            .iter()
+            .flat_map(|segments| segments.iter())
+            .filter_map(|e| positions.get(*e).ok())
+            .any(|segment_position| *segment_position == food_position)
+            && !hazards.iter().any(|hazard| hazard.covers(food_position))
+            && !terrain.blocks(food_position)
+        {
+            match items.random_drop(&mut rng.rng) {
+                Some(items::Loot::Food(food)) => {
+                    spawn_food_of(&mut commands, food_position, food);
+                }
+                Some(items::Loot::PowerUp(power_up)) => {
+                    powerup::spawn_power_up(&mut commands, food_position, power_up);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+type EatingSnakes<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Position,
+        &'static SnakeColors,
+        &'static mut SnakeSegments,
+        &'static mut LastTailPosition,
+        &'static mut PendingGrowth,
+    ),
+    With<SnakeHead>,
+>;
+
+/// Eats whatever food is under each head and grows the snake that ate it
+/// by one pending segment on the cell its tail just left, all on the step
+/// that got it there. Bigger meals, or several at once, stretch the snake
+/// out over the next few steps. Nobody eats on the step the run ended, and
+/// a snake that crashed on this step eats nothing more.
+fn snake_eating(
+    mut commands: Commands,
+    mut growth_writer: MessageWriter<FoodEaten>,
+    deaths: Res<Messages<SnakeDied>>,
+    crashes: Res<Messages<SnakeCrashed>>,
+    items: Res<items::Items>,
+    food_positions: Query<(Entity, &Position, &Food)>,
+    mut snakes: EatingSnakes,
+) {
+    if deaths.iter_current_update_messages().next().is_some() {
+        return;
+    }
+    let crashed: Vec<Entity> = crashes
+        .iter_current_update_messages()
+        .map(|crash| crash.snake)
+        .collect();
+    let mut eaten = Vec::new();
+    for (snake, head_pos, colors, mut segments, mut last_tail_position, mut pending) in
+        snakes.iter_mut()
+    {
+        if crashed.contains(&snake) {
+            continue;
+        }
+        for (ent, food_pos, food) in food_positions.iter() {
+            if food_pos != head_pos || eaten.contains(&ent) {
+                continue;
+            }
+            eaten.push(ent);
+            commands.entity(ent).despawn();
+            let kind = items.food(*food);
+            pending.0 += kind.growth;
+            growth_writer.write(FoodEaten {
+                snake,
+                kind: *food,
+                position: *food_pos,
+                segments: kind.growth,
+                points: kind.points,
+            });
+        }
+
+        if pending.0 > 0
+            && let Some(tail) = last_tail_position.0.take()
+        {
+            segments.push(spawn_segment(&mut commands, tail, colors.body));
+            pending.0 -= 1;
+        }
+    }
+}
+
+/// Scores what was eaten and speeds the snake up to match.
+fn score_food(
+    mut growth_reader: PlayerMeals,
+    mut game_state: ResMut<GameState>,
+    mut snake_timer: ResMut<SnakeTimer>,
+    rules: Res<rules::Rules>,
+) {
+    for growth in growth_reader.read() {
+        game_state.score += rules.food_points * growth.points;
+        let new_speed = calculate_speed(snake_timer.base, game_state.score, &rules);
+        snake_timer.timer.set_duration(new_speed);
+
+        info!("Score: {} | Speed: {:?}", game_state.score, new_speed);
+    }
+}
+
+/// Ends the run the moment the snake dies, leaving the board as it was for
+/// the slow motion after the crash.
+fn game_over(mut reader: MessageReader<SnakeDied>, mut game_state: ResMut<GameState>) {
+    if reader.read().next().is_some() {
+        game_state.last_score = game_state.score;
+        game_state.respawn_due = true;
+    }
+}
+
+/// Clears the board and starts the next run once the crash has played out.
+fn respawn_snake(
+    mut commands: Commands,
+    mut run_writer: MessageWriter<RunStarted>,
+    food: Query<Entity, With<Food>>,
+    segments: Query<Entity, With<SnakeSegment>>,
+    game_state: ResMut<GameState>,
+    snake_timer: ResMut<SnakeTimer>,
+    food_timer: ResMut<FoodTimer>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<arena::Arena>,
+    terrain: Res<Terrain>,
+    rules: Res<rules::Rules>,
+) {
+    if !game_state.respawn_due {
+        return;
+    }
+    for ent in food.iter().chain(segments.iter()) {
+        commands.entity(ent).despawn();
+    }
+
+    reset_game_state(game_state, snake_timer, food_timer, Res::clone(&rules));
+    reseed(&mut rng);
+    spawn_snake(commands, arena, terrain, rules, rng);
+    run_writer.write(RunStarted);
+}
+
+fn restart_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut restart_writer: MessageWriter<RestartEvent>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        restart_writer.write(RestartEvent {
+            same_seed: keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+        });
+    }
+}
+
+fn restart_run(
+    mut commands: Commands,
+    mut reader: MessageReader<RestartEvent>,
+    mut run_writer: MessageWriter<RunStarted>,
+    food: Query<Entity, With<Food>>,
+    segments: Query<Entity, With<SnakeSegment>>,
+    game_state: ResMut<GameState>,
+    snake_timer: ResMut<SnakeTimer>,
+    food_timer: ResMut<FoodTimer>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<arena::Arena>,
+    terrain: Res<Terrain>,
+    rules: Res<rules::Rules>,
+) {
+    let Some(restart) = reader.read().last() else {
+        return;
+    };
+    for ent in food.iter().chain(segments.iter()) {
+        commands.entity(ent).despawn();
+    }
+
+    reset_game_state(game_state, snake_timer, food_timer, Res::clone(&rules));
+    if restart.same_seed {
+        *rng = GameRng::with_seed(rng.seed);
+        info!("Seed: {}", rng.seed);
+    } else {
+        reseed(&mut rng);
+    }
+    spawn_snake(commands, arena, terrain, rules, rng);
+    run_writer.write(RunStarted);
+}
+
+fn return_to_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+fn calculate_speed(base: Duration, score: u32, rules: &rules::Rules) -> Duration {
+    let base_speed_ms = base.as_millis() as f32;
+    let speed =
+        (base_speed_ms - (score as f32 * rules.speed_curve.ms_per_point())).max(50.0) / rules.speed;
+    Duration::from_millis(speed as u64)
+}
+
+fn setup_score_text(mut commands: Commands, locale: Res<Locale>) {
+    commands.spawn((
+        Text::new(locale.format("hud.score", &[("score", &0)])),
+        TextFont {
+            font_size: 40.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        ScoreText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_score_text(
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    if game_state.is_changed() {
+        for mut text in query.iter_mut() {
+            text.0 = locale.format("hud.score", &[("score", &game_state.score)]);
+        }
+    }
+}
+
+/// Entry point for every platform. On Android, `bevy_main` also exports it as
+/// `android_main`; mobile builds compile this library as a `cdylib`, e.g.
+/// `cargo rustc --lib --crate-type cdylib --target aarch64-linux-android`.
+#[bevy_main]
+pub fn main() {
+    app().run();
+}
+
+/// The whole game, ready to run. Extensions add their own plugins to it
+/// before running it, and follow the game through the messages in
+/// [`events`].
+pub fn app() -> App {
+    let settings = settings::Settings::load();
+    let profile = profile::Profile::load();
+    let mods = mods::Mods::load(&settings.disabled_mods);
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Snake!".to_string(),
+            resolution: (800, 800).into(),
+            // In the browser, follow the size of the page instead.
+            fit_canvas_to_parent: true,
+            ..default()
+        }),
+        ..default()
+    }))
+    .insert_resource(ClearColor(mods.sky(profile.theme)))
+    .init_state::<AppState>()
+    .insert_resource(profile)
+    .insert_resource(mods)
+    .insert_resource(Locale::load(&settings.language))
+    .insert_resource(settings)
+    .add_plugins(CorePlugin)
+    .add_plugins((
+        ascii::AsciiPlugin,
+        countdown::CountdownPlugin,
+        daynight::DayNightPlugin,
+        focus::FocusPlugin,
+        isometric::IsometricPlugin,
+        locale::LocalePlugin,
+        menu::MenuPlugin,
+        pause::PausePlugin,
+        scene3d::Scene3dPlugin,
+        settings::SettingsPlugin,
+        shop::ShopPlugin,
+        touch::TouchPlugin,
+        tutorial::TutorialPlugin,
+        victory::VictoryPlugin,
+        weather::WeatherPlugin,
+    ))
+    // Level contents.
+    .add_plugins((
+        arena_event::ArenaEventPlugin,
+        boss::BossPlugin,
+        brain::BrainPlugin,
+        hazard::HazardPlugin,
+        hunter::HunterPlugin,
+        items::ItemsPlugin,
+        key::KeyPlugin,
+        mods::ModsPlugin,
+        poison::PoisonPlugin,
+        powerup::PowerUpPlugin,
+        puzzle::PuzzlePlugin,
+        reverse::ReversePlugin,
+        rival::RivalPlugin,
+        terrain::TerrainPlugin,
+        arena::ArenaPlugin,
+    ))
+    // Scoring and feedback.
+    .add_plugins((
+        animation::AnimationPlugin,
+        body_color::BodyColorPlugin,
+        bonk::BonkPlugin,
+        combo::ComboPlugin,
+        haptics::HapticsPlugin,
+        idle::IdlePlugin,
+        killcam::KillcamPlugin,
+        music::MusicPlugin,
+        near_miss::NearMissPlugin,
+        pulse::PulsePlugin,
+        replay::ReplayPlugin,
+        results::ResultsPlugin,
+        rumble::RumblePlugin,
+        session::SessionPlugin,
+        sound::SoundPlugin,
+    ))
+    // Modes and optional rules.
+    .add_plugins((
+        attract::AttractPlugin,
+        coop::CoopPlugin,
+        custom::CustomPlugin,
+        decay::DecayPlugin,
+        hardcore::HardcorePlugin,
+        hunger::HungerPlugin,
+        invulnerable::InvulnerablePlugin,
+        mirror::MirrorPlugin,
+        modifier::ModifierPlugin,
+        team::TeamPlugin,
+        tournament::TournamentPlugin,
+        upgrade::UpgradePlugin,
+    ))
+    // Accessibility.
+    .add_plugins((
+        announce::AnnouncePlugin,
+        assist::AssistPlugin,
+        path_preview::PathPreviewPlugin,
+    ))
+    .add_systems(Startup, setup_camera)
+    .add_systems(OnEnter(AppState::Playing), setup_score_text)
+    .init_resource::<KeyPresses>()
+    .add_systems(Update, track_key_presses.before(GameSet::Input))
+    .add_systems(
+        Update,
+        snake_movement_input
+            .in_set(GameSet::Input)
+            .run_if(in_state(PauseState::Running))
+            .run_if(resource_equals(GameMode::Endless))
+            .run_if(replay::not_watching)
+            .run_if(not(brain::autopilot_enabled)),
+    )
+    .add_systems(Update, update_score_text)
+    .add_systems(
+        Update,
+        (
+            return_to_menu.run_if(not(in_state(PauseState::Paused))),
+            restart_input
+                .in_set(GameSet::Input)
+                .run_if(replay::not_watching),
+        )
+            .run_if(in_state(AppState::Playing)),
+    )
+    .add_systems(
+        PostUpdate,
+        (position_translation, size_scaling).in_set(GameSet::Rendering),
+    );
+    app
+}
+
+/// The rules of the game on their own: the snake, food, growth and death,
+/// with no input or drawing. The game adds those on top, and [`env`] runs
+/// it headless.
+struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameMode::default())
+            .insert_resource(GridShape::default())
+            .insert_resource(SnakeTimer::default())
+            .insert_resource(GameState::default())
+            .insert_resource(GameRng::default())
+            .init_resource::<StepClock>()
+            .init_resource::<FoodTimer>()
+            .insert_resource(rules::Rules::default())
+            .init_resource::<arena::Arena>()
+            .insert_resource(items::Items::load())
+            .add_plugins(events::EventsPlugin)
+            .add_message::<StepEvent>()
+            .add_message::<MovementTick>()
+            .add_message::<SnakeCrashed>()
+            .add_message::<RunStarted>()
+            .add_message::<LevelCompleteEvent>()
+            .add_message::<RestartEvent>()
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::Steering,
+                    GameSet::Movement,
+                    GameSet::Collisions,
+                    GameSet::Spawning,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (
+                    reset_game_state,
+                    (
+                        new_run_seed,
+                        spawn_snake.after(arena::sync_arena).after(new_run_seed),
+                    )
+                        .run_if(resource_equals(GameMode::Endless)),
+                    select_grid_shape,
+                    arena::sync_arena.after(select_grid_shape),
+                ),
+            )
+            .add_systems(
+                Update,
+                snake_timer_tick
+                    .in_set(GameSet::Input)
+                    .run_if(countdown::countdown_finished)
+                    .run_if(in_state(PauseState::Running))
+                    .run_if(resource_equals(GameMode::Endless)),
+            )
+            .add_systems(
+                Update,
+                arena::sync_arena.before(GameSet::Input).run_if(
+                    resource_changed::<GridShape>
+                        .or(resource_changed::<rules::Rules>)
+                        .or(resource_changed::<Terrain>),
+                ),
+            )
+            .add_systems(Update, apply_direction_changes.in_set(GameSet::Steering))
+            .add_systems(Update, snake_movement.in_set(GameSet::Movement))
+            .add_systems(
+                Update,
+                (snake_eating.run_if(on_message::<MovementTick>), score_food)
+                    .chain()
+                    .in_set(GameSet::Collisions),
+            )
+            .add_systems(
+                Update,
+                (
+                    game_over,
+                    respawn_snake.run_if(killcam::slow_motion_over),
+                    restart_run,
+                )
+                    .chain()
+                    .in_set(GameSet::Spawning)
+                    .run_if(resource_equals(GameMode::Endless)),
+            )
+            .add_systems(First, clear_step_clock)
+            .add_systems(
+                Update,
+                food_spawner
+                    .in_set(GameSet::Spawning)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAD: Position = Position { x: 5, y: 5 };
+    const NECK: Position = Position { x: 5, y: 4 };
+
+    #[test]
+    fn allowed_turn_takes_a_side_turn() {
+        let grid = GridShape::Square.sized(ARENA_WIDTH, ARENA_HEIGHT);
+        assert_eq!(
+            allowed_turn(&*grid, HEAD, Some(NECK), Direction::Up, Direction::Left),
+            Some(Direction::Left)
+        );
+    }
+
+    #[test]
+    fn allowed_turn_rejects_a_reversal() {
+        let grid = GridShape::Square.sized(ARENA_WIDTH, ARENA_HEIGHT);
+        assert_eq!(
+            allowed_turn(&*grid, HEAD, Some(NECK), Direction::Up, Direction::Down),
+            None
+        );
+    }
+
+    #[test]
+    fn allowed_turn_reverses_without_a_neck() {
+        let grid = GridShape::Square.sized(ARENA_WIDTH, ARENA_HEIGHT);
+        assert_eq!(
+            allowed_turn(&*grid, HEAD, None, Direction::Up, Direction::Down),
+            Some(Direction::Down)
+        );
+    }
+
+    #[test]
+    fn allowed_turn_rejects_a_diagonal_into_the_neck() {
+        let grid = GridShape::Freestyle.sized(ARENA_WIDTH, ARENA_HEIGHT);
+        let neck = Position { x: 4, y: 4 };
+        assert_eq!(
+            allowed_turn(
+                &*grid,
+                HEAD,
+                Some(neck),
+                Direction::UpRight,
+                Direction::DownLeft
+            ),
+            None
+        );
+    }
+
+    /// A two-cell snake heading up, with [`apply_direction_changes`] ready
+    /// to steer it.
+    fn steering_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.init_resource::<arena::Arena>()
+            .add_message::<DirectionChangeRequest>()
+            .add_systems(Update, apply_direction_changes);
+        let world = app.world_mut();
+        let neck = world.spawn(NECK).id();
+        let head = world
+            .spawn((
+                SnakeHead {
+                    direction: Direction::Up,
+                    bonked: false,
+                    turned: false,
+                },
+                HEAD,
+            ))
+            .id();
+        world
+            .entity_mut(head)
+            .insert(SnakeSegments(vec![head, neck]));
+        (app, head)
+    }
+
+    fn request(app: &mut App, snake: Entity, direction: Direction) {
+        app.world_mut()
+            .write_message(DirectionChangeRequest { snake, direction });
+    }
+
+    fn direction(app: &App, snake: Entity) -> Direction {
+        app.world().get::<SnakeHead>(snake).unwrap().direction
+    }
+
+    #[test]
+    fn apply_direction_changes_turns_the_snake() {
+        let (mut app, snake) = steering_app();
+        request(&mut app, snake, Direction::Left);
+        app.update();
+        assert_eq!(direction(&app, snake), Direction::Left);
+    }
+
+    #[test]
+    fn apply_direction_changes_rejects_a_reversal() {
+        let (mut app, snake) = steering_app();
+        request(&mut app, snake, Direction::Down);
+        app.update();
+        assert_eq!(direction(&app, snake), Direction::Up);
+        assert!(!app.world().get::<SnakeHead>(snake).unwrap().turned);
+    }
+
+    #[test]
+    fn apply_direction_changes_takes_one_turn_per_step() {
+        let (mut app, snake) = steering_app();
+        request(&mut app, snake, Direction::Left);
+        request(&mut app, snake, Direction::Right);
+        app.update();
+        assert_eq!(direction(&app, snake), Direction::Left);
+
+        // Still no step taken, so a later frame can't turn it either.
+        request(&mut app, snake, Direction::Right);
+        app.update();
+        assert_eq!(direction(&app, snake), Direction::Left);
+    }
+
+    /// The player's snake laid out along `body`, head first and heading
+    /// `direction`, with the steering and [`snake_movement`] of a real run.
+    /// Nothing moves until [`step`] says so.
+    fn movement_app(body: &[Position], direction: Direction) -> (App, Entity) {
+        let mut app = App::new();
+        app.insert_resource(locale::Locale::load("en"))
+            .init_resource::<GridShape>()
+            .init_resource::<rules::Rules>()
+            .init_resource::<Terrain>()
+            .init_resource::<arena::Arena>()
+            .init_resource::<terrain::IceHintShown>()
+            .init_resource::<powerup::Ram>()
+            .init_resource::<upgrade::Shield>()
+            .init_resource::<GameState>()
+            .add_message::<DirectionChangeRequest>()
+            .add_message::<StepEvent>()
+            .add_message::<MovementTick>()
+            .add_message::<SnakeDied>()
+            .add_message::<SnakeCrashed>()
+            .add_message::<WallCrumbled>()
+            .add_message::<bonk::WallBonked>()
+            .add_systems(
+                Update,
+                (
+                    arena::sync_arena,
+                    terrain::release_ice_turns,
+                    terrain::hold_course_on_ice,
+                    apply_direction_changes,
+                    snake_movement,
+                )
+                    .chain(),
+            );
+        let head = spawn_moving_snake(&mut app, body, direction);
+        app.world_mut().entity_mut(head).insert(Player(0));
+        (app, head)
+    }
+
+    /// A snake nobody steers, laid out along `body`, returning its head.
+    fn spawn_moving_snake(app: &mut App, body: &[Position], direction: Direction) -> Entity {
+        let world = app.world_mut();
+        let segments = body
+            .iter()
+            .map(|pos| world.spawn((SnakeSegment, *pos)).id())
+            .collect::<Vec<_>>();
+        let head = segments[0];
+        world.entity_mut(head).insert((
+            SnakeHead {
+                direction,
+                bonked: false,
+                turned: false,
+            },
+            SnakeSegments(segments),
+            LastTailPosition::default(),
+            MudDrag::default(),
+            IceSlide::default(),
+        ));
+        head
+    }
+
+    /// One step, with `turn` asked for first if there is one.
+    fn step(app: &mut App, snake: Entity, turn: Option<Direction>) {
+        if let Some(direction) = turn {
+            request(app, snake, direction);
+        }
+        app.world_mut().write_message(StepEvent);
+        app.update();
+    }
+
+    fn body(app: &App, snake: Entity) -> Vec<Position> {
+        app.world()
+            .get::<SnakeSegments>(snake)
+            .unwrap()
+            .iter()
+            .map(|segment| *app.world().get::<Position>(*segment).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ice_holds_a_turn_until_the_snake_slides_off() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        app.world_mut()
+            .resource_mut::<Terrain>()
+            .set(HEAD, Tile::Ice);
+
+        step(&mut app, snake, Some(Direction::Left));
+        assert_eq!(body(&app, snake), [Position { x: 5, y: 6 }, HEAD]);
+
+        // Off the ice, the turn goes through.
+        step(&mut app, snake, None);
+        assert_eq!(
+            body(&app, snake),
+            [Position { x: 4, y: 6 }, Position { x: 5, y: 6 }]
+        );
+    }
+
+    #[test]
+    fn conveyor_pushes_the_snake_one_cell_further() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        let mut terrain = app.world_mut().resource_mut::<Terrain>();
+        terrain.set(Position { x: 5, y: 6 }, Tile::Conveyor(Direction::Right));
+        // The push doesn't set off the conveyor it lands on.
+        terrain.set(Position { x: 6, y: 6 }, Tile::Conveyor(Direction::Up));
+
+        step(&mut app, snake, None);
+        assert_eq!(
+            body(&app, snake),
+            [Position { x: 6, y: 6 }, Position { x: 5, y: 6 }]
+        );
+        assert_eq!(direction(&app, snake), Direction::Up);
+    }
+
+    #[test]
+    fn mud_loses_every_other_step() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        let mud = Position { x: 5, y: 6 };
+        app.world_mut()
+            .resource_mut::<Terrain>()
+            .set(mud, Tile::Mud);
+
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [mud, HEAD]);
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [mud, HEAD]);
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [Position { x: 5, y: 7 }, mud]);
+    }
+
+    /// What the player died of on the last frame, if anything.
+    fn death(app: &App) -> Option<DeathCause> {
+        app.world()
+            .resource::<Messages<SnakeDied>>()
+            .iter_current_update_messages()
+            .map(|died| died.cause)
+            .next()
+    }
+
+    #[test]
+    fn walls_kill_a_snake_that_isnt_ramming() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        let wall = Position { x: 5, y: 6 };
+        app.world_mut()
+            .resource_mut::<Terrain>()
+            .set(wall, Tile::Wall { hp: 1 });
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), Some(DeathCause::Obstacle));
+    }
+
+    #[test]
+    fn ramming_wears_a_wall_down_a_hit_at_a_time() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        let wall = Position { x: 5, y: 6 };
+        app.world_mut()
+            .resource_mut::<Terrain>()
+            .set(wall, Tile::Wall { hp: 2 });
+        app.insert_resource(powerup::Ram::charged());
+
+        // The wall holds, and so does the snake.
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [HEAD, NECK]);
+        assert_eq!(death(&app), None);
+
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [wall, HEAD]);
+        assert_eq!(death(&app), None);
+        assert!(app.world().resource::<Terrain>().at(wall).is_none());
+        assert_eq!(
+            app.world()
+                .resource::<Messages<WallCrumbled>>()
+                .iter_current_update_messages()
+                .map(|crumbled| crumbled.0)
+                .collect::<Vec<_>>(),
+            [wall]
+        );
+    }
+
+    #[test]
+    fn a_shield_takes_one_crash_in_the_snakes_place() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        app.world_mut()
+            .resource_mut::<Terrain>()
+            .set(Position { x: 5, y: 6 }, Tile::Wall { hp: 1 });
+        app.insert_resource(upgrade::Shield::with_charges(1));
+
+        // Stopped short of the wall instead.
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), None);
+        assert_eq!(body(&app, snake), [HEAD, NECK]);
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), Some(DeathCause::Obstacle));
+    }
+
+    /// A three-cell snake about to run off the top of the arena, with the
+    /// edges doing what `walls` says.
+    fn edge_app(walls: WallBehavior) -> (App, Entity) {
+        let top = ARENA_HEIGHT as i32 - 1;
+        let body = (0..3)
+            .map(|i| Position { x: 5, y: top - i })
+            .collect::<Vec<_>>();
+        let (mut app, snake) = movement_app(&body, Direction::Up);
+        app.insert_resource(rules::Rules { walls, ..default() });
+        (app, snake)
+    }
+
+    #[test]
+    fn bouncing_off_the_edge_turns_the_snake_around() {
+        let (mut app, snake) = edge_app(WallBehavior::Bounce);
+        let before = body(&app, snake);
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), None);
+        assert_eq!(
+            body(&app, snake),
+            before.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(direction(&app, snake), Direction::Down);
+    }
+
+    #[test]
+    fn bonking_costs_points_once_and_waits_for_a_turn() {
+        let (mut app, snake) = edge_app(WallBehavior::Bonk);
+        app.world_mut().resource_mut::<GameState>().score = 3;
+        let before = body(&app, snake);
+        let bonks = |app: &App| {
+            app.world()
+                .resource::<Messages<bonk::WallBonked>>()
+                .iter_current_update_messages()
+                .count()
+        };
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), None);
+        assert_eq!(body(&app, snake), before);
+        assert_eq!(bonks(&app), 1);
+        assert_eq!(app.world().resource::<GameState>().score, 2);
+
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), before);
+        assert_eq!(bonks(&app), 0);
+        assert_eq!(app.world().resource::<GameState>().score, 2);
+
+        step(&mut app, snake, Some(Direction::Left));
+        assert_eq!(
+            body(&app, snake)[0],
+            Position {
+                x: 4,
+                y: before[0].y
+            }
+        );
+        assert!(!app.world().get::<SnakeHead>(snake).unwrap().bonked);
+    }
+
+    #[test]
+    fn every_snake_moves_its_own_body() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        let other = spawn_moving_snake(
+            &mut app,
+            &[Position { x: 2, y: 2 }, Position { x: 1, y: 2 }],
+            Direction::Right,
+        );
+
+        step(&mut app, snake, None);
+        assert_eq!(body(&app, snake), [Position { x: 5, y: 6 }, HEAD]);
+        assert_eq!(
+            body(&app, other),
+            [Position { x: 3, y: 2 }, Position { x: 2, y: 2 }]
+        );
+    }
+
+    #[test]
+    fn doors_block_the_way_until_their_key_opens_them() {
+        let door = Position { x: 5, y: 6 };
+        for open in [false, true] {
+            let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+            let mut terrain = app.world_mut().resource_mut::<Terrain>();
+            terrain.set(door, Tile::Door(key::KeyColor::Red));
+            terrain.set(Position { x: 6, y: 5 }, Tile::Door(key::KeyColor::Blue));
+            if open {
+                terrain.open(key::KeyColor::Red);
+            }
+
+            step(&mut app, snake, None);
+            if open {
+                assert_eq!(death(&app), None);
+                assert_eq!(body(&app, snake), [door, HEAD]);
+                // Only the red doors open.
+                assert!(
+                    app.world()
+                        .resource::<Terrain>()
+                        .blocks(Position { x: 6, y: 5 })
+                );
+            } else {
+                assert_eq!(death(&app), Some(DeathCause::Obstacle));
+            }
+        }
+    }
+
+    #[test]
+    fn running_into_another_snake_ends_the_run() {
+        let (mut app, snake) = movement_app(&[HEAD, NECK], Direction::Up);
+        // Long enough that its tail isn't what moves out of the way.
+        let across = (4..8).map(|x| Position { x, y: 6 }).collect::<Vec<_>>();
+        spawn_moving_snake(&mut app, &across, Direction::Left);
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), Some(DeathCause::Rival));
+    }
+
+    #[test]
+    fn anyone_else_crashing_into_the_player_is_out_where_it_stands() {
+        let tail = Position { x: 5, y: 3 };
+        let (mut app, snake) = movement_app(&[HEAD, NECK, tail], Direction::Right);
+        let alongside = [Position { x: 4, y: 5 }, Position { x: 3, y: 5 }];
+        let other = spawn_moving_snake(&mut app, &alongside, Direction::Right);
+
+        step(&mut app, snake, None);
+        assert_eq!(death(&app), None);
+        assert_eq!(body(&app, other), alongside);
+        assert_eq!(
+            app.world()
+                .resource::<Messages<SnakeCrashed>>()
+                .iter_current_update_messages()
+                .map(|crash| crash.snake)
+                .collect::<Vec<_>>(),
+            [other]
+        );
+    }
+
+    /// [`track_key_presses`] on its own, fed by hand.
+    fn keys_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<KeyPresses>()
+            .add_systems(Update, track_key_presses);
+        app
+    }
+
+    /// One frame in which `pressed` go down and `released` come up.
+    fn press(app: &mut App, pressed: &[KeyCode], released: &[KeyCode]) {
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        for key in pressed {
+            input.press(*key);
+        }
+        for key in released {
+            input.release(*key);
+        }
+        app.update();
+    }
+
+    fn held(app: &App, shape: GridShape) -> Option<Direction> {
+        held_direction(
+            app.world().resource::<KeyPresses>(),
+            ARROW_KEYS,
+            &*shape.sized(ARENA_WIDTH, ARENA_HEIGHT),
+        )
+    }
+
+    #[test]
+    fn held_direction_follows_a_tap_and_falls_back_to_the_held_key() {
+        let mut app = keys_app();
+        press(&mut app, &[KeyCode::ArrowLeft], &[]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Left));
+        press(&mut app, &[KeyCode::ArrowUp], &[]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Up));
+        press(&mut app, &[], &[KeyCode::ArrowUp]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Left));
+    }
+
+    #[test]
+    fn held_direction_hands_over_in_release_order() {
+        let mut app = keys_app();
+        press(&mut app, &[KeyCode::ArrowLeft], &[]);
+        press(&mut app, &[KeyCode::ArrowUp], &[]);
+        press(&mut app, &[KeyCode::ArrowRight], &[]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Right));
+        press(&mut app, &[], &[KeyCode::ArrowLeft]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Right));
+        press(&mut app, &[], &[KeyCode::ArrowRight]);
+        assert_eq!(held(&app, GridShape::Square), Some(Direction::Up));
+        press(&mut app, &[], &[KeyCode::ArrowUp]);
+        assert_eq!(held(&app, GridShape::Square), None);
+    }
+
+    #[test]
+    fn held_direction_combines_the_axes_into_a_diagonal() {
+        let mut app = keys_app();
+        press(&mut app, &[KeyCode::ArrowLeft], &[]);
+        press(&mut app, &[KeyCode::ArrowUp], &[]);
+        assert_eq!(held(&app, GridShape::Freestyle), Some(Direction::UpLeft));
+    }
+
+    #[test]
+    fn keys_pressed_together_go_in_key_code_order() {
+        for keys in [
+            [KeyCode::ArrowLeft, KeyCode::ArrowUp],
+            [KeyCode::ArrowUp, KeyCode::ArrowLeft],
+        ] {
+            let mut app = keys_app();
+            press(&mut app, &keys, &[]);
+            assert_eq!(
+                app.world().resource::<KeyPresses>().0,
+                [KeyCode::ArrowLeft, KeyCode::ArrowUp]
+            );
+            assert_eq!(held(&app, GridShape::Square), Some(Direction::Up));
+        }
+    }
+}
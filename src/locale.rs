@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Used when `assets/locale` can't be found, e.g. when the binary is run from
+/// outside the project directory.
+const EMBEDDED_ENGLISH: &str = include_str!("../assets/locale/en.ron");
+
+/// Browser builds can't list the asset directory, so they ship every language
+/// compiled in.
+#[cfg(target_arch = "wasm32")]
+const EMBEDDED_LANGUAGES: &[(&str, &str)] = &[
+    ("de", include_str!("../assets/locale/de.ron")),
+    ("en", EMBEDDED_ENGLISH),
+];
+
+/// Bevy's built-in font only covers ASCII, which isn't enough for translations.
+const FONT_DATA: &[u8] = include_bytes!("../assets/fonts/FiraMono-Medium.ttf");
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, replace_default_font);
+    }
+}
+
+/// Swaps the font behind `Handle::default()` so every `TextFont` picks it up.
+fn replace_default_font(mut fonts: ResMut<Assets<Font>>) {
+    let font = Font::try_from_bytes(FONT_DATA.to_vec()).expect("embedded font is valid");
+    fonts
+        .insert(AssetId::default(), font)
+        .expect("default font id is valid");
+}
+
+#[derive(Deserialize)]
+struct Language {
+    name: String,
+    strings: HashMap<String, String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_languages() -> Vec<(String, Language)> {
+    use bevy::asset::io::file::FileAssetReader;
+    use std::fs;
+
+    fs::read_dir(FileAssetReader::get_base_path().join("assets/locale"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let code = path.file_stem()?.to_str()?.to_string();
+            let contents = fs::read_to_string(&path).ok()?;
+            match ron::from_str(&contents) {
+                Ok(language) => Some((code, language)),
+                Err(err) => {
+                    warn!("Skipping locale {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_languages() -> Vec<(String, Language)> {
+    EMBEDDED_LANGUAGES
+        .iter()
+        .map(|(code, contents)| {
+            let language = ron::from_str(contents).expect("embedded locale is valid");
+            (code.to_string(), language)
+        })
+        .collect()
+}
+
+/// All user-facing text, keyed by message id and loaded from
+/// `assets/locale/<code>.ron`.
+#[derive(Resource)]
+pub struct Locale {
+    /// Sorted by language code.
+    languages: Vec<(String, Language)>,
+    current: usize,
+}
+
+impl Locale {
+    pub fn load(code: &str) -> Self {
+        let mut languages = read_languages();
+        if !languages.iter().any(|(code, _)| code == FALLBACK_LANGUAGE) {
+            languages.push((
+                FALLBACK_LANGUAGE.to_string(),
+                ron::from_str(EMBEDDED_ENGLISH).expect("embedded locale is valid"),
+            ));
+        }
+        languages.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut locale = Self {
+            languages,
+            current: 0,
+        };
+        locale.select(code);
+        locale
+    }
+
+    /// Switches to `code`, or to the fallback language if it isn't available.
+    pub fn select(&mut self, code: &str) {
+        self.current = self
+            .position(code)
+            .or_else(|| self.position(FALLBACK_LANGUAGE))
+            .unwrap_or(0);
+    }
+
+    /// Switches to the next available language and returns its code.
+    pub fn cycle(&mut self) -> &str {
+        self.current = (self.current + 1) % self.languages.len();
+        &self.languages[self.current].0
+    }
+
+    pub fn language_name(&self) -> &str {
+        &self.languages[self.current].1.name
+    }
+
+    /// Looks up `key` in the current language, then in the fallback language.
+    /// Missing keys are returned as-is so they stand out on screen.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.languages[self.current]
+            .1
+            .strings
+            .get(key)
+            .or_else(|| {
+                self.position(FALLBACK_LANGUAGE)
+                    .and_then(|index| self.languages[index].1.strings.get(key))
+            })
+            .map_or(key, String::as_str)
+    }
+
+    /// Looks up `key` and replaces each `{name}` placeholder with its value.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+
+    fn position(&self, code: &str) -> Option<usize> {
+        self.languages.iter().position(|(c, _)| c == code)
+    }
+}
@@ -0,0 +1,637 @@
+use bevy::prelude::*;
+
+use crate::focus::{FocusInput, FocusScroll, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::puzzle::Puzzles;
+use crate::rules::{HARDCORE_TARGET, Rules};
+use crate::settings::Settings;
+use crate::sound::Bus;
+use crate::victory::spawn_stars;
+use crate::{AppState, GameMode};
+
+/// Picks a puzzle by its number; the tenth is on 0, and Shift picks from the
+/// ten after those.
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+#[derive(Component)]
+struct MenuRoot;
+
+/// The key that picks the puzzle at `index`, as shown in the menu.
+fn puzzle_key_label(index: usize) -> String {
+    let digit = (index + 1) % DIGIT_KEYS.len();
+    if index < DIGIT_KEYS.len() {
+        digit.to_string()
+    } else {
+        format!("⇧{digit}")
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Menu), setup_menu)
+            .add_systems(
+                Update,
+                (
+                    menu_input.after(navigate_focus),
+                    refresh_menu.after(menu_input).run_if(
+                        resource_changed::<Locale>
+                            .or(resource_changed::<Settings>)
+                            .or(resource_changed::<Puzzles>),
+                    ),
+                )
+                    .run_if(in_state(AppState::Menu)),
+            );
+    }
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    profile: Res<Profile>,
+    puzzles: Res<Puzzles>,
+) {
+    let modes = [
+        locale.get("menu.endless").to_string(),
+        locale.get("menu.battle_royale").to_string(),
+        locale.get("menu.team_match").to_string(),
+        locale.get("menu.coop").to_string(),
+        locale.get("menu.roguelike").to_string(),
+        locale.format(
+            "menu.hardcore",
+            &[
+                ("target", &HARDCORE_TARGET),
+                ("streak", &profile.hardcore_streak),
+                ("best", &profile.best_hardcore_streak),
+            ],
+        ),
+        locale.get("menu.tournament").to_string(),
+        locale.get("menu.custom").to_string(),
+        locale.get("menu.shop").to_string(),
+        locale.get("menu.upgrades").to_string(),
+        locale.get("menu.replays").to_string(),
+        locale.get("menu.mods").to_string(),
+    ];
+    let puzzles = puzzles.0.iter().enumerate().map(|(index, puzzle)| {
+        let line = locale.format(
+            "menu.puzzle",
+            &[
+                ("number", &puzzle_key_label(index)),
+                ("name", &locale.get(&puzzle.name)),
+            ],
+        );
+        (line, profile.stars(&puzzle.name))
+    });
+    let mut options =
+        vec![locale.format("menu.language", &[("language", &locale.language_name())])];
+    options.push(locale.format(
+        "menu.ui_scale",
+        &[("percent", &(settings.ui_scale * 100.0).round())],
+    ));
+    options.push(locale.format("menu.speed", &[("ms", &settings.step_ms)]));
+    let path_preview = if settings.path_preview {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format("menu.path_preview", &[("state", &locale.get(path_preview))]));
+    options.push(locale.format(
+        "menu.touch_layout",
+        &[("layout", &locale.get(settings.touch_layout.name()))],
+    ));
+    options.push(locale.format(
+        "menu.control_scheme",
+        &[("scheme", &locale.get(settings.control_scheme.name()))],
+    ));
+    options.push(locale.format(
+        "menu.render_style",
+        &[("style", &locale.get(settings.render_style.name()))],
+    ));
+    options.push(locale.format(
+        "menu.grid_shape",
+        &[("shape", &locale.get(settings.grid_shape.name()))],
+    ));
+    options.push(locale.format(
+        "menu.weather",
+        &[("weather", &locale.get(settings.weather.name()))],
+    ));
+    let reduced_motion = if settings.reduced_motion {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format(
+        "menu.reduced_motion",
+        &[("state", &locale.get(reduced_motion))],
+    ));
+    let friendly_fire = if settings.friendly_fire {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format(
+        "menu.friendly_fire",
+        &[("state", &locale.get(friendly_fire))],
+    ));
+    let self_collision = if settings.self_collision {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format(
+        "menu.self_collision",
+        &[("state", &locale.get(self_collision))],
+    ));
+    let border_walls = if settings.border_walls {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format("menu.border_walls", &[("state", &locale.get(border_walls))]));
+    for bus in Bus::ALL {
+        options.push(locale.format(
+            "menu.volume",
+            &[
+                ("bus", &locale.get(bus.name())),
+                ("level", &volume_level(&settings, bus, &locale)),
+            ],
+        ));
+    }
+    options.push(locale.format(
+        "menu.rumble",
+        &[("level", &rumble_level(&settings, &locale))],
+    ));
+    let haptics = if settings.haptics {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format("menu.haptics", &[("state", &locale.get(haptics))]));
+    let announcements = if settings.announcements {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format(
+        "menu.announcements",
+        &[("state", &locale.get(announcements))],
+    ));
+    let auto_turn = if settings.auto_turn {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format("menu.auto_turn", &[("state", &locale.get(auto_turn))]));
+    let autopilot = if settings.autopilot {
+        "toggle.on"
+    } else {
+        "toggle.off"
+    };
+    options.push(locale.format("menu.autopilot", &[("state", &locale.get(autopilot))]));
+    options.push(locale.get("menu.quit").to_string());
+
+    // The list is taller than most windows, so it scrolls to keep the focused
+    // line in view. Auto margins center the column whenever it does fit.
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            ScrollPosition::default(),
+            FocusScroll,
+            MenuRoot,
+            DespawnOnExit(AppState::Menu),
+        ))
+        .with_children(|root| {
+            root.spawn(Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                margin: UiRect::vertical(Val::Auto),
+                // Keeps the last line clear of the control hints.
+                padding: UiRect::vertical(Val::Px(48.0)),
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(locale.get("menu.title")),
+                    TextFont {
+                        font_size: 64.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Label,
+                ));
+                // Lines are focused in the same order as [`menu_actions`].
+                let mut order = 0..;
+                for line in modes {
+                    parent.spawn((menu_line(line), Focusable::new(order.next().unwrap())));
+                }
+                for (line, stars) in puzzles {
+                    parent
+                        .spawn(Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(16.0),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((menu_line(line), Focusable::new(order.next().unwrap())));
+                            row.spawn(Node {
+                                column_gap: Val::Px(8.0),
+                                ..default()
+                            })
+                            .with_children(|stars_row| spawn_stars(stars_row, stars, 10.0));
+                        });
+                }
+                for line in options {
+                    parent.spawn((menu_line(line), Focusable::new(order.next().unwrap())));
+                }
+            });
+        });
+}
+
+/// The rumble strength as a percentage, or "off".
+pub fn rumble_level(settings: &Settings, locale: &Locale) -> String {
+    if settings.rumble > 0.0 {
+        format!("{}%", (settings.rumble * 100.0).round())
+    } else {
+        locale.get("toggle.off").to_string()
+    }
+}
+
+/// The volume of `bus` as a percentage, or "off".
+pub fn volume_level(settings: &Settings, bus: Bus, locale: &Locale) -> String {
+    let volume = settings.volume(bus);
+    if volume > 0.0 {
+        format!("{}%", (volume * 100.0).round())
+    } else {
+        locale.get("toggle.off").to_string()
+    }
+}
+
+fn menu_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_menu(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    profile: Res<Profile>,
+    puzzles: Res<Puzzles>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_menu(commands, locale, settings, profile, puzzles);
+}
+
+/// What a menu line does, whether it's picked with its shortcut or focused
+/// and activated.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    Endless,
+    BattleRoyale,
+    TeamMatch,
+    Coop,
+    Roguelike,
+    Hardcore,
+    Tournament,
+    Custom,
+    Shop,
+    Upgrades,
+    Replays,
+    Mods,
+    Puzzle(usize),
+    Language,
+    UiScale,
+    Speed,
+    PathPreview,
+    TouchLayout,
+    ControlScheme,
+    RenderStyle,
+    GridShape,
+    Weather,
+    ReducedMotion,
+    FriendlyFire,
+    SelfCollision,
+    BorderWalls,
+    Volume(Bus),
+    Rumble,
+    Haptics,
+    Announcements,
+    AutoTurn,
+    Autopilot,
+    Quit,
+}
+
+/// Every line of the menu in the order it's listed, which is also the
+/// order the focus moves through them.
+fn menu_actions(puzzles: usize) -> Vec<MenuAction> {
+    let mut actions = vec![
+        MenuAction::Endless,
+        MenuAction::BattleRoyale,
+        MenuAction::TeamMatch,
+        MenuAction::Coop,
+        MenuAction::Roguelike,
+        MenuAction::Hardcore,
+        MenuAction::Tournament,
+        MenuAction::Custom,
+        MenuAction::Shop,
+        MenuAction::Upgrades,
+        MenuAction::Replays,
+        MenuAction::Mods,
+    ];
+    actions.extend((0..puzzles).map(MenuAction::Puzzle));
+    actions.extend([
+        MenuAction::Language,
+        MenuAction::UiScale,
+        MenuAction::Speed,
+        MenuAction::PathPreview,
+        MenuAction::TouchLayout,
+        MenuAction::ControlScheme,
+        MenuAction::RenderStyle,
+        MenuAction::GridShape,
+        MenuAction::Weather,
+        MenuAction::ReducedMotion,
+        MenuAction::FriendlyFire,
+        MenuAction::SelfCollision,
+        MenuAction::BorderWalls,
+    ]);
+    actions.extend(Bus::ALL.map(MenuAction::Volume));
+    actions.extend([
+        MenuAction::Rumble,
+        MenuAction::Haptics,
+        MenuAction::Announcements,
+        MenuAction::AutoTurn,
+        MenuAction::Autopilot,
+        MenuAction::Quit,
+    ]);
+    actions
+}
+
+/// Keys that jump straight to a line, with how far they nudge a setting.
+const SHORTCUTS: [(KeyCode, MenuAction, i32); 32] = [
+    (KeyCode::KeyB, MenuAction::BattleRoyale, 1),
+    (KeyCode::KeyT, MenuAction::TeamMatch, 1),
+    (KeyCode::KeyC, MenuAction::Coop, 1),
+    (KeyCode::KeyR, MenuAction::Roguelike, 1),
+    (KeyCode::KeyD, MenuAction::Hardcore, 1),
+    (KeyCode::KeyY, MenuAction::Tournament, 1),
+    (KeyCode::KeyO, MenuAction::Custom, 1),
+    (KeyCode::KeyS, MenuAction::Shop, 1),
+    (KeyCode::KeyU, MenuAction::Upgrades, 1),
+    (KeyCode::KeyP, MenuAction::Replays, 1),
+    (KeyCode::KeyZ, MenuAction::Mods, 1),
+    (KeyCode::KeyL, MenuAction::Language, 1),
+    (KeyCode::Minus, MenuAction::UiScale, -1),
+    (KeyCode::Equal, MenuAction::UiScale, 1),
+    (KeyCode::BracketLeft, MenuAction::Speed, -1),
+    (KeyCode::BracketRight, MenuAction::Speed, 1),
+    (KeyCode::KeyH, MenuAction::TouchLayout, 1),
+    (KeyCode::KeyJ, MenuAction::ControlScheme, 1),
+    (KeyCode::KeyG, MenuAction::RenderStyle, 1),
+    (KeyCode::KeyX, MenuAction::GridShape, 1),
+    (KeyCode::KeyW, MenuAction::Weather, 1),
+    (KeyCode::KeyM, MenuAction::ReducedMotion, 1),
+    (KeyCode::KeyF, MenuAction::FriendlyFire, 1),
+    (KeyCode::KeyN, MenuAction::SelfCollision, 1),
+    (KeyCode::KeyE, MenuAction::BorderWalls, 1),
+    (KeyCode::KeyV, MenuAction::Rumble, 1),
+    (KeyCode::KeyK, MenuAction::Haptics, 1),
+    (KeyCode::KeyI, MenuAction::Announcements, 1),
+    (KeyCode::KeyQ, MenuAction::AutoTurn, 1),
+    (KeyCode::KeyA, MenuAction::Autopilot, 1),
+    (KeyCode::NumpadSubtract, MenuAction::UiScale, -1),
+    (KeyCode::NumpadAdd, MenuAction::UiScale, 1),
+];
+
+/// The action picked this frame and how far it nudges a setting, from a
+/// shortcut or from the focused line. Enter on a setting nudges it forward.
+fn picked_action(
+    keyboard_input: &ButtonInput<KeyCode>,
+    input_reader: &mut MessageReader<FocusInput>,
+    puzzles: usize,
+) -> Option<(MenuAction, i32)> {
+    let actions = menu_actions(puzzles);
+    let focused = input_reader.read().find_map(|input| match *input {
+        FocusInput::Activate(order) => Some((*actions.get(order)?, 1)),
+        FocusInput::Nudge(order, steps) => Some((*actions.get(order)?, steps)),
+        FocusInput::Back => Some((MenuAction::Quit, 1)),
+    });
+    if focused.is_some() {
+        return focused;
+    }
+    if let Some((_, action, steps)) = SHORTCUTS
+        .iter()
+        .find(|(key, _, _)| keyboard_input.just_pressed(*key))
+    {
+        return Some((*action, *steps));
+    }
+    let page = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        DIGIT_KEYS.len()
+    } else {
+        0
+    };
+    DIGIT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+        .map(|digit| page + digit)
+        .filter(|index| *index < puzzles)
+        .map(|index| (MenuAction::Puzzle(index), 1))
+}
+
+fn menu_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut input_reader: MessageReader<FocusInput>,
+    mut mode: ResMut<GameMode>,
+    mut rules: ResMut<Rules>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut locale: ResMut<Locale>,
+    mut settings: ResMut<Settings>,
+    profile: Res<Profile>,
+    puzzles: Res<Puzzles>,
+) {
+    let Some((action, steps)) = picked_action(&keyboard_input, &mut input_reader, puzzles.0.len())
+    else {
+        return;
+    };
+    let selected = match action {
+        MenuAction::Endless => {
+            *rules = Rules::default();
+            Some(GameMode::Endless)
+        }
+        MenuAction::BattleRoyale => {
+            *rules = Rules::battle_royale();
+            Some(GameMode::Endless)
+        }
+        MenuAction::TeamMatch => {
+            *rules = Rules::team_match(settings.friendly_fire);
+            Some(GameMode::Endless)
+        }
+        MenuAction::Coop => {
+            *rules = Rules::coop();
+            Some(GameMode::Endless)
+        }
+        MenuAction::Hardcore => {
+            // Self-collision stays on whatever the settings say.
+            *rules = Rules::hardcore();
+            *mode = GameMode::Endless;
+            next_state.set(AppState::Playing);
+            return;
+        }
+        MenuAction::Puzzle(index) => {
+            *rules = Rules::default();
+            Some(GameMode::Puzzle(index))
+        }
+        MenuAction::Roguelike => {
+            *rules = Rules::roguelike(&profile.upgrades);
+            *mode = GameMode::Endless;
+            next_state.set(AppState::Draft);
+            return;
+        }
+        MenuAction::Tournament => {
+            next_state.set(AppState::Tournament);
+            return;
+        }
+        MenuAction::Custom => {
+            next_state.set(AppState::Custom);
+            return;
+        }
+        MenuAction::Shop => {
+            next_state.set(AppState::Shop);
+            return;
+        }
+        MenuAction::Upgrades => {
+            next_state.set(AppState::Upgrades);
+            return;
+        }
+        MenuAction::Replays => {
+            next_state.set(AppState::Replays);
+            return;
+        }
+        MenuAction::Mods => {
+            next_state.set(AppState::Mods);
+            return;
+        }
+        MenuAction::Quit => {
+            next_state.set(AppState::Summary);
+            return;
+        }
+        MenuAction::Language => {
+            settings.language = locale.cycle().to_string();
+            None
+        }
+        MenuAction::UiScale => {
+            settings.adjust_ui_scale(steps as f32);
+            None
+        }
+        MenuAction::Speed => {
+            // Forward is faster, which is a shorter step.
+            settings.adjust_step_ms(-steps);
+            None
+        }
+        MenuAction::PathPreview => {
+            settings.path_preview = !settings.path_preview;
+            None
+        }
+        MenuAction::TouchLayout => {
+            settings.touch_layout = settings.touch_layout.next();
+            None
+        }
+        MenuAction::ControlScheme => {
+            settings.control_scheme = settings.control_scheme.next();
+            None
+        }
+        MenuAction::RenderStyle => {
+            settings.render_style = settings.render_style.next();
+            None
+        }
+        MenuAction::GridShape => {
+            settings.grid_shape = settings.grid_shape.next();
+            None
+        }
+        MenuAction::Weather => {
+            settings.weather = settings.weather.next();
+            None
+        }
+        MenuAction::ReducedMotion => {
+            settings.reduced_motion = !settings.reduced_motion;
+            None
+        }
+        MenuAction::FriendlyFire => {
+            settings.friendly_fire = !settings.friendly_fire;
+            None
+        }
+        MenuAction::SelfCollision => {
+            settings.self_collision = !settings.self_collision;
+            None
+        }
+        MenuAction::BorderWalls => {
+            settings.border_walls = !settings.border_walls;
+            None
+        }
+        MenuAction::Volume(bus) => {
+            settings.adjust_volume(bus, steps as f32);
+            None
+        }
+        MenuAction::Rumble => {
+            settings.adjust_rumble(steps as f32);
+            None
+        }
+        MenuAction::Haptics => {
+            settings.haptics = !settings.haptics;
+            None
+        }
+        MenuAction::Announcements => {
+            settings.announcements = !settings.announcements;
+            None
+        }
+        MenuAction::AutoTurn => {
+            settings.auto_turn = !settings.auto_turn;
+            None
+        }
+        MenuAction::Autopilot => {
+            settings.autopilot = !settings.autopilot;
+            None
+        }
+    };
+
+    // The settings lines change a setting and stay on the menu.
+    let Some(selected) = selected else {
+        settings.save();
+        return;
+    };
+    if selected == GameMode::Endless {
+        rules.self_collision = settings.self_collision;
+    }
+    *mode = selected;
+    next_state.set(AppState::Playing);
+}
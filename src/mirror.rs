@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::rules::Rules;
+
+pub struct MirrorPlugin;
+
+impl Plugin for MirrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), mirror_camera)
+            .add_systems(OnExit(AppState::Playing), restore_camera);
+    }
+}
+
+/// Draws the arena flipped left to right when the rules ask for it. Only
+/// the picture changes: the snake still moves, and turns, as usual. The HUD
+/// isn't drawn by the camera's transform, so it stays readable.
+fn mirror_camera(rules: Res<Rules>, mut cameras: Query<&mut Transform, With<Camera2d>>) {
+    let flip = if rules.mirrored { -1.0 } else { 1.0 };
+    for mut transform in cameras.iter_mut() {
+        transform.scale.x = flip;
+    }
+}
+
+fn restore_camera(mut cameras: Query<&mut Transform, With<Camera2d>>) {
+    for mut transform in cameras.iter_mut() {
+        transform.scale.x = 1.0;
+    }
+}
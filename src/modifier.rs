@@ -0,0 +1,389 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::rules::{Rules, WallBehavior};
+use crate::{AppState, GameMode, SnakeDied};
+
+/// How many modifiers each draft offers.
+const OFFERED: usize = 3;
+
+const DRAFT_KEYS: [KeyCode; OFFERED] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+
+/// Time between poison pellets for the first modifier that brings them;
+/// every further one halves it.
+const POISON_INTERVAL: Duration = Duration::from_secs(12);
+
+/// A twist on the rules drafted at the start of a roguelike run. Each one
+/// mostly trades a bonus for a drawback, and they stack.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Modifier {
+    Frenzy,
+    Wraparound,
+    Chaos,
+    Calm,
+    Crawl,
+}
+
+impl Modifier {
+    pub const ALL: [Self; 5] = [
+        Self::Frenzy,
+        Self::Wraparound,
+        Self::Chaos,
+        Self::Calm,
+        Self::Crawl,
+    ];
+
+    pub fn apply(self, rules: &mut Rules) {
+        match self {
+            Self::Frenzy => {
+                rules.food_points *= 2;
+                rules.speed *= 1.5;
+            }
+            Self::Wraparound => {
+                rules.walls = WallBehavior::Wrap;
+                add_poison(rules);
+            }
+            Self::Chaos => {
+                rules.event_interval /= 3;
+                rules.food_points += 1;
+            }
+            Self::Calm => {
+                rules.event_pool.clear();
+                rules.speed *= 1.25;
+            }
+            Self::Crawl => {
+                rules.speed *= 0.75;
+                add_poison(rules);
+            }
+        }
+    }
+
+    /// Locale key of the modifier's name; its description is under the same
+    /// key with `.info` appended.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Frenzy => "modifier.frenzy",
+            Self::Wraparound => "modifier.wraparound",
+            Self::Chaos => "modifier.chaos",
+            Self::Calm => "modifier.calm",
+            Self::Crawl => "modifier.crawl",
+        }
+    }
+}
+
+fn add_poison(rules: &mut Rules) {
+    rules.poison_interval = Some(
+        rules
+            .poison_interval
+            .map_or(POISON_INTERVAL, |interval| interval / 2),
+    );
+}
+
+/// Modifiers on offer in the current draft.
+#[derive(Resource, Default)]
+struct DraftOffer(Vec<Modifier>);
+
+#[derive(Component)]
+struct ModifiersText;
+
+pub struct ModifierPlugin;
+
+impl Plugin for ModifierPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DraftOffer>()
+            .add_systems(OnEnter(AppState::Draft), setup_draft)
+            .add_systems(
+                Update,
+                draft_input
+                    .after(navigate_focus)
+                    .run_if(in_state(AppState::Draft)),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                setup_modifiers_text
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(roguelike),
+            )
+            .add_systems(
+                Update,
+                next_draft_on_game_over
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(roguelike),
+            );
+    }
+}
+
+fn roguelike(rules: Res<Rules>) -> bool {
+    rules.roguelike
+}
+
+fn modifier_list(locale: &Locale, modifiers: &[Modifier]) -> String {
+    modifiers
+        .iter()
+        .map(|modifier| locale.get(modifier.name()).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn setup_draft(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    rules: Res<Rules>,
+    mut offer: ResMut<DraftOffer>,
+) {
+    offer.0 = Modifier::ALL
+        .choose_multiple(&mut rand::rng(), OFFERED)
+        .copied()
+        .collect();
+
+    let options: Vec<String> = offer
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, modifier)| {
+            locale.format(
+                "draft.option",
+                &[
+                    ("number", &(index + 1)),
+                    ("name", &locale.get(modifier.name())),
+                    (
+                        "description",
+                        &locale.get(&format!("{}.info", modifier.name())),
+                    ),
+                ],
+            )
+        })
+        .collect();
+    let mut lines = vec![locale.format("draft.lives", &[("lives", &rules.lives)])];
+    if !rules.modifiers.is_empty() {
+        lines.push(locale.format(
+            "draft.active",
+            &[("list", &modifier_list(&locale, &rules.modifiers))],
+        ));
+    }
+    lines.push(locale.get("draft.back").to_string());
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Draft),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("draft.title")),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for (index, line) in options.into_iter().enumerate() {
+                parent.spawn((draft_line(line), Focusable::new(index)));
+            }
+            for line in lines {
+                parent.spawn(draft_line(line));
+            }
+        });
+}
+
+fn draft_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+/// A digit or Enter on a modifier adds it to the ones already drafted and
+/// starts the run. Escape gives up on the whole roguelike run.
+fn draft_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut input_reader: MessageReader<FocusInput>,
+    offer: Res<DraftOffer>,
+    mut rules: ResMut<Rules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut picked = DRAFT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Activate(order) => picked = Some(order),
+            FocusInput::Nudge(..) => {}
+        }
+    }
+    let Some(modifier) = picked.and_then(|index| offer.0.get(index).copied()) else {
+        return;
+    };
+    modifier.apply(&mut rules);
+    rules.modifiers.push(modifier);
+    next_state.set(AppState::Playing);
+}
+
+fn setup_modifiers_text(mut commands: Commands, locale: Res<Locale>, rules: Res<Rules>) {
+    commands.spawn((
+        Text::new(locale.format(
+            "hud.modifiers",
+            &[("list", &modifier_list(&locale, &rules.modifiers))],
+        )),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        ModifiersText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// A death ends this run and costs a life. The next one starts with another
+/// draft, unless that was the last life.
+fn next_draft_on_game_over(
+    mut reader: MessageReader<SnakeDied>,
+    mut rules: ResMut<Rules>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if reader.read().last().is_none() {
+        return;
+    }
+    rules.lives = rules.lives.saturating_sub(1);
+    next_state.set(if rules.lives == 0 {
+        AppState::Menu
+    } else {
+        AppState::Draft
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::headless_app;
+    use crate::{DeathCause, Food, FoodEaten, GameState, Player, Position, SnakeTimer};
+
+    /// A roguelike run with lives to spare, at its first draft.
+    fn roguelike_app() -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            roguelike: true,
+            lives: 3,
+            ..default()
+        })
+        .insert_resource(Locale::load("en"))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_message::<FocusInput>()
+        .add_plugins(ModifierPlugin);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Draft);
+        app.update();
+        app
+    }
+
+    /// Picks `modifier` in the draft and starts the run with it.
+    fn draft(app: &mut App, modifier: Modifier) {
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::Draft
+        );
+        app.world_mut().resource_mut::<DraftOffer>().0 = vec![modifier];
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.press(DRAFT_KEYS[0]);
+        app.update();
+        // Nothing clears the key's press between frames here.
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .reset_all();
+        app.update();
+    }
+
+    fn player(app: &mut App) -> Entity {
+        let world = app.world_mut();
+        world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .unwrap()
+    }
+
+    /// Ends the run, which leads to the next draft.
+    fn die(app: &mut App) {
+        let snake = player(app);
+        app.world_mut().write_message(SnakeDied {
+            snake,
+            cause: DeathCause::Wall,
+        });
+        app.update();
+        app.update();
+    }
+
+    #[test]
+    fn drafted_modifiers_stack() {
+        let mut app = roguelike_app();
+        draft(&mut app, Modifier::Frenzy);
+        die(&mut app);
+        draft(&mut app, Modifier::Frenzy);
+
+        let rules = app.world().resource::<Rules>();
+        assert!(rules.modifiers == [Modifier::Frenzy, Modifier::Frenzy]);
+        assert_eq!(rules.food_points, 4);
+        assert_eq!(rules.speed, 2.25);
+        assert_eq!(rules.lives, 2);
+
+        // The run plays by both: twice as fast, twice over, and a meal
+        // scores four times.
+        let step = app.world().resource::<SnakeTimer>().timer.duration();
+        assert_eq!(step, Duration::from_millis(222));
+        let snake = player(&mut app);
+        app.world_mut().write_message(FoodEaten {
+            snake,
+            kind: Food::BASIC,
+            position: Position { x: 0, y: 0 },
+            segments: 1,
+            points: 1,
+        });
+        app.update();
+        assert_eq!(app.world().resource::<GameState>().score, 4);
+    }
+
+    #[test]
+    fn each_modifier_with_poison_brings_it_sooner() {
+        let mut app = roguelike_app();
+        draft(&mut app, Modifier::Wraparound);
+        assert_eq!(
+            app.world().resource::<Rules>().poison_interval,
+            Some(POISON_INTERVAL)
+        );
+        die(&mut app);
+        draft(&mut app, Modifier::Crawl);
+
+        let rules = app.world().resource::<Rules>();
+        assert_eq!(rules.poison_interval, Some(POISON_INTERVAL / 2));
+        assert!(rules.walls == WallBehavior::Wrap);
+        assert_eq!(rules.speed, 0.75);
+    }
+}
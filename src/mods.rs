@@ -0,0 +1,506 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::AppState;
+use crate::calendar::today;
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::items::{ItemKind, Items};
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::puzzle::{Puzzle, PuzzleData};
+use crate::replay::watching;
+use crate::rules::Rules;
+use crate::settings::Settings;
+use crate::shop::Theme;
+use crate::storage;
+use crate::tournament::in_tournament;
+use crate::weather::Weather;
+
+/// Each `.ron` file in here is a pack, named after the file.
+const MODS_DIR: &str = "mods";
+
+/// Packs that come with the game, each in effect only during its season.
+/// They're listed before the player's own, which can override their theme.
+const SEASONAL_PACKS: [(&str, &str); 2] = [
+    (
+        "seasons/winter.ron",
+        include_str!("../assets/seasons/winter.ron"),
+    ),
+    (
+        "seasons/halloween.ron",
+        include_str!("../assets/seasons/halloween.ron"),
+    ),
+];
+
+/// A mod pack as written, any part of which can be left out:
+///
+/// ```ron
+/// (
+///     name: "Lava fields",
+///     puzzles: [(name: "Hot steps", body: [(x: 3, y: 3)], direction: Up,
+///         food: [(x: 3, y: 8)], max_moves: 10, goal: EatAllFood)],
+///     theme: Some((sky: (0.2, 0.05, 0.02))),
+///     presets: [(name: "Buffet", rules: (food_count: 5))],
+///     food: [(color: (1.0, 0.3, 0.0), glyph: '%', points: 5, growth: 2,
+///         weight: 0.5)],
+///     power_ups: [(color: (0.9, 0.1, 0.1), effect: Some(Ram), steps: 30,
+///         weight: 0.2)],
+///     weather: Some(Dust),
+///     season: Some((from: (7, 1), to: (8, 31))),
+/// )
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PackData {
+    name: String,
+    puzzles: Vec<PuzzleData>,
+    theme: Option<ThemeData>,
+    /// Weather shown instead of the one in the settings.
+    weather: Option<Weather>,
+    /// The pack only takes effect between these dates.
+    season: Option<Season>,
+    presets: Vec<Preset>,
+    food: Vec<ItemKind>,
+    power_ups: Vec<ItemKind>,
+}
+
+/// Colors a pack paints the game in while it's enabled, over the theme
+/// bought in the shop.
+#[derive(Deserialize, Clone, Copy)]
+struct ThemeData {
+    sky: (f32, f32, f32),
+}
+
+/// Days of the year a pack is in effect, as month and day, both included.
+/// A season can run over the new year.
+#[derive(Deserialize, Clone, Copy)]
+struct Season {
+    from: (u32, u32),
+    to: (u32, u32),
+}
+
+impl Season {
+    fn contains(self, date: (u32, u32)) -> bool {
+        if self.from <= self.to {
+            self.from <= date && date <= self.to
+        } else {
+            self.from <= date || date <= self.to
+        }
+    }
+}
+
+/// Named rules to start the custom game screen from.
+#[derive(Deserialize, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub rules: Rules,
+}
+
+/// A pack found in [`MODS_DIR`].
+pub struct ModPack {
+    /// The file name without its extension, which is what the settings
+    /// remember it by.
+    id: String,
+    /// The file as last read, to tell when it's been edited.
+    source: String,
+    name: String,
+    pub enabled: bool,
+    pub puzzles: Vec<Puzzle>,
+    sky: Option<Color>,
+    weather: Option<Weather>,
+    season: Option<Season>,
+    pub presets: Vec<Preset>,
+    food: Vec<ItemKind>,
+    power_ups: Vec<ItemKind>,
+}
+
+/// Every pack found, sorted by file name.
+#[derive(Resource, Default)]
+pub struct Mods {
+    packs: Vec<ModPack>,
+    /// Files that didn't parse, as last read, so they're only reported
+    /// again once they're edited.
+    broken: HashMap<String, String>,
+}
+
+impl Mods {
+    /// Reads every pack, enabling all but the `disabled` ones. A pack that
+    /// doesn't parse is left out.
+    pub fn load(disabled: &[String]) -> Self {
+        let mut mods = Self::default();
+        mods.reload(disabled);
+        mods
+    }
+
+    /// Rereads the packs that were edited, added or removed since the last
+    /// look, returning whether any were. An edited pack stays switched on or
+    /// off, and one that no longer parses is kept as it was until it's fixed.
+    fn reload(&mut self, disabled: &[String]) -> bool {
+        let mut changed = false;
+        let mut packs = Vec::new();
+        for (path, source) in pack_sources() {
+            let old = self
+                .packs
+                .iter()
+                .position(|pack| pack.id == pack_id(&path))
+                .map(|index| self.packs.remove(index));
+            if old.as_ref().is_some_and(|old| old.source == source)
+                || self.broken.get(&path) == Some(&source)
+            {
+                packs.extend(old);
+                continue;
+            }
+            match load_pack(&path, source.clone(), disabled) {
+                Ok(mut pack) => {
+                    info!("Loaded mod {}", pack.name);
+                    if let Some(old) = old {
+                        pack.enabled = old.enabled;
+                    }
+                    self.broken.remove(&path);
+                    packs.push(pack);
+                    changed = true;
+                }
+                Err(err) => {
+                    warn!("Failed to load mod {}: {}", path, err);
+                    self.broken.insert(path, source);
+                    packs.extend(old);
+                }
+            }
+        }
+        // Whatever is left had its file removed.
+        changed |= !self.packs.is_empty();
+        self.packs = packs;
+        changed
+    }
+
+    /// Packs that are switched on and in season.
+    pub fn enabled(&self) -> impl Iterator<Item = &ModPack> {
+        let today = today();
+        self.packs.iter().filter(move |pack| {
+            pack.enabled && pack.season.is_none_or(|season| season.contains(today))
+        })
+    }
+
+    /// The sky of the last enabled pack with a theme, or else `theme`'s.
+    pub fn sky(&self, theme: Theme) -> Color {
+        self.enabled()
+            .filter_map(|pack| pack.sky)
+            .last()
+            .unwrap_or(theme.sky())
+    }
+
+    /// The weather of the last enabled pack with any.
+    pub fn weather(&self) -> Option<Weather> {
+        self.enabled().filter_map(|pack| pack.weather).last()
+    }
+
+    /// The rule presets of every enabled pack.
+    pub fn presets(&self) -> impl Iterator<Item = &Preset> {
+        self.enabled().flat_map(|pack| &pack.presets)
+    }
+
+    /// The game's own items followed by those of every enabled pack.
+    pub fn items(&self) -> Items {
+        let mut items = Items::load();
+        for pack in self.enabled() {
+            items.food.extend(pack.food.iter().cloned());
+            items.power_ups.extend(pack.power_ups.iter().cloned());
+        }
+        items
+    }
+}
+
+/// Path and contents of every pack: the seasonal ones, then those in
+/// [`MODS_DIR`].
+fn pack_sources() -> Vec<(String, String)> {
+    SEASONAL_PACKS
+        .iter()
+        .map(|(path, source)| (path.to_string(), source.to_string()))
+        .chain(
+            pack_paths()
+                .into_iter()
+                .filter_map(|path| storage::read(&path).map(|source| (path, source))),
+        )
+        .collect()
+}
+
+/// Paths of every pack, sorted by file name.
+fn pack_paths() -> Vec<String> {
+    let mut paths: Vec<String> = storage::list(MODS_DIR)
+        .into_iter()
+        .filter(|path| path.ends_with(".ron"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn pack_id(path: &str) -> String {
+    path.trim_start_matches(MODS_DIR)
+        .trim_start_matches('/')
+        .trim_end_matches(".ron")
+        .to_string()
+}
+
+fn load_pack(path: &str, source: String, disabled: &[String]) -> Result<ModPack, String> {
+    let data: PackData = ron::from_str(&source).map_err(|err| err.to_string())?;
+    let id = pack_id(path);
+    Ok(ModPack {
+        name: if data.name.is_empty() {
+            id.clone()
+        } else {
+            data.name
+        },
+        enabled: !disabled.contains(&id),
+        puzzles: data
+            .puzzles
+            .into_iter()
+            .map(PuzzleData::into_puzzle)
+            .collect::<Result<_, _>>()?,
+        sky: data.theme.map(|theme| {
+            let (red, green, blue) = theme.sky;
+            Color::srgb(red, green, blue)
+        }),
+        weather: data.weather,
+        season: data.season,
+        presets: data.presets,
+        food: data.food,
+        power_ups: data.power_ups,
+        id,
+        source,
+    })
+}
+
+#[derive(Component)]
+struct ModsRoot;
+
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Startup, watch_mods);
+        app.add_systems(OnEnter(AppState::Mods), setup_mods)
+            .add_systems(Update, reload_mods.run_if(mods_edited))
+            .add_systems(
+                Update,
+                (
+                    apply_sky,
+                    apply_items.run_if(not(watching)).run_if(not(in_tournament)),
+                )
+                    .after(reload_mods)
+                    .run_if(resource_changed::<Mods>),
+            )
+            .add_systems(
+                Update,
+                (
+                    mods_input.after(navigate_focus),
+                    refresh_mods
+                        .after(mods_input)
+                        .run_if(resource_changed::<Mods>.or(resource_changed::<Locale>)),
+                )
+                    .run_if(in_state(AppState::Mods)),
+            );
+    }
+}
+
+/// Reports every change to the files in [`MODS_DIR`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource)]
+struct ModsWatcher {
+    /// Stops reporting once dropped.
+    _watcher: notify::RecommendedWatcher,
+    events: std::sync::Mutex<std::sync::mpsc::Receiver<notify::Result<notify::Event>>>,
+}
+
+/// Starts watching [`MODS_DIR`], making it first if need be, so packs
+/// dropped in later are picked up too.
+#[cfg(not(target_arch = "wasm32"))]
+fn watch_mods(mut commands: Commands) {
+    use notify::Watcher;
+
+    let (sender, events) = std::sync::mpsc::channel();
+    let watcher = std::fs::create_dir_all(MODS_DIR)
+        .map_err(notify::Error::io)
+        .and_then(|()| notify::recommended_watcher(sender))
+        .and_then(|mut watcher| {
+            watcher.watch(
+                std::path::Path::new(MODS_DIR),
+                notify::RecursiveMode::NonRecursive,
+            )?;
+            Ok(watcher)
+        });
+    match watcher {
+        Ok(watcher) => commands.insert_resource(ModsWatcher {
+            _watcher: watcher,
+            events: std::sync::Mutex::new(events),
+        }),
+        Err(err) => warn!("Not watching {} for edits: {}", MODS_DIR, err),
+    }
+}
+
+/// Whether anything in [`MODS_DIR`] changed since the last look. Takes
+/// every change reported so far, as saving a file can report several.
+#[cfg(not(target_arch = "wasm32"))]
+fn mods_edited(watcher: Option<Res<ModsWatcher>>) -> bool {
+    watcher.is_some_and(|watcher| {
+        watcher
+            .events
+            .lock()
+            .is_ok_and(|events| events.try_iter().count() > 0)
+    })
+}
+
+/// Browsers keep packs in local storage, which nothing edits while the game
+/// runs.
+#[cfg(target_arch = "wasm32")]
+fn mods_edited() -> bool {
+    false
+}
+
+/// Picks up edits to the packs while the game runs, so a level or theme can
+/// be tweaked without restarting.
+fn reload_mods(mut mods: ResMut<Mods>, settings: Res<Settings>) {
+    // Only a pack that actually changed should redo everything built on
+    // them.
+    if mods
+        .bypass_change_detection()
+        .reload(&settings.disabled_mods)
+    {
+        mods.set_changed();
+    }
+}
+
+fn apply_sky(mods: Res<Mods>, profile: Res<Profile>, mut sky: ResMut<ClearColor>) {
+    sky.0 = mods.sky(profile.theme);
+}
+
+/// A replay being watched keeps the items it was recorded with, and a
+/// tournament attempt the game's own.
+fn apply_items(mods: Res<Mods>, mut items: ResMut<Items>) {
+    *items = mods.items();
+}
+
+/// What a pack adds, like "puzzles: 3, items: 2, theme".
+fn contents(pack: &ModPack, locale: &Locale) -> String {
+    let mut parts = Vec::new();
+    if !pack.puzzles.is_empty() {
+        parts.push(locale.format("mods.puzzles", &[("count", &pack.puzzles.len())]));
+    }
+    if !pack.presets.is_empty() {
+        parts.push(locale.format("mods.presets", &[("count", &pack.presets.len())]));
+    }
+    let items = pack.food.len() + pack.power_ups.len();
+    if items > 0 {
+        parts.push(locale.format("mods.items", &[("count", &items)]));
+    }
+    if pack.sky.is_some() {
+        parts.push(locale.get("mods.theme").to_string());
+    }
+    if let Some(weather) = pack.weather {
+        parts.push(locale.get(weather.name()).to_string());
+    }
+    if let Some(Season { from, to }) = pack.season {
+        let date = |(month, day): (u32, u32)| format!("{:02}-{:02}", month, day);
+        parts.push(locale.format("mods.season", &[("from", &date(from)), ("to", &date(to))]));
+    }
+    parts.join(", ")
+}
+
+fn setup_mods(mut commands: Commands, mods: Res<Mods>, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ModsRoot,
+            DespawnOnExit(AppState::Mods),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("mods.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            if mods.packs.is_empty() {
+                parent.spawn(mods_line(locale.get("mods.empty").to_string()));
+            }
+            for (index, pack) in mods.packs.iter().enumerate() {
+                let state = if pack.enabled {
+                    "toggle.on"
+                } else {
+                    "toggle.off"
+                };
+                parent.spawn((
+                    mods_line(locale.format(
+                        "mods.line",
+                        &[
+                            ("name", &pack.name),
+                            ("state", &locale.get(state)),
+                            ("contents", &contents(pack, &locale)),
+                        ],
+                    )),
+                    Focusable::new(index),
+                ));
+            }
+            parent.spawn(mods_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn mods_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_mods(
+    mut commands: Commands,
+    mods: Res<Mods>,
+    locale: Res<Locale>,
+    roots: Query<Entity, With<ModsRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_mods(commands, mods, locale);
+}
+
+/// Enter switches the focused pack on or off, Escape goes back to the menu.
+fn mods_input(
+    mut input_reader: MessageReader<FocusInput>,
+    mut mods: ResMut<Mods>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => next_state.set(AppState::Menu),
+            FocusInput::Activate(index) => {
+                let Some(pack) = mods.packs.get_mut(index) else {
+                    continue;
+                };
+                pack.enabled = !pack.enabled;
+                settings.disabled_mods.retain(|id| *id != pack.id);
+                if !pack.enabled {
+                    settings.disabled_mods.push(pack.id.clone());
+                }
+                settings.save();
+            }
+            FocusInput::Nudge(..) => {}
+        }
+    }
+}
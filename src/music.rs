@@ -0,0 +1,272 @@
+use bevy::audio::{AddAudioSource, Decodable, Source, Volume};
+use bevy::prelude::*;
+use core::f32::consts::TAU;
+use core::time::Duration;
+
+use crate::settings::Settings;
+use crate::sound::Bus;
+use crate::{AppState, GameState, Player, SnakeDied, SnakeSegments, StepEvent, attract};
+
+const SAMPLE_RATE: u32 = 44_100;
+const BEATS_PER_MINUTE: f32 = 112.0;
+const BEAT_SECS: f32 = 60.0 / BEATS_PER_MINUTE;
+const BEATS_PER_BAR: u32 = 4;
+/// Every stem is four bars long, so they line up on every repeat.
+const LOOP_BEATS: u32 = 16;
+const LOOP_SAMPLES: u32 = (LOOP_BEATS as f32 * BEAT_SECS * SAMPLE_RATE as f32) as u32;
+/// Seconds a layer takes to fade fully in or out.
+const FADE_SECS: f32 = BEAT_SECS;
+
+/// Root note of each bar of the loop, in hertz: A, F, C, G.
+const ROOTS: [f32; 4] = [110.0, 87.31, 130.81, 98.0];
+/// A minor pentatonic, an octave above the bass.
+const SCALE: [f32; 6] = [220.0, 261.63, 293.66, 329.63, 392.0, 440.0];
+/// The lead's notes, one per eighth of a beat, as steps of [`SCALE`]. Dots
+/// are rests.
+const MELODY: &[u8; 32] = b"0.234.320.121...4.543.234.320...";
+
+/// One of the parts of the soundtrack, each played from its own loop.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    /// Plays throughout a run.
+    Base,
+    Percussion,
+    Lead,
+}
+
+impl Layer {
+    const ALL: [Self; 3] = [Self::Base, Self::Percussion, Self::Lead];
+
+    /// Whether the layer should play this far into a run. The snake's
+    /// length counts as well as the score, so modes that score differently
+    /// still build up.
+    fn wanted(self, score: u32, length: usize) -> bool {
+        match self {
+            Self::Base => true,
+            Self::Percussion => score >= 5 || length >= 8,
+            Self::Lead => score >= 15 || length >= 16,
+        }
+    }
+
+    /// The layer's sample `index` samples into the loop.
+    fn sample(self, index: u32) -> f32 {
+        let t = index as f32 / SAMPLE_RATE as f32;
+        let beat = (t / BEAT_SECS) as u32;
+        let into_beat = t - beat as f32 * BEAT_SECS;
+        match self {
+            // A plucked bass on every beat, following the roots.
+            Self::Base => {
+                let root = ROOTS[(beat / BEATS_PER_BAR) as usize % ROOTS.len()];
+                0.25 * pluck(into_beat, BEAT_SECS) * (TAU * root * into_beat).sin()
+            }
+            // A kick on every other beat and a hi-hat on every eighth.
+            Self::Percussion => {
+                let kick = if beat.is_multiple_of(2) {
+                    let phase =
+                        TAU * (45.0 * into_beat + 75.0 * (1.0 - (-into_beat * 30.0).exp()) / 30.0);
+                    0.5 * (-into_beat * 12.0).exp() * phase.sin()
+                } else {
+                    0.0
+                };
+                let into_eighth = into_beat % (BEAT_SECS / 2.0);
+                0.12 * (-into_eighth * 60.0).exp() * noise(index) + kick
+            }
+            Self::Lead => {
+                let eighth = (t / (BEAT_SECS / 2.0)) as usize;
+                let note = MELODY[eighth % MELODY.len()];
+                if note == b'.' {
+                    return 0.0;
+                }
+                let step = (note - b'0') as usize;
+                let into_eighth = t - eighth as f32 * BEAT_SECS / 2.0;
+                let phase = TAU * SCALE[step] * into_eighth;
+                0.15 * pluck(into_eighth, BEAT_SECS / 2.0)
+                    * (phase.sin() + 0.3 * (3.0 * phase).sin())
+            }
+        }
+    }
+}
+
+/// Loudness of a note `t` seconds into a note `length` seconds long: a quick
+/// attack, then dying away to nothing by its end so notes don't click.
+fn pluck(t: f32, length: f32) -> f32 {
+    (t / 0.005).min(1.0) * (1.0 - t / length).powi(2)
+}
+
+/// The same white noise for the same sample on every loop.
+fn noise(index: u32) -> f32 {
+    let mut x = index.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    x as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// One loop of a [`Layer`], worked out sample by sample.
+#[derive(Asset, TypePath)]
+struct Stem(Layer);
+
+impl Decodable for Stem {
+    type DecoderItem = f32;
+    type Decoder = StemDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        StemDecoder {
+            layer: self.0,
+            index: 0,
+        }
+    }
+}
+
+struct StemDecoder {
+    layer: Layer,
+    index: u32,
+}
+
+impl Iterator for StemDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index >= LOOP_SAMPLES {
+            return None;
+        }
+        let sample = self.layer.sample(self.index);
+        self.index += 1;
+        Some(sample)
+    }
+}
+
+impl Source for StemDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((LOOP_SAMPLES - self.index) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            LOOP_SAMPLES as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+/// Where the soundtrack is up to and how loud each layer is, indexed like
+/// [`Layer::ALL`].
+#[derive(Resource, Default)]
+struct Soundtrack {
+    /// The shared beat clock: seconds since the stems started together.
+    clock: f32,
+    gains: [f32; 3],
+    targets: [f32; 3],
+    /// Set when the snake dies, until the next run gets moving.
+    dropped: bool,
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Stem>()
+            .init_resource::<Soundtrack>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                start_soundtrack.run_if(attract::not_demo),
+            )
+            .add_systems(
+                Update,
+                (follow_the_run, fade_layers)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Starts every stem in the same frame, so they stay in step, with only the
+/// base layer audible.
+fn start_soundtrack(
+    mut commands: Commands,
+    mut soundtrack: ResMut<Soundtrack>,
+    settings: Res<Settings>,
+    mut stems: ResMut<Assets<Stem>>,
+) {
+    let base = [1.0, 0.0, 0.0];
+    *soundtrack = Soundtrack {
+        gains: base,
+        targets: base,
+        ..default()
+    };
+    for (layer, gain) in Layer::ALL.into_iter().zip(base) {
+        commands.spawn((
+            AudioPlayer(stems.add(Stem(layer))),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(gain * settings.volume(Bus::Music))),
+            layer,
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+/// Brings layers in as the run builds up, on the next bar line so they come
+/// in on the beat. A death drops them at once.
+fn follow_the_run(
+    real: Res<Time<Real>>,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut step_reader: MessageReader<StepEvent>,
+    game_state: Res<GameState>,
+    mut soundtrack: ResMut<Soundtrack>,
+    bodies: Query<&SnakeSegments, With<Player>>,
+) {
+    if step_reader.read().next().is_some() {
+        soundtrack.dropped = false;
+    }
+    if game_over_reader.read().next().is_some() {
+        soundtrack.dropped = true;
+        soundtrack.targets = [1.0, 0.0, 0.0];
+    }
+
+    let bar = BEAT_SECS * BEATS_PER_BAR as f32;
+    let last_bar = (soundtrack.clock / bar) as u32;
+    soundtrack.clock += real.delta_secs();
+    if (soundtrack.clock / bar) as u32 == last_bar || soundtrack.dropped {
+        return;
+    }
+    let length = bodies
+        .iter()
+        .map(|segments| segments.len())
+        .max()
+        .unwrap_or(0);
+    soundtrack.targets = Layer::ALL.map(|layer| {
+        if layer.wanted(game_state.score, length) {
+            1.0
+        } else {
+            0.0
+        }
+    });
+}
+
+/// Moves every layer towards its target and sets it to that share of the
+/// music volume.
+fn fade_layers(
+    real: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut soundtrack: ResMut<Soundtrack>,
+    mut sinks: Query<(&Layer, &mut AudioSink)>,
+) {
+    let step = real.delta_secs() / FADE_SECS;
+    let Soundtrack { gains, targets, .. } = &mut *soundtrack;
+    for (gain, target) in gains.iter_mut().zip(*targets) {
+        *gain += (target - *gain).clamp(-step, step);
+    }
+    for (layer, mut sink) in sinks.iter_mut() {
+        let index = Layer::ALL
+            .iter()
+            .position(|other| other == layer)
+            .unwrap_or(0);
+        sink.set_volume(Volume::Linear(gains[index] * settings.volume(Bus::Music)));
+    }
+}
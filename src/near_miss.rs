@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::grid::{Grid, WindowLayout};
+use crate::locale::Locale;
+use crate::results::RunStats;
+use crate::rules::{Rules, WallBehavior};
+use crate::terrain::{Terrain, Tile};
+use crate::{
+    AppState, GameMode, GameSet, GameState, Player, Position, SnakeDied, SnakeSegments, StepEvent,
+};
+
+const POPUP_COLOR: Color = Color::srgb(0.5, 1.0, 0.9);
+
+/// Points for slipping past something that would have killed the snake.
+const CLOSE_CALL_POINTS: u32 = 2;
+
+/// Segments right behind the head are always next to it, so only the body
+/// beyond these counts as a close call.
+const SAFE_SEGMENTS: usize = 4;
+
+const POPUP_LIFETIME: Duration = Duration::from_millis(900);
+
+/// How fast a popup floats up, in pixels per second.
+const POPUP_RISE: f32 = 40.0;
+
+/// Whether the head ended the last step next to danger, so hugging a wall
+/// only pays out once.
+#[derive(Resource, Default)]
+struct NearDanger(bool);
+
+/// A "close call" bonus floating up from where it was earned.
+#[derive(Component)]
+struct Popup(Timer);
+
+pub struct NearMissPlugin;
+
+impl Plugin for NearMissPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NearDanger>()
+            .add_systems(OnEnter(AppState::Playing), reset_near_danger)
+            .add_systems(
+                Update,
+                (
+                    close_calls
+                        .in_set(GameSet::Collisions)
+                        .run_if(resource_equals(GameMode::Endless)),
+                    float_popups,
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn reset_near_danger(mut near: ResMut<NearDanger>) {
+    *near = NearDanger::default();
+}
+
+/// Whether moving onto `cell` would have ended the run.
+fn deadly(
+    cell: Position,
+    grid: &dyn Grid,
+    rules: &Rules,
+    terrain: &Terrain,
+    body: &[Position],
+) -> bool {
+    let off_board = !grid.contains(cell) && rules.walls == WallBehavior::Kill;
+    let wall = matches!(terrain.at(cell), Some(Tile::Wall { .. } | Tile::Door(_)));
+    let bitten = rules.self_collision && body.contains(&cell);
+    off_board || wall || bitten
+}
+
+/// Awards a bonus when a step brings the head right next to a wall or its
+/// own body without hitting it.
+fn close_calls(
+    mut commands: Commands,
+    mut step_reader: MessageReader<StepEvent>,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    layout: WindowLayout,
+    arena: Res<Arena>,
+    rules: Res<Rules>,
+    terrain: Res<Terrain>,
+    locale: Res<Locale>,
+    mut near: ResMut<NearDanger>,
+    mut game_state: ResMut<GameState>,
+    mut stats: ResMut<RunStats>,
+    heads: Query<(&Position, &SnakeSegments), With<Player>>,
+    positions: Query<&Position>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    if game_over_reader.read().next().is_some() {
+        near.0 = false;
+        return;
+    }
+    let Ok((head, segments)) = heads.single() else {
+        return;
+    };
+    let grid = arena.grid();
+    let body = segments
+        .iter()
+        .skip(SAFE_SEGMENTS)
+        .filter_map(|segment| positions.get(*segment).ok().copied())
+        .collect::<Vec<Position>>();
+    let was_near = near.0;
+    near.0 = grid
+        .neighbors(*head)
+        .into_iter()
+        .any(|cell| deadly(cell, grid, &rules, &terrain, &body));
+    if !near.0 || was_near {
+        return;
+    }
+
+    game_state.score += CLOSE_CALL_POINTS;
+    stats.close_call_bonus += CLOSE_CALL_POINTS;
+    let Some(layout) = layout.get() else {
+        return;
+    };
+    let position = layout.cell_to_world(*head);
+    commands.spawn((
+        Text2d::new(locale.format("popup.close_call", &[("points", &CLOSE_CALL_POINTS)])),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(POPUP_COLOR),
+        // Flipped back in a mirrored arena, so it still reads.
+        Transform::from_translation(position.extend(1.0)).with_scale(Vec3::new(
+            if rules.mirrored { -1.0 } else { 1.0 },
+            1.0,
+            1.0,
+        )),
+        Popup(Timer::new(POPUP_LIFETIME, TimerMode::Once)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn float_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popups: Query<(Entity, &mut Popup, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut popup, mut transform, mut color) in popups.iter_mut() {
+        if popup.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += POPUP_RISE * time.delta_secs();
+        color.0.set_alpha(popup.0.fraction_remaining());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::rules::Spawn;
+    use crate::{Direction, DirectionChangeRequest, SnakeTimer};
+
+    /// An endless run with the snake laid out as `spawn` says and no food
+    /// to get in its way.
+    fn near_miss_app(spawn: Spawn) -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            spawns: vec![spawn],
+            food_count: 0,
+            ..default()
+        })
+        .insert_resource(Locale::load("en"))
+        .init_resource::<RunStats>()
+        .add_plugins(NearMissPlugin);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(60));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    /// One step of the snake, turning towards `turn` first if given.
+    fn step(app: &mut App, turn: Option<Direction>) {
+        let world = app.world_mut();
+        let snake = world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .unwrap();
+        if let Some(direction) = turn {
+            world.write_message(DirectionChangeRequest { snake, direction });
+        }
+        let remaining = world.resource::<SnakeTimer>().timer.remaining();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(remaining));
+        app.update();
+    }
+
+    fn bonus(app: &App) -> u32 {
+        app.world().resource::<RunStats>().close_call_bonus
+    }
+
+    #[test]
+    fn hugging_a_wall_pays_out_once() {
+        let mut app = near_miss_app(Spawn {
+            position: Position { x: 2, y: 5 },
+            length: 2,
+            direction: Some(Direction::Left),
+        });
+        step(&mut app, None);
+        assert_eq!(bonus(&app), 0);
+
+        // Up to the edge, then along it.
+        step(&mut app, None);
+        assert_eq!(bonus(&app), CLOSE_CALL_POINTS);
+        step(&mut app, Some(Direction::Up));
+        step(&mut app, None);
+        step(&mut app, None);
+        assert_eq!(bonus(&app), CLOSE_CALL_POINTS);
+    }
+
+    #[test]
+    fn segments_right_behind_the_head_never_count() {
+        // Turning back on itself puts the head right beside the last of the
+        // segments that are left out.
+        let mut app = near_miss_app(Spawn {
+            position: Position { x: 10, y: 10 },
+            length: SAFE_SEGMENTS as u32 + 1,
+            direction: Some(Direction::Right),
+        });
+        step(&mut app, Some(Direction::Up));
+        step(&mut app, Some(Direction::Left));
+        assert_eq!(bonus(&app), 0);
+    }
+
+    #[test]
+    fn dying_next_to_danger_scores_nothing() {
+        let mut app = near_miss_app(Spawn {
+            position: Position { x: 0, y: 5 },
+            length: 2,
+            direction: Some(Direction::Left),
+        });
+        step(&mut app, None);
+        assert_eq!(bonus(&app), 0);
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::grid::WindowLayout;
+use crate::rules::WallBehavior;
+use crate::settings::Settings;
+use crate::{AppState, Player, Position, SnakeHead, attract};
+
+/// Steps ahead of the head that are marked.
+const PREVIEW_STEPS: usize = 3;
+/// Side of a marker, as a share of a cell.
+const MARKER_SIZE: f32 = 0.3;
+/// How opaque each marker is, nearest first.
+const MARKER_ALPHA: [f32; PREVIEW_STEPS] = [0.6, 0.4, 0.25];
+/// Above the ground, below the snake and everything it can run into.
+const MARKER_DEPTH: f32 = -0.5;
+
+/// One dot of the line of cells a head is about to cross.
+#[derive(Component)]
+struct PathMarker;
+
+pub struct PathPreviewPlugin;
+
+impl Plugin for PathPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            draw_path_preview
+                .run_if(in_state(AppState::Playing))
+                .run_if(attract::not_demo),
+        );
+    }
+}
+
+/// The cells `head` crosses over the next [`PREVIEW_STEPS`] steps if it
+/// isn't turned, stopping at the edge of the arena unless it wraps.
+fn path_ahead(arena: &Arena, head_pos: Position, head: &SnakeHead) -> Vec<Position> {
+    let grid = arena.grid();
+    let mut cells = Vec::with_capacity(PREVIEW_STEPS);
+    let mut pos = head_pos;
+    for _ in 0..PREVIEW_STEPS {
+        pos = grid.step(pos, head.direction);
+        if matches!(arena.walls, WallBehavior::Wrap) {
+            pos = grid.wrap(pos);
+        }
+        if !arena.contains(pos) {
+            break;
+        }
+        cells.push(pos);
+    }
+    cells
+}
+
+/// Dots the way ahead of every head, following turns as soon as they're
+/// made. Markers are kept and reused from frame to frame, and hidden while
+/// the preview is off.
+fn draw_path_preview(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    arena: Res<Arena>,
+    layout: WindowLayout,
+    heads: Query<(&Position, &SnakeHead), With<Player>>,
+    mut markers: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<PathMarker>>,
+) {
+    let Some(layout) = layout.get() else {
+        return;
+    };
+    let mut dots = Vec::new();
+    if settings.path_preview {
+        for (head_pos, head) in heads.iter() {
+            dots.extend(
+                path_ahead(&arena, *head_pos, head)
+                    .into_iter()
+                    .zip(MARKER_ALPHA),
+            );
+        }
+    }
+
+    let scale = Vec3::new(
+        layout.cell_size * MARKER_SIZE,
+        layout.cell_size * MARKER_SIZE,
+        1.0,
+    );
+    let mut markers = markers.iter_mut();
+    for (pos, alpha) in dots {
+        let transform = Transform::from_translation(layout.cell_to_world(pos).extend(MARKER_DEPTH))
+            .with_scale(scale);
+        let color = Color::WHITE.with_alpha(alpha);
+        match markers.next() {
+            Some((mut marker, mut sprite, mut visibility)) => {
+                *marker = transform;
+                sprite.color = color;
+                *visibility = Visibility::Inherited;
+            }
+            None => {
+                commands.spawn((
+                    Sprite::from_color(color, Vec2::ONE),
+                    transform,
+                    PathMarker,
+                    DespawnOnExit(AppState::Playing),
+                ));
+            }
+        }
+    }
+    for (_, _, mut visibility) in markers {
+        *visibility = Visibility::Hidden;
+    }
+}
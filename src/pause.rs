@@ -0,0 +1,357 @@
+use bevy::prelude::*;
+use bevy::window::AppLifecycle;
+use core::time::Duration;
+
+use crate::countdown::{self, Countdown};
+use crate::focus::{Focus, FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::menu::{rumble_level, volume_level};
+use crate::replay;
+use crate::settings::Settings;
+use crate::sound::Bus;
+use crate::{AppState, RestartEvent};
+
+const DIM_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+/// Whether gameplay is frozen. Only exists while playing.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(AppState = AppState::Playing)]
+pub(crate) enum PauseState {
+    #[default]
+    Running,
+    Paused,
+    /// The results of the run that just ended are up, see [`crate::results`].
+    Results,
+}
+
+/// What [`on_game_timer`] looks at to tell whether the run is going.
+type RunGoing<'w> = (
+    Option<Res<'w, State<PauseState>>>,
+    Option<Res<'w, Countdown>>,
+);
+
+/// Like [`on_timer`](bevy::time::common_conditions::on_timer), but only
+/// counts time while the run is going: not paused, counting down or on a
+/// menu. Every run condition of a system is checked each frame, so a plain
+/// timer next to [`PauseState::Running`] keeps filling up meanwhile and
+/// fires the moment play resumes.
+pub(crate) fn on_game_timer(duration: Duration) -> impl FnMut(Res<Time>, RunGoing) -> bool + Clone {
+    let mut timer = Timer::new(duration, TimerMode::Repeating);
+    move |time, (pause, countdown)| {
+        let running = pause.is_some_and(|pause| *pause.get() == PauseState::Running)
+            && countdown::countdown_finished(countdown);
+        running && timer.tick(time.delta()).just_finished()
+    }
+}
+
+/// A line of the pause menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PauseItem {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+    Language,
+    UiScale,
+    Speed,
+    PathPreview,
+    ReducedMotion,
+    Volume(Bus),
+    Rumble,
+    Haptics,
+    Announcements,
+    AutoTurn,
+    Back,
+}
+
+const MAIN_ITEMS: [PauseItem; 4] = [
+    PauseItem::Resume,
+    PauseItem::Restart,
+    PauseItem::Settings,
+    PauseItem::Quit,
+];
+
+/// The settings that can safely change mid-run.
+const SETTINGS_ITEMS: [PauseItem; 13] = [
+    PauseItem::Language,
+    PauseItem::UiScale,
+    PauseItem::Speed,
+    PauseItem::PathPreview,
+    PauseItem::ReducedMotion,
+    PauseItem::Volume(Bus::Music),
+    PauseItem::Volume(Bus::Sfx),
+    PauseItem::Volume(Bus::Ui),
+    PauseItem::Rumble,
+    PauseItem::Haptics,
+    PauseItem::Announcements,
+    PauseItem::AutoTurn,
+    PauseItem::Back,
+];
+
+/// Which page of the pause menu is open.
+#[derive(Resource, Default)]
+struct PauseMenu {
+    in_settings: bool,
+}
+
+impl PauseMenu {
+    fn items(&self) -> &'static [PauseItem] {
+        if self.in_settings {
+            &SETTINGS_ITEMS
+        } else {
+            &MAIN_ITEMS
+        }
+    }
+
+    /// Switches page, focusing its first line.
+    fn open(&mut self, in_settings: bool, focus: &mut Focus) {
+        self.in_settings = in_settings;
+        focus.order = 0;
+    }
+}
+
+#[derive(Component)]
+struct PauseRoot;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseState>()
+            .init_resource::<PauseMenu>()
+            .add_systems(OnEnter(PauseState::Paused), open_pause_menu)
+            .add_systems(
+                Update,
+                (toggle_pause.run_if(replay::not_watching), pause_on_suspend)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    pause_menu_input.after(navigate_focus),
+                    refresh_pause_menu.after(pause_menu_input).run_if(
+                        resource_changed::<PauseMenu>
+                            .or(resource_changed::<Settings>)
+                            .or(resource_changed::<Locale>),
+                    ),
+                )
+                    .run_if(in_state(PauseState::Paused)),
+            );
+    }
+}
+
+fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    let start = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::Start));
+    if keyboard_input.just_pressed(KeyCode::KeyP) || start {
+        next_state.set(match state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+            PauseState::Results => return,
+        });
+    }
+}
+
+/// Mobile platforms can background the app at any moment; don't let the snake
+/// run into a wall while the player is away.
+fn pause_on_suspend(
+    mut reader: MessageReader<AppLifecycle>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if reader.read().any(|lifecycle| {
+        matches!(
+            lifecycle,
+            AppLifecycle::WillSuspend | AppLifecycle::Suspended
+        )
+    }) {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+fn open_pause_menu(
+    commands: Commands,
+    mut menu: ResMut<PauseMenu>,
+    mut focus: ResMut<Focus>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+) {
+    menu.open(false, &mut focus);
+    setup_pause_menu(commands, menu.into(), locale, settings);
+}
+
+fn item_line(item: PauseItem, locale: &Locale, settings: &Settings) -> String {
+    let toggle = |on: bool| locale.get(if on { "toggle.on" } else { "toggle.off" });
+    match item {
+        PauseItem::Resume => locale.get("pause.resume").to_string(),
+        PauseItem::Restart => locale.get("pause.restart").to_string(),
+        PauseItem::Settings => locale.get("pause.settings").to_string(),
+        PauseItem::Quit => locale.get("pause.quit").to_string(),
+        PauseItem::Language => {
+            locale.format("pause.language", &[("language", &locale.language_name())])
+        }
+        PauseItem::UiScale => locale.format(
+            "pause.ui_scale",
+            &[("percent", &(settings.ui_scale * 100.0).round())],
+        ),
+        PauseItem::Speed => locale.format("pause.speed", &[("ms", &settings.step_ms)]),
+        PauseItem::PathPreview => locale.format(
+            "pause.path_preview",
+            &[("state", &toggle(settings.path_preview))],
+        ),
+        PauseItem::ReducedMotion => locale.format(
+            "pause.reduced_motion",
+            &[("state", &toggle(settings.reduced_motion))],
+        ),
+        PauseItem::Volume(bus) => locale.format(
+            "pause.volume",
+            &[
+                ("bus", &locale.get(bus.name())),
+                ("level", &volume_level(settings, bus, locale)),
+            ],
+        ),
+        PauseItem::Rumble => locale.format(
+            "pause.rumble",
+            &[("level", &rumble_level(settings, locale))],
+        ),
+        PauseItem::Haptics => {
+            locale.format("pause.haptics", &[("state", &toggle(settings.haptics))])
+        }
+        PauseItem::Announcements => locale.format(
+            "pause.announcements",
+            &[("state", &toggle(settings.announcements))],
+        ),
+        PauseItem::AutoTurn => {
+            locale.format("pause.auto_turn", &[("state", &toggle(settings.auto_turn))])
+        }
+        PauseItem::Back => locale.get("pause.back").to_string(),
+    }
+}
+
+/// Dims the board and lists the current page.
+fn setup_pause_menu(
+    mut commands: Commands,
+    menu: Res<PauseMenu>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+) {
+    let title = if menu.in_settings {
+        "pause.settings_title"
+    } else {
+        "pause.title"
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(DIM_COLOR),
+            PauseRoot,
+            DespawnOnExit(PauseState::Paused),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get(title)),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Label,
+            ));
+            for (index, item) in menu.items().iter().enumerate() {
+                parent.spawn((
+                    Text::new(item_line(*item, &locale, &settings)),
+                    TextFont {
+                        font_size: 28.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                    Focusable::new(index),
+                ));
+            }
+        });
+}
+
+fn refresh_pause_menu(
+    mut commands: Commands,
+    menu: Res<PauseMenu>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    roots: Query<Entity, With<PauseRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_pause_menu(commands, menu, locale, settings);
+}
+
+/// Enter uses the focused line, left and right change a setting and Escape
+/// backs out.
+fn pause_menu_input(
+    mut input_reader: MessageReader<FocusInput>,
+    mut menu: ResMut<PauseMenu>,
+    mut focus: ResMut<Focus>,
+    mut locale: ResMut<Locale>,
+    mut settings: ResMut<Settings>,
+    mut restart_writer: MessageWriter<RestartEvent>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for input in input_reader.read() {
+        // Enter nudges a setting the same way as right does.
+        let (order, steps) = match *input {
+            FocusInput::Back if menu.in_settings => {
+                menu.open(false, &mut focus);
+                continue;
+            }
+            FocusInput::Back => {
+                next_pause.set(PauseState::Running);
+                continue;
+            }
+            FocusInput::Activate(order) => (order, None),
+            FocusInput::Nudge(order, steps) => (order, Some(steps)),
+        };
+        let Some(item) = menu.items().get(order).copied() else {
+            continue;
+        };
+        let nudge = steps.unwrap_or(1);
+        match item {
+            PauseItem::Language => settings.language = locale.cycle().to_string(),
+            PauseItem::UiScale => settings.adjust_ui_scale(nudge as f32),
+            // Right makes the snake faster, which is a shorter step.
+            PauseItem::Speed => settings.adjust_step_ms(-nudge),
+            PauseItem::PathPreview => settings.path_preview = !settings.path_preview,
+            PauseItem::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            PauseItem::Volume(bus) => settings.adjust_volume(bus, nudge as f32),
+            PauseItem::Rumble => settings.adjust_rumble(nudge as f32),
+            PauseItem::Haptics => settings.haptics = !settings.haptics,
+            PauseItem::Announcements => settings.announcements = !settings.announcements,
+            PauseItem::AutoTurn => settings.auto_turn = !settings.auto_turn,
+            _ if steps.is_some() => continue,
+            PauseItem::Resume => next_pause.set(PauseState::Running),
+            PauseItem::Restart => {
+                restart_writer.write(RestartEvent { same_seed: false });
+                next_pause.set(PauseState::Running);
+            }
+            PauseItem::Settings => menu.open(true, &mut focus),
+            PauseItem::Quit => next_state.set(AppState::Menu),
+            PauseItem::Back => menu.open(false, &mut focus),
+        }
+        if menu.in_settings {
+            settings.save();
+        }
+    }
+}
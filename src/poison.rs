@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::boss::{PoisonFood, spawn_poison};
+use crate::countdown;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::terrain::Terrain;
+use crate::{
+    AppState, DeathCause, GameMode, GameRng, GameSet, Player, Position, RunStarted, SnakeDied,
+    StepClock,
+};
+
+/// Poison stops appearing once this many pellets are lying around.
+const MAX_POISON: usize = 8;
+
+#[derive(Resource)]
+struct PoisonTimer(Timer);
+
+/// Poison pellets that turn up over time in endless runs whose rules ask for
+/// them.
+pub struct PoisonPlugin;
+
+impl Plugin for PoisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            start_poison_timer
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(poison_enabled),
+        )
+        .add_systems(
+            Update,
+            (
+                clear_poison_on_respawn,
+                spawn_poison_over_time
+                    .in_set(GameSet::Spawning)
+                    .run_if(in_state(PauseState::Running))
+                    .run_if(countdown::countdown_finished),
+                poison_contact.in_set(GameSet::Collisions),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(poison_enabled),
+        );
+    }
+}
+
+fn poison_enabled(rules: Res<Rules>) -> bool {
+    rules.poison_interval.is_some()
+}
+
+fn start_poison_timer(mut commands: Commands, rules: Res<Rules>) {
+    if let Some(interval) = rules.poison_interval {
+        commands.insert_resource(PoisonTimer(Timer::new(interval, TimerMode::Repeating)));
+    }
+}
+
+fn clear_poison_on_respawn(
+    mut commands: Commands,
+    mut run_reader: MessageReader<RunStarted>,
+    timer: Option<ResMut<PoisonTimer>>,
+    poison: Query<Entity, With<PoisonFood>>,
+) {
+    if run_reader.read().next().is_none() {
+        return;
+    }
+    for entity in poison.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some(mut timer) = timer {
+        timer.0.reset();
+    }
+}
+
+fn spawn_poison_over_time(
+    mut commands: Commands,
+    step_clock: Res<StepClock>,
+    timer: Option<ResMut<PoisonTimer>>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    poison: Query<(), With<PoisonFood>>,
+    occupied: Query<&Position>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+    if !timer.0.tick(step_clock.delta()).just_finished() || poison.iter().count() >= MAX_POISON {
+        return;
+    }
+    let grid = arena.grid();
+    let cell = (0..20)
+        .map(|_| grid.random_cell(&mut rng.rng))
+        .find(|cell| !terrain.blocks(*cell) && !occupied.iter().any(|pos| pos == cell));
+    if let Some(cell) = cell {
+        spawn_poison(&mut commands, cell);
+    }
+}
+
+/// Eating poison is fatal.
+fn poison_contact(
+    mut game_over_writer: MessageWriter<SnakeDied>,
+    heads: Query<(Entity, &Position), With<Player>>,
+    poison: Query<&Position, With<PoisonFood>>,
+) {
+    if let Some((snake, _)) = heads
+        .iter()
+        .find(|(_, head)| poison.iter().any(|pos| pos == *head))
+    {
+        game_over_writer.write(SnakeDied {
+            snake,
+            cause: DeathCause::Poison,
+        });
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::items::{Effect, ItemKind, Items};
+use crate::locale::Locale;
+use crate::reverse::ReversedControls;
+use crate::{AppState, GameSet, Player, PlayerMeals, Position, Size, StepEvent};
+
+const RAM_COLOR: Color = Color::srgb(1.0, 0.55, 0.1);
+
+/// Steps a ram lasts when bought in the shop.
+const RAM_STEPS: u32 = 12;
+
+/// A pickup of the type at this index in [`Items`], lying on the board
+/// until the snake's head reaches it.
+#[derive(Component, Clone, Copy)]
+pub struct PowerUp(pub usize);
+
+impl PowerUp {
+    /// The first type defined, which puzzles place.
+    pub const BASIC: Self = Self(0);
+}
+
+/// While active, running into a wall knocks a hit point off it instead of
+/// ending the run.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct Ram {
+    steps: u32,
+}
+
+impl Ram {
+    /// A full ram, as if just picked up.
+    pub fn charged() -> Self {
+        Self::for_steps(RAM_STEPS)
+    }
+
+    pub fn for_steps(steps: u32) -> Self {
+        Self { steps }
+    }
+
+    pub fn active(self) -> bool {
+        self.steps > 0
+    }
+}
+
+#[derive(Component)]
+struct RamText;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ram>()
+            .add_systems(OnEnter(AppState::Playing), setup_ram_text)
+            .add_systems(OnExit(AppState::Playing), clear_ram)
+            .add_systems(
+                Update,
+                (
+                    tick_ram.after(GameSet::Movement),
+                    collect_power_ups.after(tick_ram),
+                    food_effects.after(GameSet::Collisions).after(tick_ram),
+                    update_ram_text.after(collect_power_ups).after(food_effects),
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Spawns `power_up` in white; the game paints it in its type's colors.
+pub fn spawn_power_up(commands: &mut Commands, position: Position, power_up: PowerUp) {
+    commands.spawn((
+        Sprite::from_color(Color::WHITE, Vec2::ONE),
+        Transform::default(),
+        position,
+        Size::square(0.6),
+        power_up,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn clear_ram(mut ram: ResMut<Ram>) {
+    *ram = Ram::default();
+}
+
+fn tick_ram(mut step_reader: MessageReader<StepEvent>, mut ram: ResMut<Ram>) {
+    if step_reader.read().next().is_some() && ram.active() {
+        ram.steps -= 1;
+    }
+}
+
+/// Starts what `kind`'s effect does, for as long as it says.
+fn apply_effect(kind: &ItemKind, ram: &mut Ram, reversed: &mut ReversedControls) {
+    match kind.effect {
+        Some(Effect::Ram) => *ram = Ram::for_steps(kind.steps),
+        Some(Effect::Reverse) => *reversed = ReversedControls::for_secs(kind.seconds),
+        None => {}
+    }
+}
+
+fn collect_power_ups(
+    mut commands: Commands,
+    items: Res<Items>,
+    mut ram: ResMut<Ram>,
+    mut reversed: ResMut<ReversedControls>,
+    power_ups: Query<(Entity, &Position, &PowerUp)>,
+    heads: Query<&Position, With<Player>>,
+) {
+    for head_pos in heads.iter() {
+        for (entity, position, power_up) in power_ups.iter() {
+            if position == head_pos {
+                commands.entity(entity).despawn();
+                apply_effect(items.power_up(*power_up), &mut ram, &mut reversed);
+            }
+        }
+    }
+}
+
+/// Food can carry an effect too.
+fn food_effects(
+    mut growth_reader: PlayerMeals,
+    items: Res<Items>,
+    mut ram: ResMut<Ram>,
+    mut reversed: ResMut<ReversedControls>,
+) {
+    for growth in growth_reader.read() {
+        apply_effect(items.food(growth.kind), &mut ram, &mut reversed);
+    }
+}
+
+fn setup_ram_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(RAM_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(56.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        RamText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_ram_text(ram: Res<Ram>, locale: Res<Locale>, mut query: Query<&mut Text, With<RamText>>) {
+    if !ram.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.0 = if ram.active() {
+            locale.format("hud.ram", &[("steps", &ram.steps)])
+        } else {
+            String::new()
+        };
+    }
+}
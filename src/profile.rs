@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::shop::{CustomSkin, Loadout, ShopItem, Skin, Theme};
+use crate::storage;
+use crate::upgrade::Upgrade;
+
+const PROFILE_PATH: &str = "profile.ron";
+
+/// Player progress that survives between sessions.
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub tutorial_completed: bool,
+    /// Best star rating of each completed puzzle, by its name key.
+    pub puzzle_stars: HashMap<String, u8>,
+    /// Earned in endless runs and spent in the shop.
+    pub coins: u32,
+    pub owned: Vec<ShopItem>,
+    pub skin: Skin,
+    /// Colors of [`Skin::Custom`], also kept while another skin is worn.
+    pub custom_skin: CustomSkin,
+    pub theme: Theme,
+    pub loadout: Loadout,
+    /// Bought with coins, and in effect in every roguelike run.
+    pub upgrades: Vec<Upgrade>,
+    /// Hardcore runs in a row that reached the target score, and the most
+    /// there have ever been.
+    pub hardcore_streak: u32,
+    pub best_hardcore_streak: u32,
+    /// Seeds of the tournaments entered, each of which gets one attempt.
+    pub tournament_seeds: Vec<String>,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        storage::load(PROFILE_PATH)
+    }
+
+    pub fn stars(&self, puzzle: &str) -> u8 {
+        self.puzzle_stars.get(puzzle).copied().unwrap_or(0)
+    }
+
+    /// Keeps `stars` if it beats the puzzle's best rating so far, returning
+    /// whether it did.
+    pub fn record_stars(&mut self, puzzle: &str, stars: u8) -> bool {
+        if stars <= self.stars(puzzle) {
+            return false;
+        }
+        self.puzzle_stars.insert(puzzle.to_string(), stars);
+        true
+    }
+
+    /// Extends the hardcore streak if the run made the target, or ends it.
+    pub fn record_hardcore_run(&mut self, made_target: bool) {
+        if made_target {
+            self.hardcore_streak += 1;
+            self.best_hardcore_streak = self.best_hardcore_streak.max(self.hardcore_streak);
+        } else {
+            self.hardcore_streak = 0;
+        }
+    }
+
+    pub fn owns(&self, item: ShopItem) -> bool {
+        self.owned.contains(&item)
+    }
+
+    pub fn equipped(&self, item: ShopItem) -> bool {
+        match item {
+            ShopItem::Skin(skin) => self.skin == skin,
+            ShopItem::Theme(theme) => self.theme == theme,
+            ShopItem::Loadout(loadout) => self.loadout == loadout,
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(PROFILE_PATH, self);
+    }
+}
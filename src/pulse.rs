@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use std::f32::consts::TAU;
+
+use crate::pause::PauseState;
+use crate::settings::Settings;
+use crate::{AppState, Food, Size};
+
+/// Time for one swell and shrink.
+const PULSE_PERIOD: Duration = Duration::from_millis(1200);
+
+/// How far food swells, as a share of its size.
+const PULSE_AMPLITUDE: f32 = 0.12;
+
+/// Number of different starting points, so neighbouring food doesn't pulse
+/// in lockstep.
+const PULSE_PHASES: u32 = 5;
+
+/// Gently swells and shrinks an entity around its `base` size, so it reads
+/// as something to pick up rather than part of the walls.
+#[derive(Component)]
+struct Pulse {
+    timer: Timer,
+    base: f32,
+}
+
+pub struct PulsePlugin;
+
+impl Plugin for PulsePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (start_pulses, pulse.after(start_pulses))
+                .run_if(in_state(AppState::Playing))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+fn start_pulses(mut commands: Commands, food: Query<(Entity, &Size), Added<Food>>) {
+    for (entity, size) in food.iter() {
+        let mut timer = Timer::new(PULSE_PERIOD, TimerMode::Repeating);
+        let phase = (entity.index() % PULSE_PHASES) as f32 / PULSE_PHASES as f32;
+        timer.set_elapsed(PULSE_PERIOD.mul_f32(phase));
+        commands.entity(entity).insert(Pulse {
+            timer,
+            base: size.width,
+        });
+    }
+}
+
+fn pulse(time: Res<Time>, settings: Res<Settings>, mut pulses: Query<(&mut Pulse, &mut Size)>) {
+    for (mut pulse, mut size) in pulses.iter_mut() {
+        let scale = if settings.reduced_motion {
+            1.0
+        } else {
+            pulse.timer.tick(time.delta());
+            1.0 + PULSE_AMPLITUDE * (pulse.timer.fraction() * TAU).sin()
+        };
+        *size = Size::square(pulse.base * scale);
+    }
+}
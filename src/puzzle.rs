@@ -0,0 +1,1170 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+use crate::arena::Arena;
+use crate::boss::{Boss, BossPart, PoisonFood, SweepMarker, spawn_boss, spawn_poison};
+use crate::hazard::{Hazard, spawn_hazard};
+use crate::hunter::{Hunter, spawn_hunter};
+use crate::key::{Key, KeyColor, spawn_key};
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::pause::PauseState;
+use crate::powerup::{PowerUp, Ram, spawn_power_up};
+use crate::profile::Profile;
+use crate::terrain::{IceSlide, MudDrag, Terrain, TerrainArea, Tile};
+use crate::touch::TouchDirection;
+use crate::victory::{MAX_STARS, VictoryScreen};
+use crate::{
+    AppState, Direction, DirectionChangeRequest, Food, GameMode, GameSet, GameState,
+    LastTailPosition, LevelCompleteEvent, PendingGrowth, Position, RestartEvent, SnakeDied,
+    SnakeHead, SnakeSegment, SnakeSegments, SnakeTimer, StepEvent, allowed_turn, spawn_food,
+    spawn_player_snake,
+};
+
+#[derive(Deserialize, Clone, PartialEq)]
+enum PuzzleGoal {
+    EatAllFood,
+    ReachLength(usize),
+    DefeatBoss,
+}
+
+impl PuzzleGoal {
+    fn describe(&self, locale: &Locale) -> String {
+        match self {
+            Self::EatAllFood => locale.get("puzzle.goal.eat_all").to_string(),
+            Self::ReachLength(length) => {
+                locale.format("puzzle.goal.reach_length", &[("length", length)])
+            }
+            Self::DefeatBoss => locale.get("puzzle.goal.defeat_boss").to_string(),
+        }
+    }
+}
+
+/// A hand-authored level where every key press moves the snake one cell.
+/// The built-in ones borrow their layout from [`PUZZLES`], and those of mod
+/// packs own theirs, so a reloaded pack drops its old puzzles.
+#[derive(Clone, PartialEq)]
+pub struct Puzzle {
+    /// Locale key of the puzzle's display name.
+    pub name: Cow<'static, str>,
+    /// Starting body, head first.
+    body: Cow<'static, [Position]>,
+    direction: Direction,
+    food: Cow<'static, [Position]>,
+    power_ups: Cow<'static, [Position]>,
+    keys: Cow<'static, [(Position, KeyColor)]>,
+    /// Each hazard's patrol route, starting from its first corner.
+    patrols: Cow<'static, [Cow<'static, [Position]>]>,
+    hunters: Cow<'static, [Position]>,
+    /// Bottom left cell of the boss, if the level has one.
+    boss: Option<Position>,
+    poison: Cow<'static, [Position]>,
+    terrain: Cow<'static, [TerrainArea]>,
+    max_moves: u32,
+    goal: PuzzleGoal,
+}
+
+/// A puzzle as written in a mod pack's RON, see [`crate::mods`]. The name
+/// is shown as is rather than looked up in the locale.
+#[derive(Deserialize)]
+pub struct PuzzleData {
+    name: String,
+    body: Vec<Position>,
+    direction: Direction,
+    #[serde(default)]
+    food: Vec<Position>,
+    #[serde(default)]
+    power_ups: Vec<Position>,
+    #[serde(default)]
+    keys: Vec<(Position, KeyColor)>,
+    #[serde(default)]
+    patrols: Vec<Vec<Position>>,
+    #[serde(default)]
+    hunters: Vec<Position>,
+    #[serde(default)]
+    boss: Option<Position>,
+    #[serde(default)]
+    poison: Vec<Position>,
+    #[serde(default)]
+    terrain: Vec<TerrainArea>,
+    max_moves: u32,
+    goal: PuzzleGoal,
+}
+
+impl PuzzleData {
+    pub fn into_puzzle(self) -> Result<Puzzle, String> {
+        if self.body.is_empty() || self.patrols.iter().any(Vec::is_empty) {
+            return Err(format!("puzzle {} has an empty body or patrol", self.name));
+        }
+        Ok(Puzzle {
+            name: self.name.into(),
+            body: self.body.into(),
+            direction: self.direction,
+            food: self.food.into(),
+            power_ups: self.power_ups.into(),
+            keys: self.keys.into(),
+            patrols: self.patrols.into_iter().map(Cow::Owned).collect(),
+            hunters: self.hunters.into(),
+            boss: self.boss,
+            poison: self.poison.into(),
+            terrain: self.terrain.into(),
+            max_moves: self.max_moves,
+            goal: self.goal,
+        })
+    }
+}
+
+/// Every puzzle on offer: the built-in ones, then those of the enabled mod
+/// packs. [`GameMode::Puzzle`] indexes into it.
+#[derive(Resource, Default)]
+pub struct Puzzles(pub Vec<Puzzle>);
+
+pub const PUZZLES: &[Puzzle] = &[
+    Puzzle {
+        name: Cow::Borrowed("puzzle.first_bite"),
+        body: Cow::Borrowed(&[Position { x: 3, y: 3 }, Position { x: 3, y: 2 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[
+            Position { x: 3, y: 6 },
+            Position { x: 6, y: 6 },
+            Position { x: 6, y: 3 },
+            Position { x: 10, y: 3 },
+            Position { x: 10, y: 10 },
+        ]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[]),
+        max_moves: 24,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.pick_your_meals"),
+        body: Cow::Borrowed(&[Position { x: 10, y: 3 }, Position { x: 10, y: 2 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[
+            Position { x: 10, y: 9 },
+            Position { x: 12, y: 9 },
+            Position { x: 12, y: 12 },
+            Position { x: 15, y: 15 },
+            Position { x: 4, y: 15 },
+        ]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[]),
+        max_moves: 12,
+        goal: PuzzleGoal::ReachLength(5),
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.switchback"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 2 }, Position { x: 2, y: 1 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[
+            Position { x: 2, y: 8 },
+            Position { x: 4, y: 2 },
+            Position { x: 6, y: 8 },
+            Position { x: 8, y: 2 },
+        ]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[]),
+        max_moves: 30,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.thin_ice"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 3 }, Position { x: 2, y: 2 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[Position { x: 6, y: 6 }, Position { x: 6, y: 2 }]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[TerrainArea {
+            tile: Tile::Ice,
+            min: Position { x: 4, y: 4 },
+            max: Position { x: 8, y: 8 },
+        }]),
+        max_moves: 18,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.conveyor_belts"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 3 }, Position { x: 1, y: 3 }]),
+        direction: Direction::Right,
+        food: Cow::Borrowed(&[Position { x: 18, y: 3 }, Position { x: 2, y: 7 }]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[
+            TerrainArea {
+                tile: Tile::Conveyor(Direction::Right),
+                min: Position { x: 4, y: 3 },
+                max: Position { x: 15, y: 3 },
+            },
+            TerrainArea {
+                tile: Tile::Conveyor(Direction::Left),
+                min: Position { x: 4, y: 7 },
+                max: Position { x: 17, y: 7 },
+            },
+        ]),
+        max_moves: 24,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.mud_flats"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 2 }, Position { x: 1, y: 2 }]),
+        direction: Direction::Right,
+        food: Cow::Borrowed(&[Position { x: 16, y: 12 }]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[
+            TerrainArea {
+                tile: Tile::Mud,
+                min: Position { x: 0, y: 5 },
+                max: Position { x: 11, y: 9 },
+            },
+            TerrainArea {
+                tile: Tile::Mud,
+                min: Position { x: 12, y: 6 },
+                max: Position { x: 19, y: 8 },
+            },
+        ]),
+        max_moves: 27,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.battering_ram"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 2 }, Position { x: 2, y: 1 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[Position { x: 12, y: 12 }]),
+        power_ups: Cow::Borrowed(&[Position { x: 10, y: 5 }]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[
+            TerrainArea {
+                tile: Tile::Wall { hp: 2 },
+                min: Position { x: 10, y: 10 },
+                max: Position { x: 14, y: 10 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 2 },
+                min: Position { x: 10, y: 14 },
+                max: Position { x: 14, y: 14 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 2 },
+                min: Position { x: 10, y: 11 },
+                max: Position { x: 10, y: 13 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 2 },
+                min: Position { x: 14, y: 11 },
+                max: Position { x: 14, y: 13 },
+            },
+        ]),
+        max_moves: 22,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.locked_doors"),
+        body: Cow::Borrowed(&[Position { x: 2, y: 2 }, Position { x: 2, y: 1 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[Position { x: 15, y: 18 }]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[
+            (Position { x: 6, y: 6 }, KeyColor::Red),
+            (Position { x: 17, y: 5 }, KeyColor::Blue),
+        ]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[
+            TerrainArea {
+                tile: Tile::Wall { hp: 1 },
+                min: Position { x: 10, y: 0 },
+                max: Position { x: 10, y: 9 },
+            },
+            TerrainArea {
+                tile: Tile::Door(KeyColor::Red),
+                min: Position { x: 10, y: 10 },
+                max: Position { x: 10, y: 10 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 1 },
+                min: Position { x: 10, y: 11 },
+                max: Position { x: 10, y: 19 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 1 },
+                min: Position { x: 11, y: 15 },
+                max: Position { x: 14, y: 15 },
+            },
+            TerrainArea {
+                tile: Tile::Door(KeyColor::Blue),
+                min: Position { x: 15, y: 15 },
+                max: Position { x: 15, y: 15 },
+            },
+            TerrainArea {
+                tile: Tile::Wall { hp: 1 },
+                min: Position { x: 16, y: 15 },
+                max: Position { x: 19, y: 15 },
+            },
+        ]),
+        max_moves: 46,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.night_watch"),
+        body: Cow::Borrowed(&[Position { x: 10, y: 2 }, Position { x: 10, y: 1 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[Position { x: 10, y: 10 }, Position { x: 10, y: 17 }]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[
+            Cow::Borrowed(&[Position { x: 0, y: 7 }, Position { x: 19, y: 7 }]),
+            Cow::Borrowed(&[Position { x: 19, y: 13 }, Position { x: 0, y: 13 }]),
+        ]),
+        hunters: Cow::Borrowed(&[]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[]),
+        max_moves: 18,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.cat_and_mouse"),
+        body: Cow::Borrowed(&[Position { x: 3, y: 3 }, Position { x: 3, y: 2 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[
+            Position { x: 3, y: 16 },
+            Position { x: 16, y: 3 },
+            Position { x: 10, y: 10 },
+        ]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[Position { x: 16, y: 16 }]),
+        boss: None,
+        poison: Cow::Borrowed(&[]),
+        terrain: Cow::Borrowed(&[]),
+        max_moves: 42,
+        goal: PuzzleGoal::EatAllFood,
+    },
+    Puzzle {
+        name: Cow::Borrowed("puzzle.boss_lair"),
+        body: Cow::Borrowed(&[Position { x: 3, y: 3 }, Position { x: 3, y: 2 }]),
+        direction: Direction::Up,
+        food: Cow::Borrowed(&[]),
+        power_ups: Cow::Borrowed(&[]),
+        keys: Cow::Borrowed(&[]),
+        patrols: Cow::Borrowed(&[]),
+        hunters: Cow::Borrowed(&[]),
+        boss: Some(Position { x: 14, y: 14 }),
+        poison: Cow::Borrowed(&[Position { x: 6, y: 14 }]),
+        terrain: Cow::Borrowed(&[TerrainArea {
+            tile: Tile::Wall { hp: 3 },
+            min: Position { x: 9, y: 9 },
+            max: Position { x: 10, y: 10 },
+        }]),
+        max_moves: 24,
+        goal: PuzzleGoal::DefeatBoss,
+    },
+];
+
+/// Board state captured before a move so it can be undone.
+struct Snapshot {
+    body: Vec<Position>,
+    direction: Direction,
+    /// The snake's [`LastTailPosition`] and [`PendingGrowth`], so a meal
+    /// still being digested is undone with the rest.
+    last_tail: Option<Position>,
+    pending_growth: u32,
+    /// The head's [`MudDrag`] and [`IceSlide`], so a step lost to mud or a
+    /// turn held back on ice comes back with it.
+    mud_drag: MudDrag,
+    ice_slide: IceSlide,
+    food: Vec<Position>,
+    power_ups: Vec<Position>,
+    keys: Vec<(Position, KeyColor)>,
+    hazards: Vec<(Position, Hazard)>,
+    hunters: Vec<(Position, Hunter)>,
+    boss: Option<(Position, Boss)>,
+    poison: Vec<Position>,
+    terrain: Terrain,
+    ram: Ram,
+    score: u32,
+    /// The snake's step timer, which eating speeds up.
+    step_timer: Timer,
+    moves: u32,
+}
+
+/// The parts of the board a [`Snapshot`] is taken from.
+#[derive(SystemParam)]
+struct BoardState<'w, 's> {
+    bodies: Query<
+        'w,
+        's,
+        (
+            &'static SnakeSegments,
+            &'static LastTailPosition,
+            &'static PendingGrowth,
+            &'static MudDrag,
+            &'static IceSlide,
+        ),
+    >,
+    game_state: Res<'w, GameState>,
+    snake_timer: Res<'w, SnakeTimer>,
+    terrain: Res<'w, Terrain>,
+    ram: Res<'w, Ram>,
+    positions: Query<'w, 's, &'static Position>,
+    food: Query<'w, 's, &'static Position, With<Food>>,
+    power_ups: Query<'w, 's, &'static Position, With<PowerUp>>,
+    keys: Query<'w, 's, (&'static Position, &'static Key)>,
+    hazards: Query<'w, 's, (&'static Position, &'static Hazard)>,
+    hunters: Query<'w, 's, (&'static Position, &'static Hunter)>,
+    bosses: Query<'w, 's, (&'static Position, &'static Boss)>,
+    poison: Query<'w, 's, &'static Position, With<PoisonFood>>,
+}
+
+impl BoardState<'_, '_> {
+    fn snapshot(&self, direction: Direction, moves: u32) -> Snapshot {
+        let (last_tail, pending_growth, mud_drag, ice_slide) = self
+            .bodies
+            .iter()
+            .next()
+            .map_or_else(Default::default, |(_, last_tail, pending, mud, slide)| {
+                (last_tail.0, pending.0, *mud, *slide)
+            });
+        Snapshot {
+            body: self
+                .bodies
+                .iter()
+                .flat_map(|(segments, ..)| segments.iter())
+                .map(|e| *self.positions.get(*e).unwrap())
+                .collect(),
+            direction,
+            last_tail,
+            pending_growth,
+            mud_drag,
+            ice_slide,
+            food: self.food.iter().copied().collect(),
+            power_ups: self.power_ups.iter().copied().collect(),
+            keys: self
+                .keys
+                .iter()
+                .map(|(position, key)| (*position, key.0))
+                .collect(),
+            hazards: self
+                .hazards
+                .iter()
+                .map(|(position, hazard)| (*position, hazard.clone()))
+                .collect(),
+            hunters: self
+                .hunters
+                .iter()
+                .map(|(position, hunter)| (*position, *hunter))
+                .collect(),
+            boss: self
+                .bosses
+                .iter()
+                .next()
+                .map(|(position, boss)| (*position, *boss)),
+            poison: self.poison.iter().copied().collect(),
+            terrain: self.terrain.clone(),
+            ram: *self.ram,
+            score: self.game_state.score,
+            step_timer: self.snake_timer.timer.clone(),
+            moves,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Outcome {
+    InProgress,
+    Solved,
+    OutOfMoves,
+}
+
+#[derive(Resource)]
+struct PuzzleRun {
+    index: usize,
+    puzzle: Puzzle,
+    moves: u32,
+    /// Seconds spent on the current attempt, not counting pauses.
+    secs: f32,
+    undo: Option<Snapshot>,
+    outcome: Outcome,
+}
+
+impl PuzzleRun {
+    fn new(index: usize, puzzle: Puzzle) -> Self {
+        Self {
+            index,
+            puzzle,
+            moves: 0,
+            secs: 0.0,
+            undo: None,
+            outcome: Outcome::InProgress,
+        }
+    }
+
+    fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// Full marks, less a star for taking longer than a second per allowed
+    /// move and another for leaving food behind.
+    fn stars(&self, food_left: usize) -> u8 {
+        let slow = self.secs > self.puzzle().max_moves as f32;
+        MAX_STARS - u8::from(slow) - u8::from(food_left > 0)
+    }
+}
+
+#[derive(Component)]
+struct MovesText;
+
+#[derive(Component)]
+struct StatusText;
+
+type BoardQuery<'w, 's> = Query<
+    'w,
+    's,
+    Entity,
+    Or<(
+        With<SnakeSegment>,
+        With<Food>,
+        With<PowerUp>,
+        With<Key>,
+        With<Hazard>,
+        With<Hunter>,
+        With<BossPart>,
+        With<PoisonFood>,
+        With<SweepMarker>,
+        With<VictoryScreen>,
+    )>,
+>;
+
+pub struct PuzzlePlugin;
+
+impl Plugin for PuzzlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Puzzles>()
+            .add_systems(Update, collect_puzzles.run_if(resource_changed::<Mods>))
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (setup_puzzle_hud, start_puzzle).run_if(in_puzzle),
+            )
+            .add_systems(
+                Update,
+                (
+                    puzzle_move_input
+                        .in_set(GameSet::Input)
+                        .run_if(in_state(PauseState::Running)),
+                    puzzle_undo
+                        .in_set(GameSet::Input)
+                        .before(puzzle_move_input)
+                        .run_if(in_state(PauseState::Running)),
+                    tick_puzzle_clock.run_if(in_state(PauseState::Running)),
+                    puzzle_continue,
+                    puzzle_restart,
+                    reload_puzzle
+                        .after(collect_puzzles)
+                        .run_if(resource_changed::<Puzzles>),
+                    resolve_puzzle_step.after(GameSet::Collisions),
+                    update_puzzle_hud.after(resolve_puzzle_step),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(in_puzzle),
+            );
+    }
+}
+
+fn collect_puzzles(mods: Res<Mods>, mut puzzles: ResMut<Puzzles>) {
+    puzzles.0 = PUZZLES
+        .iter()
+        .chain(mods.enabled().flat_map(|pack| &pack.puzzles))
+        .cloned()
+        .collect();
+}
+
+fn in_puzzle(mode: Res<GameMode>) -> bool {
+    matches!(*mode, GameMode::Puzzle(_))
+}
+
+fn load_board(
+    commands: &mut Commands,
+    body: &[Position],
+    direction: Direction,
+    food: &[Position],
+    power_ups: &[Position],
+    keys: &[(Position, KeyColor)],
+    hazards: &[(Position, Hazard)],
+    hunters: &[(Position, Hunter)],
+    boss: Option<(Position, Boss)>,
+    poison: &[Position],
+) -> Entity {
+    let head = spawn_player_snake(commands, body, direction);
+    for position in food {
+        spawn_food(commands, *position);
+    }
+    for position in power_ups {
+        spawn_power_up(commands, *position, PowerUp::BASIC);
+    }
+    for (position, color) in keys {
+        spawn_key(commands, *position, *color);
+    }
+    for (position, hazard) in hazards {
+        spawn_hazard(commands, *position, hazard.clone());
+    }
+    for (position, hunter) in hunters {
+        spawn_hunter(commands, *position, *hunter);
+    }
+    if let Some((position, boss)) = boss {
+        spawn_boss(commands, position, boss);
+    }
+    for position in poison {
+        spawn_poison(commands, *position);
+    }
+    head
+}
+
+fn clear_board(commands: &mut Commands, board: &BoardQuery) {
+    for entity in board.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn load_puzzle(commands: &mut Commands, game_state: &mut GameState, index: usize, puzzle: &Puzzle) {
+    start_attempt(commands, game_state, PuzzleRun::new(index, puzzle.clone()));
+}
+
+/// Lays out the board of `run`'s puzzle and makes it the run in play.
+fn start_attempt(commands: &mut Commands, game_state: &mut GameState, run: PuzzleRun) {
+    let puzzle = run.puzzle();
+    load_board(
+        commands,
+        &puzzle.body,
+        puzzle.direction,
+        &puzzle.food,
+        &puzzle.power_ups,
+        &puzzle.keys,
+        &puzzle
+            .patrols
+            .iter()
+            .map(|route| (route[0], Hazard::new(route.clone())))
+            .collect::<Vec<_>>(),
+        &puzzle
+            .hunters
+            .iter()
+            .map(|position| (*position, Hunter::default()))
+            .collect::<Vec<_>>(),
+        puzzle.boss.map(|position| (position, Boss::default())),
+        &puzzle.poison,
+    );
+    game_state.score = 0;
+    commands.insert_resource(Terrain::new(&puzzle.terrain));
+    commands.insert_resource(Ram::default());
+    commands.insert_resource(run);
+}
+
+fn start_puzzle(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    puzzles: Res<Puzzles>,
+    mut game_state: ResMut<GameState>,
+) {
+    if let GameMode::Puzzle(index) = *mode
+        && let Some(puzzle) = puzzles.0.get(index)
+    {
+        load_puzzle(&mut commands, &mut game_state, index, puzzle);
+    }
+}
+
+fn setup_puzzle_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 40.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        MovesText,
+        DespawnOnExit(AppState::Playing),
+    ));
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        StatusText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn puzzle_move_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut touch_reader: MessageReader<TouchDirection>,
+    board: BoardState,
+    mut run: ResMut<PuzzleRun>,
+    arena: Res<Arena>,
+    mut step_writer: MessageWriter<StepEvent>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<(Entity, &SnakeHead, &SnakeSegments)>,
+) {
+    if run.outcome != Outcome::InProgress {
+        return;
+    }
+    let Some((snake, head, segments)) = heads.iter().next() else {
+        return;
+    };
+    let touched = touch_reader.read().last().map(|touch| touch.0);
+    let Some(dir) = touched.or_else(|| {
+        [
+            (KeyCode::ArrowLeft, Direction::Left),
+            (KeyCode::ArrowRight, Direction::Right),
+            (KeyCode::ArrowDown, Direction::Down),
+            (KeyCode::ArrowUp, Direction::Up),
+        ]
+        .into_iter()
+        .find(|(key, _)| keyboard_input.just_pressed(*key))
+        .map(|(_, dir)| dir)
+    }) else {
+        return;
+    };
+    // A move the snake can't take doesn't cost one.
+    let mut body = segments.iter().filter_map(|e| board.positions.get(*e).ok());
+    let (Some(head_pos), neck) = (body.next(), body.next()) else {
+        return;
+    };
+    if allowed_turn(arena.grid(), *head_pos, neck.copied(), head.direction, dir).is_none() {
+        return;
+    }
+
+    run.undo = Some(board.snapshot(head.direction, run.moves));
+    run.moves += 1;
+    request_writer.write(DirectionChangeRequest {
+        snake,
+        direction: dir,
+    });
+    step_writer.write(StepEvent);
+}
+
+/// U takes back the last move, even the one the snake died on.
+fn puzzle_undo(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut run: ResMut<PuzzleRun>,
+    mut game_state: ResMut<GameState>,
+    mut snake_timer: ResMut<SnakeTimer>,
+    board: BoardQuery,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyU) || run.outcome == Outcome::Solved {
+        return;
+    }
+    let Some(snapshot) = run.undo.take() else {
+        return;
+    };
+
+    clear_board(&mut commands, &board);
+    let head = load_board(
+        &mut commands,
+        &snapshot.body,
+        snapshot.direction,
+        &snapshot.food,
+        &snapshot.power_ups,
+        &snapshot.keys,
+        &snapshot.hazards,
+        &snapshot.hunters,
+        snapshot.boss,
+        &snapshot.poison,
+    );
+    commands.entity(head).insert((
+        LastTailPosition(snapshot.last_tail),
+        PendingGrowth(snapshot.pending_growth),
+        snapshot.mud_drag,
+        snapshot.ice_slide,
+    ));
+    commands.insert_resource(snapshot.terrain);
+    commands.insert_resource(snapshot.ram);
+    game_state.score = snapshot.score;
+    snake_timer.timer = snapshot.step_timer;
+    run.moves = snapshot.moves;
+    // The clock isn't wound back: time spent on a move that was taken back
+    // still counts against the time star, or undoing would make it free.
+    run.outcome = Outcome::InProgress;
+}
+
+/// Enter moves on to the next puzzle once solved, or retries after running
+/// out of moves.
+fn puzzle_continue(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    run: Res<PuzzleRun>,
+    puzzles: Res<Puzzles>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut game_state: ResMut<GameState>,
+    board: BoardQuery,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    let index = match run.outcome {
+        Outcome::InProgress => return,
+        Outcome::Solved => run.index + 1,
+        Outcome::OutOfMoves => run.index,
+    };
+    let Some(puzzle) = puzzles.0.get(index) else {
+        next_state.set(AppState::Menu);
+        return;
+    };
+
+    *mode = GameMode::Puzzle(index);
+    clear_board(&mut commands, &board);
+    load_puzzle(&mut commands, &mut game_state, index, puzzle);
+}
+
+fn puzzle_restart(
+    mut commands: Commands,
+    mut reader: MessageReader<RestartEvent>,
+    run: Res<PuzzleRun>,
+    mut game_state: ResMut<GameState>,
+    board: BoardQuery,
+) {
+    if reader.read().next().is_some() {
+        clear_board(&mut commands, &board);
+        load_puzzle(&mut commands, &mut game_state, run.index, run.puzzle());
+    }
+}
+
+/// Starts the puzzle over when its level file was edited mid-game, so a
+/// designer sees the change right away.
+fn reload_puzzle(
+    mut commands: Commands,
+    puzzles: Res<Puzzles>,
+    run: Res<PuzzleRun>,
+    mut game_state: ResMut<GameState>,
+    board: BoardQuery,
+) {
+    let Some(puzzle) = puzzles.0.get(run.index) else {
+        return;
+    };
+    // Packs that weren't touched keep their puzzles as they were.
+    if puzzle == run.puzzle() {
+        return;
+    }
+    clear_board(&mut commands, &board);
+    load_puzzle(&mut commands, &mut game_state, run.index, puzzle);
+}
+
+fn tick_puzzle_clock(time: Res<Time>, mut run: ResMut<PuzzleRun>) {
+    if run.outcome == Outcome::InProgress {
+        // Every frame would otherwise count as a change and redraw the HUD.
+        run.bypass_change_detection().secs += time.delta_secs();
+    }
+}
+
+fn resolve_puzzle_step(
+    mut commands: Commands,
+    mut step_reader: MessageReader<StepEvent>,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut complete_writer: MessageWriter<LevelCompleteEvent>,
+    mut run: ResMut<PuzzleRun>,
+    mut profile: ResMut<Profile>,
+    bodies: Query<&SnakeSegments>,
+    mut game_state: ResMut<GameState>,
+    food: Query<(), With<Food>>,
+    bosses: Query<(), With<Boss>>,
+    board: BoardQuery,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let stepped = step_reader.read().next().is_some();
+
+    // Hazards can kill the snake between moves. The move that led there
+    // can still be undone on the fresh attempt.
+    if died && run.outcome == Outcome::InProgress {
+        clear_board(&mut commands, &board);
+        let mut retry = PuzzleRun::new(run.index, run.puzzle().clone());
+        retry.undo = run.undo.take();
+        start_attempt(&mut commands, &mut game_state, retry);
+        return;
+    }
+    if !stepped {
+        return;
+    }
+
+    let puzzle = run.puzzle();
+    let solved = match puzzle.goal {
+        PuzzleGoal::EatAllFood => food.is_empty(),
+        PuzzleGoal::ReachLength(length) => bodies.iter().any(|segments| segments.len() >= length),
+        PuzzleGoal::DefeatBoss => bosses.is_empty(),
+    };
+    if solved {
+        let stars = run.stars(food.iter().count());
+        if profile.record_stars(&puzzle.name, stars) {
+            profile.save();
+        }
+        run.outcome = Outcome::Solved;
+        complete_writer.write(LevelCompleteEvent { stars });
+    } else if run.moves >= puzzle.max_moves {
+        run.outcome = Outcome::OutOfMoves;
+    }
+}
+
+fn update_puzzle_hud(
+    run: Res<PuzzleRun>,
+    locale: Res<Locale>,
+    mut moves_text: Query<&mut Text, (With<MovesText>, Without<StatusText>)>,
+    mut status_text: Query<&mut Text, (With<StatusText>, Without<MovesText>)>,
+) {
+    if !run.is_changed() {
+        return;
+    }
+    let puzzle = run.puzzle();
+    for mut text in moves_text.iter_mut() {
+        text.0 = locale.format(
+            "puzzle.moves",
+            &[("moves", &run.moves), ("max", &puzzle.max_moves)],
+        );
+    }
+    for mut text in status_text.iter_mut() {
+        text.0 = match run.outcome {
+            Outcome::InProgress => locale.format(
+                "puzzle.status",
+                &[
+                    ("name", &locale.get(&puzzle.name)),
+                    ("goal", &puzzle.goal.describe(&locale)),
+                    ("max", &puzzle.max_moves),
+                ],
+            ),
+            Outcome::Solved => locale.get("puzzle.solved").to_string(),
+            Outcome::OutOfMoves => locale.get("puzzle.out_of_moves").to_string(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::terrain::TerrainPlugin;
+
+    /// A straight run up the left side, with two pellets in the way so one
+    /// meal doesn't solve it.
+    fn corridor() -> Puzzle {
+        Puzzle {
+            name: Cow::Borrowed("corridor"),
+            body: Cow::Borrowed(&[Position { x: 0, y: 2 }, Position { x: 0, y: 1 }]),
+            direction: Direction::Up,
+            food: Cow::Borrowed(&[Position { x: 0, y: 4 }, Position { x: 0, y: 8 }]),
+            power_ups: Cow::Borrowed(&[]),
+            keys: Cow::Borrowed(&[]),
+            patrols: Cow::Borrowed(&[]),
+            hunters: Cow::Borrowed(&[]),
+            boss: None,
+            poison: Cow::Borrowed(&[]),
+            terrain: Cow::Borrowed(&[]),
+            max_moves: 20,
+            goal: PuzzleGoal::EatAllFood,
+        }
+    }
+
+    /// The corridor with `tile` over the cells the snake passes through
+    /// after its first step.
+    fn corridor_on(tile: Tile) -> Puzzle {
+        Puzzle {
+            terrain: Cow::Owned(vec![TerrainArea {
+                tile,
+                min: Position { x: 0, y: 3 },
+                max: Position { x: 0, y: 5 },
+            }]),
+            ..corridor()
+        }
+    }
+
+    fn puzzle_app() -> App {
+        puzzle_app_with(corridor())
+    }
+
+    fn puzzle_app_with(puzzle: Puzzle) -> App {
+        let mut app = headless_app();
+        app.insert_resource(Locale::load("en"))
+            .init_resource::<Mods>()
+            .init_resource::<Profile>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_message::<TouchDirection>()
+            .add_plugins((TerrainPlugin, PuzzlePlugin));
+        app.update();
+        app.insert_resource(Puzzles(vec![puzzle]))
+            .insert_resource(GameMode::Puzzle(0));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    /// One frame with `key` just pressed.
+    fn press(app: &mut App, key: KeyCode) {
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.release_all();
+        input.clear();
+        input.press(key);
+        app.update();
+    }
+
+    /// What an undo has to bring back: the body from the head back, the
+    /// growth still to come, the score, the step timer and the moves made.
+    fn state(app: &mut App) -> (Vec<Position>, Option<Position>, u32, u32, Duration, u32) {
+        let world = app.world_mut();
+        let (segments, last_tail, pending) = world
+            .query_filtered::<(&SnakeSegments, &LastTailPosition, &PendingGrowth), With<SnakeHead>>(
+            )
+            .single(world)
+            .unwrap();
+        let (segments, last_tail, pending) = (segments.0.clone(), last_tail.0, pending.0);
+        let body = segments
+            .iter()
+            .map(|segment| *world.get::<Position>(*segment).unwrap())
+            .collect();
+        (
+            body,
+            last_tail,
+            pending,
+            world.resource::<GameState>().score,
+            world.resource::<SnakeTimer>().timer.duration(),
+            world.resource::<PuzzleRun>().moves,
+        )
+    }
+
+    fn food(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world.query_filtered::<(), With<Food>>().iter(world).count()
+    }
+
+    #[test]
+    fn undo_after_eating_puts_the_meal_back() {
+        let mut app = puzzle_app();
+        press(&mut app, KeyCode::ArrowUp);
+        // Still digesting an earlier meal.
+        let world = app.world_mut();
+        let mut pending = world.query::<&mut PendingGrowth>();
+        pending.single_mut(world).unwrap().0 = 1;
+        let before = state(&mut app);
+
+        press(&mut app, KeyCode::ArrowUp);
+        assert_eq!(food(&mut app), 1);
+        assert_ne!(state(&mut app), before);
+
+        press(&mut app, KeyCode::KeyU);
+        assert_eq!(food(&mut app), 2);
+        assert_eq!(state(&mut app), before);
+    }
+
+    #[test]
+    fn undo_after_dying_takes_back_the_fatal_move() {
+        let mut app = puzzle_app();
+        press(&mut app, KeyCode::ArrowUp);
+        let before = state(&mut app);
+
+        // Off the left edge, which starts the puzzle over.
+        press(&mut app, KeyCode::ArrowLeft);
+        assert_eq!(state(&mut app).5, 0);
+
+        press(&mut app, KeyCode::KeyU);
+        assert_eq!(state(&mut app), before);
+    }
+
+    /// Plays `keys` and returns where that leaves the snake.
+    fn play(
+        app: &mut App,
+        keys: &[KeyCode],
+    ) -> (Vec<Position>, Option<Position>, u32, u32, Duration, u32) {
+        for key in keys {
+            press(app, *key);
+        }
+        state(app)
+    }
+
+    #[test]
+    fn undo_in_mud_brings_back_the_lost_step() {
+        use KeyCode::{ArrowUp as Up, KeyU as Undo};
+        // Into the mud, a step lost to it, and the one that goes through.
+        let moved = play(&mut puzzle_app_with(corridor_on(Tile::Mud)), &[Up, Up, Up]);
+
+        let mut app = puzzle_app_with(corridor_on(Tile::Mud));
+        let again = play(&mut app, &[Up, Up, Up, Undo, Up]);
+        assert_eq!(again, moved);
+    }
+
+    #[test]
+    fn undo_on_ice_keeps_the_held_back_turn() {
+        use KeyCode::{ArrowRight as Right, ArrowUp as Up, KeyU as Undo};
+        // A turn made on the ice waits until the snake slides off it.
+        let keys = [Up, Right, Up, Up, Up];
+        let slid = play(&mut puzzle_app_with(corridor_on(Tile::Ice)), &keys);
+        assert_eq!(slid.0[0], Position { x: 1, y: 6 });
+
+        let mut app = puzzle_app_with(corridor_on(Tile::Ice));
+        play(&mut app, &keys[..3]);
+        let again = play(&mut app, &[Undo, Up, Up, Up]);
+        assert_eq!(again, slid);
+    }
+
+    #[test]
+    fn undo_waits_while_paused() {
+        let mut app = puzzle_app();
+        press(&mut app, KeyCode::ArrowUp);
+        app.world_mut()
+            .resource_mut::<NextState<PauseState>>()
+            .set(PauseState::Paused);
+        app.update();
+
+        press(&mut app, KeyCode::KeyU);
+        assert_eq!(state(&mut app).5, 1);
+    }
+}
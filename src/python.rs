@@ -0,0 +1,136 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::env::{Action, Observation, SnakeEnv};
+
+/// `snake.SnakeEnv(seed)`, one game with `reset()` and `step(action)`.
+/// Actions are 0 to 3 for left, up, right and down.
+#[pyclass(name = "SnakeEnv", unsendable)]
+struct PySnakeEnv(SnakeEnv);
+
+#[pymethods]
+impl PySnakeEnv {
+    #[new]
+    #[pyo3(signature = (seed = 0))]
+    fn new(seed: u64) -> Self {
+        Self(SnakeEnv::new(seed))
+    }
+
+    fn reset(&mut self) -> Observation {
+        self.0.reset()
+    }
+
+    fn step(&mut self, action: usize) -> PyResult<(Observation, f32, bool)> {
+        Ok(self.0.step(action_from(action)?))
+    }
+}
+
+/// `snake.VecSnakeEnv(count, seed)`, many games stepped together. A game
+/// that ends is reset straight away, so every observation returned is one
+/// to act on, like gym's vector environments.
+#[pyclass(name = "VecSnakeEnv", unsendable)]
+struct PyVecSnakeEnv(Vec<SnakeEnv>);
+
+#[pymethods]
+impl PyVecSnakeEnv {
+    /// Game `i` draws its episodes' seeds from `seed + i * 2^32`, so no two
+    /// games play the same board.
+    #[new]
+    #[pyo3(signature = (count, seed = 0))]
+    fn new(count: usize, seed: u64) -> Self {
+        Self(
+            (0..count as u64)
+                .map(|index| SnakeEnv::new(seed.wrapping_add(index << 32)))
+                .collect(),
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn reset(&mut self) -> Vec<Observation> {
+        self.0.iter_mut().map(SnakeEnv::reset).collect()
+    }
+
+    /// Takes one action per game, returning the observations, rewards and
+    /// done flags as three lists.
+    fn step(&mut self, actions: Vec<usize>) -> PyResult<(Vec<Observation>, Vec<f32>, Vec<bool>)> {
+        if actions.len() != self.0.len() {
+            return Err(PyValueError::new_err(format!(
+                "expected {} actions, got {}",
+                self.0.len(),
+                actions.len()
+            )));
+        }
+        let actions = actions
+            .into_iter()
+            .map(action_from)
+            .collect::<PyResult<Vec<_>>>()?;
+        let mut observations = Vec::with_capacity(actions.len());
+        let mut rewards = Vec::with_capacity(actions.len());
+        let mut dones = Vec::with_capacity(actions.len());
+        for (env, action) in self.0.iter_mut().zip(actions) {
+            let (observation, reward, done) = env.step(action);
+            observations.push(if done { env.reset() } else { observation });
+            rewards.push(reward);
+            dones.push(done);
+        }
+        Ok((observations, rewards, dones))
+    }
+}
+
+fn action_from(action: usize) -> PyResult<Action> {
+    Action::ALL
+        .get(action)
+        .copied()
+        .ok_or_else(|| PyValueError::new_err(format!("no action {}, expected 0 to 3", action)))
+}
+
+#[pymodule]
+fn snake(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySnakeEnv>()?;
+    module.add_class::<PyVecSnakeEnv>()?;
+    module.add_class::<Observation>()?;
+    module.add("CHANNELS", crate::env::CHANNELS)?;
+    module.add("FEATURES", crate::env::FEATURES)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vec_env_steps_every_game_and_resets_the_finished_ones() {
+        let mut envs = PyVecSnakeEnv::new(3, 1);
+        assert_eq!(envs.__len__(), 3);
+        assert_eq!(envs.reset().len(), 3);
+
+        // Going up and nothing else runs every snake into the top wall.
+        let up = 1;
+        let mut finished = [false; 3];
+        for _ in 0..40 {
+            let (observations, rewards, dones) = envs.step(vec![up; 3]).unwrap();
+            assert_eq!((observations.len(), rewards.len(), dones.len()), (3, 3, 3));
+            for (index, done) in dones.into_iter().enumerate() {
+                if done {
+                    finished[index] = true;
+                    // The next episode is already on the board.
+                    let observation = &observations[index];
+                    let heads = &observation.grid[..observation.width * observation.height];
+                    assert_eq!(heads.iter().filter(|cell| **cell == 1.0).count(), 1);
+                }
+            }
+        }
+        assert_eq!(finished, [true; 3]);
+    }
+
+    #[test]
+    fn vec_env_wants_one_action_per_game() {
+        let mut envs = PyVecSnakeEnv::new(2, 0);
+        envs.reset();
+        assert!(envs.step(vec![0]).is_err());
+        assert!(envs.step(vec![0, 4]).is_err());
+    }
+}
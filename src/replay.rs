@@ -0,0 +1,1072 @@
+use std::collections::HashMap;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::window::FileDragAndDrop;
+use core::time::Duration;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::sync_arena;
+use crate::attract::{self, Demo};
+use crate::calendar::{date, now_millis};
+use crate::focus::{Focus, FocusInput, Focusable, navigate_focus};
+use crate::grid::GridShape;
+use crate::items::Items;
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::rules::{Rules, Spawn};
+use crate::settings::Settings;
+use crate::storage;
+use crate::tournament::Tournament;
+use crate::{
+    AppState, Direction, DirectionChangeRequest, Food, GameMode, GameRng, GameSet, GameState,
+    Player, Position, RestartEvent, SnakeDied, SnakeHead, SnakeSegments, SnakeTimer, StepEvent,
+    calculate_speed, new_run_seed, reset_game_state, select_grid_shape, snake_timer_tick,
+};
+
+/// Where replays are kept, one file each.
+const REPLAY_DIR: &str = "replays";
+
+/// Longest name a replay can be given.
+const MAX_NAME_LENGTH: usize = 24;
+
+/// Recorded replays kept around; older ones are deleted unless they've been
+/// given a name.
+const MAX_REPLAYS: usize = 20;
+
+/// Version of the [`Replay`] layout. Adding a field doesn't need a bump, as
+/// older readers skip fields they don't know and newer ones default the
+/// fields an older file lacks. Bump it when an existing field changes
+/// meaning, so older builds refuse the file instead of misreading it.
+/// Format 2 hashes the whole board into the `checksum`, see [`step_hash`].
+pub const REPLAY_FORMAT: u32 = 2;
+
+/// Starting value of the FNV-1a hashes below.
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// A recorded endless run, stored as RON. The header says what the run was
+/// played under: the seed, the rules, the items and the settings that change
+/// the simulation. The body is every turn the player made and every change of
+/// speed, stamped with the snake step it took effect on, starting from 0 for
+/// the first step.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Replay {
+    /// [`REPLAY_FORMAT`] of the build that recorded it.
+    pub format: u32,
+    /// Crate version of the build that recorded it, for the player's
+    /// information; compatibility goes by `format`.
+    pub game_version: String,
+    /// Given by the player in the replay browser; empty until then.
+    pub name: String,
+    /// Seed of the [`crate::tournament`] the run was an attempt in; empty
+    /// for other runs.
+    pub tournament: String,
+    /// Milliseconds since the Unix epoch when the run ended.
+    pub recorded_at: u64,
+    /// [`rules_hash`] of `rules`, to catch edits to them.
+    pub rules_hash: u64,
+    pub rules: Rules,
+    /// [`items_hash`] of the food and power-ups in play, which mod and
+    /// seasonal packs add to. A replay is only watched with the same items.
+    pub items_hash: u64,
+    pub seed: u64,
+    pub grid_shape: GridShape,
+    /// Milliseconds between snake steps at the start of the run.
+    pub step_ms: u32,
+    /// Every change of `step_ms` after that, like the player picking another
+    /// speed mid-run.
+    pub speed_changes: Vec<ReplaySpeed>,
+    /// The score the run ended with.
+    pub score: u32,
+    /// Steps the snake took before the run ended.
+    pub ticks: u32,
+    /// Rolling [`step_hash`] over every step of the run. Playing the replay
+    /// back has to arrive at the same value.
+    pub checksum: u64,
+    pub inputs: Vec<ReplayInput>,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            format: REPLAY_FORMAT,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            name: String::new(),
+            tournament: String::new(),
+            recorded_at: 0,
+            rules_hash: 0,
+            rules: Rules::default(),
+            items_hash: 0,
+            seed: 0,
+            grid_shape: GridShape::default(),
+            step_ms: 0,
+            speed_changes: Vec::new(),
+            score: 0,
+            ticks: 0,
+            checksum: FNV_OFFSET,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+/// The direction the snake was heading from step `tick` on.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ReplayInput {
+    pub tick: u32,
+    pub direction: Direction,
+}
+
+/// The milliseconds between snake steps from step `tick` on, before the
+/// score speeds the snake up.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ReplaySpeed {
+    pub tick: u32,
+    pub step_ms: u32,
+}
+
+/// Just enough of a [`Replay`] to check its version before reading the rest.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReplayHeader {
+    format: u32,
+}
+
+impl Replay {
+    /// Reads a replay, refusing ones written in a newer format than this
+    /// build understands and ones whose rules were edited.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let header: ReplayHeader = ron::from_str(contents).map_err(|err| err.to_string())?;
+        if header.format > REPLAY_FORMAT {
+            return Err(format!(
+                "replay format {} is newer than the supported {}",
+                header.format, REPLAY_FORMAT
+            ));
+        }
+        let replay: Self = ron::from_str(contents).map_err(|err| err.to_string())?;
+        if rules_hash(&replay.rules) != replay.rules_hash {
+            return Err("the rules don't match their hash".to_string());
+        }
+        Ok(replay)
+    }
+
+    /// A free place to store this replay. The recording time doubles as its
+    /// id, with a number after it when another replay already has it, like
+    /// one imported from another player's machine.
+    fn new_path(&self) -> String {
+        (0..)
+            .map(|copy| match copy {
+                0 => format!("{}/{}.ron", REPLAY_DIR, self.recorded_at),
+                copy => format!("{}/{}-{}.ron", REPLAY_DIR, self.recorded_at, copy),
+            })
+            .find(|path| storage::read(path).is_none())
+            .unwrap_or_default()
+    }
+
+    /// Every stored replay that can be read, newest first, with its path.
+    pub fn saved() -> Vec<(String, Self)> {
+        let mut replays = storage::list(REPLAY_DIR)
+            .into_iter()
+            .filter_map(|path| {
+                let contents = storage::read(&path)?;
+                match Self::parse(&contents) {
+                    Ok(replay) => Some((path, replay)),
+                    Err(err) => {
+                        warn!("Skipping replay {}: {}", path, err);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        replays.sort_by_key(|(_, replay)| core::cmp::Reverse(replay.recorded_at));
+        replays
+    }
+
+    /// Where the snake is heading on step `tick`.
+    fn direction_at(&self, tick: u32) -> Option<Direction> {
+        self.inputs
+            .iter()
+            .take_while(|input| input.tick <= tick)
+            .last()
+            .map(|input| input.direction)
+    }
+
+    /// Milliseconds between snake steps on step `tick`.
+    fn step_ms_at(&self, tick: u32) -> u32 {
+        self.speed_changes
+            .iter()
+            .take_while(|change| change.tick <= tick)
+            .last()
+            .map_or(self.step_ms, |change| change.step_ms)
+    }
+}
+
+/// Folds `values` into an FNV-1a `hash`.
+pub fn fnv(hash: u64, values: &[u64]) -> u64 {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .fold(hash, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Adds one step's outcome to a rolling checksum: the score, where the run's
+/// random numbers are at, every cell of the snake from the head back and
+/// every food, in the order given.
+fn step_hash(
+    checksum: u64,
+    score: u32,
+    rng: u64,
+    body: &[Position],
+    food: &[(Position, Food)],
+) -> u64 {
+    let mut values = vec![score as u64, rng, body.len() as u64];
+    values.extend(body.iter().flat_map(|pos| [pos.x as u64, pos.y as u64]));
+    values.push(food.len() as u64);
+    values.extend(
+        food.iter()
+            .flat_map(|(pos, food)| [pos.x as u64, pos.y as u64, food.0 as u64]),
+    );
+    fnv(checksum, &values)
+}
+
+/// The [`step_hash`] of format 1 replays, which only took in where the head
+/// ended up and how long the snake is.
+fn legacy_step_hash(checksum: u64, head: Position, length: usize) -> u64 {
+    fnv(checksum, &[head.x as u64, head.y as u64, length as u64])
+}
+
+/// FNV-1a over every rule that changes how a run plays out, so a replay can
+/// tell whether it's being played under the rules it was recorded with.
+/// Spelled out by hand since the standard hasher may change between Rust
+/// releases and platforms.
+pub fn rules_hash(rules: &Rules) -> u64 {
+    let millis = |duration: Option<Duration>| duration.map_or(0, |d| d.as_millis() as u64 + 1);
+    let mut values = vec![
+        rules.event_interval.as_millis() as u64,
+        rules.rivals as u64,
+        rules.teams.map_or(0, |teams| teams.target as u64 + 1),
+        rules.teams.is_some_and(|teams| teams.friendly_fire).into(),
+        rules.coop.into(),
+        rules.food_points as u64,
+        rules.speed.to_bits() as u64,
+        rules.walls as u64,
+        millis(rules.poison_interval),
+        rules.roguelike.into(),
+        rules.lives as u64,
+        rules.shields as u64,
+        rules.self_collision.into(),
+        rules.food_count as u64,
+        rules.speed_curve as u64,
+        millis(rules.decay),
+        millis(rules.hunger),
+        millis(rules.idle_drain),
+    ];
+    values.push(rules.event_pool.len() as u64);
+    values.extend(rules.event_pool.iter().map(|event| *event as u64));
+    values.push(rules.modifiers.len() as u64);
+    values.extend(rules.modifiers.iter().map(|modifier| *modifier as u64));
+    // Only moved spawns count, so replays recorded before they could be
+    // moved keep their hash.
+    if rules.spawns != [Spawn::default()] {
+        values.push(rules.spawns.len() as u64);
+        for spawn in &rules.spawns {
+            values.extend([
+                spawn.position.x as u64,
+                spawn.position.y as u64,
+                spawn.length as u64,
+                spawn.direction.map_or(0, |direction| direction as u64 + 1),
+            ]);
+        }
+    }
+    // Likewise only resized arenas.
+    if rules.arena_size != Rules::default().arena_size {
+        values.extend([rules.arena_size.0 as u64, rules.arena_size.1 as u64]);
+    }
+    fnv(FNV_OFFSET, &values)
+}
+
+/// FNV-1a over what every food and power-up type does and how often it
+/// drops, which decides the food sequence a seed gives. Looks don't count.
+pub fn items_hash(items: &Items) -> u64 {
+    let mut values = vec![items.food.len() as u64, items.power_ups.len() as u64];
+    for kind in items.food.iter().chain(&items.power_ups) {
+        values.extend([
+            kind.points as u64,
+            kind.growth as u64,
+            kind.effect.map_or(0, |effect| effect as u64 + 1),
+            kind.steps as u64,
+            kind.seconds as u64,
+            kind.weight.to_bits() as u64,
+        ]);
+    }
+    fnv(FNV_OFFSET, &values)
+}
+
+/// The item tables a replay could have been recorded with: the ones in play
+/// now, and the game's own, which tournaments use.
+fn item_tables(mods: &Mods) -> [Items; 2] {
+    [mods.items(), Items::load()]
+}
+
+/// The replay of the run in progress.
+#[derive(Resource, Default)]
+pub struct Recording {
+    replay: Replay,
+    last_direction: Option<Direction>,
+    last_step_ms: u32,
+}
+
+/// A replay being watched. The run is simulated again from the replay's
+/// seed, rules and inputs, and has to end the way the recording did.
+#[derive(Resource, Default)]
+pub struct Watching(Option<Playback>);
+
+struct Playback {
+    path: String,
+    replay: Replay,
+    tick: u32,
+    checksum: u64,
+}
+
+/// Whether each replay watched this session played back to the same end as
+/// it was recorded with, by path.
+#[derive(Resource, Default)]
+struct Verdicts(HashMap<String, bool>);
+
+/// The replays listed in the browser, and what the player is doing there.
+#[derive(Resource, Default)]
+struct Browser {
+    replays: Vec<(String, Replay)>,
+    /// The replay being renamed, by its place in the list, and the name
+    /// typed so far.
+    renaming: Option<(usize, String)>,
+    /// What happened last, like an export or a failed import.
+    status: String,
+}
+
+#[derive(Component)]
+struct BrowserRoot;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .init_resource::<Watching>()
+            .init_resource::<Verdicts>()
+            .init_resource::<Browser>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (
+                    reset_recording,
+                    set_up_playback
+                        .run_if(watching)
+                        .after(new_run_seed)
+                        .after(select_grid_shape)
+                        .after(reset_game_state)
+                        .before(sync_arena),
+                ),
+            )
+            .add_systems(OnEnter(AppState::Menu), stop_watching)
+            .add_systems(
+                OnEnter(AppState::Replays),
+                (stop_watching, load_browser, setup_browser).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    browser_input.after(navigate_focus),
+                    import_dropped_files,
+                    refresh_browser
+                        .after(browser_input)
+                        .after(import_dropped_files)
+                        .run_if(
+                            resource_changed::<Browser>
+                                .or(resource_changed::<Verdicts>)
+                                .or(resource_changed::<Locale>),
+                        ),
+                )
+                    .run_if(in_state(AppState::Replays)),
+            )
+            .add_systems(
+                Update,
+                (
+                    record_step
+                        .run_if(not_watching)
+                        .after(GameSet::Steering)
+                        .before(GameSet::Movement),
+                    play_step
+                        .run_if(watching)
+                        .in_set(GameSet::Input)
+                        .after(snake_timer_tick),
+                    hash_step
+                        .after(GameSet::Collisions)
+                        .before(GameSet::Spawning),
+                    finish_recording
+                        .run_if(not_watching)
+                        .after(GameSet::Spawning),
+                    finish_playback.run_if(watching).after(GameSet::Spawning),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(single_snake),
+            );
+    }
+}
+
+pub fn watching(watching: Res<Watching>) -> bool {
+    watching.0.is_some()
+}
+
+/// Whether the player is in control, rather than watching a replay or the
+/// attract demo.
+pub fn not_watching(watching: Res<Watching>, demo: Res<Demo>) -> bool {
+    watching.0.is_none() && !attract::demo(demo)
+}
+
+/// Co-op has a second snake whose turns aren't recorded.
+fn single_snake(rules: Res<Rules>) -> bool {
+    !rules.coop
+}
+
+fn reset_recording(mut recording: ResMut<Recording>) {
+    *recording = Recording::default();
+}
+
+/// Playback overrides the speed setting and the items, so they're applied
+/// again for the next run.
+fn stop_watching(
+    mods: Res<Mods>,
+    mut watching: ResMut<Watching>,
+    mut settings: ResMut<Settings>,
+    mut items: ResMut<Items>,
+) {
+    if watching.0.take().is_some() {
+        settings.set_changed();
+        *items = mods.items();
+    }
+}
+
+/// Puts the run back the way the replay started: same seed, board and
+/// speed.
+fn set_up_playback(
+    mut commands: Commands,
+    watching: Res<Watching>,
+    game_state: Res<GameState>,
+    mut rng: ResMut<GameRng>,
+    mut snake_timer: ResMut<SnakeTimer>,
+) {
+    let Some(playback) = &watching.0 else {
+        return;
+    };
+    let replay = &playback.replay;
+    *rng = GameRng::with_seed(replay.seed);
+    commands.insert_resource(replay.grid_shape);
+    snake_timer.base = Duration::from_millis(replay.step_ms.into());
+    let speed = calculate_speed(snake_timer.base, game_state.score, &replay.rules);
+    snake_timer.timer.set_duration(speed);
+}
+
+/// Writes down the header on the first step, and the heading and the speed
+/// whenever they change after that.
+pub fn record_step(
+    mut step_reader: MessageReader<StepEvent>,
+    rng: Res<GameRng>,
+    rules: Res<Rules>,
+    items: Res<Items>,
+    shape: Res<GridShape>,
+    snake_timer: Res<SnakeTimer>,
+    tournament: Res<Tournament>,
+    mut recording: ResMut<Recording>,
+    heads: Query<&SnakeHead, With<Player>>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let Ok(head) = heads.single() else {
+        return;
+    };
+    let recording = &mut *recording;
+    let replay = &mut recording.replay;
+    let step_ms = snake_timer.base.as_millis() as u32;
+    let tick = replay.ticks;
+    if tick == 0 {
+        replay.seed = rng.seed;
+        replay.rules_hash = rules_hash(&rules);
+        replay.rules = rules.clone();
+        replay.items_hash = items_hash(&items);
+        replay.grid_shape = *shape;
+        replay.step_ms = step_ms;
+        replay.tournament = tournament.entered().unwrap_or_default().to_string();
+    } else if step_ms != recording.last_step_ms {
+        replay.speed_changes.push(ReplaySpeed { tick, step_ms });
+    }
+    recording.last_step_ms = step_ms;
+    replay.ticks += 1;
+    if recording.last_direction != Some(head.direction) {
+        recording.last_direction = Some(head.direction);
+        replay.inputs.push(ReplayInput {
+            tick,
+            direction: head.direction,
+        });
+    }
+}
+
+/// Steers the snake the way the recording did, and sets the speed the next
+/// step is taken at. The player's controls are off while watching.
+fn play_step(
+    mut step_reader: MessageReader<StepEvent>,
+    game_state: Res<GameState>,
+    mut watching: ResMut<Watching>,
+    mut snake_timer: ResMut<SnakeTimer>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    heads: Query<Entity, With<Player>>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let (Some(playback), Ok(snake)) = (&mut watching.0, heads.single()) else {
+        return;
+    };
+    if let Some(direction) = playback.replay.direction_at(playback.tick) {
+        request_writer.write(DirectionChangeRequest { snake, direction });
+    }
+    playback.tick += 1;
+    let replay = &playback.replay;
+    let base = Duration::from_millis(replay.step_ms_at(playback.tick).into());
+    if snake_timer.base != base {
+        snake_timer.base = base;
+        let speed = calculate_speed(base, game_state.score, &replay.rules);
+        snake_timer.timer.set_duration(speed);
+    }
+}
+
+/// Adds the step just taken to the checksum of the run being recorded or
+/// played back, the way the replay's format hashes it.
+fn hash_step(
+    mut step_reader: MessageReader<StepEvent>,
+    mut recording: ResMut<Recording>,
+    mut watching: ResMut<Watching>,
+    game_state: Res<GameState>,
+    rng: Res<GameRng>,
+    heads: Query<(&Position, &SnakeSegments), With<Player>>,
+    positions: Query<&Position>,
+    food: Query<(&Position, &Food)>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let Ok((head, segments)) = heads.single() else {
+        return;
+    };
+    let (checksum, format) = match &mut watching.0 {
+        Some(playback) => (&mut playback.checksum, playback.replay.format),
+        None => (&mut recording.replay.checksum, REPLAY_FORMAT),
+    };
+    if format < 2 {
+        *checksum = legacy_step_hash(*checksum, *head, segments.len());
+        return;
+    }
+    let body: Vec<Position> = segments
+        .iter()
+        .filter_map(|segment| positions.get(*segment).ok())
+        .copied()
+        .collect();
+    // Sorted, so the hash doesn't hang on the order the food was stored in.
+    let mut food: Vec<(Position, Food)> = food.iter().map(|(pos, food)| (*pos, *food)).collect();
+    food.sort_by_key(|(pos, food)| (pos.x, pos.y, food.0));
+    // The next number the run would draw, drawn from a copy to leave the
+    // run's own sequence alone.
+    let next_draw = rng.rng.clone().next_u64();
+    *checksum = step_hash(*checksum, game_state.score, next_draw, &body, &food);
+}
+
+/// Checks the replay ended the way it was recorded and heads back to the
+/// replay browser.
+fn finish_playback(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    game_state: Res<GameState>,
+    watching: Res<Watching>,
+    mut verdicts: ResMut<Verdicts>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if game_over_reader.read().next().is_none() {
+        return;
+    }
+    let Some(playback) = &watching.0 else {
+        return;
+    };
+    let replay = &playback.replay;
+    let verified = playback.checksum == replay.checksum
+        && playback.tick == replay.ticks
+        && game_state.last_score == replay.score;
+    if verified {
+        info!("Replay verified: score {}", replay.score);
+    } else {
+        warn!(
+            "Replay desynced: scored {} where the recording scored {}",
+            game_state.last_score, replay.score
+        );
+    }
+    verdicts.0.insert(playback.path.clone(), verified);
+    next_state.set(AppState::Replays);
+}
+
+/// Saves the replay when the snake dies and starts a new one; a restarted
+/// run is thrown away.
+fn finish_recording(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    mut recording: ResMut<Recording>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if !died && !restarted {
+        return;
+    }
+    if died && recording.replay.ticks > 0 {
+        recording.replay.score = game_state.last_score;
+        recording.replay.recorded_at = now_millis();
+        storage::save(&recording.replay.new_path(), &recording.replay);
+        prune_replays();
+    }
+    *recording = Recording::default();
+}
+
+/// Deletes the oldest unnamed replays past [`MAX_REPLAYS`]. Tournament
+/// attempts are kept for the standings.
+fn prune_replays() {
+    let unnamed = Replay::saved()
+        .into_iter()
+        .filter(|(_, replay)| replay.name.is_empty() && replay.tournament.is_empty());
+    for (path, _) in unnamed.skip(MAX_REPLAYS) {
+        if let Err(err) = storage::remove(&path) {
+            warn!("Failed to delete {}: {}", path, err);
+        }
+    }
+}
+
+fn load_browser(mut browser: ResMut<Browser>) {
+    *browser = Browser {
+        replays: Replay::saved(),
+        ..default()
+    };
+}
+
+fn replay_line(
+    index: usize,
+    path: &str,
+    replay: &Replay,
+    browser: &Browser,
+    verdicts: &Verdicts,
+    locale: &Locale,
+) -> String {
+    if let Some((_, name)) = browser.renaming.as_ref().filter(|(at, _)| *at == index) {
+        return locale.format("replays.renaming", &[("name", name)]);
+    }
+    let name = if replay.name.is_empty() {
+        locale.get("replays.unnamed")
+    } else {
+        &replay.name
+    };
+    let mut line = locale.format(
+        "replays.line",
+        &[
+            ("name", &name),
+            ("score", &replay.score),
+            ("steps", &replay.ticks),
+            ("date", &date(replay.recorded_at)),
+            ("seed", &replay.seed),
+        ],
+    );
+    if !replay.tournament.is_empty() {
+        line.push(' ');
+        line.push_str(&locale.format("replays.tournament", &[("seed", &replay.tournament)]));
+    }
+    if let Some(verified) = verdicts.0.get(path) {
+        line.push(' ');
+        line.push_str(locale.get(if *verified {
+            "replays.verified"
+        } else {
+            "replays.desynced"
+        }));
+    }
+    line
+}
+
+fn setup_browser(
+    mut commands: Commands,
+    browser: Res<Browser>,
+    verdicts: Res<Verdicts>,
+    locale: Res<Locale>,
+) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BrowserRoot,
+            DespawnOnExit(AppState::Replays),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("replays.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            if browser.replays.is_empty() {
+                parent.spawn(browser_line(locale.get("replays.empty").to_string()));
+            }
+            for (index, (path, replay)) in browser.replays.iter().enumerate() {
+                parent.spawn((
+                    browser_line(replay_line(
+                        index, path, replay, &browser, &verdicts, &locale,
+                    )),
+                    Focusable::new(index),
+                ));
+            }
+            parent.spawn(browser_line(locale.get("replays.keys").to_string()));
+            parent.spawn(browser_line(browser.status.clone()));
+            parent.spawn(browser_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn browser_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_browser(
+    mut commands: Commands,
+    browser: Res<Browser>,
+    verdicts: Res<Verdicts>,
+    locale: Res<Locale>,
+    roots: Query<Entity, With<BrowserRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_browser(commands, browser, verdicts, locale);
+}
+
+/// Typing while renaming a replay: text goes into the name, Enter keeps it
+/// and Escape drops it.
+fn rename_input(
+    key_reader: &mut MessageReader<KeyboardInput>,
+    input_reader: &mut MessageReader<FocusInput>,
+    browser: &mut Browser,
+) {
+    let Some((index, name)) = &mut browser.renaming else {
+        return;
+    };
+    for key in key_reader.read().filter(|key| key.state.is_pressed()) {
+        match &key.logical_key {
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Character(text) if name.chars().count() < MAX_NAME_LENGTH => {
+                name.push_str(text);
+            }
+            Key::Space if name.chars().count() < MAX_NAME_LENGTH => name.push(' '),
+            _ => {}
+        }
+    }
+    let index = *index;
+    for input in input_reader.read() {
+        match input {
+            FocusInput::Activate(_) => {
+                let (_, name) = browser.renaming.take().unwrap_or_default();
+                if let Some((path, replay)) = browser.replays.get_mut(index) {
+                    replay.name = name.trim().to_string();
+                    storage::save(path, replay);
+                }
+                return;
+            }
+            FocusInput::Back => {
+                browser.renaming = None;
+                return;
+            }
+            FocusInput::Nudge(..) => {}
+        }
+    }
+}
+
+/// Enter watches the focused replay, R renames it, X deletes it and E
+/// exports it. Escape goes back to the menu.
+fn browser_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut key_reader: MessageReader<KeyboardInput>,
+    mut input_reader: MessageReader<FocusInput>,
+    focus: Res<Focus>,
+    locale: Res<Locale>,
+    mods: Res<Mods>,
+    mut browser: ResMut<Browser>,
+    mut watching: ResMut<Watching>,
+    mut rules: ResMut<Rules>,
+    mut items: ResMut<Items>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if browser.renaming.is_some() {
+        rename_input(&mut key_reader, &mut input_reader, &mut browser);
+        return;
+    }
+    // The key that starts a rename shouldn't end up in the name.
+    key_reader.clear();
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Activate(index) => {
+                let Some((path, replay)) = browser.replays.get(index) else {
+                    continue;
+                };
+                // Other items would drop other food from the same seed.
+                let Some(table) = item_tables(&mods)
+                    .into_iter()
+                    .find(|table| items_hash(table) == replay.items_hash)
+                else {
+                    browser.status = locale.get("replays.other_items").to_string();
+                    continue;
+                };
+                *items = table;
+                *rules = replay.rules.clone();
+                *mode = GameMode::Endless;
+                watching.0 = Some(Playback {
+                    path: path.clone(),
+                    replay: replay.clone(),
+                    tick: 0,
+                    checksum: FNV_OFFSET,
+                });
+                next_state.set(AppState::Playing);
+                return;
+            }
+            FocusInput::Nudge(..) => {}
+        }
+    }
+
+    let index = focus.order;
+    let Some((path, replay)) = browser.replays.get(index).cloned() else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        browser.renaming = Some((index, replay.name.clone()));
+    } else if keyboard_input.any_just_pressed([KeyCode::KeyX, KeyCode::Delete]) {
+        if let Err(err) = storage::remove(&path) {
+            warn!("Failed to delete {}: {}", path, err);
+            return;
+        }
+        browser.replays.remove(index);
+        browser.status = locale.get("replays.deleted").to_string();
+    } else if keyboard_input.just_pressed(KeyCode::KeyE) {
+        let file_name = format!("snake-replay-{}.ron", replay.recorded_at);
+        let result = ron::ser::to_string_pretty(&replay, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| storage::export(&file_name, &contents));
+        browser.status = match result {
+            Ok(exported) => locale.format("replays.exported", &[("path", &exported)]),
+            Err(err) => locale.format("replays.failed", &[("error", &err)]),
+        };
+    }
+}
+
+/// Adds replay files dropped onto the window to the list. Browsers don't
+/// hand dropped files over, so this only happens on desktops.
+fn import_dropped_files(
+    mut drop_reader: MessageReader<FileDragAndDrop>,
+    locale: Res<Locale>,
+    mut browser: ResMut<Browser>,
+) {
+    for dropped in drop_reader.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = dropped else {
+            continue;
+        };
+        let imported = std::fs::read_to_string(path_buf)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| Replay::parse(&contents));
+        browser.status = match imported {
+            Ok(replay) => {
+                storage::save(&replay.new_path(), &replay);
+                let status = locale.format("replays.imported", &[("score", &replay.score)]);
+                browser.replays = Replay::saved();
+                status
+            }
+            Err(err) => locale.format("replays.failed", &[("error", &err)]),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::ItemKind;
+    use crate::rules::WallBehavior;
+
+    /// A short recorded run under the default rules.
+    fn recorded() -> Replay {
+        let rules = Rules::default();
+        Replay {
+            rules_hash: rules_hash(&rules),
+            rules,
+            seed: 42,
+            step_ms: 500,
+            speed_changes: vec![ReplaySpeed {
+                tick: 3,
+                step_ms: 300,
+            }],
+            ticks: 5,
+            inputs: vec![
+                ReplayInput {
+                    tick: 0,
+                    direction: Direction::Up,
+                },
+                ReplayInput {
+                    tick: 2,
+                    direction: Direction::Left,
+                },
+            ],
+            ..Replay::default()
+        }
+    }
+
+    fn to_ron(replay: &Replay) -> String {
+        ron::ser::to_string(replay).unwrap()
+    }
+
+    #[test]
+    fn fnv_of_nothing_is_the_offset() {
+        assert_eq!(fnv(FNV_OFFSET, &[]), FNV_OFFSET);
+    }
+
+    #[test]
+    fn fnv_folds_each_value_little_endian() {
+        assert_eq!(fnv(FNV_OFFSET, &[1]), 0x89cd_3129_1d2a_efa4);
+        assert_eq!(fnv(fnv(FNV_OFFSET, &[1]), &[2]), fnv(FNV_OFFSET, &[1, 2]));
+    }
+
+    const BODY: [Position; 2] = [Position { x: 3, y: 3 }, Position { x: 2, y: 3 }];
+
+    #[test]
+    fn step_hash_catches_food_in_another_place() {
+        let food = [(Position { x: 7, y: 4 }, Food::BASIC)];
+        let moved = [(Position { x: 8, y: 4 }, Food::BASIC)];
+        let recorded = step_hash(FNV_OFFSET, 0, 1, &BODY, &food);
+        assert_eq!(recorded, step_hash(FNV_OFFSET, 0, 1, &BODY, &food));
+        assert_ne!(recorded, step_hash(FNV_OFFSET, 0, 1, &BODY, &moved));
+        assert_ne!(recorded, step_hash(FNV_OFFSET, 0, 1, &BODY, &[]));
+    }
+
+    #[test]
+    fn step_hash_catches_the_body_score_and_draws() {
+        let recorded = step_hash(FNV_OFFSET, 0, 1, &BODY, &[]);
+        let bent = [BODY[0], Position { x: 3, y: 2 }];
+        assert_ne!(recorded, step_hash(FNV_OFFSET, 0, 1, &bent, &[]));
+        assert_ne!(recorded, step_hash(FNV_OFFSET, 1, 1, &BODY, &[]));
+        assert_ne!(recorded, step_hash(FNV_OFFSET, 0, 2, &BODY, &[]));
+    }
+
+    /// Replays already on disk were hashed with this; a new rule has to
+    /// leave it alone at its default.
+    #[test]
+    fn rules_hash_of_the_default_rules_is_stable() {
+        assert_eq!(rules_hash(&Rules::default()), 0x0fe7_ee25_a4cc_8add);
+    }
+
+    #[test]
+    fn rules_hash_follows_the_rules() {
+        let rules = Rules::default();
+        let walls = Rules {
+            walls: WallBehavior::Wrap,
+            ..Rules::default()
+        };
+        let food = Rules {
+            food_count: 3,
+            ..Rules::default()
+        };
+        let arena = Rules {
+            arena_size: (30, 20),
+            ..Rules::default()
+        };
+        assert_eq!(rules_hash(&rules), rules_hash(&rules.clone()));
+        assert_ne!(rules_hash(&rules), rules_hash(&walls));
+        assert_ne!(rules_hash(&rules), rules_hash(&food));
+        assert_ne!(rules_hash(&rules), rules_hash(&arena));
+    }
+
+    #[test]
+    fn items_hash_follows_what_items_do_not_their_looks() {
+        let items = Items {
+            food: vec![ItemKind::default()],
+            power_ups: vec![ItemKind::default()],
+        };
+        let mut recolored = items.clone();
+        recolored.food[0].color = (0.0, 0.0, 0.0);
+        recolored.food[0].glyph = '%';
+        let mut added = items.clone();
+        added.food.push(ItemKind {
+            weight: 9.0,
+            ..ItemKind::default()
+        });
+        let mut reweighted = items.clone();
+        reweighted.power_ups[0].weight = 0.5;
+        assert_eq!(items_hash(&items), items_hash(&recolored));
+        assert_ne!(items_hash(&items), items_hash(&added));
+        assert_ne!(items_hash(&items), items_hash(&reweighted));
+    }
+
+    #[test]
+    fn parse_reads_back_a_saved_replay() {
+        let parsed = Replay::parse(&to_ron(&recorded())).unwrap();
+        assert_eq!(parsed.seed, 42);
+        assert_eq!(parsed.ticks, 5);
+        assert_eq!(parsed.direction_at(1), Some(Direction::Up));
+        assert_eq!(parsed.direction_at(4), Some(Direction::Left));
+        assert_eq!(parsed.step_ms_at(2), 500);
+        assert_eq!(parsed.step_ms_at(3), 300);
+    }
+
+    #[test]
+    fn parse_skips_fields_it_does_not_know() {
+        let contents = to_ron(&recorded()).replacen('(', "(added_later:(ghosts:3),", 1);
+        let parsed = Replay::parse(&contents).unwrap();
+        assert_eq!(parsed.seed, 42);
+        assert_eq!(parsed.inputs.len(), 2);
+    }
+
+    #[test]
+    fn parse_refuses_a_newer_format() {
+        let replay = Replay {
+            format: REPLAY_FORMAT + 1,
+            ..recorded()
+        };
+        assert!(Replay::parse(&to_ron(&replay)).is_err());
+    }
+
+    #[test]
+    fn parse_refuses_edited_rules() {
+        let mut replay = recorded();
+        replay.rules.food_points = 10;
+        assert!(Replay::parse(&to_ron(&replay)).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use accesskit::{Node as AccessNode, Role};
+use bevy::a11y::AccessibilityNode;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use crate::clipboard;
+use crate::countdown;
+use crate::custom;
+use crate::focus::InputDevice;
+use crate::killcam;
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::rules::Rules;
+use crate::{
+    AppState, DeathCause, Food, GameMode, GameSet, GameState, PlayerMeals, RestartEvent, SnakeDied,
+};
+
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.45, 0.35);
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const HINT_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+/// Where the points of the current run came from, and how it went.
+#[derive(Resource, Default, Clone)]
+pub struct RunStats {
+    /// Points for the food itself.
+    base: u32,
+    pub combo_bonus: u32,
+    pub close_call_bonus: u32,
+    pub max_combo: u32,
+    /// Seconds the snake has been on the move.
+    time: f32,
+    basic_eaten: u32,
+    /// Food of every other type.
+    special_eaten: u32,
+}
+
+/// What the results screen shows: the stats of the run that just ended.
+#[derive(Resource, Default)]
+struct Results {
+    stats: RunStats,
+    score: u32,
+    cause: Option<DeathCause>,
+    /// Set when a run ends, until the results come up once the slow motion
+    /// after the crash is over.
+    due: bool,
+}
+
+#[derive(Component)]
+struct ResultsHint;
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<Results>()
+            .add_systems(OnEnter(AppState::Playing), reset_stats)
+            .add_systems(OnEnter(PauseState::Results), setup_results)
+            .add_systems(
+                Update,
+                (
+                    count_food,
+                    clock_run
+                        .run_if(in_state(PauseState::Running))
+                        .run_if(countdown::countdown_finished),
+                    end_run.after(GameSet::Spawning),
+                    show_results
+                        .after(end_run)
+                        .run_if(killcam::slow_motion_over),
+                    results_input.run_if(in_state(PauseState::Results)),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(solo_run),
+            );
+    }
+}
+
+/// Co-op has no single snake to sum up, and roguelike runs go straight on
+/// to the next draft.
+fn solo_run(rules: Res<Rules>) -> bool {
+    !rules.coop && !rules.roguelike
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn count_food(mut growth_reader: PlayerMeals, rules: Res<Rules>, mut stats: ResMut<RunStats>) {
+    for growth in growth_reader.read() {
+        stats.base += rules.food_points * growth.points;
+        if growth.kind == Food::BASIC {
+            stats.basic_eaten += 1;
+        } else {
+            stats.special_eaten += 1;
+        }
+    }
+}
+
+fn clock_run(time: Res<Time>, mut stats: ResMut<RunStats>) {
+    stats.time += time.delta_secs();
+}
+
+/// A death sums up the run for the results, which come up once the slow
+/// motion is over; restarting just starts the stats over.
+fn end_run(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    mut stats: ResMut<RunStats>,
+    mut results: ResMut<Results>,
+) {
+    let restarted = restart_reader.read().next().is_some();
+    if let Some(game_over) = game_over_reader.read().last() {
+        *results = Results {
+            stats: stats.clone(),
+            score: game_state.last_score,
+            cause: Some(game_over.cause),
+            due: true,
+        };
+    } else if !restarted {
+        return;
+    }
+    *stats = RunStats::default();
+}
+
+fn show_results(mut results: ResMut<Results>, mut next_state: ResMut<NextState<PauseState>>) {
+    if std::mem::take(&mut results.due) {
+        next_state.set(PauseState::Results);
+    }
+}
+
+/// "m:ss", for the time survived.
+pub fn clock(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// A one-line brag to paste elsewhere.
+fn share_text(results: &Results, locale: &Locale) -> String {
+    locale.format(
+        "results.share",
+        &[
+            ("score", &results.score),
+            ("time", &clock(results.stats.time)),
+            ("combo", &results.stats.max_combo.max(1)),
+            ("cause", &cause_name(results.cause, locale)),
+        ],
+    )
+}
+
+fn cause_name(cause: Option<DeathCause>, locale: &Locale) -> String {
+    cause.map_or(String::new(), |cause| locale.get(cause.name()).to_string())
+}
+
+fn setup_results(
+    mut commands: Commands,
+    results: Res<Results>,
+    rules: Res<Rules>,
+    locale: Res<Locale>,
+    device: Res<InputDevice>,
+    mut input_focus: ResMut<InputFocus>,
+) {
+    let hint = match *device {
+        InputDevice::Keyboard => "results.hint",
+        InputDevice::Gamepad => "results.hint.gamepad",
+    };
+    let stats = &results.stats;
+    let mut lines = vec![
+        locale.format("results.base", &[("points", &stats.base)]),
+        locale.format("results.combo_bonus", &[("points", &stats.combo_bonus)]),
+        locale.format(
+            "results.close_call_bonus",
+            &[("points", &stats.close_call_bonus)],
+        ),
+        locale.format("results.time", &[("time", &clock(stats.time))]),
+        locale.format("results.max_combo", &[("combo", &stats.max_combo.max(1))]),
+        locale.format(
+            "results.food",
+            &[
+                ("basic", &stats.basic_eaten),
+                ("special", &stats.special_eaten),
+            ],
+        ),
+        locale.format(
+            "results.cause",
+            &[("cause", &cause_name(results.cause, &locale))],
+        ),
+    ];
+    if rules.custom {
+        lines.extend(custom::summary(&rules, &locale));
+    }
+    let title = locale.format("results.title", &[("score", &results.score)]);
+    // Screen readers get the panel as a dialog named after the title, and
+    // read out the lines in it once it takes the focus.
+    let mut dialog = AccessNode::new(Role::Dialog);
+    dialog.set_label(title.as_str());
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            DespawnOnExit(PauseState::Results),
+        ))
+        .with_children(|overlay| {
+            let panel = overlay
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(6.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    BackgroundColor(PANEL_COLOR),
+                    AccessibilityNode::from(dialog),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(title),
+                        TextFont {
+                            font_size: 48.0,
+                            ..default()
+                        },
+                        TextColor(TITLE_COLOR),
+                        Label,
+                    ));
+                    for line in lines {
+                        panel.spawn((
+                            Text::new(line),
+                            TextFont {
+                                font_size: 22.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                            Label,
+                        ));
+                    }
+                    panel.spawn((
+                        Text::new(locale.get(hint)),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(HINT_COLOR),
+                        Label,
+                        ResultsHint,
+                    ));
+                })
+                .id();
+            input_focus.set(panel);
+        });
+}
+
+/// Enter, a gamepad's south button or a tap plays on, C copies the share
+/// summary.
+fn results_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    results: Res<Results>,
+    locale: Res<Locale>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    mut hints: Query<&mut Text, With<ResultsHint>>,
+) {
+    let south = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    if keyboard_input.just_pressed(KeyCode::Enter) || south || touches.any_just_pressed() {
+        next_state.set(PauseState::Running);
+    } else if keyboard_input.just_pressed(KeyCode::KeyC) {
+        clipboard::copy(&share_text(&results, &locale));
+        for mut hint in hints.iter_mut() {
+            hint.0 = locale.get("results.copied").to_string();
+        }
+    }
+}
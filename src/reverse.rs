@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::locale::Locale;
+use crate::{AppState, GameSet, RunStarted, StepClock};
+
+const REVERSE_COLOR: Color = Color::srgb(0.7, 0.3, 1.0);
+
+/// How far in from the screen's edges the vignette fades out, as a share of
+/// the distance to the corners.
+const VIGNETTE_CLEAR: f32 = 70.0;
+
+/// Counts down while the controls are reversed: up for down and left for
+/// right. It runs on [`StepClock`] time, so it lasts the same game time at
+/// any speed and plays out the same in a replay.
+#[derive(Resource, Default)]
+pub struct ReversedControls(Option<Timer>);
+
+impl ReversedControls {
+    pub fn for_secs(seconds: u32) -> Self {
+        Self(Some(Timer::new(
+            Duration::from_secs(seconds.into()),
+            TimerMode::Once,
+        )))
+    }
+
+    pub fn active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Only shown while the controls are reversed: the tinted edges of the
+/// screen, and the icon and countdown in the corner.
+#[derive(Component)]
+struct ReverseIndicator;
+
+#[derive(Component)]
+struct ReverseText;
+
+pub struct ReversePlugin;
+
+impl Plugin for ReversePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReversedControls>()
+            .add_systems(OnEnter(AppState::Playing), setup_reverse_hud)
+            .add_systems(OnExit(AppState::Playing), clear_reverse)
+            .add_systems(
+                Update,
+                (
+                    clear_reverse_on_respawn,
+                    tick_reverse.after(GameSet::Movement),
+                    update_reverse_hud,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn clear_reverse(mut reversed: ResMut<ReversedControls>) {
+    *reversed = ReversedControls::default();
+}
+
+/// A new run starts with the controls the right way round.
+fn clear_reverse_on_respawn(
+    mut run_reader: MessageReader<RunStarted>,
+    mut reversed: ResMut<ReversedControls>,
+) {
+    if run_reader.read().next().is_some() {
+        *reversed = ReversedControls::default();
+    }
+}
+
+fn tick_reverse(step_clock: Res<StepClock>, mut reversed: ResMut<ReversedControls>) {
+    if let Some(timer) = reversed.0.as_mut()
+        && timer.tick(step_clock.delta()).just_finished()
+    {
+        reversed.0 = None;
+    }
+}
+
+fn setup_reverse_hud(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundGradient::from(RadialGradient::new(
+            UiPosition::CENTER,
+            RadialGradientShape::FarthestCorner,
+            vec![
+                ColorStop::percent(Color::NONE, VIGNETTE_CLEAR),
+                ColorStop::percent(REVERSE_COLOR.with_alpha(0.45), 100.0),
+            ],
+        )),
+        // Clicks and touches go through to whatever is underneath.
+        Pickable::IGNORE,
+        Visibility::Hidden,
+        ReverseIndicator,
+        DespawnOnExit(AppState::Playing),
+    ));
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(176.0),
+                left: Val::Px(10.0),
+                column_gap: Val::Px(8.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            ReverseIndicator,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                Node {
+                    width: Val::Px(20.0),
+                    height: Val::Px(20.0),
+                    ..default()
+                },
+                BackgroundColor(REVERSE_COLOR),
+            ));
+            hud.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(REVERSE_COLOR),
+                ReverseText,
+            ));
+        });
+}
+
+fn update_reverse_hud(
+    reversed: Res<ReversedControls>,
+    locale: Res<Locale>,
+    mut shown: Query<&mut Visibility, With<ReverseIndicator>>,
+    mut texts: Query<&mut Text, With<ReverseText>>,
+) {
+    let visibility = if reversed.active() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut shown in shown.iter_mut() {
+        shown.set_if_neq(visibility);
+    }
+    let Some(timer) = &reversed.0 else {
+        return;
+    };
+    let seconds = timer.remaining_secs().ceil() as u32;
+    let text = locale.format("hud.reversed", &[("seconds", &seconds)]);
+    for mut line in texts.iter_mut() {
+        if line.0 != text {
+            line.0 = text.clone();
+        }
+    }
+}
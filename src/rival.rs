@@ -0,0 +1,530 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::brain::{self, Bot, Brains};
+use crate::grid::Grid;
+use crate::hazard::Hazard;
+use crate::locale::Locale;
+use crate::rules::Rules;
+use crate::team::{Team, TeamScores};
+use crate::terrain::Terrain;
+use crate::{
+    AppState, Direction, DirectionChangeRequest, Food, GameMode, GameRng, GameSet, Player,
+    Position, RestartEvent, RunStarted, SnakeColors, SnakeCrashed, SnakeHead, SnakeSegment,
+    SnakeSegments, StepEvent, snake_eating, snake_timer_tick, spawn_food, spawn_snake,
+    spawn_snake_body,
+};
+
+const RIVAL_COLORS: [Color; 8] = [
+    Color::srgb(0.9, 0.3, 0.3),
+    Color::srgb(0.3, 0.6, 0.95),
+    Color::srgb(0.95, 0.75, 0.2),
+    Color::srgb(0.3, 0.85, 0.45),
+    Color::srgb(0.85, 0.45, 0.9),
+    Color::srgb(0.3, 0.85, 0.85),
+    Color::srgb(0.95, 0.55, 0.25),
+    Color::srgb(0.6, 0.6, 0.95),
+];
+const BANNER_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// How long the victory banner stays up before the next round starts.
+const VICTORY_DELAY: Duration = Duration::from_secs(3);
+
+/// Rivals don't start closer than this many tiles to the player's head.
+const SPAWN_CLEARANCE: f32 = 5.0;
+
+/// A snake steered by the computer. It moves on the player's steps, eats the
+/// same food, and dies on anything the player would die on. Kept on the head.
+#[derive(Component)]
+pub struct Rival {
+    color: Color,
+    /// Its side in a team match.
+    team: Option<Team>,
+}
+
+impl Rival {
+    pub fn team(&self) -> Option<Team> {
+        self.team
+    }
+}
+
+#[derive(Component)]
+struct RivalsText;
+
+#[derive(Component)]
+struct VictoryBanner(Timer);
+
+/// The player's snake, whose head the rivals keep their distance from.
+type PlayerBodies<'w, 's> = Query<'w, 's, &'static SnakeSegments, With<Player>>;
+
+pub struct RivalPlugin;
+
+impl Plugin for RivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            (spawn_rivals.after(spawn_snake), setup_rivals_text)
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(rivals_enabled),
+        )
+        .add_systems(
+            Update,
+            (
+                steer_rivals.in_set(GameSet::Input).after(snake_timer_tick),
+                fall_rivals.in_set(GameSet::Collisions).before(snake_eating),
+                finish_round.in_set(GameSet::Collisions).after(fall_rivals),
+                respawn_rivals.after(GameSet::Spawning),
+                update_rivals_text.after(respawn_rivals),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(rivals_enabled),
+        );
+    }
+}
+
+fn rivals_enabled(rules: Res<Rules>) -> bool {
+    rules.rivals > 0
+}
+
+fn spawn_rivals(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    players: PlayerBodies,
+    positions: Query<&Position, With<SnakeSegment>>,
+) {
+    let grid = arena.grid();
+    let mut taken: HashSet<Position> = positions.iter().copied().collect();
+    let player = player_head(grid, &players, &positions);
+
+    for index in 0..rules.rivals {
+        let team = rules.teams.map(|_| Team::for_rival(index));
+        let color = team.map_or(
+            RIVAL_COLORS[index as usize % RIVAL_COLORS.len()],
+            Team::color,
+        );
+        place_rival(
+            &mut commands,
+            &mut rng,
+            grid,
+            &mut taken,
+            player,
+            color,
+            team,
+        );
+    }
+}
+
+/// Where the player's head is, in tiles from the middle of the board.
+fn player_head(
+    grid: &dyn Grid,
+    players: &PlayerBodies,
+    positions: &Query<&Position, With<SnakeSegment>>,
+) -> Vec2 {
+    players
+        .iter()
+        .next()
+        .and_then(|segments| segments.first())
+        .and_then(|head| positions.get(*head).ok())
+        .map_or(Vec2::ZERO, |head| grid.to_world(*head, 1.0))
+}
+
+/// Spawns a two-cell rival on a random free spot away from `player`, giving
+/// up if there's no room after a while.
+fn place_rival(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    grid: &dyn Grid,
+    taken: &mut HashSet<Position>,
+    player: Vec2,
+    color: Color,
+    team: Option<Team>,
+) {
+    let direction = grid.start_direction();
+    for _ in 0..50 {
+        let head = grid.random_cell(&mut rng.rng);
+        let tail = grid.step(head, direction.opposite());
+        if !grid.contains(tail)
+            || taken.contains(&head)
+            || taken.contains(&tail)
+            || grid.to_world(head, 1.0).distance(player) < SPAWN_CLEARANCE
+        {
+            continue;
+        }
+        let colors = SnakeColors {
+            head: color,
+            body: color.darker(0.25),
+        };
+        let body = spawn_snake_body(commands, &[head, tail], direction, colors);
+        commands.entity(body[0]).insert(Rival { color, team });
+        taken.extend([head, tail]);
+        return;
+    }
+}
+
+/// Every rival picks its next step, just before the snakes take it: its bot
+/// script if there's one, or else the open cell closest to food. Rivals that
+/// steer into something die of it, as the player would.
+fn steer_rivals(
+    mut step_reader: MessageReader<StepEvent>,
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    rules: Res<Rules>,
+    brains: Res<Brains>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    snakes: Query<(Entity, &SnakeHead, &SnakeSegments, Option<&Rival>)>,
+    positions: Query<&Position, With<SnakeSegment>>,
+    food: Query<&Position, With<Food>>,
+    hazards: Query<&Position, With<Hazard>>,
+) {
+    if step_reader.read().next().is_none() {
+        return;
+    }
+    let grid = arena.grid();
+    let forgiving = rules.teams.is_some_and(|teams| !teams.friendly_fire);
+    let player_team = rules.teams.map(|_| Team::PLAYER);
+    // Who is in each cell, so teammates can pass through each other. Tails
+    // move out of the way on this step.
+    let mut occupied: HashMap<Position, Option<Team>> =
+        hazards.iter().map(|pos| (*pos, None)).collect();
+    for (_, _, segments, rival) in snakes.iter() {
+        let team = rival.map_or(player_team, |rival| rival.team);
+        occupied.extend(
+            segments[..segments.len() - 1]
+                .iter()
+                .filter_map(|e| positions.get(*e).ok())
+                .map(|pos| (*pos, team)),
+        );
+    }
+    let targets: Vec<Position> = food.iter().copied().collect();
+
+    for (entity, head, segments, rival) in snakes.iter() {
+        let Some(rival) = rival else {
+            continue;
+        };
+        let Ok(head_pos) = positions.get(segments[0]) else {
+            continue;
+        };
+        let team = rival.team;
+        let open = |pos: &Position| {
+            grid.contains(*pos)
+                && !terrain.blocks(*pos)
+                && occupied
+                    .get(pos)
+                    .is_none_or(|owner| forgiving && owner.is_some() && *owner == team)
+        };
+        let scripted = brains.think(Bot::Rival, || {
+            let blocked: Vec<Position> = occupied.keys().copied().collect();
+            brain::board(
+                grid,
+                *head_pos,
+                head.direction,
+                segments.len(),
+                &targets,
+                &blocked,
+                &open,
+            )
+        });
+        let direction = scripted.or_else(|| {
+            let next = choose_step(grid, &open, *head_pos, segments.len(), &targets)?;
+            Direction::ALL
+                .into_iter()
+                .find(|direction| grid.step(*head_pos, *direction) == next)
+        });
+        let Some(direction) = direction else {
+            continue;
+        };
+        occupied.insert(grid.step(*head_pos, direction), team);
+        request_writer.write(DirectionChangeRequest {
+            snake: entity,
+            direction,
+        });
+    }
+}
+
+/// A rival that crashed leaves a trail of food where its body was; in a team
+/// match, it's replaced by a fresh one so its team can keep scoring.
+fn fall_rivals(
+    mut commands: Commands,
+    mut crash_reader: MessageReader<SnakeCrashed>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    rivals: Query<(&Rival, &SnakeSegments)>,
+    players: PlayerBodies,
+    positions: Query<&Position, With<SnakeSegment>>,
+) {
+    let grid = arena.grid();
+    let mut taken: Option<HashSet<Position>> = None;
+    let mut fallen = HashSet::new();
+    for crash in crash_reader.read() {
+        let Ok((rival, segments)) = rivals.get(crash.snake) else {
+            continue;
+        };
+        // A snake can crash into more than one thing at once.
+        if !fallen.insert(crash.snake) {
+            continue;
+        }
+        for segment in segments.iter() {
+            if let Ok(position) = positions.get(*segment) {
+                spawn_food(&mut commands, *position);
+            }
+            commands.entity(*segment).despawn();
+        }
+        if rival.team.is_some() {
+            let taken = taken.get_or_insert_with(|| positions.iter().copied().collect());
+            place_rival(
+                &mut commands,
+                &mut rng,
+                grid,
+                taken,
+                player_head(grid, &players, &positions),
+                rival.color,
+                rival.team,
+            );
+        }
+    }
+}
+
+/// Of the open cells next to `head`, prefers ones with room for the whole
+/// body, then the one closest to food. `None` when every way is blocked.
+fn choose_step(
+    grid: &dyn Grid,
+    open: &impl Fn(&Position) -> bool,
+    head: Position,
+    length: usize,
+    food: &[Position],
+) -> Option<Position> {
+    let distance = |cell: Position| {
+        let at = grid.to_world(cell, 1.0);
+        food.iter()
+            .map(|pos| grid.to_world(*pos, 1.0).distance_squared(at))
+            .fold(f32::MAX, f32::min)
+    };
+    grid.neighbors(head)
+        .into_iter()
+        .filter(|cell| open(cell))
+        .min_by(|a, b| {
+            let cramped = |cell| room(grid, open, cell, length) < length;
+            cramped(*a)
+                .cmp(&cramped(*b))
+                .then(distance(*a).total_cmp(&distance(*b)))
+        })
+}
+
+/// The number of open cells reachable from `start`, counting no further than
+/// `limit`.
+fn room(
+    grid: &dyn Grid,
+    open: &impl Fn(&Position) -> bool,
+    start: Position,
+    limit: usize,
+) -> usize {
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(cell) = queue.pop_front() {
+        if seen.len() >= limit {
+            break;
+        }
+        for next in grid.neighbors(cell) {
+            if open(&next) && seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+/// The round is won once every rival is dead or, in a team match, once a team
+/// reaches the target score. A new one starts shortly after.
+fn finish_round(
+    mut commands: Commands,
+    time: Res<Time>,
+    locale: Res<Locale>,
+    rules: Res<Rules>,
+    scores: Res<TeamScores>,
+    rivals: Query<(), With<Rival>>,
+    mut banners: Query<(Entity, &mut VictoryBanner)>,
+    mut restart_writer: MessageWriter<RestartEvent>,
+) {
+    if let Ok((entity, mut banner)) = banners.single_mut() {
+        if banner.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+            restart_writer.write(RestartEvent { same_seed: false });
+        }
+        return;
+    }
+    let banner = match rules.teams {
+        Some(teams) => Team::ALL
+            .into_iter()
+            .find(|team| scores.get(*team) >= teams.target)
+            .map(|team| locale.format("team.won", &[("team", &locale.get(team.name()))])),
+        None => rivals
+            .is_empty()
+            .then(|| locale.get("battle.won").to_string()),
+    };
+    let Some(banner) = banner else {
+        return;
+    };
+    commands.spawn((
+        Text::new(banner),
+        TextFont {
+            font_size: 48.0,
+            ..default()
+        },
+        TextColor(BANNER_COLOR),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        VictoryBanner(Timer::new(VICTORY_DELAY, TimerMode::Once)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Each new round, after a death, a restart or a win, brings a fresh set of
+/// rivals.
+/// The old rivals went with the rest of the board when the round ended.
+fn respawn_rivals(
+    mut commands: Commands,
+    mut run_reader: MessageReader<RunStarted>,
+    banners: Query<Entity, With<VictoryBanner>>,
+    rules: Res<Rules>,
+    rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    players: PlayerBodies,
+    positions: Query<&Position, With<SnakeSegment>>,
+) {
+    if run_reader.read().next().is_none() {
+        return;
+    }
+    for entity in banners.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_rivals(commands, rules, rng, arena, players, positions);
+}
+
+fn setup_rivals_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        RivalsText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_rivals_text(
+    locale: Res<Locale>,
+    rules: Res<Rules>,
+    scores: Res<TeamScores>,
+    rivals: Query<(), With<Rival>>,
+    mut query: Query<&mut Text, With<RivalsText>>,
+) {
+    let text = match rules.teams {
+        Some(teams) => locale.format(
+            "hud.teams",
+            &[
+                ("blue", &scores.get(Team::Blue)),
+                ("red", &scores.get(Team::Red)),
+                ("target", &teams.target),
+            ],
+        ),
+        None => locale.format("hud.rivals", &[("count", &rivals.iter().count())]),
+    };
+    for mut line in query.iter_mut() {
+        if line.0 != text {
+            line.0 = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::env::headless_app;
+    use crate::killcam::KillcamPlugin;
+    use crate::{DeathCause, SnakeDied, SnakeTimer};
+
+    /// Real time per frame, so the slow motion plays out in a known number
+    /// of frames.
+    const FRAME: Duration = Duration::from_millis(50);
+
+    /// A battle royale against three rivals, with the kill-cam's slow motion
+    /// and a snake too slow to ever take a step.
+    fn battle_app() -> App {
+        let mut app = headless_app();
+        app.insert_resource(Rules {
+            rivals: 3,
+            ..default()
+        })
+        .insert_resource(SnakeTimer {
+            base: Duration::from_secs(3600),
+            ..default()
+        })
+        .insert_resource(Locale::load("en"))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+        .init_resource::<Brains>()
+        .init_resource::<TeamScores>()
+        .add_plugins((KillcamPlugin, RivalPlugin));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+        app
+    }
+
+    fn rivals(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world
+            .query_filtered::<(), With<Rival>>()
+            .iter(world)
+            .count()
+    }
+
+    #[test]
+    fn rivals_come_back_after_the_player_dies() {
+        let mut app = battle_app();
+        assert_eq!(rivals(&mut app), 3);
+
+        let world = app.world_mut();
+        let player = world
+            .query_filtered::<Entity, With<Player>>()
+            .single(world)
+            .unwrap();
+        world.write_message(SnakeDied {
+            snake: player,
+            cause: DeathCause::Wall,
+        });
+        // Long enough for the slow motion to play and the board to clear.
+        for _ in 0..20 {
+            app.update();
+        }
+
+        assert_eq!(rivals(&mut app), 3);
+        let world = app.world_mut();
+        let players = world
+            .query_filtered::<(), With<Player>>()
+            .iter(world)
+            .count();
+        assert_eq!(players, 1);
+    }
+}
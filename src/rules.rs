@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena_event::ArenaEvent;
+use crate::grid::Grid;
+use crate::modifier::Modifier;
+use crate::terrain::Terrain;
+use crate::upgrade::Upgrade;
+use crate::{ARENA_HEIGHT, ARENA_WIDTH, Direction, Position};
+
+/// Score a hardcore run has to reach to count towards the streak.
+pub const HARDCORE_TARGET: u32 = 25;
+
+/// Columns across and rows up a battle royale's arena.
+const BATTLE_ROYALE_ARENA: (u32, u32) = (36, 28);
+
+/// Tunable rules of an endless run.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Time between random arena events.
+    pub event_interval: Duration,
+    /// Events to pick from; leave it empty to turn them off.
+    pub event_pool: Vec<ArenaEvent>,
+    /// Computer-controlled snakes sharing the arena. With any, the run is a
+    /// battle royale: the last snake alive wins the round.
+    pub rivals: u32,
+    /// Splits the player and the rivals into two teams.
+    pub teams: Option<TeamRules>,
+    /// Two players, each steering a snake of their own, sharing one score.
+    pub coop: bool,
+    /// Points per food eaten.
+    pub food_points: u32,
+    /// How much faster the snake moves than usual.
+    pub speed: f32,
+    /// What happens when the snake runs off the edge of the arena.
+    pub walls: WallBehavior,
+    /// Time between poison pellets appearing, if they do at all.
+    pub poison_interval: Option<Duration>,
+    /// Each run starts by drafting a modifier, and dying leads to the next
+    /// draft rather than straight back in.
+    pub roguelike: bool,
+    /// Modifiers drafted so far, already applied to the rest of the rules.
+    pub modifiers: Vec<Modifier>,
+    /// Deaths a roguelike run can take before it's over.
+    pub lives: u32,
+    /// Crashes the snake shrugs off in each run.
+    pub shields: u32,
+    /// Whether the snake dies running into its own body.
+    pub self_collision: bool,
+    /// Food pellets dropped on the board every second.
+    pub food_count: u32,
+    /// How quickly the snake speeds up as the score grows.
+    pub speed_curve: SpeedCurve,
+    /// Going this long without eating costs the snake its tail.
+    pub decay: Option<Duration>,
+    /// How long a full hunger meter lasts, if the snake gets hungry at all.
+    pub hunger: Option<Duration>,
+    /// Going this long without eating starts draining the score, a point a
+    /// second.
+    pub idle_drain: Option<Duration>,
+    /// Put together on the custom game screen. Deaths show what the rules
+    /// were, since they can be anything.
+    pub custom: bool,
+    /// Marks a hardcore run, and the score it has to reach to keep the
+    /// player's streak going. Any run that falls short ends the streak.
+    pub hardcore: Option<u32>,
+    /// A challenge: the arena is drawn flipped left to right, while the
+    /// snake moves as usual.
+    pub mirrored: bool,
+    /// Where the snake may start a run, and how long. Each run picks one
+    /// at random, so hand-made levels don't play the same every time.
+    pub spawns: Vec<Spawn>,
+    /// Columns across and rows up the arena.
+    pub arena_size: (u32, u32),
+}
+
+/// The snake at the start of a run: its head at `position`, heading in
+/// `direction`, or the grid's usual one, with the rest of its `length`
+/// trailing straight behind.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spawn {
+    pub position: Position,
+    pub length: u32,
+    pub direction: Option<Direction>,
+}
+
+impl Default for Spawn {
+    fn default() -> Self {
+        Self {
+            position: Position { x: 3, y: 3 },
+            length: 2,
+            direction: None,
+        }
+    }
+}
+
+impl Spawn {
+    /// The direction the snake starts off in on `grid`.
+    pub fn direction(&self, grid: &dyn Grid) -> Direction {
+        self.direction
+            .filter(|direction| grid.steer(*direction, *direction) == *direction)
+            .unwrap_or(grid.start_direction())
+    }
+
+    /// The cells of the starting body, head first, or why it doesn't fit:
+    /// every cell must be on the board and clear of walls.
+    pub fn body(&self, grid: &dyn Grid, terrain: &Terrain) -> Result<Vec<Position>, String> {
+        if self.length == 0 {
+            return Err("the snake needs a length of at least 1".to_string());
+        }
+        let behind = self.direction(grid).opposite();
+        let body: Vec<Position> =
+            std::iter::successors(Some(self.position), |pos| Some(grid.step(*pos, behind)))
+                .take(self.length as usize)
+                .collect();
+        if let Some(pos) = body.iter().find(|pos| !grid.contains(**pos)) {
+            return Err(format!("({}, {}) is off the board", pos.x, pos.y));
+        }
+        if let Some(pos) = body.iter().find(|pos| terrain.blocks(**pos)) {
+            return Err(format!("({}, {}) is blocked", pos.x, pos.y));
+        }
+        Ok(body)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct TeamRules {
+    /// Team score that wins the round.
+    pub target: u32,
+    /// Whether teammates die running into each other.
+    pub friendly_fire: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WallBehavior {
+    Kill,
+    /// The snake comes back in on the opposite side.
+    Wrap,
+    /// The snake turns around, its tail becoming its head.
+    Bounce,
+    /// The snake bumps its head and stops, losing the points of a food,
+    /// until it's turned away from the edge.
+    Bonk,
+}
+
+impl WallBehavior {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Kill => Self::Wrap,
+            Self::Wrap => Self::Bounce,
+            Self::Bounce => Self::Bonk,
+            Self::Bonk => Self::Kill,
+        }
+    }
+
+    /// Locale key of the behavior's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kill => "walls.deadly",
+            Self::Wrap => "walls.wrap",
+            Self::Bounce => "walls.bounce",
+            Self::Bonk => "walls.bonk",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeedCurve {
+    Flat,
+    Gentle,
+    Normal,
+    Steep,
+}
+
+impl SpeedCurve {
+    /// Milliseconds taken off each step per point scored.
+    pub fn ms_per_point(self) -> f32 {
+        match self {
+            Self::Flat => 0.0,
+            Self::Gentle => 5.0,
+            Self::Normal => 10.0,
+            Self::Steep => 20.0,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Flat => Self::Gentle,
+            Self::Gentle => Self::Normal,
+            Self::Normal => Self::Steep,
+            Self::Steep => Self::Flat,
+        }
+    }
+
+    /// Locale key of the curve's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Flat => "speed_curve.flat",
+            Self::Gentle => "speed_curve.gentle",
+            Self::Normal => "speed_curve.normal",
+            Self::Steep => "speed_curve.steep",
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            event_interval: Duration::from_secs(30),
+            event_pool: vec![
+                ArenaEvent::ObstacleRow,
+                ArenaEvent::RelocateFood,
+                ArenaEvent::MirrorControls,
+            ],
+            rivals: 0,
+            teams: None,
+            coop: false,
+            food_points: 1,
+            speed: 1.0,
+            walls: WallBehavior::Kill,
+            poison_interval: None,
+            roguelike: false,
+            modifiers: Vec::new(),
+            lives: 1,
+            shields: 0,
+            self_collision: true,
+            food_count: 1,
+            speed_curve: SpeedCurve::Normal,
+            decay: None,
+            hunger: None,
+            idle_drain: None,
+            custom: false,
+            hardcore: None,
+            mirrored: false,
+            spawns: vec![Spawn::default()],
+            arena_size: (ARENA_WIDTH, ARENA_HEIGHT),
+        }
+    }
+}
+
+impl Rules {
+    /// Picks one of the spawns that fit the board, returning the direction
+    /// the snake starts off in and its body, head first. Falls back to the
+    /// usual spawn if none do. Only draws from `rng` when there's a choice,
+    /// so runs with a single spawn keep their food sequence.
+    pub fn pick_spawn(
+        &self,
+        grid: &dyn Grid,
+        terrain: &Terrain,
+        rng: &mut StdRng,
+    ) -> (Direction, Vec<Position>) {
+        let mut fitting: Vec<(Direction, Vec<Position>)> = self
+            .spawns
+            .iter()
+            .filter_map(|spawn| match spawn.body(grid, terrain) {
+                Ok(body) => Some((spawn.direction(grid), body)),
+                Err(err) => {
+                    warn!("Failed to place the snake: {}", err);
+                    None
+                }
+            })
+            .collect();
+        match fitting.len() {
+            0 => {
+                let spawn = Spawn::default();
+                let body = spawn.body(grid, &Terrain::default()).unwrap_or_default();
+                (spawn.direction(grid), body)
+            }
+            1 => fitting.remove(0),
+            count => fitting.swap_remove(rng.random_range(0..count)),
+        }
+    }
+
+    /// The player against eight rivals, on an arena big enough for all of
+    /// them.
+    pub fn battle_royale() -> Self {
+        Self {
+            rivals: 8,
+            arena_size: BATTLE_ROYALE_ARENA,
+            ..default()
+        }
+    }
+
+    /// Two against two: the player and one rival against the other two.
+    pub fn team_match(friendly_fire: bool) -> Self {
+        Self {
+            rivals: 3,
+            teams: Some(TeamRules {
+                target: 15,
+                friendly_fire,
+            }),
+            ..default()
+        }
+    }
+
+    /// Faster, always with self-collision, and counted towards the
+    /// player's streak of runs reaching [`HARDCORE_TARGET`].
+    pub fn hardcore() -> Self {
+        Self {
+            hardcore: Some(HARDCORE_TARGET),
+            speed: 1.25,
+            self_collision: true,
+            ..default()
+        }
+    }
+
+    pub fn coop() -> Self {
+        Self {
+            coop: true,
+            ..default()
+        }
+    }
+
+    /// A roguelike run with the player's upgrades applied.
+    pub fn roguelike(upgrades: &[Upgrade]) -> Self {
+        let mut rules = Self {
+            roguelike: true,
+            lives: 3,
+            ..default()
+        };
+        for upgrade in upgrades {
+            upgrade.apply(&mut rules);
+        }
+        rules
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::attract;
+use crate::settings::Settings;
+use crate::{AppState, PlayerMeals, SnakeDied};
+
+const EAT_RUMBLE: Duration = Duration::from_millis(120);
+const DEATH_RUMBLE: Duration = Duration::from_millis(450);
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            rumble
+                .run_if(in_state(AppState::Playing))
+                .run_if(rumble_enabled)
+                .run_if(attract::not_demo),
+        );
+    }
+}
+
+fn rumble_enabled(settings: Res<Settings>) -> bool {
+    settings.rumble > 0.0
+}
+
+/// A light buzz for eating and a heavy one for dying, on every connected
+/// gamepad, scaled by the rumble setting.
+fn rumble(
+    mut growth_reader: PlayerMeals,
+    mut game_over_reader: MessageReader<SnakeDied>,
+    settings: Res<Settings>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut rumble_writer: MessageWriter<GamepadRumbleRequest>,
+) {
+    let ate = growth_reader.read().next().is_some();
+    let died = game_over_reader.read().next().is_some();
+    let (duration, intensity) = if died {
+        (
+            DEATH_RUMBLE,
+            GamepadRumbleIntensity {
+                strong_motor: settings.rumble,
+                weak_motor: settings.rumble / 2.0,
+            },
+        )
+    } else if ate {
+        (
+            EAT_RUMBLE,
+            GamepadRumbleIntensity::weak_motor(settings.rumble),
+        )
+    } else {
+        return;
+    };
+    for gamepad in gamepads.iter() {
+        rumble_writer.write(GamepadRumbleRequest::Add {
+            duration,
+            intensity,
+            gamepad,
+        });
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+
+use crate::arena::Arena;
+use crate::grid::Grid;
+use crate::items::ItemColor;
+use crate::profile::Profile;
+use crate::rules::Rules;
+use crate::settings::{RenderStyle, Settings};
+use crate::{
+    AppState, Ground, Player, Position, SNAKE_HEAD_COLOR, SNAKE_SEGMENT_COLOR, Size, SnakeColors,
+    SnakeHead, SnakeSegment, SnakeSegments,
+};
+
+/// Height of tiles lying on the ground, just enough to show above it.
+const GROUND_TILE_HEIGHT: f32 = 0.02;
+
+const CAMERA_DISTANCE: f32 = 30.0;
+const MIN_PITCH: f32 = 15.0_f32.to_radians();
+const MAX_PITCH: f32 = 89.0_f32.to_radians();
+/// Radians per second while a camera key is held.
+const CAMERA_SPEED: f32 = 1.5;
+
+/// Where the 3D camera sits on its orbit around the arena center.
+#[derive(Resource)]
+struct OrbitCamera {
+    /// Rotation around the vertical axis; zero looks at the board from below
+    /// its bottom edge.
+    yaw: f32,
+    /// Angle above the board.
+    pitch: f32,
+}
+
+#[derive(Resource)]
+struct CubeAssets {
+    cube: Handle<Mesh>,
+    head: Handle<StandardMaterial>,
+    segment: Handle<StandardMaterial>,
+}
+
+pub struct Scene3dPlugin;
+
+impl Plugin for Scene3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(OrbitCamera {
+            yaw: 0.0,
+            pitch: 55.0_f32.to_radians(),
+        })
+        .add_systems(Startup, setup_cube_assets)
+        .add_systems(
+            OnEnter(AppState::Playing),
+            spawn_scene
+                .after(crate::arena::sync_arena)
+                .run_if(three_d_active),
+        )
+        .add_systems(OnExit(AppState::Playing), restore_ui_camera)
+        .add_systems(
+            Update,
+            (attach_cubes, orbit_camera_input, update_orbit_camera)
+                .chain()
+                .run_if(in_state(AppState::Playing))
+                .run_if(three_d_active),
+        )
+        .add_systems(
+            PostUpdate,
+            place_cubes
+                .after(crate::GameSet::Rendering)
+                .run_if(in_state(AppState::Playing))
+                .run_if(three_d_active),
+        );
+    }
+}
+
+fn three_d_active(settings: Res<Settings>) -> bool {
+    settings.render_style == RenderStyle::ThreeD
+}
+
+fn setup_cube_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(CubeAssets {
+        cube: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+        head: materials.add(SNAKE_HEAD_COLOR),
+        segment: materials.add(SNAKE_SEGMENT_COLOR),
+    });
+}
+
+/// Maps a board position onto the ground plane, one unit per tile, with the
+/// board's up pointing away from the camera's starting point.
+fn ground_position(grid: &dyn Grid, rules: &Rules, pos: Position) -> Vec3 {
+    let world = grid.to_world(pos, 1.0);
+    let x = if rules.mirrored { -world.x } else { world.x };
+    Vec3::new(x, 0.0, -world.y)
+}
+
+fn spawn_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    arena: Res<Arena>,
+    rules: Res<Rules>,
+    profile: Res<Profile>,
+    mut ui_cameras: Query<&mut Camera, With<Camera2d>>,
+) {
+    let grid = arena.grid();
+    // Hex rows alternate their offset, so look at the two outermost rows on
+    // each side.
+    let (width, height) = (arena.width(), arena.height());
+    let (min, max) = [0, width - 1]
+        .into_iter()
+        .flat_map(|column| [0, 1, height - 2, height - 1].map(|row| (column, row)))
+        .map(|(column, row)| ground_position(grid, &rules, grid.cell_position(column, row)))
+        .fold((Vec3::MAX, Vec3::MIN), |(min, max), corner| {
+            (min.min(corner), max.max(corner))
+        });
+    let size = max - min + Vec3::ONE;
+
+    commands.spawn((
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(size.x, size.z))),
+        MeshMaterial3d(materials.add(profile.theme.floor())),
+        Transform::from_translation((min + max) / 2.0),
+        DespawnOnExit(AppState::Playing),
+    ));
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(-8.0, 20.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y),
+        DespawnOnExit(AppState::Playing),
+    ));
+    // Drawn first so the 2D camera can lay the HUD over it.
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: -1,
+            ..default()
+        },
+        Transform::default(),
+        DespawnOnExit(AppState::Playing),
+    ));
+
+    for mut camera in ui_cameras.iter_mut() {
+        camera.clear_color = ClearColorConfig::None;
+    }
+}
+
+fn restore_ui_camera(mut ui_cameras: Query<&mut Camera, With<Camera2d>>) {
+    for mut camera in ui_cameras.iter_mut() {
+        camera.clear_color = ClearColorConfig::Default;
+    }
+}
+
+type NewTileQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Sprite,
+        Option<&'static ItemColor>,
+        Has<SnakeHead>,
+        Has<SnakeSegment>,
+    ),
+    (With<Position>, Without<Mesh3d>),
+>;
+
+/// Board entities keep their sprites, which the 2D camera hides in this
+/// style, and get a cube for the 3D camera. The player's snake shares one
+/// pair of materials; every other snake is in the colors it was spawned in.
+fn attach_cubes(
+    mut commands: Commands,
+    assets: Res<CubeAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_tiles: NewTileQuery,
+    snakes: Query<(&SnakeSegments, &SnakeColors, Option<&Player>)>,
+) {
+    let colors_of = |entity: Entity| {
+        snakes
+            .iter()
+            .find(|(segments, _, _)| segments.contains(&entity))
+            .filter(|(_, _, player)| !player.is_some_and(|player| player.0 == 0))
+            .map(|(_, colors, _)| *colors)
+    };
+    for (entity, sprite, item_color, head, segment) in new_tiles.iter() {
+        let material = if segment && let Some(colors) = colors_of(entity) {
+            materials.add(if head { colors.head } else { colors.body })
+        } else if head {
+            assets.head.clone()
+        } else if segment {
+            assets.segment.clone()
+        } else {
+            materials.add(item_color.map_or(sprite.color, |item_color| item_color.0))
+        };
+        commands
+            .entity(entity)
+            .insert((Mesh3d(assets.cube.clone()), MeshMaterial3d(material)));
+    }
+}
+
+fn orbit_camera_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut orbit: ResMut<OrbitCamera>,
+) {
+    let step = CAMERA_SPEED * time.delta_secs();
+    if keyboard_input.pressed(KeyCode::BracketLeft) {
+        orbit.yaw -= step;
+    }
+    if keyboard_input.pressed(KeyCode::BracketRight) {
+        orbit.yaw += step;
+    }
+    if keyboard_input.pressed(KeyCode::PageUp) {
+        orbit.pitch = (orbit.pitch + step).min(MAX_PITCH);
+    }
+    if keyboard_input.pressed(KeyCode::PageDown) {
+        orbit.pitch = (orbit.pitch - step).max(MIN_PITCH);
+    }
+}
+
+fn update_orbit_camera(
+    orbit: Res<OrbitCamera>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    let offset = Quat::from_euler(EulerRot::YXZ, orbit.yaw, -orbit.pitch, 0.0) * Vec3::Z;
+    for mut transform in cameras.iter_mut() {
+        *transform =
+            Transform::from_translation(offset * CAMERA_DISTANCE).looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
+
+/// Overrides the 2D placement with a spot on the ground plane.
+fn place_cubes(
+    arena: Res<Arena>,
+    rules: Res<Rules>,
+    mut cubes: Query<(&Position, &Size, &mut Transform, Has<Ground>), With<Mesh3d>>,
+) {
+    for (pos, size, mut transform, ground) in cubes.iter_mut() {
+        let height = if ground {
+            GROUND_TILE_HEIGHT
+        } else {
+            size.width.min(size.height)
+        };
+        let scale = Vec3::new(size.width, height, size.height);
+        *transform = Transform::from_translation(
+            ground_position(arena.grid(), &rules, *pos) + Vec3::Y * scale.y / 2.0,
+        )
+        .with_scale(scale);
+    }
+}
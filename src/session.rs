@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::now_millis;
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::pause::PauseState;
+use crate::replay::not_watching;
+use crate::results::clock;
+use crate::storage;
+use crate::{AppState, GameMode, GameSet, GameState, PlayerMeals, RestartEvent, SnakeDied};
+
+const STATS_PATH: &str = "stats.ron";
+
+const TITLE_COLOR: Color = Color::srgb(0.5, 0.85, 1.0);
+
+/// What the player has been up to since the game started, not counting
+/// replays they watched.
+#[derive(Resource, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Endless runs ended, by dying, restarting or leaving for the menu.
+    pub runs: u32,
+    pub best_score: u32,
+    pub food_eaten: u32,
+    /// Seconds spent playing, not counting pauses.
+    pub seconds: f32,
+    /// Milliseconds since the Unix epoch when the session ended.
+    pub ended_at: u64,
+}
+
+impl Session {
+    fn end_run(&mut self, score: u32) {
+        self.runs += 1;
+        self.best_score = self.best_score.max(score);
+    }
+}
+
+/// Every session played, oldest first.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Stats {
+    sessions: Vec<Session>,
+}
+
+/// Whether the session has gone into the stats file, so quitting from the
+/// summary doesn't log it again on the way out.
+#[derive(Resource, Default)]
+struct Logged(bool);
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Session>()
+            .init_resource::<Logged>()
+            .add_systems(
+                Update,
+                (
+                    count_runs
+                        .after(GameSet::Spawning)
+                        .run_if(resource_equals(GameMode::Endless)),
+                    count_food,
+                    clock_session.run_if(in_state(PauseState::Running)),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not_watching),
+            )
+            .add_systems(
+                OnExit(AppState::Playing),
+                count_abandoned_run
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(not_watching),
+            )
+            .add_systems(OnEnter(AppState::Summary), setup_summary)
+            .add_systems(
+                Update,
+                summary_input
+                    .after(navigate_focus)
+                    .run_if(in_state(AppState::Summary)),
+            )
+            .add_systems(Last, log_on_exit);
+    }
+}
+
+fn count_runs(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    mut session: ResMut<Session>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        session.end_run(game_state.last_score);
+    }
+}
+
+/// Leaving for the menu ends a run too, unless it never got going, as when
+/// the player quits right after a death.
+fn count_abandoned_run(game_state: Res<GameState>, mut session: ResMut<Session>) {
+    if game_state.score > 0 {
+        session.end_run(game_state.score);
+    }
+}
+
+fn count_food(mut growth_reader: PlayerMeals, mut session: ResMut<Session>) {
+    session.food_eaten += growth_reader.read().count() as u32;
+}
+
+fn clock_session(time: Res<Time>, mut session: ResMut<Session>) {
+    session.seconds += time.delta_secs();
+}
+
+/// Adds the session to the stats file, unless nothing was played.
+fn log_session(session: &mut Session, logged: &mut Logged) {
+    if logged.0 || session.seconds <= 0.0 {
+        return;
+    }
+    session.ended_at = now_millis();
+    let mut stats: Stats = storage::load(STATS_PATH);
+    stats.sessions.push(*session);
+    storage::save(STATS_PATH, &stats);
+    logged.0 = true;
+}
+
+/// Closing the window skips the summary, but the session still counts.
+fn log_on_exit(
+    mut exit_reader: MessageReader<AppExit>,
+    mut session: ResMut<Session>,
+    mut logged: ResMut<Logged>,
+) {
+    if exit_reader.read().next().is_some() {
+        log_session(&mut session, &mut logged);
+    }
+}
+
+fn setup_summary(mut commands: Commands, locale: Res<Locale>, session: Res<Session>) {
+    let lines = [
+        locale.format("session.runs", &[("count", &session.runs)]),
+        locale.format("session.best", &[("score", &session.best_score)]),
+        locale.format("session.food", &[("count", &session.food_eaten)]),
+        locale.format("session.time", &[("time", &clock(session.seconds))]),
+    ];
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Summary),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("session.title")),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(TITLE_COLOR),
+            ));
+            for line in lines {
+                parent.spawn(summary_line(line, Color::srgb(0.85, 0.85, 0.85)));
+            }
+            for (order, key) in ["session.quit", "session.back"].into_iter().enumerate() {
+                parent.spawn((
+                    summary_line(locale.get(key).to_string(), Color::srgb(0.7, 0.7, 0.7)),
+                    Focusable::new(order),
+                ));
+            }
+        });
+}
+
+fn summary_line(text: String, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(color),
+    )
+}
+
+/// Quitting logs the session and closes the game. Browsers can't close the
+/// page, so there the next session starts back on the menu instead. Escape
+/// goes back to the menu with the session still going.
+fn summary_input(
+    mut input_reader: MessageReader<FocusInput>,
+    mut session: ResMut<Session>,
+    mut logged: ResMut<Logged>,
+    mut exit_writer: MessageWriter<AppExit>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Activate(0) => {
+                log_session(&mut session, &mut logged);
+                if cfg!(target_arch = "wasm32") {
+                    *session = Session::default();
+                    logged.0 = false;
+                    next_state.set(AppState::Menu);
+                } else {
+                    exit_writer.write(AppExit::Success);
+                }
+            }
+            FocusInput::Activate(_) | FocusInput::Back => next_state.set(AppState::Menu),
+            FocusInput::Nudge(..) => {}
+        }
+    }
+}
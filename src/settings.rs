@@ -0,0 +1,339 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::grid::GridShape;
+use crate::replay::watching;
+use crate::rules::Rules;
+use crate::sound::Bus;
+use crate::storage;
+use crate::tournament::Tournament;
+use crate::weather::Weather;
+use crate::{AppState, GameSet, GameState, SnakeTimer, calculate_speed};
+
+const SETTINGS_PATH: &str = "settings.ron";
+
+const MIN_UI_SCALE: f32 = 0.75;
+const MAX_UI_SCALE: f32 = 2.0;
+const UI_SCALE_STEP: f32 = 0.25;
+
+/// Rumble strength is picked in quarters, from off to full.
+const RUMBLE_STEP: f32 = 0.25;
+
+/// Volumes are picked in tenths, from off to full.
+const VOLUME_STEP: f32 = 0.1;
+
+/// The speed a fresh install starts at, and the one every ranked run is
+/// played at.
+const DEFAULT_STEP_MS: u32 = 500;
+const MIN_STEP_MS: u32 = 100;
+const MAX_STEP_MS: u32 = 1000;
+const STEP_MS_STEP: u32 = 50;
+/// The extra-slow tier, one notch past [`MAX_STEP_MS`], for players who
+/// need a lot longer to react.
+const EXTRA_SLOW_STEP_MS: u32 = 1800;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_ui_scale.run_if(resource_changed::<Settings>),
+                apply_step_interval
+                    .before(GameSet::Input)
+                    .run_if(
+                        resource_changed::<Settings>
+                            .or(resource_changed::<Tournament>)
+                            .or(resource_changed::<Rules>),
+                    )
+                    .run_if(not(watching)),
+            ),
+        )
+        .add_systems(
+            Update,
+            step_interval_input
+                .before(apply_step_interval)
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(speed_pinned))
+                .run_if(not(watching)),
+        );
+    }
+}
+
+fn apply_ui_scale(settings: Res<Settings>, mut ui_scale: ResMut<UiScale>) {
+    ui_scale.0 = settings.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+}
+
+/// Whether the run is a ranked one, hardcore or a tournament attempt, which
+/// all start at the same speed whatever the settings say.
+fn speed_pinned(rules: Res<Rules>, tournament: Res<Tournament>) -> bool {
+    rules.hardcore.is_some() || tournament.entered().is_some()
+}
+
+/// Takes effect right away, keeping whatever the score has sped the snake up
+/// by. It lands before the step of the frame, so a replay records the step
+/// the change took effect on. A replay being watched sets its own speed.
+fn apply_step_interval(
+    settings: Res<Settings>,
+    tournament: Res<Tournament>,
+    game_state: Res<GameState>,
+    rules: Res<Rules>,
+    mut snake_timer: ResMut<SnakeTimer>,
+) {
+    let step_ms = if speed_pinned(Res::clone(&rules), tournament) {
+        DEFAULT_STEP_MS
+    } else {
+        settings.step_ms
+    };
+    let base = Duration::from_millis(step_ms.clamp(MIN_STEP_MS, EXTRA_SLOW_STEP_MS).into());
+    if snake_timer.base == base {
+        return;
+    }
+    snake_timer.base = base;
+    let speed = calculate_speed(base, game_state.score, &rules);
+    snake_timer.timer.set_duration(speed);
+}
+
+/// `[` and `]` slow the snake down and speed it up mid-run.
+fn step_interval_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        settings.adjust_step_ms(1);
+        settings.save();
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        settings.adjust_step_ms(-1);
+        settings.save();
+    }
+}
+
+/// Where the on-screen direction buttons are placed.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TouchLayout {
+    /// One button centered on each screen edge.
+    #[default]
+    Edges,
+    /// A d-pad in the bottom-left corner.
+    LeftHanded,
+    /// A d-pad in the bottom-right corner.
+    RightHanded,
+}
+
+impl TouchLayout {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Edges => Self::LeftHanded,
+            Self::LeftHanded => Self::RightHanded,
+            Self::RightHanded => Self::Edges,
+        }
+    }
+
+    /// Locale key of the layout's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Edges => "touch_layout.edges",
+            Self::LeftHanded => "touch_layout.left_handed",
+            Self::RightHanded => "touch_layout.right_handed",
+        }
+    }
+}
+
+/// How the player steers their snake.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Each arrow key heads the snake that way on the board.
+    #[default]
+    Absolute,
+    /// Left and right turn the snake a step either way from where it's
+    /// heading, so two buttons are enough. For one hand or a switch device.
+    TwoButton,
+}
+
+impl ControlScheme {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Absolute => Self::TwoButton,
+            Self::TwoButton => Self::Absolute,
+        }
+    }
+
+    /// Locale key of the scheme's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Absolute => "control_scheme.absolute",
+            Self::TwoButton => "control_scheme.two_button",
+        }
+    }
+}
+
+/// How the board is drawn.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderStyle {
+    /// Colored squares.
+    #[default]
+    Sprites,
+    /// Monospaced glyphs on a text grid, like a terminal.
+    Ascii,
+    /// Diamond tiles seen from above at an angle.
+    Isometric,
+    /// Cubes on a plane, seen through an orbiting camera. Experimental.
+    ThreeD,
+}
+
+impl RenderStyle {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Sprites => Self::Ascii,
+            Self::Ascii => Self::Isometric,
+            Self::Isometric => Self::ThreeD,
+            Self::ThreeD => Self::Sprites,
+        }
+    }
+
+    /// Locale key of the style's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sprites => "render_style.sprites",
+            Self::Ascii => "render_style.ascii",
+            Self::Isometric => "render_style.isometric",
+            Self::ThreeD => "render_style.three_d",
+        }
+    }
+}
+
+/// Player preferences that survive between sessions.
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Language code matching a file in `assets/locale`.
+    pub language: String,
+    /// Multiplier for all HUD and menu text, between [`MIN_UI_SCALE`] and
+    /// [`MAX_UI_SCALE`].
+    pub ui_scale: f32,
+    pub touch_layout: TouchLayout,
+    pub control_scheme: ControlScheme,
+    pub render_style: RenderStyle,
+    /// Cell layout for endless runs.
+    pub grid_shape: GridShape,
+    pub weather: Weather,
+    /// Turns off purely decorative movement.
+    pub reduced_motion: bool,
+    /// Whether teammates collide in team matches.
+    pub friendly_fire: bool,
+    /// Whether the snake dies running into itself in endless runs.
+    pub self_collision: bool,
+    /// Whether walls are drawn around the edge of the arena when running
+    /// off it doesn't wrap around.
+    pub border_walls: bool,
+    /// Milliseconds between snake steps at the start of a run, between
+    /// [`MIN_STEP_MS`] and [`MAX_STEP_MS`], or [`EXTRA_SLOW_STEP_MS`].
+    pub step_ms: u32,
+    /// Whether the cells the head will cross over the next few steps are
+    /// marked, see [`crate::path_preview`].
+    pub path_preview: bool,
+    /// How hard gamepads rumble, from 0 for off up to 1.
+    pub rumble: f32,
+    /// Volume of each [`Bus`], from 0 for off up to 1.
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub ui_volume: f32,
+    /// Whether phones and tablets vibrate on turns, food and deaths.
+    pub haptics: bool,
+    /// Whether turns, the score and where the food is are read out, see
+    /// [`crate::announce`].
+    pub announcements: bool,
+    /// Whether the snake is turned aside from a crash it's about to make,
+    /// see [`crate::assist`].
+    pub auto_turn: bool,
+    /// Whether the autopilot bot script steers the player's snake, see
+    /// [`crate::brain`].
+    pub autopilot: bool,
+    /// Mod packs switched off on the mods screen, by file name. Packs are
+    /// on unless they're listed here.
+    pub disabled_mods: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            ui_scale: 1.0,
+            touch_layout: TouchLayout::default(),
+            control_scheme: ControlScheme::default(),
+            render_style: RenderStyle::default(),
+            grid_shape: GridShape::default(),
+            weather: Weather::default(),
+            reduced_motion: false,
+            friendly_fire: false,
+            self_collision: true,
+            border_walls: false,
+            step_ms: DEFAULT_STEP_MS,
+            path_preview: false,
+            rumble: 0.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            ui_volume: 1.0,
+            haptics: true,
+            announcements: false,
+            auto_turn: false,
+            autopilot: false,
+            disabled_mods: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load(SETTINGS_PATH)
+    }
+
+    /// Changes the UI scale by `steps` increments of [`UI_SCALE_STEP`].
+    pub fn adjust_ui_scale(&mut self, steps: f32) {
+        self.ui_scale = (self.ui_scale + steps * UI_SCALE_STEP).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    }
+
+    /// Changes the step interval by `steps` increments of [`STEP_MS_STEP`];
+    /// positive steps slow the snake down. One step past [`MAX_STEP_MS`] is
+    /// the extra-slow tier.
+    pub fn adjust_step_ms(&mut self, steps: i32) {
+        let notch = self.step_ms.min(MAX_STEP_MS + STEP_MS_STEP);
+        let stepped = notch
+            .saturating_add_signed(steps * STEP_MS_STEP as i32)
+            .clamp(MIN_STEP_MS, MAX_STEP_MS + STEP_MS_STEP);
+        self.step_ms = if stepped > MAX_STEP_MS {
+            EXTRA_SLOW_STEP_MS
+        } else {
+            stepped
+        };
+    }
+
+    /// Changes the rumble strength by `steps` increments of [`RUMBLE_STEP`].
+    pub fn adjust_rumble(&mut self, steps: f32) {
+        self.rumble = (self.rumble + steps * RUMBLE_STEP).clamp(0.0, 1.0);
+    }
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Music => self.music_volume,
+            Bus::Sfx => self.sfx_volume,
+            Bus::Ui => self.ui_volume,
+        }
+    }
+
+    /// Changes the volume of `bus` by `steps` increments of [`VOLUME_STEP`].
+    pub fn adjust_volume(&mut self, bus: Bus, steps: f32) {
+        let volume = match bus {
+            Bus::Music => &mut self.music_volume,
+            Bus::Sfx => &mut self.sfx_volume,
+            Bus::Ui => &mut self.ui_volume,
+        };
+        // Rounded, so repeated steps don't drift off the steps.
+        let stepped = (*volume / VOLUME_STEP).round() + steps;
+        *volume = (stepped * VOLUME_STEP).clamp(0.0, 1.0);
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_PATH, self);
+    }
+}
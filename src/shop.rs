@@ -0,0 +1,469 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cosmetics::CosmeticsPlugin;
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::powerup::Ram;
+use crate::profile::Profile;
+use crate::replay;
+use crate::rules::Rules;
+use crate::team::teams_enabled;
+use crate::{
+    AppState, GameMode, GameSet, GameState, Player, RestartEvent, RunStarted, SNAKE_HEAD_COLOR,
+    SNAKE_SEGMENT_COLOR, SnakeColors, SnakeDied, SnakeSegments,
+};
+
+/// Picks an item by its position in [`ITEMS`], with 0 for the tenth.
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+/// Colors of the player's snake.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Skin {
+    #[default]
+    Classic,
+    Emerald,
+    Sunset,
+    Frost,
+    /// A body that cycles through the hues, see [`crate::body_color`].
+    Rainbow,
+    /// Colors picked on the cosmetics screen, see [`crate::cosmetics`].
+    Custom,
+}
+
+impl Skin {
+    /// The skins whose colors can be copied as a starting point for
+    /// [`Skin::Custom`].
+    pub const PRESETS: [Self; 4] = [Self::Classic, Self::Emerald, Self::Sunset, Self::Frost];
+
+    /// Head and body colors, with `custom` for [`Skin::Custom`].
+    pub fn colors(self, custom: &CustomSkin) -> (Color, Color) {
+        match self {
+            Self::Classic => (SNAKE_HEAD_COLOR, SNAKE_SEGMENT_COLOR),
+            Self::Emerald => (Color::srgb(0.5, 0.95, 0.6), Color::srgb(0.1, 0.5, 0.25)),
+            Self::Sunset => (Color::srgb(1.0, 0.75, 0.4), Color::srgb(0.75, 0.3, 0.2)),
+            Self::Frost => (Color::srgb(0.85, 0.95, 1.0), Color::srgb(0.35, 0.55, 0.75)),
+            Self::Rainbow => (Color::srgb(0.95, 0.95, 0.95), Color::hsl(0.0, 0.85, 0.55)),
+            Self::Custom => (custom.head.color(), custom.body.color()),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "skin.classic",
+            Self::Emerald => "skin.emerald",
+            Self::Sunset => "skin.sunset",
+            Self::Frost => "skin.frost",
+            Self::Rainbow => "skin.rainbow",
+            Self::Custom => "skin.custom",
+        }
+    }
+}
+
+/// A color as the cosmetics screen edits it: hue in degrees, saturation
+/// and value from 0 to 1.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl Hsv {
+    pub fn from_color(color: Color) -> Self {
+        let hsva = Hsva::from(color);
+        Self {
+            hue: hsva.hue,
+            saturation: hsva.saturation,
+            value: hsva.value,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        Color::hsv(self.hue, self.saturation, self.value)
+    }
+}
+
+/// Head and body colors of [`Skin::Custom`], kept in the profile.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CustomSkin {
+    pub head: Hsv,
+    pub body: Hsv,
+}
+
+impl Default for CustomSkin {
+    fn default() -> Self {
+        Self {
+            head: Hsv::from_color(SNAKE_HEAD_COLOR),
+            body: Hsv::from_color(SNAKE_SEGMENT_COLOR),
+        }
+    }
+}
+
+/// Background color behind the arena, and the color of the board's floor
+/// where the render style draws one.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Dusk,
+    Forest,
+    Light,
+    Sepia,
+    /// Pure black, which OLED screens show by switching the pixels off.
+    Oled,
+}
+
+impl Theme {
+    pub const ALL: [Self; 6] = [
+        Self::Classic,
+        Self::Dusk,
+        Self::Forest,
+        Self::Light,
+        Self::Sepia,
+        Self::Oled,
+    ];
+
+    pub fn sky(self) -> Color {
+        match self {
+            Self::Classic => Color::srgb(0.04, 0.04, 0.04),
+            Self::Dusk => Color::srgb(0.12, 0.06, 0.14),
+            Self::Forest => Color::srgb(0.04, 0.1, 0.06),
+            Self::Light => Color::srgb(0.86, 0.86, 0.83),
+            Self::Sepia => Color::srgb(0.42, 0.34, 0.24),
+            Self::Oled => Color::BLACK,
+        }
+    }
+
+    pub fn floor(self) -> Color {
+        match self {
+            Self::Classic => Color::srgb(0.1, 0.1, 0.12),
+            Self::Dusk => Color::srgb(0.2, 0.12, 0.22),
+            Self::Forest => Color::srgb(0.1, 0.18, 0.12),
+            Self::Light => Color::srgb(0.74, 0.74, 0.71),
+            Self::Sepia => Color::srgb(0.54, 0.45, 0.33),
+            Self::Oled => Color::srgb(0.06, 0.06, 0.06),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "theme.classic",
+            Self::Dusk => "theme.dusk",
+            Self::Forest => "theme.forest",
+            Self::Light => "theme.light",
+            Self::Sepia => "theme.sepia",
+            Self::Oled => "theme.oled",
+        }
+    }
+
+    /// Whether the player can pick this theme: it's free, bought, or not
+    /// sold in the shop at all.
+    pub fn available(self, profile: &Profile) -> bool {
+        ITEMS
+            .iter()
+            .find(|(item, _)| *item == ShopItem::Theme(self))
+            .is_none_or(|(item, price)| *price == 0 || profile.owns(*item))
+    }
+}
+
+/// What an endless run starts with.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Loadout {
+    #[default]
+    Empty,
+    Ram,
+}
+
+impl Loadout {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Empty => "loadout.empty",
+            Self::Ram => "loadout.ram",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShopItem {
+    Skin(Skin),
+    Theme(Theme),
+    Loadout(Loadout),
+}
+
+/// Everything on sale, in the order it's listed. The defaults are free, so
+/// they can always be switched back to.
+const ITEMS: [(ShopItem, u32); 10] = [
+    (ShopItem::Skin(Skin::Classic), 0),
+    (ShopItem::Skin(Skin::Emerald), 50),
+    (ShopItem::Skin(Skin::Sunset), 50),
+    (ShopItem::Skin(Skin::Frost), 50),
+    (ShopItem::Skin(Skin::Rainbow), 150),
+    (ShopItem::Theme(Theme::Classic), 0),
+    (ShopItem::Theme(Theme::Dusk), 80),
+    (ShopItem::Theme(Theme::Forest), 80),
+    (ShopItem::Loadout(Loadout::Empty), 0),
+    (ShopItem::Loadout(Loadout::Ram), 120),
+];
+
+impl ShopItem {
+    fn describe(self, locale: &Locale) -> String {
+        let (kind, name) = match self {
+            Self::Skin(skin) => ("shop.skin", skin.name()),
+            Self::Theme(theme) => ("shop.theme", theme.name()),
+            Self::Loadout(loadout) => ("shop.loadout", loadout.name()),
+        };
+        locale.format(kind, &[("name", &locale.get(name))])
+    }
+}
+
+#[derive(Component)]
+struct ShopRoot;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(CosmeticsPlugin)
+            .add_systems(OnEnter(AppState::Shop), setup_shop)
+            .add_systems(
+                Update,
+                (
+                    shop_input.after(navigate_focus),
+                    refresh_shop
+                        .after(shop_input)
+                        .run_if(resource_changed::<Profile>),
+                )
+                    .run_if(in_state(AppState::Shop)),
+            )
+            .add_systems(
+                OnExit(AppState::Playing),
+                award_coins_on_exit.run_if(replay::not_watching),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                equip_loadout.run_if(resource_equals(GameMode::Endless)),
+            )
+            .add_systems(
+                Update,
+                (
+                    award_coins_on_respawn
+                        .after(GameSet::Spawning)
+                        .run_if(replay::not_watching),
+                    reequip_loadout_on_respawn,
+                    apply_skin.run_if(not(teams_enabled)),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless)),
+            );
+    }
+}
+
+/// One coin per point.
+fn award_coins(score: u32, profile: &mut Profile) {
+    if score > 0 {
+        profile.coins += score;
+        profile.save();
+    }
+}
+
+fn award_coins_on_respawn(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    mut profile: ResMut<Profile>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    if died || restarted {
+        award_coins(game_state.last_score, &mut profile);
+    }
+}
+
+fn award_coins_on_exit(
+    mode: Res<GameMode>,
+    game_state: Res<GameState>,
+    mut profile: ResMut<Profile>,
+) {
+    if *mode == GameMode::Endless {
+        award_coins(game_state.score, &mut profile);
+    }
+}
+
+fn equip_loadout(profile: Res<Profile>, mut ram: ResMut<Ram>) {
+    if profile.loadout == Loadout::Ram {
+        *ram = Ram::charged();
+    }
+}
+
+fn reequip_loadout_on_respawn(
+    mut run_reader: MessageReader<RunStarted>,
+    profile: Res<Profile>,
+    ram: ResMut<Ram>,
+) {
+    if run_reader.read().next().is_some() {
+        equip_loadout(profile, ram);
+    }
+}
+
+/// Dresses the player's snake in the equipped skin. Co-op snakes keep their
+/// own colors, to tell the players apart.
+fn apply_skin(
+    profile: Res<Profile>,
+    rules: Res<Rules>,
+    mut heads: Query<(&Player, &SnakeSegments, &mut SnakeColors), Added<Player>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    if rules.coop {
+        return;
+    }
+    let (head, body) = profile.skin.colors(&profile.custom_skin);
+    for (player, segments, mut colors) in heads.iter_mut() {
+        if player.0 == 0 {
+            *colors = SnakeColors { head, body };
+            colors.paint(segments, &mut sprites);
+        }
+    }
+}
+
+fn setup_shop(mut commands: Commands, locale: Res<Locale>, profile: Res<Profile>) {
+    let coins = locale.format("shop.coins", &[("coins", &profile.coins)]);
+    let items = ITEMS.iter().enumerate().map(|(index, (item, price))| {
+        let status = if profile.equipped(*item) {
+            locale.get("shop.equipped").to_string()
+        } else if profile.owns(*item) || *price == 0 {
+            locale.get("shop.owned").to_string()
+        } else {
+            locale.format("shop.price", &[("price", price)])
+        };
+        locale.format(
+            "shop.item",
+            &[
+                ("number", &((index + 1) % DIGIT_KEYS.len())),
+                ("name", &item.describe(&locale)),
+                ("status", &status),
+            ],
+        )
+    });
+    let custom = if profile.skin == Skin::Custom {
+        "shop.equipped"
+    } else {
+        "shop.owned"
+    };
+    let custom = locale.format("shop.custom", &[("status", &locale.get(custom))]);
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ShopRoot,
+            DespawnOnExit(AppState::Shop),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("shop.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn(shop_line(coins));
+            for (index, line) in items.enumerate() {
+                parent.spawn((shop_line(line), Focusable::new(index)));
+            }
+            parent.spawn((shop_line(custom), Focusable::new(ITEMS.len())));
+            parent.spawn(shop_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn shop_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_shop(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    profile: Res<Profile>,
+    roots: Query<Entity, With<ShopRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_shop(commands, locale, profile);
+}
+
+/// A digit or Enter on an item equips it, buying it first if need be. C or
+/// the line after the items opens the cosmetics screen. Escape goes back to
+/// the menu.
+fn shop_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut input_reader: MessageReader<FocusInput>,
+    mut profile: ResMut<Profile>,
+    mods: Res<Mods>,
+    mut sky: ResMut<ClearColor>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut picked = DIGIT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Activate(order) => picked = Some(order),
+            FocusInput::Nudge(..) => {}
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyC) || picked == Some(ITEMS.len()) {
+        next_state.set(AppState::Cosmetics);
+        return;
+    }
+    let Some((item, price)) = picked.and_then(|index| ITEMS.get(index).copied()) else {
+        return;
+    };
+    if !profile.owns(item) && price > 0 {
+        if profile.coins < price {
+            return;
+        }
+        profile.coins -= price;
+        profile.owned.push(item);
+    }
+    match item {
+        ShopItem::Skin(skin) => profile.skin = skin,
+        ShopItem::Theme(theme) => {
+            profile.theme = theme;
+            sky.0 = mods.sky(theme);
+        }
+        ShopItem::Loadout(loadout) => profile.loadout = loadout,
+    }
+    profile.save();
+}
@@ -0,0 +1,189 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::focus::{FocusInput, navigate_focus};
+use crate::grid::WindowLayout;
+use crate::hazard::HazardHit;
+use crate::settings::Settings;
+use crate::{AppState, PlayerMeals, Position, attract};
+
+/// A short, bright blip.
+const FOOD_PITCH: f32 = 660.0;
+const FOOD_LENGTH: Duration = Duration::from_millis(70);
+/// A low buzz, longer than the wall's bonk.
+const HAZARD_PITCH: f32 = 82.0;
+const HAZARD_LENGTH: Duration = Duration::from_millis(260);
+/// A soft tick for picking and changing menu lines.
+const CLICK_PITCH: f32 = 1200.0;
+const CLICK_LENGTH: Duration = Duration::from_millis(20);
+/// Distance between the listener's ears. Sounds are placed between them, so
+/// they're never further than this from either ear and only the balance
+/// between the two changes, not the volume.
+const EAR_GAP: f32 = 1.0;
+
+/// Which volume setting a sound follows.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    /// Everything happening on the board.
+    Sfx,
+    /// Menus and other screens.
+    Ui,
+}
+
+impl Bus {
+    pub const ALL: [Self; 3] = [Self::Music, Self::Sfx, Self::Ui];
+
+    /// Locale key of the bus's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Music => "bus.music",
+            Self::Sfx => "bus.sfx",
+            Self::Ui => "bus.ui",
+        }
+    }
+
+    /// Plays a sound once at this bus's volume and despawns it afterwards.
+    /// Spawn it along with the bus itself, so the sound follows later
+    /// changes to the volume too.
+    pub fn play_once(self, settings: &Settings) -> PlaybackSettings {
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(settings.volume(self)))
+    }
+}
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_listener).add_systems(
+            Update,
+            (
+                (play_food_sound, play_hazard_sound)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(attract::not_demo),
+                play_ui_sound.after(navigate_focus),
+                apply_volumes.run_if(resource_changed::<Settings>),
+            ),
+        );
+    }
+}
+
+/// Turns sounds that are already playing up or down along with their bus.
+fn apply_volumes(
+    settings: Res<Settings>,
+    mut sinks: Query<(&Bus, &mut AudioSink)>,
+    mut spatial_sinks: Query<(&Bus, &mut SpatialAudioSink)>,
+) {
+    for (bus, mut sink) in sinks.iter_mut() {
+        sink.set_volume(Volume::Linear(settings.volume(*bus)));
+    }
+    for (bus, mut sink) in spatial_sinks.iter_mut() {
+        sink.set_volume(Volume::Linear(settings.volume(*bus)));
+    }
+}
+
+/// Sits at the origin for good; where a sound comes from is worked out
+/// against the camera in [`panned`].
+fn spawn_listener(mut commands: Commands) {
+    commands.spawn((SpatialListener::new(EAR_GAP), Transform::default()));
+}
+
+/// Plays a tone from the left or the right depending on where `pos` is
+/// relative to the middle of the view: fully in one ear at the edges of the
+/// arena, in both at the camera's center.
+fn panned(
+    pos: Position,
+    pitch: Pitch,
+    layout: &WindowLayout,
+    arena: &Arena,
+    settings: &Settings,
+    cameras: &Query<&Transform, With<Camera2d>>,
+    pitches: &mut Assets<Pitch>,
+) -> impl Bundle {
+    let pan = layout
+        .get()
+        .map(|layout| {
+            let center = cameras
+                .iter()
+                .next()
+                .map_or(0.0, |camera| camera.translation.x);
+            let half_width = layout.cell_size * arena.width() as f32 / 2.0;
+            ((layout.cell_to_world(pos).x - center) / half_width).clamp(-1.0, 1.0)
+        })
+        .unwrap_or(0.0);
+    (
+        AudioPlayer(pitches.add(pitch)),
+        Bus::Sfx,
+        Bus::Sfx.play_once(settings).with_spatial(true),
+        from_side(pan),
+    )
+}
+
+/// Where a spatial sound sits to be heard from `side`: -1 is all in the left
+/// ear, 1 all in the right and 0 in both alike.
+pub fn from_side(side: f32) -> Transform {
+    Transform::from_xyz(side.clamp(-1.0, 1.0) * EAR_GAP / 2.0, 0.0, 0.0)
+}
+
+fn play_food_sound(
+    mut commands: Commands,
+    mut growth_reader: PlayerMeals,
+    layout: WindowLayout,
+    arena: Res<Arena>,
+    settings: Res<Settings>,
+    cameras: Query<&Transform, With<Camera2d>>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    for eaten in growth_reader.read() {
+        commands.spawn(panned(
+            eaten.position,
+            Pitch::new(FOOD_PITCH, FOOD_LENGTH),
+            &layout,
+            &arena,
+            &settings,
+            &cameras,
+            &mut pitches,
+        ));
+    }
+}
+
+fn play_hazard_sound(
+    mut commands: Commands,
+    mut hit_reader: MessageReader<HazardHit>,
+    layout: WindowLayout,
+    arena: Res<Arena>,
+    settings: Res<Settings>,
+    cameras: Query<&Transform, With<Camera2d>>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    for hit in hit_reader.read() {
+        commands.spawn(panned(
+            hit.position,
+            Pitch::new(HAZARD_PITCH, HAZARD_LENGTH),
+            &layout,
+            &arena,
+            &settings,
+            &cameras,
+            &mut pitches,
+        ));
+    }
+}
+
+/// Clicks whenever a menu line is picked or changed, or a screen is left.
+fn play_ui_sound(
+    mut commands: Commands,
+    mut input_reader: MessageReader<FocusInput>,
+    settings: Res<Settings>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    if input_reader.read().next().is_none() {
+        return;
+    }
+    commands.spawn((
+        AudioPlayer(pitches.add(Pitch::new(CLICK_PITCH, CLICK_LENGTH))),
+        Bus::Ui,
+        Bus::Ui.play_once(&settings),
+    ));
+}
@@ -0,0 +1,340 @@
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{ScalingMode, Viewport};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::arena::Arena;
+use crate::arenas::{ArenaId, ArenasPlugin};
+use crate::items::Items;
+use crate::locale::Locale;
+use crate::powerup::PowerUp;
+use crate::settings::Settings;
+use crate::{
+    Direction, DirectionChangeRequest, Food, GameState, Player, Position, Size, SnakeHead,
+    SnakeSegment,
+};
+
+/// The keys each side steers with: WASD on the left, the arrows on the
+/// right.
+const CONTROLS: [[(KeyCode, Direction); 4]; 2] = [
+    [
+        (KeyCode::KeyA, Direction::Left),
+        (KeyCode::KeyD, Direction::Right),
+        (KeyCode::KeyW, Direction::Up),
+        (KeyCode::KeyS, Direction::Down),
+    ],
+    [
+        (KeyCode::ArrowLeft, Direction::Left),
+        (KeyCode::ArrowRight, Direction::Right),
+        (KeyCode::ArrowUp, Direction::Up),
+        (KeyCode::ArrowDown, Direction::Down),
+    ],
+];
+
+const FLOOR_COLOR: Color = Color::srgb(0.12, 0.12, 0.12);
+
+/// Food and power-ups are drawn a little smaller than a tile.
+const ITEM_SIZE: f32 = 0.8;
+
+/// What there is to see of an arena: its size in tiles, everything on it as
+/// where it is in tiles from the middle, its color and its size, and the
+/// score.
+#[derive(Default)]
+struct Board {
+    size: (u32, u32),
+    cells: Vec<(Vec2, Color, f32)>,
+    score: u32,
+}
+
+/// The [`Board`] of each arena, by its [`ArenaId`], copied out of the arena
+/// before each of its updates.
+#[derive(Resource, Default)]
+struct Boards(Vec<Board>);
+
+/// The camera drawing an arena into its side of the window, with the sprites
+/// it draws the board with, kept from frame to frame.
+#[derive(Component)]
+struct ArenaView {
+    index: usize,
+    sprites: Vec<Entity>,
+}
+
+#[derive(Component)]
+struct ScoreText(usize);
+
+/// Two players side by side on the rules of endless snake, each in an
+/// arena of their own, see [`ArenasPlugin`].
+pub fn main() {
+    app().run();
+}
+
+/// The split-screen game, ready to run.
+pub fn app() -> App {
+    let settings = Settings::load();
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Snake! Split screen".to_string(),
+            resolution: (1600, 800).into(),
+            ..default()
+        }),
+        ..default()
+    }))
+    .insert_resource(Locale::load(&settings.language))
+    .add_plugins(SplitScreenPlugin);
+    app
+}
+
+/// An arena for each side of the window, each drawn by a camera of its own
+/// on a render layer of its own.
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ArenasPlugin {
+            count: CONTROLS.len(),
+        });
+        link_arenas(app);
+        app.add_systems(Startup, spawn_views)
+            .add_systems(Update, (fit_viewports, draw_boards, update_scores));
+    }
+}
+
+/// Hooks the arenas up to the host: each side's keys steer its arena's
+/// snake, and its board is copied out to be drawn.
+fn link_arenas(app: &mut App) {
+    app.insert_resource(Boards(CONTROLS.iter().map(|_| Board::default()).collect()));
+    for (index, controls) in CONTROLS.into_iter().enumerate() {
+        app.sub_app_mut(ArenaId(index))
+            .set_extract(move |host, arena| {
+                steer(host, arena, &controls);
+                copy_board(arena, &mut host.resource_mut::<Boards>().0[index]);
+            });
+    }
+}
+
+fn steer(host: &World, arena: &mut World, controls: &[(KeyCode, Direction)]) {
+    let keys = host.resource::<ButtonInput<KeyCode>>();
+    let Some(&(_, direction)) = controls.iter().find(|(key, _)| keys.just_pressed(*key)) else {
+        return;
+    };
+    let mut heads = arena.query_filtered::<Entity, With<Player>>();
+    if let Ok(snake) = heads.single(arena) {
+        arena.write_message(DirectionChangeRequest { snake, direction });
+    }
+}
+
+fn copy_board(arena: &mut World, board: &mut Board) {
+    let mut food = arena.query::<(&Position, &Food)>();
+    let mut power_ups = arena.query::<(&Position, &PowerUp)>();
+    let mut segments =
+        arena.query_filtered::<(&Position, &Sprite, &Size, Has<SnakeHead>), With<SnakeSegment>>();
+    let layout = arena.resource::<Arena>();
+    let grid = layout.grid();
+    let items = arena.resource::<Items>();
+    board.size = (layout.width(), layout.height());
+    board.score = arena.resource::<GameState>().score;
+    board.cells.clear();
+    board.cells.extend(
+        food.iter(arena)
+            .map(|(pos, food)| (*pos, items.food(*food).color(), ITEM_SIZE))
+            .chain(
+                power_ups
+                    .iter(arena)
+                    .map(|(pos, power_up)| (*pos, items.power_up(*power_up).color(), ITEM_SIZE)),
+            )
+            // Heads last, so they're drawn over the body.
+            .chain(
+                segments
+                    .iter(arena)
+                    .sort_by_key::<Has<SnakeHead>, _>(|head| *head)
+                    .map(|(pos, sprite, size, _)| (*pos, sprite.color, size.width)),
+            )
+            .map(|(pos, color, size)| (grid.to_world(pos, 1.0), color, size)),
+    );
+}
+
+fn spawn_views(mut commands: Commands, boards: Res<Boards>, locale: Res<Locale>) {
+    for index in 0..boards.0.len() {
+        let camera = commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    order: index as isize,
+                    ..default()
+                },
+                RenderLayers::layer(index + 1),
+                ArenaView {
+                    index,
+                    sprites: Vec::new(),
+                },
+            ))
+            .id();
+        commands.spawn((
+            Text::new(locale.format("hud.score", &[("score", &0)])),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            UiTargetCamera(camera),
+            ScoreText(index),
+        ));
+    }
+}
+
+/// Splits the window into a side for each arena.
+fn fit_viewports(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut views: Query<(&ArenaView, &mut Camera)>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let window_size = window.physical_size();
+    let sides = views.iter().len() as u32;
+    let size = UVec2::new(window_size.x / sides.max(1), window_size.y);
+    // Minimized.
+    if size.x == 0 || size.y == 0 {
+        return;
+    }
+    for (view, mut camera) in views.iter_mut() {
+        let position = UVec2::new(view.index as u32 * size.x, 0);
+        if camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == size
+        }) {
+            continue;
+        }
+        camera.viewport = Some(Viewport {
+            physical_position: position,
+            physical_size: size,
+            ..default()
+        });
+    }
+}
+
+/// Draws each board with its camera's sprites, the floor first, and zooms
+/// the camera to fit it.
+fn draw_boards(
+    mut commands: Commands,
+    boards: Res<Boards>,
+    mut views: Query<(&mut ArenaView, &mut Projection)>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut Visibility), Without<ArenaView>>,
+) {
+    for (mut view, mut projection) in views.iter_mut() {
+        let board = &boards.0[view.index];
+        let (width, height) = (board.size.0 as f32, board.size.1 as f32);
+        if let Projection::Orthographic(ortho) = &mut *projection
+            && !matches!(ortho.scaling_mode, ScalingMode::AutoMin { min_width, min_height }
+                if min_width == width + 1.0 && min_height == height + 1.0)
+        {
+            ortho.scaling_mode = ScalingMode::AutoMin {
+                min_width: width + 1.0,
+                min_height: height + 1.0,
+            };
+        }
+
+        let floor = (Vec2::ZERO, FLOOR_COLOR, Vec2::new(width, height));
+        let cells = board
+            .cells
+            .iter()
+            .map(|(position, color, size)| (*position, *color, Vec2::splat(*size)));
+        let layer = RenderLayers::layer(view.index + 1);
+        let mut drawn = 0;
+        for (depth, (position, color, size)) in std::iter::once(floor).chain(cells).enumerate() {
+            let translation = position.extend(depth as f32 * 0.001 - 1.0);
+            if let Some(entity) = view.sprites.get(depth) {
+                if let Ok((mut sprite, mut transform, mut visibility)) = sprites.get_mut(*entity) {
+                    sprite.color = color;
+                    sprite.custom_size = Some(size);
+                    transform.translation = translation;
+                    *visibility = Visibility::Inherited;
+                }
+            } else {
+                let sprite = commands
+                    .spawn((
+                        Sprite::from_color(color, size),
+                        Transform::from_translation(translation),
+                        layer.clone(),
+                    ))
+                    .id();
+                view.sprites.push(sprite);
+            }
+            drawn += 1;
+        }
+        for entity in &view.sprites[drawn..] {
+            if let Ok((_, _, mut visibility)) = sprites.get_mut(*entity) {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
+
+fn update_scores(
+    boards: Res<Boards>,
+    locale: Res<Locale>,
+    mut texts: Query<(&ScoreText, &mut Text)>,
+) {
+    for (score, mut text) in texts.iter_mut() {
+        let line = locale.format("hud.score", &[("score", &boards.0[score.0].score)]);
+        if text.0 != line {
+            text.0 = line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .add_plugins(ArenasPlugin {
+                count: CONTROLS.len(),
+            });
+        link_arenas(&mut app);
+        app.update();
+        app
+    }
+
+    fn heading(app: &mut App, index: usize) -> Direction {
+        let world = app.sub_app_mut(ArenaId(index)).world_mut();
+        let mut heads = world.query_filtered::<&SnakeHead, With<Player>>();
+        heads.single(world).unwrap().direction
+    }
+
+    #[test]
+    fn each_side_steers_its_own_snake() {
+        let mut app = split_app();
+        assert_eq!(heading(&mut app, 0), Direction::Up);
+        assert_eq!(heading(&mut app, 1), Direction::Up);
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::KeyA);
+        keys.press(KeyCode::ArrowRight);
+        app.update();
+        assert_eq!(heading(&mut app, 0), Direction::Left);
+        assert_eq!(heading(&mut app, 1), Direction::Right);
+    }
+
+    #[test]
+    fn every_board_is_copied_out_to_be_drawn() {
+        let mut app = split_app();
+        app.update();
+        let boards = &app.world().resource::<Boards>().0;
+        assert_eq!(boards.len(), 2);
+        for board in boards {
+            let (width, height) = board.size;
+            assert!(width > 0 && height > 0);
+            // The snake, at least.
+            assert!(board.cells.len() >= 2);
+        }
+    }
+}
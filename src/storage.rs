@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Reads a RON save file, falling back to the default value if it is missing
+/// or unreadable.
+pub fn load<T: DeserializeOwned + Default>(path: &str) -> T {
+    read(path)
+        .and_then(|contents| ron::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(path: &str, value: &T) {
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| write(path, &contents));
+    if let Err(err) = result {
+        warn!("Failed to save {}: {}", path, err);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(path: &str, contents: &str) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|err| err.to_string())
+}
+
+/// Paths of the saves in `dir`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list(dir: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .map(|name| format!("{}/{}", dir, name))
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(path: &str) -> Result<(), String> {
+    std::fs::remove_file(path).map_err(|err| err.to_string())
+}
+
+/// Writes `contents` somewhere the player can pick it up from, returning
+/// where it went.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(file_name: &str, contents: &str) -> Result<String, String> {
+    write(file_name, contents)?;
+    Ok(file_name.to_string())
+}
+
+/// Browsers have no filesystem, so saves go to `localStorage` keyed by path.
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read(path: &str) -> Option<String> {
+    local_storage()?.get_item(path).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(path: &str, contents: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or_else(|| "localStorage is unavailable".to_string())?
+        .set_item(path, contents)
+        .map_err(|err| format!("{:?}", err))
+}
+
+/// Keys under `dir/`, standing in for the files of a directory.
+#[cfg(target_arch = "wasm32")]
+pub fn list(dir: &str) -> Vec<String> {
+    let Some(storage) = local_storage() else {
+        return Vec::new();
+    };
+    let prefix = format!("{}/", dir);
+    (0..storage.length().unwrap_or(0))
+        .filter_map(|index| storage.key(index).ok()?)
+        .filter(|key| key.starts_with(&prefix))
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(path: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or_else(|| "localStorage is unavailable".to_string())?
+        .remove_item(path)
+        .map_err(|err| format!("{:?}", err))
+}
+
+/// Hands `contents` to the browser as a download.
+#[cfg(target_arch = "wasm32")]
+pub fn export(file_name: &str, contents: &str) -> Result<String, String> {
+    use web_sys::wasm_bindgen::JsCast;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| "no document".to_string())?;
+    let link = document
+        .create_element("a")
+        .map_err(|err| format!("{:?}", err))?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|err| format!("{:?}", err))?;
+    let data = js_sys::encode_uri_component(contents);
+    link.set_href(&format!("data:application/octet-stream,{}", data));
+    link.set_download(file_name);
+    link.click();
+    Ok(file_name.to_string())
+}
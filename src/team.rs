@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+use crate::rival::Rival;
+use crate::rules::Rules;
+use crate::{AppState, FoodEaten, GameMode, Player, RunStarted, SnakeColors, SnakeSegments};
+
+/// Sides in a team match. The player is always on [`Team::PLAYER`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Team {
+    Blue,
+    Red,
+}
+
+impl Team {
+    pub const ALL: [Self; 2] = [Self::Blue, Self::Red];
+    pub const PLAYER: Self = Self::Blue;
+
+    /// Rivals alternate sides starting with the other team, so the player's
+    /// team is never the bigger one.
+    pub fn for_rival(index: u32) -> Self {
+        if index.is_multiple_of(2) {
+            Self::Red
+        } else {
+            Self::Blue
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Blue => Color::srgb(0.3, 0.55, 1.0),
+            Self::Red => Color::srgb(1.0, 0.35, 0.3),
+        }
+    }
+
+    /// Locale key of the team's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blue => "team.blue",
+            Self::Red => "team.red",
+        }
+    }
+}
+
+/// Food eaten by each team's snakes this round.
+#[derive(Resource, Default)]
+pub struct TeamScores([u32; 2]);
+
+impl TeamScores {
+    pub fn get(&self, team: Team) -> u32 {
+        self.0[team as usize]
+    }
+
+    pub fn add(&mut self, team: Team) {
+        self.0[team as usize] += 1;
+    }
+}
+
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeamScores>()
+            .add_systems(OnEnter(AppState::Playing), reset_scores)
+            .add_systems(
+                Update,
+                (reset_scores_on_respawn, score_growth, team_skin)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_equals(GameMode::Endless))
+                    .run_if(teams_enabled),
+            );
+    }
+}
+
+pub fn teams_enabled(rules: Res<Rules>) -> bool {
+    rules.teams.is_some()
+}
+
+fn reset_scores(mut scores: ResMut<TeamScores>) {
+    *scores = TeamScores::default();
+}
+
+fn reset_scores_on_respawn(
+    mut run_reader: MessageReader<RunStarted>,
+    mut scores: ResMut<TeamScores>,
+) {
+    if run_reader.read().next().is_some() {
+        *scores = TeamScores::default();
+    }
+}
+
+/// Each food eaten counts for the team of the snake that ate it.
+fn score_growth(
+    mut growth_reader: MessageReader<FoodEaten>,
+    mut scores: ResMut<TeamScores>,
+    rivals: Query<&Rival>,
+) {
+    for eaten in growth_reader.read() {
+        let team = rivals
+            .get(eaten.snake)
+            .map_or(Some(Team::PLAYER), Rival::team);
+        if let Some(team) = team {
+            scores.add(team);
+        }
+    }
+}
+
+/// Dresses the player's snake in its team's colors, with a brighter head to
+/// tell it apart from its teammates.
+fn team_skin(
+    mut heads: Query<(&SnakeSegments, &mut SnakeColors), Added<Player>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let color = Team::PLAYER.color();
+    for (segments, mut colors) in heads.iter_mut() {
+        *colors = SnakeColors {
+            head: color.lighter(0.2),
+            body: color.darker(0.25),
+        };
+        colors.paint(segments, &mut sprites);
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use core::time::Duration;
+use serde::Deserialize;
+
+use crate::arena::Arena;
+use crate::key::KeyColor;
+use crate::locale::Locale;
+use crate::{
+    AppState, Direction, DirectionChangeRequest, GameSet, Ground, Player, Position, Size,
+    SnakeHead, apply_direction_changes,
+};
+
+const ICE_COLOR: Color = Color::srgb(0.3, 0.5, 0.65);
+const CONVEYOR_COLOR: Color = Color::srgb(0.35, 0.3, 0.2);
+const CONVEYOR_ARROW_COLOR: Color = Color::srgb(0.6, 0.5, 0.3);
+const MUD_COLOR: Color = Color::srgb(0.22, 0.15, 0.08);
+const WALL_COLOR: Color = Color::srgb(0.55, 0.55, 0.6);
+const CRACKED_WALL_COLOR: Color = Color::srgb(0.45, 0.38, 0.36);
+
+const DEBRIS_LIFETIME: Duration = Duration::from_millis(600);
+/// Tiles per second.
+const DEBRIS_SPEED: f32 = 1.5;
+
+/// A special floor tile placed by level data.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Tile {
+    /// The snake can't turn until it slides off.
+    Ice,
+    /// Pushes the snake one extra cell in this direction after every step
+    /// that ends on it.
+    Conveyor(Direction),
+    /// The snake only moves on every other step while its head is in it.
+    Mud,
+    /// Deadly to run into, unless the snake is ramming. Each rammed step
+    /// takes a hit point; the last one brings it down.
+    Wall { hp: u8 },
+    /// A wall that goes away once the key of its color is picked up.
+    Door(KeyColor),
+}
+
+impl Tile {
+    pub fn color(self) -> Color {
+        match self {
+            Self::Ice => ICE_COLOR,
+            Self::Conveyor(_) => CONVEYOR_COLOR,
+            Self::Mud => MUD_COLOR,
+            Self::Wall { hp: 1 } => CRACKED_WALL_COLOR,
+            Self::Wall { .. } => WALL_COLOR,
+            Self::Door(key) => key.color().darker(0.3),
+        }
+    }
+
+    /// Drawn by the ASCII render style.
+    pub fn glyph(self) -> char {
+        match self {
+            Self::Ice => '~',
+            Self::Conveyor(direction) => match direction {
+                Direction::Left => '<',
+                Direction::Right => '>',
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::UpRight | Direction::DownLeft => '/',
+                Direction::UpLeft | Direction::DownRight => '\\',
+            },
+            Self::Mud => '%',
+            Self::Wall { .. } => '#',
+            Self::Door(_) => '=',
+        }
+    }
+}
+
+/// A rectangle of one tile kind, corners inclusive.
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct TerrainArea {
+    pub tile: Tile,
+    pub min: Position,
+    pub max: Position,
+}
+
+/// The current level's special tiles. Replacing it respawns the tile
+/// entities; endless runs have none.
+#[derive(Resource, Default, Clone)]
+pub struct Terrain(HashMap<Position, Tile>);
+
+impl Terrain {
+    pub fn new(areas: &[TerrainArea]) -> Self {
+        let mut tiles = HashMap::new();
+        for area in areas {
+            for x in area.min.x..=area.max.x {
+                for y in area.min.y..=area.max.y {
+                    tiles.insert(Position { x, y }, area.tile);
+                }
+            }
+        }
+        Self(tiles)
+    }
+
+    pub fn at(&self, pos: Position) -> Option<Tile> {
+        self.0.get(&pos).copied()
+    }
+
+    pub fn set(&mut self, pos: Position, tile: Tile) {
+        self.0.insert(pos, tile);
+    }
+
+    /// Whether `pos` can't be entered: walls, and doors that are still shut.
+    pub fn blocks(&self, pos: Position) -> bool {
+        matches!(self.at(pos), Some(Tile::Wall { .. } | Tile::Door(_)))
+    }
+
+    /// Every cell that [`Terrain::blocks`].
+    pub fn blocked(&self) -> impl Iterator<Item = Position> + '_ {
+        self.0.keys().copied().filter(|pos| self.blocks(*pos))
+    }
+
+    /// Knocks a hit point off the wall at `pos`, removing it when none are
+    /// left. Returns true if the wall came down.
+    pub fn damage(&mut self, pos: Position) -> bool {
+        match self.0.get_mut(&pos) {
+            Some(Tile::Wall { hp }) if *hp > 1 => {
+                *hp -= 1;
+                false
+            }
+            Some(Tile::Wall { .. }) => {
+                self.0.remove(&pos);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes every door of the given color.
+    pub fn open(&mut self, key: KeyColor) {
+        self.0.retain(|_, tile| *tile != Tile::Door(key));
+    }
+}
+
+/// A wall was rammed down at this position.
+#[derive(Message)]
+pub struct WallCrumbled(pub Position);
+
+/// The remains of a crumbled wall, cleared away once they've faded.
+#[derive(Component)]
+struct Debris(Timer);
+
+/// A piece of [`Debris`], drifting away from where the wall stood, in tiles
+/// per second.
+#[derive(Component)]
+struct DebrisChunk(Vec2);
+
+#[derive(Component)]
+struct TerrainTile;
+
+/// A turn made on ice, held back until the snake is off it. Kept on the
+/// head.
+#[derive(Component, Default, Clone, Copy)]
+pub struct IceSlide {
+    pending: Option<Direction>,
+}
+
+/// The ice hint is shown once per session.
+#[derive(Resource, Default)]
+pub(crate) struct IceHintShown(bool);
+
+#[derive(Component)]
+struct IceHint(Timer);
+
+/// Whether the last step was lost to mud, so the next one goes through.
+/// Kept on the head.
+#[derive(Component, Default, Clone, Copy)]
+pub struct MudDrag {
+    stuck: bool,
+}
+
+impl MudDrag {
+    /// Returns true if a step taken with the head `in_mud` should be skipped.
+    pub fn holds(&mut self, in_mud: bool) -> bool {
+        self.stuck = in_mud && !self.stuck;
+        self.stuck
+    }
+}
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Terrain>()
+            .init_resource::<IceHintShown>()
+            .add_message::<WallCrumbled>()
+            .add_systems(OnExit(AppState::Playing), clear_terrain)
+            .add_systems(
+                Update,
+                (
+                    spawn_terrain_tiles.run_if(resource_changed::<Terrain>),
+                    (release_ice_turns, hold_course_on_ice)
+                        .chain()
+                        .in_set(GameSet::Steering)
+                        .before(apply_direction_changes),
+                    dismiss_ice_hint,
+                    spawn_debris.after(GameSet::Movement),
+                    animate_debris,
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn clear_terrain(mut terrain: ResMut<Terrain>) {
+    *terrain = Terrain::default();
+}
+
+fn spawn_terrain_tiles(
+    mut commands: Commands,
+    terrain: Res<Terrain>,
+    arena: Res<Arena>,
+    tiles: Query<Entity, With<TerrainTile>>,
+) {
+    for entity in tiles.iter() {
+        commands.entity(entity).despawn();
+    }
+    let grid = arena.grid();
+    for (position, tile) in terrain.0.iter() {
+        let mut entity = commands.spawn((
+            Sprite::from_color(tile.color(), Vec2::ONE),
+            Transform::default(),
+            *position,
+            Size::square(1.0),
+            Ground,
+            TerrainTile,
+            DespawnOnExit(AppState::Playing),
+        ));
+        if let Tile::Conveyor(direction) = tile {
+            // A notch on the side the belt pushes towards.
+            let arrow = (grid.to_world(grid.step(*position, *direction), 1.0)
+                - grid.to_world(*position, 1.0))
+            .normalize();
+            entity.with_child((
+                Sprite::from_color(CONVEYOR_ARROW_COLOR, Vec2::splat(0.3)),
+                Transform::from_translation((arrow * 0.3).extend(0.1)),
+            ));
+        }
+    }
+}
+
+/// Keeps every snake heading the way it slid onto the ice: a turn asked for
+/// while on it is held back, by counting the snake as having turned already.
+pub(crate) fn hold_course_on_ice(
+    mut commands: Commands,
+    mut request_reader: MessageReader<DirectionChangeRequest>,
+    terrain: Res<Terrain>,
+    arena: Res<Arena>,
+    locale: Res<Locale>,
+    mut hint_shown: ResMut<IceHintShown>,
+    mut heads: Query<(&mut SnakeHead, &mut IceSlide, &Position, Has<Player>)>,
+) {
+    let grid = arena.grid();
+    for request in request_reader.read() {
+        let Ok((head, mut slide, head_pos, player)) = heads.get_mut(request.snake) else {
+            continue;
+        };
+        if terrain.at(*head_pos) != Some(Tile::Ice)
+            || grid.steer(head.direction, request.direction) == head.direction
+        {
+            continue;
+        }
+        slide.pending = Some(request.direction);
+        if player && !hint_shown.0 {
+            hint_shown.0 = true;
+            spawn_ice_hint(&mut commands, &locale);
+        }
+    }
+    for (mut head, _, head_pos, _) in heads.iter_mut() {
+        if terrain.at(*head_pos) == Some(Tile::Ice) {
+            head.turned = true;
+        }
+    }
+}
+
+/// A turn held back on the ice is asked for again on the first step after
+/// leaving it. Asked after the snake's own steering, so a direction picked
+/// since wins.
+pub(crate) fn release_ice_turns(
+    mut request_writer: MessageWriter<DirectionChangeRequest>,
+    terrain: Res<Terrain>,
+    mut heads: Query<(Entity, &mut IceSlide, &Position)>,
+) {
+    for (snake, mut slide, head_pos) in heads.iter_mut() {
+        if terrain.at(*head_pos) == Some(Tile::Ice) {
+            continue;
+        }
+        if let Some(direction) = slide.pending.take() {
+            request_writer.write(DirectionChangeRequest { snake, direction });
+        }
+    }
+}
+
+fn spawn_ice_hint(commands: &mut Commands, locale: &Locale) {
+    commands.spawn((
+        Text::new(locale.get("terrain.ice_hint")),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(ICE_COLOR.lighter(0.3)),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        IceHint(Timer::new(Duration::from_secs(3), TimerMode::Once)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn dismiss_ice_hint(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hints: Query<(Entity, &mut IceHint)>,
+) {
+    for (entity, mut hint) in hints.iter_mut() {
+        if hint.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Scatters a few chunks from the cell the wall stood on. The chunks are
+/// children of an anchor placed like any board entity, so they move in tiles.
+fn spawn_debris(mut commands: Commands, mut reader: MessageReader<WallCrumbled>) {
+    for WallCrumbled(position) in reader.read() {
+        commands
+            .spawn((
+                Transform::default(),
+                Visibility::default(),
+                *position,
+                Size::square(1.0),
+                Ground,
+                Debris(Timer::new(DEBRIS_LIFETIME, TimerMode::Once)),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .with_children(|anchor| {
+                for corner in [
+                    Vec2::ONE,
+                    Vec2::NEG_ONE,
+                    Vec2::X - Vec2::Y,
+                    Vec2::Y - Vec2::X,
+                ] {
+                    anchor.spawn((
+                        Sprite::from_color(CRACKED_WALL_COLOR, Vec2::splat(0.3)),
+                        Transform::from_translation((corner * 0.2).extend(0.5)),
+                        DebrisChunk(corner * DEBRIS_SPEED),
+                    ));
+                }
+            });
+    }
+}
+
+fn animate_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris: Query<(Entity, &mut Debris)>,
+    mut chunks: Query<(&ChildOf, &DebrisChunk, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut debris) in debris.iter_mut() {
+        if debris.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (parent, chunk, mut transform, mut sprite) in chunks.iter_mut() {
+        let Ok((_, debris)) = debris.get(parent.parent()) else {
+            continue;
+        };
+        transform.translation += (chunk.0 * time.delta_secs()).extend(0.0);
+        sprite.color.set_alpha(debris.0.fraction_remaining());
+    }
+}
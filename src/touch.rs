@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+
+use crate::grid::GridShape;
+use crate::settings::{Settings, TouchLayout};
+use crate::{AppState, Direction};
+
+const BUTTON_SIZE: f32 = 80.0;
+const BUTTON_MARGIN: f32 = 10.0;
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.15);
+const BUTTON_PRESSED_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+
+/// A direction picked with an on-screen button, handled alongside the arrow
+/// keys.
+#[derive(Message)]
+pub struct TouchDirection(pub Direction);
+
+/// Whether the on-screen buttons are shown. Turned on for mobile builds and as
+/// soon as a touch is seen anywhere else.
+#[derive(Resource)]
+pub struct TouchControls {
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct TouchButton(Direction);
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TouchControls {
+            enabled: cfg!(any(target_os = "android", target_os = "ios")),
+        })
+        .add_message::<TouchDirection>()
+        .add_systems(Update, detect_touch)
+        .add_systems(
+            Update,
+            (
+                spawn_touch_buttons.run_if(touch_buttons_missing),
+                touch_button_input,
+            )
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+fn detect_touch(touches: Res<Touches>, mut controls: ResMut<TouchControls>) {
+    if !controls.enabled && touches.any_just_pressed() {
+        controls.enabled = true;
+    }
+}
+
+/// Whether the player is on a touch screen, as far as the game can tell.
+pub fn touch_enabled(controls: Res<TouchControls>) -> bool {
+    controls.enabled
+}
+
+fn touch_buttons_missing(
+    controls: Res<TouchControls>,
+    buttons: Query<(), With<TouchButton>>,
+) -> bool {
+    controls.enabled && buttons.is_empty()
+}
+
+fn edge_node(direction: Direction) -> Node {
+    let centered = Val::Px(-BUTTON_SIZE / 2.0);
+    match direction {
+        Direction::Left => Node {
+            left: Val::Px(BUTTON_MARGIN),
+            top: Val::Percent(50.0),
+            margin: UiRect::top(centered),
+            ..default()
+        },
+        Direction::Right => Node {
+            right: Val::Px(BUTTON_MARGIN),
+            top: Val::Percent(50.0),
+            margin: UiRect::top(centered),
+            ..default()
+        },
+        Direction::Up => Node {
+            top: Val::Px(BUTTON_MARGIN),
+            left: Val::Percent(50.0),
+            margin: UiRect::left(centered),
+            ..default()
+        },
+        Direction::Down => Node {
+            bottom: Val::Px(BUTTON_MARGIN),
+            left: Val::Percent(50.0),
+            margin: UiRect::left(centered),
+            ..default()
+        },
+        Direction::UpLeft => Node {
+            top: Val::Px(BUTTON_MARGIN),
+            left: Val::Px(BUTTON_MARGIN),
+            ..default()
+        },
+        Direction::UpRight => Node {
+            top: Val::Px(BUTTON_MARGIN),
+            right: Val::Px(BUTTON_MARGIN),
+            ..default()
+        },
+        Direction::DownLeft => Node {
+            bottom: Val::Px(BUTTON_MARGIN),
+            left: Val::Px(BUTTON_MARGIN),
+            ..default()
+        },
+        Direction::DownRight => Node {
+            bottom: Val::Px(BUTTON_MARGIN),
+            right: Val::Px(BUTTON_MARGIN),
+            ..default()
+        },
+    }
+}
+
+/// Places the button on a 3x3 d-pad in the bottom corner on the side of the
+/// player's dominant hand.
+fn dpad_node(layout: TouchLayout, direction: Direction) -> Node {
+    let (column, row) = match direction {
+        Direction::Left => (0.0, 1.0),
+        Direction::Up => (1.0, 2.0),
+        Direction::Right => (2.0, 1.0),
+        Direction::Down => (1.0, 0.0),
+        Direction::UpLeft => (0.0, 2.0),
+        Direction::UpRight => (2.0, 2.0),
+        Direction::DownLeft => (0.0, 0.0),
+        Direction::DownRight => (2.0, 0.0),
+    };
+    let step = BUTTON_SIZE + BUTTON_MARGIN;
+    let bottom = Val::Px(BUTTON_MARGIN + row * step);
+    if layout == TouchLayout::LeftHanded {
+        Node {
+            left: Val::Px(BUTTON_MARGIN + column * step),
+            bottom,
+            ..default()
+        }
+    } else {
+        Node {
+            right: Val::Px(BUTTON_MARGIN + (2.0 - column) * step),
+            bottom,
+            ..default()
+        }
+    }
+}
+
+fn button_node(layout: TouchLayout, direction: Direction) -> Node {
+    let mut node = match layout {
+        TouchLayout::Edges => edge_node(direction),
+        TouchLayout::LeftHanded | TouchLayout::RightHanded => dpad_node(layout, direction),
+    };
+    node.position_type = PositionType::Absolute;
+    node.width = Val::Px(BUTTON_SIZE);
+    node.height = Val::Px(BUTTON_SIZE);
+    node.align_items = AlignItems::Center;
+    node.justify_content = JustifyContent::Center;
+    node
+}
+
+fn spawn_touch_buttons(mut commands: Commands, settings: Res<Settings>, shape: Res<GridShape>) {
+    let diagonals = [
+        (Direction::UpLeft, "\u{2196}"),
+        (Direction::UpRight, "\u{2197}"),
+        (Direction::DownRight, "\u{2198}"),
+        (Direction::DownLeft, "\u{2199}"),
+    ];
+    let buttons = [
+        (Direction::Left, "\u{2190}"),
+        (Direction::Up, "\u{2191}"),
+        (Direction::Right, "\u{2192}"),
+        (Direction::Down, "\u{2193}"),
+    ]
+    .into_iter()
+    .chain(
+        diagonals
+            .into_iter()
+            .filter(|_| *shape != GridShape::Square),
+    );
+    for (direction, glyph) in buttons {
+        commands
+            .spawn((
+                Button,
+                button_node(settings.touch_layout, direction),
+                BackgroundColor(BUTTON_COLOR),
+                BorderRadius::all(Val::Px(12.0)),
+                TouchButton(direction),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .with_child((
+                Text::new(glyph),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.6)),
+            ));
+    }
+}
+
+fn touch_button_input(
+    mut writer: MessageWriter<TouchDirection>,
+    mut buttons: Query<(&Interaction, &TouchButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        if *interaction == Interaction::Pressed {
+            writer.write(TouchDirection(button.0));
+            color.0 = BUTTON_PRESSED_COLOR;
+        } else {
+            color.0 = BUTTON_COLOR;
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::arena::sync_arena;
+use crate::calendar;
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::grid::GridShape;
+use crate::items::Items;
+use crate::locale::Locale;
+use crate::mods::Mods;
+use crate::profile::Profile;
+use crate::replay::{FNV_OFFSET, Replay, fnv};
+use crate::rules::Rules;
+use crate::{
+    AppState, GameMode, GameRng, GameSet, GameState, RestartEvent, SnakeDied, new_run_seed,
+    select_grid_shape,
+};
+
+/// Longest seed that can be typed.
+const MAX_SEED_LENGTH: usize = 24;
+
+/// Runs listed under a seed, best first.
+const MAX_STANDINGS: usize = 10;
+
+/// A group agrees on a seed and everyone gets one attempt at it, with the
+/// same food sequence on the same board. Each attempt is saved as a replay
+/// tagged with the seed, which friends can import to check and compare.
+#[derive(Resource, Default)]
+pub struct Tournament {
+    /// The seed as typed on the tournament screen.
+    seed: String,
+    /// Whether the attempt on `seed` is being played.
+    playing: bool,
+    /// How the last attempt went, or why one couldn't start.
+    status: String,
+}
+
+impl Tournament {
+    /// The seed of the attempt being played, if any.
+    pub fn entered(&self) -> Option<&str> {
+        self.playing.then_some(self.seed.as_str())
+    }
+}
+
+#[derive(Component)]
+struct TournamentRoot;
+
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tournament>()
+            .add_systems(OnEnter(AppState::Tournament), setup_screen)
+            .add_systems(
+                Update,
+                (
+                    tournament_input.after(navigate_focus),
+                    refresh_screen
+                        .after(tournament_input)
+                        .run_if(resource_changed::<Tournament>.or(resource_changed::<Locale>)),
+                )
+                    .run_if(in_state(AppState::Tournament)),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                seed_attempt
+                    .run_if(in_tournament)
+                    .after(new_run_seed)
+                    .after(select_grid_shape)
+                    .before(sync_arena),
+            )
+            .add_systems(
+                Update,
+                end_attempt
+                    .after(GameSet::Spawning)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(in_tournament),
+            )
+            .add_systems(OnExit(AppState::Playing), leave_attempt);
+    }
+}
+
+pub fn in_tournament(tournament: Res<Tournament>) -> bool {
+    tournament.playing
+}
+
+/// The game seed for a typed one. Spelled out like the replay hashes, so
+/// every build and platform turns the same text into the same seed.
+fn game_seed(seed: &str) -> u64 {
+    let bytes: Vec<u64> = seed.bytes().map(u64::from).collect();
+    fnv(FNV_OFFSET, &bytes)
+}
+
+/// Every attempt starts from the seed, on the square grid, whatever the
+/// settings say. Only the game's own items drop, as mod and seasonal packs
+/// differ between players and would change what the seed gives.
+fn seed_attempt(
+    mut commands: Commands,
+    tournament: Res<Tournament>,
+    mut rng: ResMut<GameRng>,
+    mut items: ResMut<Items>,
+) {
+    *rng = GameRng::with_seed(game_seed(&tournament.seed));
+    info!("Tournament seed \"{}\": {}", tournament.seed, rng.seed);
+    commands.insert_resource(GridShape::Square);
+    *items = Items::load();
+}
+
+/// The attempt is over when the snake dies, and forfeited on a restart.
+fn end_attempt(
+    mut game_over_reader: MessageReader<SnakeDied>,
+    mut restart_reader: MessageReader<RestartEvent>,
+    game_state: Res<GameState>,
+    locale: Res<Locale>,
+    mut tournament: ResMut<Tournament>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let died = game_over_reader.read().next().is_some();
+    let restarted = restart_reader.read().next().is_some();
+    tournament.status = if died {
+        locale.format("tournament.scored", &[("score", &game_state.last_score)])
+    } else if restarted {
+        locale.get("tournament.abandoned").to_string()
+    } else {
+        return;
+    };
+    next_state.set(AppState::Tournament);
+}
+
+/// Quitting to the menu mid-run still uses up the attempt. The packs' items
+/// come back for whatever is played next.
+fn leave_attempt(mods: Res<Mods>, mut tournament: ResMut<Tournament>, mut items: ResMut<Items>) {
+    if tournament.playing {
+        *items = mods.items();
+    }
+    tournament.playing = false;
+}
+
+/// The best runs saved under `seed`, the player's and imported ones alike.
+fn standings(seed: &str) -> Vec<Replay> {
+    let mut runs: Vec<Replay> = Replay::saved()
+        .into_iter()
+        .map(|(_, replay)| replay)
+        .filter(|replay| !seed.is_empty() && replay.tournament == seed)
+        .collect();
+    runs.sort_by_key(|replay| core::cmp::Reverse(replay.score));
+    runs.truncate(MAX_STANDINGS);
+    runs
+}
+
+fn setup_screen(mut commands: Commands, tournament: Res<Tournament>, locale: Res<Locale>) {
+    let runs = standings(&tournament.seed);
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            TournamentRoot,
+            DespawnOnExit(AppState::Tournament),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("tournament.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn(tournament_line(locale.get("tournament.hint").to_string()));
+            parent.spawn((
+                tournament_line(locale.format("tournament.seed", &[("seed", &tournament.seed)])),
+                Focusable::new(0),
+            ));
+            parent.spawn(tournament_line(tournament.status.clone()));
+            parent.spawn(tournament_line(
+                locale.get("tournament.standings").to_string(),
+            ));
+            if runs.is_empty() {
+                parent.spawn(tournament_line(locale.get("tournament.none").to_string()));
+            }
+            for (rank, replay) in runs.iter().enumerate() {
+                let name = if replay.name.is_empty() {
+                    locale.get("replays.unnamed")
+                } else {
+                    &replay.name
+                };
+                parent.spawn(tournament_line(locale.format(
+                    "tournament.standing",
+                    &[
+                        ("rank", &(rank + 1)),
+                        ("name", &name),
+                        ("score", &replay.score),
+                        ("date", &calendar::date(replay.recorded_at)),
+                    ],
+                )));
+            }
+            parent.spawn(tournament_line(locale.get("tournament.share").to_string()));
+            parent.spawn(tournament_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn tournament_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_screen(
+    mut commands: Commands,
+    tournament: Res<Tournament>,
+    locale: Res<Locale>,
+    roots: Query<Entity, With<TournamentRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_screen(commands, tournament, locale);
+}
+
+/// Typing edits the seed and Enter starts the attempt on it, unless it was
+/// had already. Escape goes back to the menu.
+fn tournament_input(
+    app_state: Res<State<AppState>>,
+    mut key_reader: MessageReader<KeyboardInput>,
+    mut input_reader: MessageReader<FocusInput>,
+    locale: Res<Locale>,
+    mut profile: ResMut<Profile>,
+    mut tournament: ResMut<Tournament>,
+    mut rules: ResMut<Rules>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // The press that opened the screen shouldn't end up in the seed.
+    if app_state.is_changed() {
+        key_reader.clear();
+        input_reader.clear();
+        return;
+    }
+    for key in key_reader.read().filter(|key| key.state.is_pressed()) {
+        let seed = &mut tournament.seed;
+        match &key.logical_key {
+            Key::Backspace => {
+                seed.pop();
+            }
+            Key::Character(text) if seed.chars().count() < MAX_SEED_LENGTH => {
+                seed.push_str(text);
+            }
+            Key::Space if seed.chars().count() < MAX_SEED_LENGTH => seed.push(' '),
+            _ => {}
+        }
+    }
+    for input in input_reader.read() {
+        match input {
+            FocusInput::Activate(_) => {
+                let seed = tournament.seed.trim().to_string();
+                if seed.is_empty() {
+                    continue;
+                }
+                if profile.tournament_seeds.contains(&seed) {
+                    tournament.status = locale.get("tournament.played").to_string();
+                    continue;
+                }
+                profile.tournament_seeds.push(seed.clone());
+                profile.save();
+                tournament.seed = seed;
+                tournament.playing = true;
+                tournament.status.clear();
+                // Everyone plays the same rules, whatever their settings.
+                *rules = Rules::default();
+                *mode = GameMode::Endless;
+                next_state.set(AppState::Playing);
+                return;
+            }
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Nudge(..) => {}
+        }
+    }
+}
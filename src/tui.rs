@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use core::time::Duration;
+use ratatui::crossterm::event::{self, Event, KeyCode as TermKey, KeyEventKind};
+use ratatui::layout::Rect;
+use ratatui::style::{Color as TermColor, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::arena::Arena;
+use crate::env::headless_app;
+use crate::items::Items;
+use crate::pause::PauseState;
+use crate::powerup::PowerUp;
+use crate::terrain::Terrain;
+use crate::{
+    AppState, Direction, DirectionChangeRequest, Food, GameState, Player, Position, RestartEvent,
+    SnakeHead, SnakeSegment,
+};
+
+/// Longest the game waits for a key before moving on to the next frame.
+const FRAME: Duration = Duration::from_millis(16);
+
+/// The ASCII render style's green.
+const SNAKE_COLOR: TermColor = TermColor::Rgb(77, 255, 77);
+const BOARD_COLOR: TermColor = TermColor::DarkGray;
+
+/// Plays endless snake in the terminal until the player quits, on the same
+/// rules as the game, see [`crate::CorePlugin`]. The board is drawn like the
+/// ASCII render style.
+pub fn run() -> std::io::Result<()> {
+    let mut app = headless_app();
+    app.finish();
+    app.cleanup();
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Playing);
+    let mut terminal = ratatui::init();
+    let result = play(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn play(terminal: &mut DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    loop {
+        app.update();
+        terminal.draw(|frame| draw(frame, app.world_mut()))?;
+        // Waits out the frame for the first key, then takes whatever else
+        // came in.
+        let mut timeout = FRAME;
+        while event::poll(timeout)? {
+            timeout = Duration::ZERO;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !press(app.world_mut(), key.code)
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Arrows or WASD steer, P pauses, R restarts and Q or Esc quits. Returns
+/// whether to keep playing.
+fn press(world: &mut World, key: TermKey) -> bool {
+    let direction = match key {
+        TermKey::Left | TermKey::Char('a') => Direction::Left,
+        TermKey::Right | TermKey::Char('d') => Direction::Right,
+        TermKey::Up | TermKey::Char('w') => Direction::Up,
+        TermKey::Down | TermKey::Char('s') => Direction::Down,
+        TermKey::Char('p') => {
+            let paused = *world.resource::<State<PauseState>>().get() == PauseState::Paused;
+            world
+                .resource_mut::<NextState<PauseState>>()
+                .set(if paused {
+                    PauseState::Running
+                } else {
+                    PauseState::Paused
+                });
+            return true;
+        }
+        TermKey::Char('r') => {
+            world.write_message(RestartEvent { same_seed: false });
+            return true;
+        }
+        TermKey::Char('q') | TermKey::Esc => return false,
+        _ => return true,
+    };
+    let mut heads = world.query_filtered::<Entity, With<Player>>();
+    if let Ok(snake) = heads.single(world) {
+        world.write_message(DirectionChangeRequest { snake, direction });
+    }
+    true
+}
+
+/// A color of the game in the terminal.
+fn term_color(color: Color) -> TermColor {
+    let color = color.to_srgba();
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u8;
+    TermColor::Rgb(
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue),
+    )
+}
+
+fn draw(frame: &mut Frame, world: &mut World) {
+    let arena = world.resource::<Arena>().clone();
+    let grid = arena.grid();
+    let terrain = world.resource::<Terrain>();
+    let mut rows = (0..arena.height())
+        .map(|row| {
+            (0..arena.width())
+                .map(|column| match terrain.at(grid.cell_position(column, row)) {
+                    Some(tile) => (tile.glyph(), BOARD_COLOR),
+                    None => ('.', BOARD_COLOR),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut food = world.query::<(&Position, &Food)>();
+    let mut power_ups = world.query::<(&Position, &PowerUp)>();
+    let mut segments =
+        world.query_filtered::<&Position, (With<SnakeSegment>, Without<SnakeHead>)>();
+    let mut heads = world.query_filtered::<&Position, With<SnakeHead>>();
+    let items = world.resource::<Items>();
+    let glyphs = food
+        .iter(world)
+        .map(|(pos, food)| (*pos, items.food(*food)))
+        .chain(
+            power_ups
+                .iter(world)
+                .map(|(pos, power_up)| (*pos, items.power_up(*power_up))),
+        )
+        .map(|(pos, kind)| (pos, kind.glyph, term_color(kind.color())))
+        .chain(segments.iter(world).map(|pos| (*pos, 'o', SNAKE_COLOR)))
+        .chain(heads.iter(world).map(|pos| (*pos, '@', SNAKE_COLOR)))
+        .collect::<Vec<_>>();
+    for (pos, glyph, color) in glyphs {
+        // The head briefly sits outside the arena on the step that kills it.
+        if let Some((column, row)) = grid.to_cell(pos) {
+            rows[row as usize][column as usize] = (glyph, color);
+        }
+    }
+    let lines = rows
+        .iter()
+        .rev()
+        .map(|glyphs| {
+            Line::from(
+                glyphs
+                    .iter()
+                    .map(|(glyph, color)| Span::styled(format!("{glyph} "), *color))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let game_state = world.resource::<GameState>();
+    let paused = *world.resource::<State<PauseState>>().get() == PauseState::Paused;
+    let title = format!(
+        " Score: {}  Last: {}{} ",
+        game_state.score,
+        game_state.last_score,
+        if paused { "  Paused" } else { "" }
+    );
+    let board = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(title)
+            .title_bottom(" Arrows steer, P pauses, R restarts, Q quits ")
+            .style(Style::new().fg(SNAKE_COLOR)),
+    );
+    // Two columns a cell, as terminal cells are about twice as tall as wide.
+    let width = (arena.width() * 2 + 2) as u16;
+    let height = (arena.height() + 2) as u16;
+    let area = frame.area();
+    let centered = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+    frame.render_widget(board, centered);
+}
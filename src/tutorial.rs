@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use core::time::Duration;
+
+use crate::arena::Arena;
+use crate::attract;
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::{AppState, GameMode, Player, PlayerMeals, SnakeHead};
+
+/// Foods to eat after the first one before the tutorial is complete.
+const PRACTICE_FOODS: u32 = 2;
+
+enum TutorialStep {
+    Turn,
+    Eat,
+    Practice { eaten: u32 },
+    Done,
+}
+
+impl TutorialStep {
+    fn prompt(&self, locale: &Locale) -> String {
+        match self {
+            Self::Turn => locale.get("tutorial.turn").to_string(),
+            Self::Eat => locale.get("tutorial.eat").to_string(),
+            Self::Practice { eaten } => {
+                locale.format("tutorial.practice", &[("count", &(PRACTICE_FOODS - eaten))])
+            }
+            Self::Done => locale.get("tutorial.done").to_string(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Tutorial {
+    step: TutorialStep,
+    /// Hides the final prompt a little while after completion.
+    dismiss_timer: Timer,
+}
+
+#[derive(Component)]
+struct TutorialText;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            start_tutorial
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(tutorial_pending)
+                .run_if(attract::not_demo),
+        )
+        .add_systems(OnExit(AppState::Playing), stop_tutorial)
+        .add_systems(
+            Update,
+            (
+                advance_tutorial,
+                update_tutorial_text.after(advance_tutorial),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_exists::<Tutorial>),
+        );
+    }
+}
+
+fn tutorial_pending(profile: Res<Profile>) -> bool {
+    !profile.tutorial_completed
+}
+
+fn start_tutorial(mut commands: Commands) {
+    commands.insert_resource(Tutorial {
+        step: TutorialStep::Turn,
+        dismiss_timer: Timer::new(Duration::from_secs(3), TimerMode::Once),
+    });
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.9, 0.4)),
+        TextLayout::new_with_justify(Justify::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            width: Val::Percent(100.0),
+            ..default()
+        },
+        TutorialText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn stop_tutorial(mut commands: Commands) {
+    commands.remove_resource::<Tutorial>();
+}
+
+fn advance_tutorial(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tutorial: ResMut<Tutorial>,
+    mut profile: ResMut<Profile>,
+    mut growth_reader: PlayerMeals,
+    arena: Res<Arena>,
+    heads: Query<&SnakeHead, With<Player>>,
+    text: Query<Entity, With<TutorialText>>,
+) {
+    let ate = growth_reader.read().count() as u32;
+    let next = match tutorial.step {
+        TutorialStep::Turn => heads
+            .iter()
+            .any(|head| head.direction != arena.grid().start_direction())
+            .then_some(TutorialStep::Eat),
+        TutorialStep::Eat => (ate > 0).then_some(TutorialStep::Practice { eaten: 0 }),
+        TutorialStep::Practice { eaten } if ate > 0 => {
+            if eaten + ate >= PRACTICE_FOODS {
+                profile.tutorial_completed = true;
+                profile.save();
+                Some(TutorialStep::Done)
+            } else {
+                Some(TutorialStep::Practice { eaten: eaten + ate })
+            }
+        }
+        TutorialStep::Practice { .. } => None,
+        TutorialStep::Done => {
+            if tutorial.dismiss_timer.tick(time.delta()).just_finished() {
+                for entity in text.iter() {
+                    commands.entity(entity).despawn();
+                }
+                commands.remove_resource::<Tutorial>();
+            }
+            None
+        }
+    };
+
+    if let Some(next) = next {
+        tutorial.step = next;
+    }
+}
+
+fn update_tutorial_text(
+    tutorial: Res<Tutorial>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<TutorialText>>,
+) {
+    if tutorial.is_changed() {
+        for mut text in query.iter_mut() {
+            text.0 = tutorial.step.prompt(&locale);
+        }
+    }
+}
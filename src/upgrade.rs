@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::focus::{FocusInput, Focusable, navigate_focus};
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::rules::Rules;
+use crate::{AppState, GameSet, RunStarted};
+
+const SHIELD_COLOR: Color = Color::srgb(0.4, 0.8, 1.0);
+
+/// Picks an upgrade by its position in [`Upgrade::ALL`].
+const DIGIT_KEYS: [KeyCode; 3] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+
+/// Permanent boosts for roguelike runs, bought once with coins.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Upgrade {
+    Shield,
+    Steady,
+    ExtraLife,
+}
+
+impl Upgrade {
+    const ALL: [Self; 3] = [Self::Shield, Self::Steady, Self::ExtraLife];
+
+    fn price(self) -> u32 {
+        match self {
+            Self::Shield => 100,
+            Self::Steady => 150,
+            Self::ExtraLife => 250,
+        }
+    }
+
+    /// The upgrade that has to be bought first.
+    fn requires(self) -> Option<Self> {
+        match self {
+            Self::ExtraLife => Some(Self::Shield),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, rules: &mut Rules) {
+        match self {
+            Self::Shield => rules.shields += 1,
+            Self::Steady => rules.speed *= 0.8,
+            Self::ExtraLife => rules.lives += 1,
+        }
+    }
+
+    /// Locale key of the upgrade's name; its description is under the same
+    /// key with `.info` appended.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Shield => "upgrade.shield",
+            Self::Steady => "upgrade.steady",
+            Self::ExtraLife => "upgrade.extra_life",
+        }
+    }
+}
+
+/// Crashes the snake can still shrug off this run.
+#[derive(Resource, Default)]
+pub struct Shield {
+    charges: u32,
+}
+
+impl Shield {
+    pub fn with_charges(charges: u32) -> Self {
+        Self { charges }
+    }
+
+    /// Uses up a charge, returning whether there was one.
+    pub fn absorb(&mut self) -> bool {
+        if self.charges == 0 {
+            return false;
+        }
+        self.charges -= 1;
+        true
+    }
+}
+
+#[derive(Component)]
+struct UpgradesRoot;
+
+#[derive(Component)]
+struct ShieldText;
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Shield>()
+            .add_systems(OnEnter(AppState::Upgrades), setup_upgrades)
+            .add_systems(
+                Update,
+                (
+                    upgrades_input.after(navigate_focus),
+                    refresh_upgrades
+                        .after(upgrades_input)
+                        .run_if(resource_changed::<Profile>),
+                )
+                    .run_if(in_state(AppState::Upgrades)),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (charge_shield, setup_shield_text),
+            )
+            .add_systems(
+                Update,
+                (
+                    recharge_shield_on_respawn.after(GameSet::Spawning),
+                    update_shield_text,
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn charge_shield(rules: Res<Rules>, mut shield: ResMut<Shield>) {
+    *shield = Shield::with_charges(rules.shields);
+}
+
+fn recharge_shield_on_respawn(
+    mut run_reader: MessageReader<RunStarted>,
+    rules: Res<Rules>,
+    shield: ResMut<Shield>,
+) {
+    if run_reader.read().next().is_some() {
+        charge_shield(rules, shield);
+    }
+}
+
+fn setup_shield_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+        TextColor(SHIELD_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(96.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        ShieldText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn update_shield_text(
+    shield: Res<Shield>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ShieldText>>,
+) {
+    if !shield.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.0 = if shield.charges > 0 {
+            locale.format("hud.shield", &[("count", &shield.charges)])
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn setup_upgrades(mut commands: Commands, locale: Res<Locale>, profile: Res<Profile>) {
+    let coins = locale.format("shop.coins", &[("coins", &profile.coins)]);
+    let items = Upgrade::ALL.iter().enumerate().map(|(index, upgrade)| {
+        let status = if profile.upgrades.contains(upgrade) {
+            locale.get("shop.owned").to_string()
+        } else if let Some(required) = upgrade
+            .requires()
+            .filter(|required| !profile.upgrades.contains(required))
+        {
+            locale.format(
+                "upgrades.requires",
+                &[("name", &locale.get(required.name()))],
+            )
+        } else {
+            locale.format("shop.price", &[("price", &upgrade.price())])
+        };
+        locale.format(
+            "upgrades.item",
+            &[
+                ("number", &(index + 1)),
+                ("name", &locale.get(upgrade.name())),
+                (
+                    "description",
+                    &locale.get(&format!("{}.info", upgrade.name())),
+                ),
+                ("status", &status),
+            ],
+        )
+    });
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            UpgradesRoot,
+            DespawnOnExit(AppState::Upgrades),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.get("upgrades.title")),
+                TextFont {
+                    font_size: 64.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn(upgrades_line(coins));
+            for (index, line) in items.enumerate() {
+                parent.spawn((upgrades_line(line), Focusable::new(index)));
+            }
+            parent.spawn(upgrades_line(locale.get("shop.back").to_string()));
+        });
+}
+
+fn upgrades_line(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+    )
+}
+
+fn refresh_upgrades(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    profile: Res<Profile>,
+    roots: Query<Entity, With<UpgradesRoot>>,
+) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn();
+    }
+    setup_upgrades(commands, locale, profile);
+}
+
+/// A digit or Enter on an upgrade buys it if it's affordable and unlocked.
+/// Escape goes back to the menu.
+fn upgrades_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut input_reader: MessageReader<FocusInput>,
+    mut profile: ResMut<Profile>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut picked = DIGIT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for input in input_reader.read() {
+        match *input {
+            FocusInput::Back => {
+                next_state.set(AppState::Menu);
+                return;
+            }
+            FocusInput::Activate(order) => picked = Some(order),
+            FocusInput::Nudge(..) => {}
+        }
+    }
+    let Some(upgrade) = picked.and_then(|index| Upgrade::ALL.get(index).copied()) else {
+        return;
+    };
+    let locked = upgrade
+        .requires()
+        .is_some_and(|required| !profile.upgrades.contains(&required));
+    if locked || profile.upgrades.contains(&upgrade) || profile.coins < upgrade.price() {
+        return;
+    }
+    profile.coins -= upgrade.price();
+    profile.upgrades.push(upgrade);
+    profile.save();
+}
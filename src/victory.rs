@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::{AppState, LevelCompleteEvent};
+
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+const STAR_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const EMPTY_STAR_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+
+pub const MAX_STARS: u8 = 3;
+
+/// Shown over the board once a level is complete. Whoever loads the next
+/// level clears it away.
+#[derive(Component)]
+pub struct VictoryScreen;
+
+pub struct VictoryPlugin;
+
+impl Plugin for VictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, show_victory.run_if(in_state(AppState::Playing)));
+    }
+}
+
+fn show_victory(
+    mut commands: Commands,
+    mut reader: MessageReader<LevelCompleteEvent>,
+    locale: Res<Locale>,
+) {
+    let Some(complete) = reader.read().last() else {
+        return;
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            VictoryScreen,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(24.0),
+                        padding: UiRect::all(Val::Px(32.0)),
+                        ..default()
+                    },
+                    BackgroundColor(PANEL_COLOR),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(locale.get("victory.title")),
+                        TextFont {
+                            font_size: 56.0,
+                            ..default()
+                        },
+                        TextColor(STAR_COLOR),
+                    ));
+                    panel
+                        .spawn(Node {
+                            column_gap: Val::Px(28.0),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        })
+                        .with_children(|row| spawn_stars(row, complete.stars, 36.0));
+                });
+        });
+}
+
+/// A row of [`MAX_STARS`] stars, the first `stars` of them lit. Drawn as
+/// diamonds since the UI font has no star glyph.
+pub fn spawn_stars(parent: &mut ChildSpawnerCommands, stars: u8, size: f32) {
+    for star in 0..MAX_STARS {
+        parent.spawn((
+            Node {
+                width: Val::Px(size),
+                height: Val::Px(size),
+                ..default()
+            },
+            BackgroundColor(if star < stars {
+                STAR_COLOR
+            } else {
+                EMPTY_STAR_COLOR
+            }),
+            UiTransform::from_rotation(Rot2::degrees(45.0)),
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::calendar::today;
+use crate::mods::Mods;
+use crate::settings::Settings;
+
+/// Drawn behind the board, which sits between -1 and 0.
+const PARTICLE_DEPTH: f32 = -10.0;
+
+/// Ambient particles drifting behind the arena.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Off,
+    /// Picks the effect from the time of year.
+    Seasonal,
+    Snow,
+    Rain,
+    Dust,
+}
+
+impl Weather {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Seasonal,
+            Self::Seasonal => Self::Snow,
+            Self::Snow => Self::Rain,
+            Self::Rain => Self::Dust,
+            Self::Dust => Self::Off,
+        }
+    }
+
+    /// Locale key of the weather's display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "weather.off",
+            Self::Seasonal => "weather.seasonal",
+            Self::Snow => "weather.snow",
+            Self::Rain => "weather.rain",
+            Self::Dust => "weather.dust",
+        }
+    }
+
+    /// Snow in winter, rain in spring and autumn, dust in summer.
+    fn resolve(self) -> Self {
+        match self {
+            Self::Seasonal => match today().0 {
+                12 | 1 | 2 => Self::Snow,
+                6..=8 => Self::Dust,
+                _ => Self::Rain,
+            },
+            weather => weather,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    /// Pixels per second.
+    velocity: Vec2,
+}
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_particles)
+            .add_systems(Update, drift_particles.run_if(in_state(AppState::Playing)));
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mods: Res<Mods>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+) {
+    if settings.reduced_motion {
+        return;
+    }
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+    let weather = mods.weather().unwrap_or(settings.weather).resolve();
+    let (count, color, size) = match weather {
+        Weather::Off | Weather::Seasonal => return,
+        Weather::Snow => (120, Color::srgba(1.0, 1.0, 1.0, 0.6), Vec2::splat(3.0)),
+        Weather::Rain => (150, Color::srgba(0.6, 0.7, 0.9, 0.35), Vec2::new(1.0, 14.0)),
+        Weather::Dust => (80, Color::srgba(0.8, 0.7, 0.5, 0.4), Vec2::splat(2.0)),
+    };
+    let half = window.size() / 2.0;
+    let mut rng = rand::rng();
+    for _ in 0..count {
+        let velocity = match weather {
+            Weather::Snow => Vec2::new(
+                rng.random_range(-15.0..15.0),
+                rng.random_range(-50.0..-25.0),
+            ),
+            Weather::Rain => Vec2::new(-60.0, rng.random_range(-500.0..-400.0)),
+            _ => Vec2::new(rng.random_range(-12.0..12.0), rng.random_range(-12.0..12.0)),
+        };
+        let position = Vec2::new(
+            rng.random_range(-half.x..half.x),
+            rng.random_range(-half.y..half.y),
+        );
+        commands.spawn((
+            Sprite::from_color(color, size),
+            Transform::from_translation(position.extend(PARTICLE_DEPTH)),
+            Particle { velocity },
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+/// Moves particles along and wraps them around the window's edges.
+fn drift_particles(
+    time: Res<Time>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut particles: Query<(&Particle, &mut Transform)>,
+) {
+    let Ok(window) = window_query.single() else {
+        return;
+    };
+    let half = window.size() / 2.0;
+    for (particle, mut transform) in particles.iter_mut() {
+        let moved = transform.translation.truncate() + particle.velocity * time.delta_secs();
+        let wrapped = (moved + half).rem_euclid(window.size()) - half;
+        transform.translation = wrapped.extend(PARTICLE_DEPTH);
+    }
+}